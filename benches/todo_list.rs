@@ -0,0 +1,56 @@
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use todo_app::TodoList;
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+
+fn populated(count: usize) -> TodoList {
+    let mut todo_list = TodoList::new();
+    for n in 0..count {
+        todo_list.add_todo(format!("Todo {}", n), format!("Description for todo {}", n)).unwrap();
+    }
+    todo_list
+}
+
+fn bench_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add");
+    group.sample_size(10);
+    for count in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter(|| populated(black_box(count)));
+        });
+    }
+    group.finish();
+}
+
+fn bench_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    for count in SIZES {
+        let todo_list = populated(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &todo_list, |b, todo_list| {
+            b.iter(|| todo_list.search(black_box("todo 4242")).len());
+        });
+    }
+    group.finish();
+}
+
+fn bench_save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save");
+    group.sample_size(10);
+    let path = env::temp_dir().join("todo_app_bench.json");
+    let filename = path.to_str().expect("temp dir is not valid UTF-8");
+    for count in SIZES {
+        let todo_list = populated(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &todo_list, |b, todo_list| {
+            b.iter(|| todo_list.save_to_file(filename).expect("failed to save"));
+        });
+    }
+    group.finish();
+    let _ = fs::remove_file(&path);
+}
+
+criterion_group!(benches, bench_add, bench_search, bench_save);
+criterion_main!(benches);
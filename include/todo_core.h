@@ -0,0 +1,58 @@
+#ifndef TODO_CORE_H
+#define TODO_CORE_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct TodoList TodoList;
+
+struct TodoList *todo_list_new(void);
+
+// # Safety
+// `list` must be null or come from this library and not be used afterwards.
+void todo_list_free(struct TodoList *list);
+
+// Reads a list in the format of the todo file; null if it cannot be read.
+//
+// # Safety
+// `json` must be null or a valid, NUL-terminated string.
+struct TodoList *todo_list_from_json(const char *json);
+
+// The list in the format of the todo file, to be freed with
+// todo_string_free; null on failure.
+//
+// # Safety
+// `list` must be null or a live list from this library.
+char *todo_list_to_json(const struct TodoList *list);
+
+// Adds a todo and returns its ID, or 0 if the title or description is
+// invalid. A null description counts as empty.
+//
+// # Safety
+// `list` must be null or a live list from this library, `title` and
+// `description` null or valid, NUL-terminated strings.
+size_t todo_list_add(struct TodoList *list, const char *title, const char *description);
+
+// Marks a todo as done; false if there is no such todo.
+//
+// # Safety
+// `list` must be null or a live list from this library.
+bool todo_list_complete(struct TodoList *list, size_t id);
+
+// The number of todos in the list.
+//
+// # Safety
+// `list` must be null or a live list from this library.
+size_t todo_list_len(const struct TodoList *list);
+
+// # Safety
+// `s` must be null or a string returned by this library, not used
+// afterwards.
+void todo_string_free(char *s);
+
+#endif  /* TODO_CORE_H */
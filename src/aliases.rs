@@ -0,0 +1,128 @@
+// Command aliases from the configuration, such as
+//
+//     "aliases": { "ls": "list --sort due", "note": "done $1 --note" }
+//
+// expanded before the arguments are read, as git does. `$1`, `$2`, ... stand
+// for the arguments given after the alias and `$@` for all of them; those
+// the alias does not mention are added at the end.
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum AliasError {
+    UnterminatedQuote(String),
+    MissingArgument { alias: String, needed: usize },
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasError::UnterminatedQuote(alias) => write!(f, "The alias {} has a quote that is never closed.", alias),
+            AliasError::MissingArgument { alias, needed: 1 } => write!(f, "The alias {} needs an argument.", alias),
+            AliasError::MissingArgument { alias, needed } => write!(f, "The alias {} needs at least {} arguments.", alias, needed),
+        }
+    }
+}
+
+// Splits at whitespace outside quotes, as a shell would: "a 'b c'" is two
+// words. A backslash keeps the next character as it is, except inside
+// single quotes. None if a quote is left open.
+pub fn split_words(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"') | None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            },
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            },
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            },
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// `word` with each $N replaced by the Nth of `args`. Returns the highest N
+// used, or Err(N) if there is no Nth argument.
+fn substitute(word: &str, args: &[String]) -> Result<(String, usize), usize> {
+    let mut out = String::new();
+    let mut highest = 0;
+    let mut rest = word;
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match after[..digits].parse::<usize>() {
+            Ok(n) if n > 0 => {
+                let arg = args.get(n - 1).ok_or(n)?;
+                out.push_str(arg);
+                highest = highest.max(n);
+            },
+            _ => out.push_str(&rest[at..at + 1 + digits]),
+        }
+        rest = &after[digits..];
+    }
+    out.push_str(rest);
+    Ok((out, highest))
+}
+
+// Expands the command in `args`, the first argument after any flags such as
+// --yes, for as long as it names an alias. An alias may use the name of a
+// command, and then replaces it; one that comes back to a name already
+// expanded, as in "list": "list --all", runs the command of that name.
+pub fn expand(aliases: &BTreeMap<String, String>, args: &[String]) -> Result<Vec<String>, AliasError> {
+    let mut args = args.to_vec();
+    let mut expanded = Vec::new();
+    loop {
+        let Some(at) = args.iter().position(|arg| !arg.starts_with('-')) else {
+            return Ok(args);
+        };
+        let name = args[at].clone();
+        let Some(line) = aliases.get(&name).filter(|_| !expanded.contains(&name)) else {
+            return Ok(args);
+        };
+        let words = split_words(line).ok_or_else(|| AliasError::UnterminatedQuote(name.clone()))?;
+        let given = args.split_off(at + 1);
+        args.pop();
+        let mut used = 0;
+        let mut all = false;
+        for word in words {
+            if word == "$@" {
+                args.extend(given.iter().cloned());
+                all = true;
+                continue;
+            }
+            let (word, highest) = substitute(&word, &given).map_err(|needed| AliasError::MissingArgument { alias: name.clone(), needed })?;
+            args.push(word);
+            used = used.max(highest);
+        }
+        if !all {
+            args.extend(given.into_iter().skip(used));
+        }
+        expanded.push(name);
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::Local;
+use crate::dates::days_before;
+use crate::todo::{Todo, TodoList};
+
+// "todos.json" is archived to "todos.archive.json".
+pub fn archive_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.archive.json", stem),
+        None => format!("{}.archive", filename),
+    }
+}
+
+pub fn load_archive(filename: &str) -> io::Result<Vec<Todo>> {
+    if !Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let todos: Vec<Todo> = serde_json::from_str(&contents)?;
+    Ok(todos)
+}
+
+pub fn append_to_archive(filename: &str, todos: Vec<Todo>) -> io::Result<()> {
+    let mut archived = load_archive(filename)?;
+    archived.extend(todos);
+    let json = serde_json::to_string_pretty(&archived)?;
+    let mut file = File::create(filename)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+// Moves todos completed more than `days` days ago into the archive next to
+// `filename`, returning how many were moved. The caller saves the list.
+// Nothing is that old when `days` reaches back past the first day chrono
+// knows.
+pub fn archive_completed(todo_list: &mut TodoList, filename: &str, days: u32) -> io::Result<usize> {
+    let Some(cutoff) = days_before(Local::now(), days) else {
+        return Ok(0);
+    };
+    let removed = todo_list.remove_completed_before(cutoff);
+    let count = removed.len();
+    if count > 0 {
+        append_to_archive(&archive_filename(filename), removed)?;
+    }
+    Ok(count)
+}
@@ -0,0 +1,233 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use todo_app::archive;
+use todo_app::config::{Config, StorageKind, CONFIG_FILENAME};
+use todo_app::focus;
+use todo_app::history;
+use todo_app::plan;
+use todo_app::storage;
+use todo_app::sync;
+use todo_app::tokens;
+use todo_app::users;
+use todo_app::workdays;
+use todo_app::TodoList;
+
+// The list is kept as todos.json in a backup whatever the storage, and the
+// files that go with it under the names they have next to a todos.json.
+const LIST: &str = "todos.json";
+
+const COMPANIONS: [fn(&str) -> String; 9] = [
+    archive::archive_filename,
+    focus::focus_filename,
+    history::history_filename,
+    workdays::holidays_filename,
+    plan::plan_filename,
+    sync::sync_filename,
+    sync::server_filename,
+    tokens::tokens_filename,
+    users::users_filename,
+];
+
+const BLOCK: usize = 512;
+
+// Sync keys and token hashes are in a backup, so it and what is restored
+// from it are for the owner's eyes only, as the files themselves.
+fn create_private(path: &str) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+// A ustar header for a regular file, as `tar` reads it.
+fn tar_header(name: &str, size: usize, mtime: i64) -> [u8; BLOCK] {
+    let mut header = [0; BLOCK];
+    let mut put = |at: usize, value: &[u8]| header[at..at + value.len()].copy_from_slice(value);
+    put(0, name.as_bytes());
+    put(100, b"0000600\0");
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{:011o}\0", size).as_bytes());
+    put(136, format!("{:011o}\0", mtime.max(0)).as_bytes());
+    put(148, b"        ");
+    put(156, b"0");
+    put(257, b"ustar\x0000");
+    let sum: u32 = header.iter().map(|&byte| byte as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    header
+}
+
+fn write_tar(out: &mut impl Write, files: &[(String, Vec<u8>)]) -> io::Result<()> {
+    let mtime = Utc::now().timestamp();
+    for (name, contents) in files {
+        out.write_all(&tar_header(name, contents.len(), mtime))?;
+        out.write_all(contents)?;
+        out.write_all(&vec![0; contents.len().next_multiple_of(BLOCK) - contents.len()])?;
+    }
+    // Two empty blocks end the archive.
+    out.write_all(&[0; 2 * BLOCK])
+}
+
+fn invalid(what: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what.into())
+}
+
+fn octal(field: &[u8]) -> Option<usize> {
+    let text = std::str::from_utf8(field).ok()?.trim_matches(['\0', ' ']);
+    usize::from_str_radix(text, 8).ok()
+}
+
+// The regular files of a tar archive, with their names.
+fn read_tar(input: &mut impl Read) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let mut header = [0; BLOCK];
+    loop {
+        if input.read_exact(&mut header).is_err() || header.iter().all(|&byte| byte == 0) {
+            return Ok(files);
+        }
+        let stored = octal(&header[148..156]).ok_or_else(|| invalid("This is not a backup."))?;
+        let sum: usize = header.iter().enumerate().map(|(i, &byte)| if (148..156).contains(&i) { b' ' as usize } else { byte as usize }).sum();
+        let size = octal(&header[124..136]).filter(|_| stored == sum).ok_or_else(|| invalid("This is not a backup, or it is damaged."))?;
+        let name_end = header[..100].iter().position(|&byte| byte == 0).unwrap_or(100);
+        let name = String::from_utf8_lossy(&header[..name_end]).into_owned();
+        // Read as it comes rather than allocated up front, as a damaged
+        // header may claim gigabytes.
+        let padded = size.next_multiple_of(BLOCK);
+        let mut contents = Vec::new();
+        input.by_ref().take(padded as u64).read_to_end(&mut contents)?;
+        if contents.len() < padded {
+            return Err(invalid(format!("The backup ends in the middle of {}.", name)));
+        }
+        contents.truncate(size);
+        if matches!(header[156], b'0' | 0) {
+            files.push((name, contents));
+        }
+    }
+}
+
+fn is_gzip(filename: &str) -> bool {
+    filename.ends_with(".gz") || filename.ends_with(".tgz")
+}
+
+fn is_zstd(filename: &str) -> bool {
+    filename.ends_with(".zst") || filename.ends_with(".zstd")
+}
+
+// Writes the configuration, the list and every file that goes with it to
+// `target`, a .tar, .tar.gz or .tar.zst file or "-" for a tar on standard
+// output.
+pub fn create(target: &str, filename: &str) -> io::Result<()> {
+    let mut files = Vec::new();
+    if Path::new(CONFIG_FILENAME).exists() {
+        files.push((CONFIG_FILENAME.to_string(), fs::read(CONFIG_FILENAME)?));
+    }
+    files.push((LIST.to_string(), TodoList::load_from_file(filename)?.to_json()?.into_bytes()));
+    for companion in COMPANIONS {
+        match fs::read(companion(filename)) {
+            Ok(contents) => files.push((companion(LIST), contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => return Err(e),
+        }
+    }
+
+    if target == "-" {
+        let mut out = io::stdout().lock();
+        write_tar(&mut out, &files)?;
+        return out.flush();
+    }
+    let file = create_private(target)?;
+    if is_gzip(target) {
+        let mut out = GzEncoder::new(file, Compression::default());
+        write_tar(&mut out, &files)?;
+        out.finish()?.sync_all()?;
+    } else if is_zstd(target) {
+        let mut out = zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+        write_tar(&mut out, &files)?;
+        out.finish()?.sync_all()?;
+    } else {
+        let mut out = io::BufWriter::new(file);
+        write_tar(&mut out, &files)?;
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    }
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    println!("Backed up {} to {}.", names.join(", "), target);
+    Ok(())
+}
+
+fn read_backup(source: &str) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut input: Box<dyn Read> = if source == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(File::open(source)?))
+    };
+    let mut magic = [0; 4];
+    let read = input.read(&mut magic)?;
+    let mut input = io::Cursor::new(magic[..read].to_vec()).chain(input);
+    match magic {
+        [0x1f, 0x8b, ..] => read_tar(&mut GzDecoder::new(input)),
+        [0x28, 0xb5, 0x2f, 0xfd] => read_tar(&mut zstd::Decoder::new(input)?),
+        _ => read_tar(&mut input),
+    }
+}
+
+// Sets this directory up from a backup: its configuration, then the list and
+// the files that go with it where that configuration keeps them. Nothing is
+// written if any of them exists already, unless `force` is given.
+pub fn restore(source: &str, config: &Config, force: bool) -> io::Result<()> {
+    let files = read_backup(source)?;
+    let Some((_, list)) = files.iter().find(|(name, _)| name == LIST) else {
+        println!("{} is not a backup: it holds no {}.", source, LIST);
+        return Ok(());
+    };
+    let restored_config = match files.iter().find(|(name, _)| name == CONFIG_FILENAME) {
+        Some((_, contents)) => Some(serde_json::from_slice::<Config>(contents).map_err(|e| invalid(format!("The {} in the backup cannot be read: {}", CONFIG_FILENAME, e)))?),
+        None => None,
+    };
+    let config = restored_config.as_ref().unwrap_or(config);
+    let filename = config.todo_filename();
+    let todo_list = TodoList::from_json(&String::from_utf8_lossy(list))?;
+    // A database is shared already; there is nothing to move into it.
+    let list_target = matches!(config.storage, StorageKind::Json | StorageKind::Sled).then_some(filename.as_str());
+
+    // Anything else in the archive is left alone, so that it cannot write
+    // outside the data directory.
+    let mut writes: Vec<(String, &[u8])> = Vec::new();
+    for (name, contents) in &files {
+        if name == CONFIG_FILENAME {
+            writes.push((CONFIG_FILENAME.to_string(), contents));
+        } else if let Some(companion) = COMPANIONS.iter().find(|companion| companion(LIST) == *name) {
+            writes.push((companion(&filename), contents));
+        }
+    }
+    let mut targets: Vec<&str> = writes.iter().map(|(target, _)| target.as_str()).chain(list_target).collect();
+    targets.retain(|target| Path::new(target).exists());
+    if !targets.is_empty() && !force {
+        println!("Nothing was restored, as {} exist already. Move them away or run `backup restore {} --force` to replace them.", targets.join(", "), source);
+        return Ok(());
+    }
+
+    if let Some(dir) = config.data_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    for (target, contents) in &writes {
+        create_private(target)?.write_all(contents)?;
+        println!("Restored {}.", target);
+    }
+    match list_target {
+        Some(target) => {
+            storage::open(target)?.save(&todo_list)?;
+            println!("Restored {} todos to {}.", todo_list.todos().len(), target);
+        },
+        None => println!(
+            "The {} todos of the backup were not restored, as the configured storage keeps them in a database; `import json` brings them in from a todos.json.",
+            todo_list.todos().len()
+        ),
+    }
+    Ok(())
+}
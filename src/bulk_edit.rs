@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::process::Command;
+use todo_app::crypto;
+use todo_app::{Todo, TodoList};
+use crate::ui::get_confirmation;
+
+const HEADER: &str = "\
+# Edit the todos below, one per line: #ID [ ] TITLE
+# - change a title to rename the todo
+# - write [x] or [ ] to complete or reopen it
+# - delete a line to delete the todo
+# - add a line without a #ID to create a new todo
+# Other lines starting with # are ignored. Save an empty file to abort.
+";
+
+struct Line {
+    id: Option<usize>,
+    completed: Option<bool>,
+    title: String,
+}
+
+// A todo's line starts with "#ID", so that a new todo may start with a
+// number ("3 eggs").
+fn is_todo_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line.strip_prefix('#').is_none_or(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn parse_line(line: &str) -> Line {
+    let mut rest = line.trim();
+    let mut id = None;
+    if let Some(after) = rest.strip_prefix('#') {
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        id = after[..digits].parse().ok();
+        rest = after[digits..].trim_start();
+    }
+    let mut completed = None;
+    if let Some(after) = rest.strip_prefix("[ ]") {
+        completed = Some(false);
+        rest = after.trim_start();
+    } else if let Some(after) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        completed = Some(true);
+        rest = after.trim_start();
+    }
+    Line { id, completed, title: rest.to_string() }
+}
+
+fn render(todos: &[&Todo]) -> String {
+    let mut out = String::from(HEADER);
+    for todo in todos {
+        let mark = if todo.completed { "[x]" } else { "[ ]" };
+        out.push_str(&format!("#{} {} {}\n", todo.id, mark, todo.title));
+    }
+    out
+}
+
+// The file gets a name nobody can guess and is created fresh, so that no
+// one else on the machine can have it read or replaced beforehand.
+fn open_in_editor(contents: &str) -> io::Result<Option<String>> {
+    let path = env::temp_dir().join(format!("todo_app_bulk_{}.txt", crypto::random_secret()));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(contents.as_bytes())?;
+
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program).args(words).arg(&path).status();
+
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    match status {
+        Ok(status) if status.success() => Ok(Some(edited?)),
+        Ok(_) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Opens the todos matching `query` (or all of them) in $EDITOR and applies
+// the changes. Returns whether anything changed.
+pub fn run(todo_list: &mut TodoList, query: Option<&str>, confirm_delete: bool) -> io::Result<bool> {
+    let todos: Vec<&Todo> = match query {
+        Some(query) => todo_list.search(query),
+        None => todo_list.todos().iter().collect(),
+    };
+    let listed: Vec<usize> = todos.iter().map(|todo| todo.id).collect();
+
+    let Some(edited) = open_in_editor(&render(&todos))? else {
+        println!("Editor exited with an error, nothing changed.");
+        return Ok(false);
+    };
+    let lines: Vec<Line> = edited
+        .lines()
+        .filter(|line| is_todo_line(line))
+        .map(parse_line)
+        .collect();
+    if lines.is_empty() {
+        println!("Empty file, nothing changed.");
+        return Ok(false);
+    }
+
+    let kept: HashSet<usize> = lines.iter().filter_map(|line| line.id).collect();
+    let removed: Vec<usize> = listed.iter().copied().filter(|id| !kept.contains(id)).collect();
+    if !removed.is_empty()
+        && confirm_delete
+        && !get_confirmation(&format!("This will delete {} todos. Continue?", removed.len()))
+    {
+        println!("Bulk edit cancelled.");
+        return Ok(false);
+    }
+
+    let (mut renamed, mut toggled, mut added) = (0, 0, 0);
+    for line in &lines {
+        match line.id {
+            Some(id) if listed.contains(&id) => {
+                let Some(todo) = todo_list.get_todo(id) else {
+                    continue;
+                };
+                if !line.title.is_empty() && line.title != todo.title {
+                    let description = todo.description.clone();
+                    match todo_list.edit_todo(id, line.title.clone(), description) {
+                        Ok(_) => renamed += 1,
+                        Err(e) => println!("Not renaming todo {}: {}", id, e),
+                    }
+                }
+                let completed = todo_list.get_todo(id).is_some_and(|todo| todo.completed);
+                if line.completed.is_some_and(|wanted| wanted != completed) {
+                    todo_list.toggle_completed(id);
+                    toggled += 1;
+                }
+            }
+            Some(id) => println!("Skipping line for unknown todo {}.", id),
+            None if line.title.is_empty() => {}
+            None => {
+                let id = match todo_list.add_todo(line.title.clone(), String::new()) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        println!("Not adding \"{}\": {}", line.title, e);
+                        continue;
+                    }
+                };
+                if line.completed == Some(true) {
+                    todo_list.toggle_completed(id);
+                }
+                added += 1;
+            }
+        }
+    }
+    for &id in &removed {
+        todo_list.delete_todo(id);
+    }
+
+    println!("{} renamed, {} toggled, {} added, {} deleted.", renamed, toggled, added, removed.len());
+    Ok(renamed + toggled + added + removed.len() > 0)
+}
@@ -0,0 +1,2965 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use todo_app::archive;
+use todo_app::columns::{Column, ColumnSpec};
+use todo_app::config::{Config, CONFIG_FILENAME};
+use todo_app::crypto;
+use todo_app::graph::{self, GraphFormat};
+use todo_app::groups::{self, GroupBy};
+use todo_app::dates::{apply_offset, days_before, offset_before, parse_date, postpone_date};
+use todo_app::diff::{self, TodoChange};
+use todo_app::digest::{self, Digest};
+use todo_app::estimates::{self, format_minutes, parse_minutes};
+use todo_app::export::{self, SnippetFormat};
+use todo_app::focus::{self, Focus};
+use todo_app::fsck;
+use todo_app::habits;
+use todo_app::ids::{lacks_ids, parse_ids, FILTERS};
+use todo_app::history;
+use todo_app::import::{self, ImportStrategy, Importer};
+use todo_app::journal;
+use todo_app::mail;
+use todo_app::matrix;
+use todo_app::notify::{self, Notification, Notifier};
+use todo_app::paper::{self, PaperSize};
+use todo_app::plan::{self, DEFAULT_CAPACITY_MINUTES};
+use todo_app::priority::{effective_priority, inherited_priorities};
+use todo_app::keys::{Action, MenuAction, ReviewAction};
+use todo_app::rrule::{Recurrence, Rule};
+use todo_app::status::Counts;
+use todo_app::sync::{self, Op, SyncState};
+use todo_app::template::Template;
+use todo_app::timeline;
+use todo_app::tokens::{self, Scope, Tokens};
+use todo_app::workdays::{self, Calendar, Holiday};
+use todo_app::zones::{self, Deadline};
+use todo_app::update;
+use todo_app::users::{self, Role, Users};
+use todo_app::validate::clean_line;
+use todo_app::recover;
+use todo_app::velocity;
+use todo_app::{parse_tags, Priority, Todo, TodoList};
+use crate::backup;
+use crate::bulk_edit;
+use crate::doctor;
+use crate::init;
+use crate::input;
+use crate::interrupt;
+use crate::notifiers;
+use crate::picker;
+use crate::planner;
+use crate::pomodoro;
+use crate::review;
+use crate::server;
+use crate::triage;
+#[cfg(feature = "tray")]
+use crate::tray;
+use crate::ui::{format_deadline, format_due, get_confirmation, get_input, heading, parse_progress, print_details, print_formatted, print_table, print_todos, truncate};
+use crate::watch;
+use crate::xlsx;
+
+const DEFAULT_SYNC_PORT: u16 = 7373;
+
+pub fn print_usage() {
+    println!("Usage: todo_app [COMMAND]");
+    println!();
+    println!("Without a command the interactive menu is started. -y/--yes skips all");
+    println!("confirmations, with or without a command. --accessible prints plain, screen-");
+    println!("reader-friendly output: one line per todo, no tables or symbols.");
+    println!("--ephemeral works on an empty list kept in memory and saves nothing.");
+    println!("--date-format iso|short|relative|PATTERN shows dates that way, e.g. %d.%m.%Y.");
+    println!();
+    println!("IDS names one or more todos: IDs and ranges separated by commas or spaces");
+    println!("(3,5-7), `last` for the newest todo, or a filter: {}.", FILTERS.join(", "));
+    println!();
+    println!("Commands:");
+    println!("  init                Set up where and how the todos are kept, importing an existing list");
+    println!("  list [--all] [--location PLACE] [--context @CONTEXT] [--max-effort TIME] [--stale AGE] [--limit N] [--sort priority|date|updated] [--group-by tag|project|status|due-week] [--columns LIST | --format TEMPLATE] [--watch]");
+    println!("                      List todos, optionally only the first N, highest priority first");
+    println!("                      or soonest scheduled or due date first;");
+    println!("                      --all includes todos waiting for a later date;");
+    println!("                      --stale 30d keeps pending todos not updated in 30 days;");
+    println!("                      --group-by lists the todos in sections, each with its count;");
+    println!("                      --columns picks the columns, e.g. id,title:40,due;");
+    println!("                      --format prints each todo as e.g. \"{{{{id}}}} {{{{title}}}} [{{{{due}}}}]\";");
+    println!("                      --watch redraws the list whenever the data file changes");
+    println!("  show ID             Show every field of a todo and the todos linked to it");
+    println!("  log [-n N]          Show the last N changes (default 20)");
+    println!("  done-today [--date DATE] [--format text|markdown]");
+    println!("                      List what was completed today or on DATE, with times, e.g. for a standup");
+    println!("  diff OTHER          Show what changed going from this list to the file OTHER");
+    println!("  import json FILE [--strategy skip-duplicates|overwrite|merge] [--dry-run]");
+    println!("                      Add the todos of another data file to this list");
+    println!("  import todotxt FILE [--strategy skip-duplicates|overwrite|merge] [--dry-run]");
+    println!("                      Add the tasks of a todo.txt file, with priorities, projects, contexts and due dates");
+    println!("  import reminders|mstodo FILE [--dry-run]");
+    println!("                      Add the reminders of an Apple Reminders or Microsoft To Do export (JSON or CSV), lists as projects");
+    println!("  import jsonl FILE|- [--strategy skip-duplicates|overwrite|merge]");
+    println!("                      Add todos given one JSON object per line, from FILE or stdin");
+    println!("  import -            Add a todo exported as JSON, read from stdin");
+    println!("  export ID [--format json|markdown]");
+    println!("                      Print one todo for sharing (default: json)");
+    println!("  export jsonl        Print every todo as one JSON object per line");
+    #[cfg(feature = "sqlite")]
+    println!("  export sqlite FILE  Write the todos and their history to an SQLite database for SQL queries");
+    println!("  export xlsx FILE    Write a spreadsheet with a sheet per project: status, due date, priority and tags");
+    println!("  qr ID [--invert]    Show a todo as a QR code of its JSON export");
+    println!("  search TEXT         List todos whose title or description contains TEXT");
+    println!("  bulk-edit [TEXT]    Edit all todos (or those matching TEXT) in $EDITOR");
+    println!("  delete IDS          Delete todos, after confirmation unless -y is given");
+    println!("  done IDS [--note TEXT]");
+    println!("                      Mark a todo as completed (habits: kept today), with an optional note");
+    println!("  cancel IDS [--note REASON]");
+    println!("                      Close a todo without doing it");
+    println!("  habit ID [--off]    Turn a todo into a daily habit, or back");
+    println!("  habits [--weeks N]  Show a heatmap of kept habits (default 12 weeks)");
+    println!("  tags                List all tags with the number of todos using them");
+    println!("  count [IDS]         Print the number of todos, or of those IDS names (e.g. @overdue)");
+    println!("  summary [IDS]       Count the todos, or those IDS names, by status and by tag");
+    println!("  tag rename OLD NEW  Rename a tag on every todo");
+    println!("  tag merge FROM INTO Fold tag FROM into tag INTO");
+    println!("  goal add TITLE      Create a goal");
+    println!("  goal rm GOAL        Delete a goal, unlinking its todos");
+    println!("  goal link ID GOAL   Link todo ID to a goal");
+    println!("  goal unlink ID      Remove the goal from todo ID");
+    println!("  goals               Show every goal with its progress");
+    println!("  critical-path [PROJECT]");
+    println!("                      Schedule a project's todos by their dependencies and estimates, showing slack and the earliest finish");
+    println!("  projects            Show every project with a progress bar");
+    println!("  due ID DATE|--clear Set or clear the due date (2026-05-01, tomorrow, 3d, friday, ...)");
+    println!("  due ID [DATE] TIME ZONE  Pin the due date to a time in a time zone (friday 9am Berlin, 17:00 America/New_York)");
+    println!("  postpone ID WHEN    Move the due date by 1d, 1w or 3b business days (from today if overdue), or to next-monday or a date");
+    println!("  today ID            Make a todo due today");
+    println!("  repeat ID RRULE|--clear");
+    println!("                      Make a todo recur by an RFC 5545 rule, e.g. FREQ=MONTHLY;BYDAY=2TU");
+    println!("  schedule ID DATE|--clear");
+    println!("                      Set or clear the day work on a todo is planned to start");
+    println!("  wait ID DATE|--clear");
+    println!("                      Hide a todo from lists until DATE, or show it again");
+    println!("  touch IDS           Mark todos as looked at today without changing them");
+    println!("  digest --email ADDRESS | --stdout");
+    println!("                      Send or print a summary of todos due today and overdue;");
+    println!("                      only an unauthenticated relay works, as mail goes to the smtp");
+    println!("                      relay in plain text without STARTTLS or AUTH, so use a local one");
+    println!("  print [--day DATE] [--pdf] [--paper a4|letter] [FILE]");
+    println!("                      Lay out the day's agenda with checkboxes, as text or as a PDF for FILE.pdf");
+    println!("  check [--due-within OFFSET]");
+    println!("                      Exit 1 listing todos due within OFFSET (e.g. 1d, 2w; default today), else 0 silently");
+    println!("  notify due [--days N]");
+    println!("                      Send todos due within N days (default 1) to the webhooks and notifiers");
+    println!("  priority ID low|medium|high|--clear");
+    println!("                      Set or clear the priority of a todo");
+    println!("  set IDS FIELD VALUE");
+    println!("                      Change one field: title, description, due, priority, location, tags, tags+= or tags-=");
+    println!("  project ID NAME|--clear");
+    println!("                      Put a todo in a project, or take it out");
+    println!("  location ID PLACE|--clear");
+    println!("                      Say where a todo can be done, e.g. \"hardware store\"");
+    println!("  delegate ID PERSON [--follow-up DATE] | ID --clear");
+    println!("                      Hand a todo off to someone, or take it back");
+    println!("  follow-up ID DATE|--clear");
+    println!("                      Set or clear the day to check on a delegated todo");
+    println!("  waiting-for [PERSON]");
+    println!("                      List delegated todos by person, with follow-ups that are due");
+    println!("  comment ID [--reply N] TEXT");
+    println!("                      Comment on a todo, or reply to its comment N; `show` lists them");
+    println!("  progress ID PERCENT|--clear");
+    println!("                      Set or clear how far along a todo is, from 0 to 100%");
+    println!("  points ID N|--clear Set or clear the story points of a todo");
+    println!("  velocity [--weeks N]");
+    println!("                      Points completed per week (default 8 weeks)");
+    println!("  important ID [--off]");
+    println!("                      Flag a todo as important for the matrix, or unflag it");
+    println!("  status-line [--format TEMPLATE]");
+    println!("                      Print counts and the focus for tmux, starship or i3bar, e.g. ✔3 ⏳5 ⚠1");
+    println!("  matrix              Sort pending todos into do, schedule, delegate and drop");
+    println!("  pick [TAG...]       Pick a random todo to work on now, optionally with all of these tags");
+    println!("  plan [--new]        Show today's plan, or propose one to adjust and accept");
+    println!("  timeline [--weeks N]");
+    println!("                      Chart scheduled and due dates over the coming weeks");
+    println!("  estimate ID TIME|--clear");
+    println!("                      Set how long a todo should take (45m, 2h, 1h30m)");
+    println!("  track ID TIME       Log time spent on a todo");
+    println!("  focus [ID] [--pomodoro [TIME]]|--clear");
+    println!("                      Show or set the todo worked on now, optionally running a pomodoro (25m) on it");
+    println!("  holidays [--all]    Show the workdays and the holidays to come");
+    println!("  holidays import FILE|add DATE [NAME]|remove DATE|clear");
+    println!("                      Read holidays from an iCalendar file or lines of dates, or change them one by one");
+    println!("  estimates           Compare estimated and logged time per todo and tag");
+    println!("  fsck [--fix]        Check the data file for inconsistencies, repairing them with --fix");
+    println!("  doctor              Check the configuration, files, connections and time zone, suggesting fixes");
+    println!("  self-update [--check]");
+    println!("                      Install the latest release if it is newer, or with --check only say whether it is");
+    println!("  backup create FILE  Bundle the configuration, the todos and their history into a .tar, .tar.gz or .tar.zst file");
+    println!("  backup restore FILE [--force]");
+    println!("                      Set this directory up from a backup, replacing nothing unless --force is given");
+    println!("  recover             Salvage the readable todos of a damaged data file, keeping the original");
+    println!("  gc                  Archive old completed todos per the configured policy");
+    println!("  clone ID [TITLE]    Add a pending copy of a todo, optionally with a new title");
+    println!("  split ID            Turn a todo into a parent of subtasks, asking for their titles");
+    println!("  depend ID ON        Mark todo ID as waiting on todo ON");
+    println!("  undepend ID ON      Remove that dependency again");
+    println!("  relate ID OTHER     Note that two todos are related, without either waiting on the other");
+    println!("  unrelate ID OTHER   Remove that link again");
+    println!("  review [--days N]   Walk through pending todos untouched for N days (default 7)");
+    println!("  triage              Give inbox todos a project, priority or due date, one at a time");
+    println!("  serve --sync [--port N]");
+    println!("                      Run a sync server for your devices (default port {})", DEFAULT_SYNC_PORT);
+    println!("  serve token create --name NAME [--scopes pull,push,register,users,metrics] [--expires OFFSET]");
+    println!("                      Create a token the sync server asks for; also serve token list|revoke NAME");
+    println!("  sync [remote URL [--invite CODE] [--token TOKEN]]");
+    println!("                      Exchange changes with a sync server, first registering with URL");
+    println!("  sync users          List the users of the sync server (admins only)");
+    println!("  activity [-n N]     Show who changed what on the sync server lately (default 20)");
+    println!("  user add NAME --role admin|editor|viewer");
+    println!("                      On the sync server: add a user and print an invite code for a device;");
+    println!("                      a role covers the server's whole list, as each server keeps one");
+    println!("  user invite NAME    Print a new invite code for another device of a user");
+    println!("  user role NAME ROLE Change what a user may do");
+    println!("  user rm NAME        Remove a user, locking out their devices");
+    println!("  user list           Show every user with their role and devices");
+    println!("  keys                Show the keys of the interactive menu and of review");
+    println!("  graph [--format dot|mermaid]");
+    println!("                      Print the dependency graph (default: dot)");
+    #[cfg(feature = "generate")]
+    println!("  generate --count N  Add N synthetic todos for performance testing");
+    #[cfg(feature = "tray")]
+    println!("  tray                Show due and overdue counts in the system tray");
+}
+
+// From TODO_SYNC_PASSPHRASE, or asked for twice. None if the two answers
+// differ or nothing was entered.
+fn sync_passphrase() -> Option<String> {
+    if let Ok(passphrase) = env::var("TODO_SYNC_PASSPHRASE") {
+        return Some(passphrase).filter(|p| !p.is_empty());
+    }
+    println!("Passphrase to encrypt synced todos with (the same on every device):");
+    let passphrase = input::read_secret();
+    if passphrase.is_empty() {
+        println!("A passphrase is needed to sync.");
+        return None;
+    }
+    println!("Repeat the passphrase:");
+    if input::read_secret() != passphrase {
+        println!("The passphrases differ.");
+        return None;
+    }
+    Some(passphrase)
+}
+
+// How far the history has been sent to the sync server.
+fn history_len(filename: &str) -> u64 {
+    std::fs::metadata(history::history_filename(filename)).map_or(0, |meta| meta.len())
+}
+
+enum Picked {
+    // The command needs no ID, has one, or there is no terminal to pick on.
+    Unchanged,
+    With(Vec<String>),
+    Cancelled,
+}
+
+// For a command given no ID, such as `done` or `due friday`, lets the user
+// pick the todo by its title and puts its ID where it belongs.
+fn pick_missing_id(args: &[String], filename: &str, config: &Config) -> io::Result<Picked> {
+    if !lacks_ids(args, Local::now().date_naive()) || !picker::available(config) {
+        return Ok(Picked::Unchanged);
+    }
+    let todo_list = TodoList::load_from_file(filename)?;
+    if todo_list.todos().is_empty() {
+        return Ok(Picked::Unchanged);
+    }
+    Ok(match picker::pick(todo_list.todos(), config)? {
+        Some(id) => {
+            let mut with = args.to_vec();
+            with.insert(1, id.to_string());
+            Picked::With(with)
+        },
+        None => Picked::Cancelled,
+    })
+}
+
+pub fn run_command(args: &[String], filename: &str, config: &Config) -> io::Result<()> {
+    let with_id;
+    let args = match pick_missing_id(args, filename, config)? {
+        Picked::Unchanged => args,
+        Picked::With(args) => {
+            with_id = args;
+            &with_id[..]
+        },
+        Picked::Cancelled => {
+            println!("No todo picked.");
+            return Ok(());
+        },
+    };
+    match args[0].as_str() {
+        "list" => {
+            let mut limit = None;
+            let mut sort = None;
+            let mut watch = false;
+            let mut columns = config.columns.clone();
+            let mut template = None;
+            let mut all = false;
+            let mut location = None;
+            let mut stale = None;
+            let mut context = None;
+            let mut max_effort = None;
+            let mut group_by = None;
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--limit" => match rest.next().and_then(|n| n.parse::<usize>().ok()) {
+                        Some(n) => limit = Some(n),
+                        None => {
+                            println!("--limit expects a number.");
+                            return Ok(());
+                        }
+                    },
+                    "--sort" => match rest.next().map(String::as_str) {
+                        Some(key @ ("priority" | "date" | "updated")) => sort = Some(key),
+                        _ => {
+                            println!("--sort expects one of: priority, date, updated.");
+                            return Ok(());
+                        }
+                    },
+                    "--group-by" => match rest.next().and_then(|by| GroupBy::parse(by)) {
+                        Some(by) => group_by = Some(by),
+                        None => {
+                            println!("--group-by expects one of: {}.", GroupBy::NAMES.join(", "));
+                            return Ok(());
+                        }
+                    },
+                    "--watch" => watch = true,
+                    "--all" => all = true,
+                    "--location" => match rest.next() {
+                        Some(place) => location = Some(clean_line(place).to_lowercase()),
+                        None => {
+                            println!("--location expects a place.");
+                            return Ok(());
+                        }
+                    },
+                    // "computer" is taken as "@computer".
+                    "--context" => match rest.next().map(|name| clean_line(name).to_lowercase()) {
+                        Some(name) if !name.trim_start_matches('@').is_empty() => {
+                            context = Some(if name.starts_with('@') { name } else { format!("@{}", name) })
+                        },
+                        _ => {
+                            println!("--context expects a context such as @computer.");
+                            return Ok(());
+                        }
+                    },
+                    // Pending todos with no more than that left of their
+                    // estimate.
+                    "--max-effort" => match rest.next().and_then(|time| parse_minutes(time)) {
+                        Some(minutes) => max_effort = Some(minutes),
+                        None => {
+                            println!("--max-effort expects a time such as 15m or 1h.");
+                            return Ok(());
+                        }
+                    },
+                    // Pending todos last updated on or before the day that
+                    // long ago.
+                    "--stale" => match rest.next().and_then(|offset| offset_before(offset, Local::now().date_naive())) {
+                        Some(cutoff) => stale = Some(cutoff),
+                        None => {
+                            println!("--stale expects an age such as 30d, 2w or 3m.");
+                            return Ok(());
+                        }
+                    },
+                    "--format" => match rest.next().map(|t| Template::parse(t)) {
+                        Some(Ok(parsed)) => template = Some(parsed),
+                        Some(Err(e)) => {
+                            println!("{}", e);
+                            return Ok(());
+                        },
+                        None => {
+                            println!("--format expects a template such as \"{{{{id}}}} {{{{title}}}}\".");
+                            return Ok(());
+                        }
+                    },
+                    "--columns" => match rest.next().and_then(|specs| ColumnSpec::parse_list(specs)) {
+                        Some(specs) if !specs.is_empty() => columns = specs,
+                        _ => {
+                            let names: Vec<&str> = Column::ALL.iter().map(|column| column.name()).collect();
+                            println!("--columns expects a list such as id,title:40,due, using: {}.", names.join(", "));
+                            return Ok(());
+                        }
+                    },
+                    _ => {
+                        println!("Unknown option: {}", arg);
+                        return Ok(());
+                    }
+                }
+            }
+
+            let show_all = |todos: &[Todo]| match &template {
+                Some(template) => print_formatted(todos, config, template),
+                None => print_table(todos, config, &columns),
+            };
+            // One section per group, titled with the number of todos in it;
+            // a todo with several tags is counted under each.
+            let show = |todos: &[Todo]| {
+                let Some(by) = group_by else {
+                    show_all(todos);
+                    return;
+                };
+                if todos.is_empty() && template.is_none() {
+                    println!("No todos found.");
+                }
+                for (n, (title, group)) in groups::group(todos, by, Local::now().date_naive()).into_iter().enumerate() {
+                    if n > 0 && template.is_none() {
+                        println!();
+                    }
+                    let count = if group.len() == 1 { "1 todo".to_string() } else { format!("{} todos", group.len()) };
+                    heading(&format!("{} ({})", title, count), config.accessible);
+                    show_all(&group.into_iter().cloned().collect::<Vec<Todo>>());
+                }
+            };
+            let render = || -> io::Result<()> {
+                let today = Local::now().date_naive();
+                let matches = |todo: &Todo| {
+                    let here = match &location {
+                        Some(place) => todo.location.as_ref().is_some_and(|l| l.to_lowercase() == *place),
+                        None => true,
+                    };
+                    let in_context = match &context {
+                        Some(context) => todo.tags.iter().any(|tag| tag.is_context() && tag.to_lowercase() == *context),
+                        None => true,
+                    };
+                    here && in_context && stale.is_none_or(|cutoff| !todo.completed && todo.updated_at.date_naive() <= cutoff)
+                };
+                let fits = |todo: &Todo| max_effort.is_none_or(|minutes| !todo.completed && todo.estimate.is_some() && estimates::remaining(todo) <= minutes);
+                let at = |todo: &Todo| matches(todo) && fits(todo);
+                let keep = |todo: &Todo| (all || !todo.is_waiting(today)) && at(todo);
+                // Inherited priorities need the whole list.
+                if let (Some(n), None, false) = (limit, sort, config.inherit_priority) {
+                    show(&TodoList::load_page(filename, n, keep)?);
+                    return Ok(());
+                }
+                let todo_list = TodoList::load_from_file(filename)?;
+                let mut todos: Vec<Todo> = todo_list.todos().iter().filter(|todo| keep(todo)).cloned().collect();
+                let hidden = if all { 0 } else { todo_list.todos().iter().filter(|todo| at(todo) && todo.is_waiting(today)).count() };
+                let now = Local::now();
+                let inherited =
+                    if config.inherit_priority { inherited_priorities(todo_list.todos(), config.escalate_after_days, now) } else { HashMap::new() };
+                // Shown and sorted by as the todo's own.
+                for todo in &mut todos {
+                    if let Some(&(priority, _)) = inherited.get(&todo.id) {
+                        todo.priority = Some(priority);
+                    }
+                }
+                let priority = |todo: &Todo| Reverse(effective_priority(todo, config.escalate_after_days, now));
+                match sort {
+                    Some("priority") => todos.sort_by_key(priority),
+                    // Soonest scheduled or due date first, undated todos last.
+                    Some("date") => todos.sort_by_key(|todo| (todo.next_date().is_none(), todo.next_date(), priority(todo))),
+                    Some("updated") => todos.sort_by_key(|todo| Reverse(todo.updated_at)),
+                    _ => {}
+                }
+                todos.truncate(limit.unwrap_or(todos.len()));
+                show(&todos);
+                let raised: Vec<&Todo> = todos.iter().filter(|todo| inherited.contains_key(&todo.id)).collect();
+                if !raised.is_empty() && template.is_none() {
+                    println!();
+                }
+                for todo in raised.iter().filter(|_| template.is_none()) {
+                    let (priority, chain) = &inherited[&todo.id];
+                    let source = chain[chain.len() - 1];
+                    let title = todo_list.get_todo(source).map_or("", |todo| todo.title.as_str());
+                    let through: Vec<String> = chain[1..chain.len() - 1].iter().map(|id| format!("#{}", id)).collect();
+                    let through = if through.is_empty() { String::new() } else { format!(" through {}", through.join(", ")) };
+                    println!("#{} is {} priority as #{} {} waits on it{}.", todo.id, priority, source, title, through);
+                }
+                if hidden > 0 && template.is_none() {
+                    println!("\n{} waiting todos hidden; `list --all` shows them.", hidden);
+                }
+                // Whether those fit is anyone's guess.
+                let unestimated = match max_effort {
+                    Some(_) => todo_list.todos().iter().filter(|todo| matches(todo) && !todo.completed && todo.estimate.is_none()).count(),
+                    None => 0,
+                };
+                if unestimated > 0 && template.is_none() {
+                    println!("\n{} pending todos without an estimate left out; `estimate ID TIME` gives them one.", unestimated);
+                }
+                Ok(())
+            };
+            if watch {
+                watch::run(filename, render)?;
+            } else {
+                render()?;
+            }
+        },
+        "log" => {
+            let count = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(20),
+                (Some("-n"), Some(n)) => n.parse::<usize>().ok(),
+                _ => None,
+            };
+            let Some(count) = count else {
+                println!("Usage: log [-n N]");
+                return Ok(());
+            };
+            let events = history::load_events(&history::history_filename(filename))?;
+            if events.is_empty() {
+                println!("No changes recorded yet.");
+                return Ok(());
+            }
+            for event in &events[events.len().saturating_sub(count)..] {
+                let detail = event.detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default();
+                println!("{}  {:<10} #{} {}{}",
+                    event.at.format("%Y-%m-%d %H:%M"),
+                    event.kind.to_string(),
+                    event.todo_id,
+                    event.title,
+                    detail
+                );
+            }
+        },
+        "done-today" => {
+            let mut day = Some(Local::now().date_naive());
+            let mut markdown = false;
+            let mut options = args[1..].iter();
+            while let Some(option) = options.next() {
+                match (option.as_str(), options.next().map(String::as_str)) {
+                    ("--date", Some(value)) => day = day.and(parse_date(value, Local::now().date_naive())),
+                    ("--format", Some("markdown" | "md")) => markdown = true,
+                    ("--format", Some("text")) => markdown = false,
+                    _ => day = None,
+                }
+            }
+            let Some(day) = day else {
+                println!("Usage: done-today [--date DATE] [--format text|markdown]");
+                return Ok(());
+            };
+            let mut todos = TodoList::load_from_file(filename)?.todos().to_vec();
+            todos.extend(archive::load_archive(&archive::archive_filename(filename))?);
+            let events = history::load_events(&history::history_filename(filename))?;
+            let done = journal::done_on(&todos, &events, day);
+            if markdown {
+                print!("{}", journal::markdown(day, &done));
+            } else if done.is_empty() {
+                println!("Nothing completed on {}.", day);
+            } else {
+                for done in &done {
+                    let project = done.project.as_ref().map(|project| format!(" [{}]", project)).unwrap_or_default();
+                    let note = done.note.as_ref().map(|note| format!(" ({})", note)).unwrap_or_default();
+                    println!("{}  #{} {}{}{}", done.at.format("%H:%M"), done.id, done.title, project, note);
+                }
+            }
+        },
+        "diff" => {
+            let (Some(other), None) = (args.get(1), args.get(2)) else {
+                println!("Usage: diff OTHER");
+                return Ok(());
+            };
+            let current = TodoList::load_from_file(filename)?;
+            let other = TodoList::load_from_file(other)?;
+            let changes = diff::diff(&current, &other);
+            if changes.is_empty() {
+                println!("No differences.");
+                return Ok(());
+            }
+            let (mut added, mut removed, mut modified) = (0, 0, 0);
+            for change in &changes {
+                match change {
+                    TodoChange::Added(todo) => {
+                        added += 1;
+                        println!("+ #{} {}", todo.id, todo.title);
+                    },
+                    TodoChange::Removed(todo) => {
+                        removed += 1;
+                        println!("- #{} {}", todo.id, todo.title);
+                    },
+                    TodoChange::Modified { id, title, fields } => {
+                        modified += 1;
+                        println!("~ #{} {}", id, title);
+                        for (field, before, after) in fields {
+                            println!("    {}: {} -> {}", field, before, after);
+                        }
+                    },
+                }
+            }
+            println!("\n{} added, {} removed, {} modified.", added, removed, modified);
+        },
+        "import" if args.get(1).map(String::as_str) == Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            let incoming = match export::parse_snippet(&input) {
+                Ok(todos) => todos,
+                Err(e) => {
+                    println!("Could not read an exported todo from stdin: {}", e);
+                    return Ok(());
+                }
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let summary = import::import_todos(&mut todo_list, &incoming, ImportStrategy::SkipDuplicates);
+            todo_list.save_to_file(filename)?;
+            println!("Imported {} todos, {} already present.", summary.added, summary.skipped);
+        },
+        "export" if args.get(1).map(String::as_str) == Some("jsonl") => {
+            if args.len() > 2 {
+                println!("Usage: export jsonl");
+                return Ok(());
+            }
+            let mut out = BufWriter::new(io::stdout().lock());
+            let mut result = Ok(());
+            TodoList::for_each_in_file(filename, |todo| {
+                result = serde_json::to_writer(&mut out, &todo).map_err(io::Error::from).and_then(|()| writeln!(out));
+                result.is_ok()
+            })?;
+            // A reader such as `head` going away is not an error.
+            match result.and_then(|()| out.flush()) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {},
+                other => other?,
+            }
+        },
+        #[cfg(feature = "sqlite")]
+        "export" if args.get(1).map(String::as_str) == Some("sqlite") => {
+            let (Some(path), None) = (args.get(2), args.get(3)) else {
+                println!("Usage: export sqlite FILE");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let archived = archive::load_archive(&archive::archive_filename(filename))?;
+            let events = history::load_events(&history::history_filename(filename))?;
+            match export::export_sqlite(path, &todo_list, &archived, &events) {
+                Ok(count) => println!("Wrote {} todos and {} changes to {}.", count, events.len(), path),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => println!("Not replacing {}: it is not an SQLite database.", path),
+                Err(e) => return Err(e),
+            }
+        },
+        "export" if args.get(1).map(String::as_str) == Some("xlsx") => {
+            let (Some(path), None) = (args.get(2), args.get(3)) else {
+                println!("Usage: export xlsx FILE");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let sheets = xlsx::export(path, &todo_list, config)?;
+            println!("Wrote {} todos on {} sheets to {}.", todo_list.todos().len(), sheets, path);
+        },
+        "export" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let format = match (args.get(2).map(String::as_str), args.get(3)) {
+                (None, _) => Some(SnippetFormat::Json),
+                (Some("--format"), Some(name)) => SnippetFormat::parse(name),
+                _ => None,
+            };
+            let (Some(id), Some(format)) = (id, format) else {
+                println!("Usage: export ID [--format json|markdown]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            match todo_list.get_todo(id) {
+                Some(todo) => print!("{}", export::todo_snippet(todo, format)?),
+                None => println!("Todo with ID {} not found.", id),
+            }
+        },
+        "qr" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let invert = match args.get(2).map(String::as_str) {
+                None => Some(false),
+                Some("--invert") => Some(true),
+                _ => None,
+            };
+            let (Some(id), Some(invert)) = (id, invert) else {
+                println!("Usage: qr ID [--invert]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let Some(todo) = todo_list.get_todo(id) else {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            };
+            let code = match QrCode::new(export::todo_compact_json(todo)?) {
+                Ok(code) => code,
+                Err(e) => {
+                    println!("Todo {} cannot be shown as a QR code: {}", id, e);
+                    return Ok(());
+                }
+            };
+            // Drawn light-on-dark by default, which scans best on the usual
+            // dark terminal background; --invert suits light themes.
+            let (dark, light) = if invert { (Dense1x2::Dark, Dense1x2::Light) } else { (Dense1x2::Light, Dense1x2::Dark) };
+            println!("{}", code.render::<Dense1x2>().dark_color(dark).light_color(light).build());
+        },
+        "import" if args.get(1).map(String::as_str) == Some("jsonl") => {
+            let strategy = match (args.get(3).map(String::as_str), args.get(4), args.get(5)) {
+                (None, _, _) => Some(ImportStrategy::SkipDuplicates),
+                (Some("--strategy"), Some(name), None) => ImportStrategy::parse(name),
+                _ => None,
+            };
+            let (Some(source), Some(strategy)) = (args.get(2), strategy) else {
+                println!("Usage: import jsonl FILE|- [--strategy skip-duplicates|overwrite|merge]");
+                return Ok(());
+            };
+            let reader: Box<dyn BufRead> = if source == "-" {
+                Box::new(io::stdin().lock())
+            } else {
+                Box::new(BufReader::new(File::open(source)?))
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let mut importer = Importer::new(&mut todo_list, strategy);
+            // Ctrl-C stops reading; what was read so far is still imported.
+            let _winding = interrupt::Winding::start();
+            for (n, line) in reader.lines().enumerate() {
+                if interrupt::requested() {
+                    println!("Interrupted before line {}.", n + 1);
+                    break;
+                }
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Todo>(&line) {
+                    Ok(todo) => importer.push(&todo),
+                    Err(e) => {
+                        // Nothing has been saved yet, so the list stays as it was.
+                        println!("Line {} is not a valid todo: {}. Nothing was imported.", n + 1, e);
+                        return Ok(());
+                    }
+                }
+            }
+            let summary = importer.finish();
+            todo_list.save_to_file(filename)?;
+            println!("Imported: {} added, {} updated, {} skipped.", summary.added, summary.updated, summary.skipped);
+        },
+        "import" => {
+            let mut strategy = Some(ImportStrategy::SkipDuplicates);
+            let mut dry_run = false;
+            let mut options = args.iter().skip(3);
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--strategy" => strategy = options.next().and_then(|name| ImportStrategy::parse(name)),
+                    "--dry-run" => dry_run = true,
+                    _ => strategy = None,
+                }
+            }
+            let (Some(format @ ("json" | "todotxt" | "reminders" | "mstodo")), Some(source), Some(strategy)) = (args.get(1).map(String::as_str), args.get(2), strategy) else {
+                println!("Usage: import json|todotxt|reminders|mstodo FILE [--strategy skip-duplicates|overwrite|merge] [--dry-run]");
+                return Ok(());
+            };
+            let incoming = match format {
+                "json" => TodoList::load_from_file(source)?,
+                "todotxt" => match import::parse_todo_txt(&std::fs::read_to_string(source)?) {
+                    Ok(incoming) => incoming,
+                    Err((line, e)) => {
+                        println!("Line {} cannot be imported: {} Nothing was imported.", line, e);
+                        return Ok(());
+                    },
+                },
+                _ => match import::parse_app_export(&std::fs::read_to_string(source)?) {
+                    Ok(incoming) => incoming,
+                    Err(e) => {
+                        println!("{} Nothing was imported.", e);
+                        return Ok(());
+                    },
+                },
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let existing: Vec<usize> = todo_list.todos().iter().map(|todo| todo.id).collect();
+            let summary = import::import_todos(&mut todo_list, incoming.todos(), strategy);
+            if dry_run {
+                // The list is imported into but not saved.
+                for todo in todo_list.todos().iter().filter(|todo| !existing.contains(&todo.id)) {
+                    print_import_preview(todo);
+                }
+                println!(
+                    "Would import from {}: {} added, {} updated, {} skipped. Nothing was changed; run it again without --dry-run to import.",
+                    source, summary.added, summary.updated, summary.skipped
+                );
+                return Ok(());
+            }
+            todo_list.save_to_file(filename)?;
+            println!("Imported from {}: {} added, {} updated, {} skipped.", source, summary.added, summary.updated, summary.skipped);
+        },
+        "search" => {
+            if args.len() < 2 {
+                println!("search expects the text to look for.");
+                return Ok(());
+            }
+            let todo_list = TodoList::load_from_file(filename)?;
+            let matches: Vec<Todo> = todo_list.search(&args[1..].join(" ")).into_iter().cloned().collect();
+            print_todos(&matches, config);
+        },
+        #[cfg(feature = "generate")]
+        "generate" => {
+            let count = match (args.get(1).map(String::as_str), args.get(2)) {
+                (Some("--count"), Some(n)) => n.parse::<usize>().ok(),
+                _ => None,
+            };
+            let Some(count) = count else {
+                println!("Usage: generate --count N");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.generate(count);
+            todo_list.save_to_file(filename)?;
+            println!("Generated {} todos.", count);
+        },
+        #[cfg(feature = "tray")]
+        "tray" => tray::run(filename, config.limits)?,
+        "bulk-edit" => {
+            let query = args[1..].join(" ");
+            let query = if query.is_empty() { None } else { Some(query.as_str()) };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.set_limits(config.limits);
+            todo_list.set_days_off(Calendar::days_off(config, filename)?);
+            if bulk_edit::run(&mut todo_list, query, config.confirm.bulk_delete)? {
+                todo_list.save_to_file(filename)?;
+            }
+        },
+        "delete" => {
+            if args.len() < 2 {
+                println!("Usage: delete IDS");
+                return Ok(());
+            }
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(ids) = ids_or_explain(&args[1..], &todo_list) else {
+                return Ok(());
+            };
+            let mut deleted = 0;
+            for id in ids {
+                let Some(todo) = todo_list.get_todo(id) else {
+                    println!("Todo with ID {} not found.", id);
+                    continue;
+                };
+                let prompt = format!("Delete todo {} \"{}\"?", id, todo.title);
+                if config.confirm.delete && !get_confirmation(&prompt) {
+                    println!("Kept todo {}.", id);
+                    continue;
+                }
+                todo_list.delete_todo(id);
+                println!("Todo {} deleted.", id);
+                deleted += 1;
+            }
+            if deleted > 0 {
+                todo_list.save_to_file(filename)?;
+            }
+        },
+        "done" => {
+            let end = args.iter().position(|a| a == "--note").unwrap_or(args.len());
+            let (true, Some(note)) = (end > 1, parse_note(&args[end..])) else {
+                println!("Usage: done IDS [--note TEXT]");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.set_days_off(Calendar::days_off(config, filename)?);
+            let Some(ids) = ids_or_explain(&args[1..end], &todo_list) else {
+                return Ok(());
+            };
+            let mut done = Vec::new();
+            for id in ids {
+                // Already done counts as success, but is not news.
+                let was_done = todo_list.get_todo(id).is_some_and(|todo| todo.completed);
+                if todo_list.complete_with_note(id, note.clone()) {
+                    println!("Todo {} marked as done.", id);
+                    print_next_occurrence(&todo_list, id);
+                    if !was_done {
+                        done.push(id);
+                    }
+                } else {
+                    println!("Todo with ID {} not found.", id);
+                }
+            }
+            if !done.is_empty() {
+                todo_list.save_to_file(filename)?;
+                for id in done {
+                    notify_completed(&todo_list, id, config);
+                }
+            }
+        },
+        "cancel" => {
+            let end = args.iter().position(|a| a == "--note").unwrap_or(args.len());
+            let (true, Some(note)) = (end > 1, parse_note(&args[end..])) else {
+                println!("Usage: cancel IDS [--note REASON]");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(ids) = ids_or_explain(&args[1..end], &todo_list) else {
+                return Ok(());
+            };
+            let mut cancelled = 0;
+            for id in ids {
+                if todo_list.cancel_todo(id, note.clone()) {
+                    println!("Todo {} cancelled.", id);
+                    cancelled += 1;
+                } else {
+                    println!("Todo with ID {} not found.", id);
+                }
+            }
+            if cancelled > 0 {
+                todo_list.save_to_file(filename)?;
+            }
+        },
+        "habit" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let off = match args.get(2).map(String::as_str) {
+                None => Some(false),
+                Some("--off") => Some(true),
+                _ => None,
+            };
+            let (Some(id), Some(off)) = (id, off) else {
+                println!("Usage: habit ID [--off]");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_habit(id, !off) {
+                if off {
+                    println!("Todo {} is no longer a habit.", id);
+                } else {
+                    println!("Todo {} is now a daily habit.", id);
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "habits" => {
+            let weeks = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(12),
+                (Some("--weeks"), Some(n)) => n.parse::<usize>().ok().filter(|&n| n > 0),
+                _ => None,
+            };
+            let Some(weeks) = weeks else {
+                println!("Usage: habits [--weeks N]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let today = Local::now().date_naive();
+            let mut any = false;
+            for todo in todo_list.todos().iter().filter(|t| t.habit) {
+                any = true;
+                println!("\n#{} {} (current streak: {} days)", todo.id, todo.title, habits::current_streak(todo, today));
+                if config.accessible {
+                    for (monday, kept, days) in habits::weekly_counts(todo, today, weeks) {
+                        println!("Week of {}: kept on {} of {} days.", monday, kept, days);
+                    }
+                } else {
+                    print!("{}", habits::heatmap(todo, today, weeks));
+                }
+            }
+            if !any {
+                println!("No habits yet. Turn a todo into one with `habit ID`.");
+            }
+        },
+        "tags" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let counts = todo_list.tag_counts();
+            if counts.is_empty() {
+                println!("No tags in use.");
+                return Ok(());
+            }
+            println!("{:<30} {:<6}", "TAG", "TODOS");
+            println!("{}", "-".repeat(37));
+            for (tag, count) in counts {
+                println!("{:<30} {:<6}", truncate(&tag, 27), count);
+            }
+        },
+        // Just the number, for scripts.
+        "count" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let count = if args.len() == 1 {
+                todo_list.todos().len()
+            } else {
+                match parse_ids(&args[1..].join(" "), &todo_list, Local::now().date_naive()) {
+                    Ok(ids) => ids.into_iter().filter(|&id| todo_list.get_todo(id).is_some()).count(),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        process::exit(2);
+                    },
+                }
+            };
+            println!("{}", count);
+        },
+        "summary" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let todos: Vec<Todo> = if args.len() == 1 {
+                todo_list.todos().to_vec()
+            } else {
+                let Some(ids) = ids_or_explain(&args[1..], &todo_list) else {
+                    return Ok(());
+                };
+                ids.into_iter().filter_map(|id| todo_list.get_todo(id).cloned()).collect()
+            };
+            if todos.is_empty() {
+                println!("No todos found.");
+                return Ok(());
+            }
+            let today = Local::now().date_naive();
+            for (title, by) in [("By status", GroupBy::Status), ("By tag", GroupBy::Tag)] {
+                heading(title, config.accessible);
+                for (name, group) in groups::group(&todos, by, today) {
+                    println!("{:<30} {}", truncate(&name, 27), group.len());
+                }
+                println!();
+            }
+            // The pending todos put off most often.
+            let mut postponed: Vec<&Todo> = todos.iter().filter(|todo| todo.postponed > 0 && !todo.completed).collect();
+            if !postponed.is_empty() {
+                postponed.sort_by_key(|todo| (std::cmp::Reverse(todo.postponed), todo.id));
+                heading("Most postponed", config.accessible);
+                for todo in postponed.iter().take(5) {
+                    println!("{:<30} {}", truncate(&format!("#{} {}", todo.id, todo.title), 27), todo.postponed);
+                }
+                println!();
+            }
+            println!("{:<30} {}", "Total", todos.len());
+        },
+        "tag" => return run_tag_command(&args[1..], filename),
+        "goal" => return run_goal_command(&args[1..], filename),
+        "goals" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.goals().is_empty() {
+                println!("No goals yet. Create one with `goal add TITLE`.");
+                return Ok(());
+            }
+            println!("{:<5} {:<40} {:<15}", "ID", "GOAL", "PROGRESS");
+            println!("{}", "-".repeat(62));
+            for goal in todo_list.goals() {
+                let (done, total) = todo_list.goal_progress(goal.id);
+                let percent = (done * 100).checked_div(total).unwrap_or(0);
+                println!("{:<5} {:<40} {:<15}",
+                    goal.id,
+                    truncate(&goal.title, 37),
+                    format!("{}/{} ({}%)", done, total, percent)
+                );
+            }
+        },
+        "projects" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let progress = todo_list.project_progress();
+            if progress.is_empty() {
+                println!("No projects yet. Put a todo in one with `project ID NAME`.");
+                return Ok(());
+            }
+            if !config.accessible {
+                println!("{:<30} {:<12} DONE", "PROJECT", "PROGRESS");
+                println!("{}", "-".repeat(50));
+            }
+            for (project, (done, total)) in progress {
+                let percent = (done * 100).checked_div(total).unwrap_or(0);
+                if config.accessible {
+                    println!("{}: {} of {} done ({}%).", project, done, total, percent);
+                } else {
+                    println!("{:<30} {:<12} {}/{}", truncate(project, 27), progress_bar(done, total, 10), done, total);
+                }
+            }
+        },
+        "critical-path" => {
+            let project = (args.len() > 1).then(|| clean_line(&args[1..].join(" ")));
+            let todo_list = TodoList::load_from_file(filename)?;
+            let todos: Vec<&Todo> = todo_list
+                .todos()
+                .iter()
+                .filter(|todo| !todo.completed && !todo.habit)
+                .filter(|todo| project.as_ref().is_none_or(|name| todo.project.as_ref().is_some_and(|p| p.to_lowercase() == name.to_lowercase())))
+                .collect();
+            if todos.is_empty() {
+                match &project {
+                    Some(name) => println!("No pending todos in project {}.", name),
+                    None => println!("No pending todos."),
+                }
+                return Ok(());
+            }
+            let slots = graph::schedule(&todos, plan::minutes);
+            let length = slots.iter().map(|slot| slot.finish).max().unwrap_or(0);
+            if !config.accessible {
+                println!("{:<5} {:<30} {:<8} {:<8} {:<8} SLACK", "ID", "TITLE", "WORK", "START", "FINISH");
+                println!("{}", "-".repeat(72));
+            }
+            for slot in &slots {
+                let Some(todo) = todo_list.get_todo(slot.id) else {
+                    continue;
+                };
+                let slack = if slot.slack == 0 { "none, critical".to_string() } else { format_minutes(slot.slack) };
+                if config.accessible {
+                    println!("Todo {}. {}. Work: {}. Starts after {}, done after {}. Slack: {}.",
+                        todo.id, todo.title, format_minutes(slot.minutes), format_minutes(slot.start), format_minutes(slot.finish), slack);
+                } else {
+                    println!("{:<5} {:<30} {:<8} {:<8} {:<8} {}",
+                        todo.id, truncate(&todo.title, 27), format_minutes(slot.minutes), format_minutes(slot.start), format_minutes(slot.finish), slack);
+                }
+            }
+            println!();
+            // Back from the todo finishing last, through the todos it waits on
+            // that hold it up; of several equally long chains the first.
+            let mut path = Vec::new();
+            let mut current = slots.iter().filter(|slot| slot.finish == length).min_by_key(|slot| slot.id);
+            while let Some(slot) = current {
+                path.push(format!("#{}", slot.id));
+                let depends_on = todo_list.get_todo(slot.id).map_or(&[][..], |todo| &todo.depends_on[..]);
+                current = slots.iter().filter(|dep| depends_on.contains(&dep.id) && dep.slack == 0 && dep.finish == slot.start).min_by_key(|dep| dep.id);
+            }
+            path.reverse();
+            println!("Critical path: {} ({} of work).", path.join(" -> "), format_minutes(length));
+            // The critical path is worked through at the daily capacity, one
+            // day after the other from today.
+            let per_day = config.daily_capacity().filter(|&minutes| minutes > 0).unwrap_or(DEFAULT_CAPACITY_MINUTES);
+            let days = length.div_ceil(per_day).max(1) - 1;
+            let finish = Local::now().date_naive() + Duration::days(i64::from(days));
+            println!("Earliest completion: {}, at {} of work a day.", finish, format_minutes(per_day));
+            let unestimated: Vec<String> = todos.iter().filter(|todo| todo.estimate.is_none()).map(|todo| format!("#{}", todo.id)).collect();
+            if !unestimated.is_empty() {
+                println!("Without an estimate, counted as {} each: {}.", format_minutes(plan::UNESTIMATED_MINUTES), unestimated.join(", "));
+            }
+        },
+        "set" => return run_set_command(&args[1..], filename, config),
+        "priority" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let priority = match args.get(2).map(String::as_str) {
+                Some("--clear") => Some(None),
+                Some(name) => Priority::parse(name).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(priority)) = (id, priority) else {
+                println!("Usage: priority ID low|medium|high|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_priority(id, priority) {
+                match priority {
+                    Some(priority) => println!("Todo {} now has {} priority.", id, priority),
+                    None => println!("Priority cleared for todo {}.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "due" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let due = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(Due::Day(None)),
+                Some(_) if id.is_some() => match parse_due(&args[2..].join(" ")) {
+                    Some(due) => Some(due),
+                    None => return Ok(()),
+                },
+                _ => None,
+            };
+            let (Some(id), Some(due)) = (id, due) else {
+                println!("Usage: due ID DATE|--clear, or due ID [DATE] TIME ZONE such as `due 4 friday 9am Berlin`");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let found = match &due {
+                Due::Day(day) => todo_list.set_due(id, *day),
+                Due::Deadline(deadline) => todo_list.set_deadline(id, deadline.clone()),
+            };
+            if found {
+                match due {
+                    Due::Day(Some(date)) => println!("Todo {} is due on {}.", id, date),
+                    Due::Day(None) => println!("Due date cleared for todo {}.", id),
+                    Due::Deadline(deadline) => println!("Todo {} is due on {} at {}.", id, deadline.at.date_naive(), format_deadline(&deadline, config)),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "postpone" | "today" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            // "3 business days" may come as several words.
+            let when = match (args[0].as_str(), args.get(2..).unwrap_or_default()) {
+                ("today", []) => Some("today".to_string()),
+                ("postpone", when) if !when.is_empty() => Some(when.join(" ")),
+                _ => None,
+            };
+            let (Some(id), Some(when)) = (id, when) else {
+                match args[0].as_str() {
+                    "today" => println!("Usage: today ID"),
+                    _ => println!("Usage: postpone ID 1d|1w|3b|next-monday|DATE"),
+                }
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(todo) = todo_list.get_todo(id) else {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            };
+            let calendar = Calendar::load(config, filename)?;
+            let Some(due) = postpone_date(&when, todo.due, Local::now().date_naive(), &calendar) else {
+                println!("Could not understand \"{}\"; try 1d, 1w, 3b (business days), next-monday or a date such as 2026-05-01.", when);
+                return Ok(());
+            };
+            todo_list.postpone(id, due);
+            println!("Todo {} is due on {}.", id, due);
+            todo_list.save_to_file(filename)?;
+        },
+        "repeat" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let (Some(id), Some(text)) = (id, args.get(2)) else {
+                println!("Usage: repeat ID RRULE|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(todo) = todo_list.get_todo(id) else {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            };
+            if todo.habit {
+                println!("Todo {} is a habit, which already repeats every day.", id);
+                return Ok(());
+            }
+            let recurrence = if text == "--clear" {
+                None
+            } else {
+                let rule = match Rule::parse(&args[2..].join(" ")) {
+                    Ok(rule) => rule,
+                    Err(e) => {
+                        println!("{}", e);
+                        return Ok(());
+                    }
+                };
+                // The series starts at the current due date, or today.
+                let start = todo.due.unwrap_or_else(|| Local::now().date_naive());
+                if rule.occurrences(start).next().is_none() {
+                    println!("The rule has no occurrences from {} on.", start);
+                    return Ok(());
+                }
+                Some(Recurrence { rule, start })
+            };
+            todo_list.set_days_off(Calendar::days_off(config, filename)?);
+            todo_list.set_recurrence(id, recurrence.clone());
+            match (recurrence, todo_list.get_todo(id).and_then(|todo| todo.due)) {
+                (Some(recurrence), Some(due)) => println!("Todo {} repeats {}; next due {}.", id, recurrence.rule, due),
+                _ => println!("Todo {} no longer repeats.", id),
+            }
+            todo_list.save_to_file(filename)?;
+        },
+        "schedule" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let day = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(None),
+                Some(_) => parse_date(&args[2..].join(" "), Local::now().date_naive()).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(day)) = (id, day) else {
+                println!("Usage: schedule ID DATE|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_scheduled(id, day) {
+                match day {
+                    Some(date) => println!("Todo {} is scheduled for {}.", id, date),
+                    None => println!("Todo {} is no longer scheduled.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "touch" => {
+            if args.len() < 2 {
+                println!("Usage: touch IDS");
+                return Ok(());
+            }
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(ids) = ids_or_explain(&args[1..], &todo_list) else {
+                return Ok(());
+            };
+            let mut touched = 0;
+            for id in ids {
+                if todo_list.touch_todo(id) {
+                    println!("Touched todo {}.", id);
+                    touched += 1;
+                } else {
+                    println!("Todo with ID {} not found.", id);
+                }
+            }
+            if touched > 0 {
+                todo_list.save_to_file(filename)?;
+            }
+        },
+        "wait" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let day = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(None),
+                Some(_) => parse_date(&args[2..].join(" "), Local::now().date_naive()).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(day)) = (id, day) else {
+                println!("Usage: wait ID DATE|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_wait_until(id, day) {
+                match day {
+                    Some(date) => println!("Todo {} is hidden until {}.", id, date),
+                    None => println!("Todo {} is no longer waiting.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "digest" => {
+            let to = match (args.get(1).map(String::as_str), args.get(2), args.get(3)) {
+                (Some("--stdout"), None, _) => Some(None),
+                (Some("--email"), Some(address), None) => Some(Some(address)),
+                _ => None,
+            };
+            let Some(to) = to else {
+                println!("Usage: digest --email ADDRESS | --stdout");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let mut digest = Digest::collect(&todo_list, Local::now().date_naive());
+            if let Some(capacity) = config.daily_capacity() {
+                digest.check_capacity(&todo_list, capacity);
+            }
+            match to {
+                None => print!("Subject: {}\n\n{}", digest.subject(), digest.body()),
+                Some(address) => {
+                    let Some(smtp) = &config.smtp else {
+                        println!("No SMTP relay configured. Set smtp in {}.", CONFIG_FILENAME);
+                        return Ok(());
+                    };
+                    // Nothing to report is not worth an email.
+                    if !digest.is_empty() {
+                        mail::send(smtp, address, &digest.subject(), &digest.body())?;
+                    }
+                },
+            }
+        },
+        "print" => {
+            let today = Local::now().date_naive();
+            let (mut day, mut pdf, mut size, mut target) = (Some(today), false, PaperSize::A4, None);
+            let mut valid = true;
+            let mut options = args.iter().skip(1);
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--day" => day = options.next().and_then(|value| parse_date(value, today)),
+                    "--pdf" => pdf = true,
+                    "--paper" => match options.next().and_then(|name| PaperSize::parse(name)) {
+                        Some(paper) => size = paper,
+                        None => valid = false,
+                    },
+                    _ if target.is_none() && !option.starts_with("--") => target = Some(option.as_str()),
+                    _ => valid = false,
+                }
+            }
+            let Some(day) = day.filter(|_| valid) else {
+                println!("Usage: print [--day DATE] [--pdf] [--paper a4|letter] [FILE]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let page = paper::agenda(&todo_list, day);
+            let target = target.filter(|&target| target != "-");
+            let pdf = pdf || target.is_some_and(|target| target.to_lowercase().ends_with(".pdf"));
+            let contents = if pdf { page.pdf(size) } else { page.text(72).into_bytes() };
+            match target {
+                Some(target) => {
+                    std::fs::write(target, contents)?;
+                    println!("Wrote the agenda for {} to {}.", day, target);
+                },
+                None => io::stdout().write_all(&contents)?,
+            }
+        },
+        "check" => {
+            let today = Local::now().date_naive();
+            let last_day = match (args.get(1).map(String::as_str), args.get(2), args.get(3)) {
+                (None, _, _) => Some(today),
+                (Some("--due-within"), Some(offset), None) => apply_offset(offset, today),
+                _ => None,
+            };
+            let Some(last_day) = last_day else {
+                eprintln!("Usage: check [--due-within OFFSET]");
+                process::exit(2);
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let due = digest::due_by(&todo_list, last_day);
+            if due.is_empty() {
+                return Ok(());
+            }
+            println!("{} todos due by {}:", due.len(), last_day);
+            for todo in &due {
+                let due = todo.due.unwrap_or(today);
+                let when = if due < today { format!("overdue since {}", due) } else { format!("due {}", due) };
+                println!("  #{} {} ({})", todo.id, todo.title, when);
+            }
+            process::exit(1);
+        },
+        "notify" => {
+            let days = match (args.get(1).map(String::as_str), args.get(2).map(String::as_str), args.get(3)) {
+                (Some("due"), None, _) => Some(1),
+                (Some("due"), Some("--days"), Some(n)) => n.parse::<i64>().ok(),
+                _ => None,
+            };
+            let Some(days) = days else {
+                println!("Usage: notify due [--days N]");
+                return Ok(());
+            };
+            if config.webhooks.is_empty() && notifiers::backends(config).is_empty() {
+                println!("No webhooks or notifiers configured. Add them under webhooks or notifiers in {}, or set TODO_NOTIFIERS.", CONFIG_FILENAME);
+                return Ok(());
+            }
+            let todo_list = TodoList::load_from_file(filename)?;
+            let today = Local::now().date_naive();
+            let due_soon: Vec<&Todo> = todo_list
+                .todos()
+                .iter()
+                .filter(|t| !t.completed && !t.habit)
+                .filter(|t| t.due.is_some_and(|due| due >= today && (due - today).num_days() <= days))
+                .collect();
+            if due_soon.is_empty() {
+                println!("Nothing is due in the next {} days.", days);
+                return Ok(());
+            }
+            let count = due_soon.len();
+            let (sent, total) = send_notification(config, &Notification::DueSoon(due_soon));
+            println!("Sent {} due-soon todos to {} of {} notifiers.", count, sent, total);
+        },
+        "project" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let project = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(None),
+                Some(_) => Some(clean_line(&args[2..].join(" "))).filter(|p| !p.is_empty()).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(project)) = (id, project) else {
+                println!("Usage: project ID NAME|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_project(id, project.clone()) {
+                match project {
+                    Some(name) => println!("Todo {} is now part of project {}.", id, name),
+                    None => println!("Todo {} is no longer part of a project.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "location" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let location = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(None),
+                Some(_) => Some(clean_line(&args[2..].join(" "))).filter(|l| !l.is_empty()).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(location)) = (id, location) else {
+                println!("Usage: location ID PLACE|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_location(id, location.clone()) {
+                match location {
+                    Some(place) => println!("Todo {} can be done at {}.", id, place),
+                    None => println!("Location cleared for todo {}.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "delegate" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let today = Local::now().date_naive();
+            let mut words: Vec<&str> = args.iter().skip(2).map(String::as_str).collect();
+            let follow_up = match words.iter().position(|&word| word == "--follow-up") {
+                Some(at) => parse_date(&words.split_off(at)[1..].join(" "), today).map(Some),
+                None => Some(None),
+            };
+            let person = match words.as_slice() {
+                ["--clear"] => Some(None),
+                [] => None,
+                words => Some(clean_line(&words.join(" "))).filter(|p| !p.is_empty()).map(Some),
+            };
+            let (Some(id), Some(person), Some(follow_up)) = (id, person, follow_up) else {
+                println!("Usage: delegate ID PERSON [--follow-up DATE] | delegate ID --clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_delegated_to(id, person.clone(), today) {
+                if follow_up.is_some() {
+                    todo_list.set_follow_up(id, follow_up);
+                }
+                match (person, follow_up) {
+                    (Some(person), Some(day)) => println!("Todo {} is delegated to {}; follow up on {}.", id, person, day),
+                    (Some(person), None) => println!("Todo {} is delegated to {}.", id, person),
+                    (None, _) => println!("Todo {} is no longer delegated.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "follow-up" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let day = match args.get(2).map(String::as_str) {
+                Some("--clear") if args.len() == 3 => Some(None),
+                Some(_) => parse_date(&args[2..].join(" "), Local::now().date_naive()).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(day)) = (id, day) else {
+                println!("Usage: follow-up ID DATE|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            match todo_list.get_todo(id) {
+                None => println!("Todo with ID {} not found.", id),
+                Some(todo) if todo.delegated_to.is_none() && day.is_some() => {
+                    println!("Todo {} is not delegated; use delegate ID PERSON first.", id);
+                },
+                Some(_) => {
+                    todo_list.set_follow_up(id, day);
+                    match day {
+                        Some(date) => println!("Follow up on todo {} on {}.", id, date),
+                        None => println!("Follow-up cleared for todo {}.", id),
+                    }
+                    todo_list.save_to_file(filename)?;
+                },
+            }
+        },
+        "comment" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let (reply_to, words) = match args.get(2).map(String::as_str) {
+                Some("--reply") => (args.get(3).map(|n| n.parse::<usize>().ok()), args.get(4..).unwrap_or_default()),
+                _ => (Some(None), args.get(2..).unwrap_or_default()),
+            };
+            let text = clean_line(&words.join(" "));
+            let (Some(id), Some(reply_to), false) = (id, reply_to, text.is_empty()) else {
+                println!("Usage: comment ID [--reply N] TEXT");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let Some(todo) = todo_list.get_todo(id) else {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            };
+            if let Some(n) = reply_to.filter(|&n| n == 0 || n > todo.comments.len()) {
+                println!("Todo {} has no comment {}.", id, n);
+                return Ok(());
+            }
+            if let Some(n) = todo_list.add_comment(id, config.author(), text, reply_to) {
+                println!("Added comment {} to todo {}.", n, id);
+                todo_list.save_to_file(filename)?;
+            }
+        },
+        "waiting-for" => {
+            let person = Some(clean_line(&args[1..].join(" ")).to_lowercase()).filter(|p| !p.is_empty());
+            let todo_list = TodoList::load_from_file(filename)?;
+            print_waiting_for(&todo_list, person.as_deref(), Local::now().date_naive());
+        },
+        "progress" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let progress = match args.get(2).map(String::as_str) {
+                Some("--clear") => Some(None),
+                Some(p) => parse_progress(p).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(progress)) = (id, progress) else {
+                println!("Usage: progress ID PERCENT|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_progress(id, progress) {
+                match progress {
+                    Some(p) => println!("Todo {} is {}% done.", id, p),
+                    None => println!("Progress cleared for todo {}.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "points" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let points = match args.get(2).map(String::as_str) {
+                Some("--clear") => Some(None),
+                Some(n) => n.parse::<u32>().ok().map(Some),
+                None => None,
+            };
+            let (Some(id), Some(points)) = (id, points) else {
+                println!("Usage: points ID N|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_points(id, points) {
+                match points {
+                    Some(n) => println!("Todo {} is worth {} points.", id, n),
+                    None => println!("Points cleared for todo {}.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "important" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let off = match args.get(2).map(String::as_str) {
+                None => Some(false),
+                Some("--off") => Some(true),
+                _ => None,
+            };
+            let (Some(id), Some(off)) = (id, off) else {
+                println!("Usage: important ID [--off]");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_important(id, !off) {
+                if off {
+                    println!("Todo {} is no longer flagged as important.", id);
+                } else {
+                    println!("Todo {} is flagged as important.", id);
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "status-line" => {
+            let template = match (args.get(1).map(String::as_str), args.get(2), args.get(3)) {
+                (None, _, _) => Some(config.status_line.as_str()),
+                (Some("--format"), Some(template), None) => Some(template.as_str()),
+                _ => None,
+            };
+            let Some(template) = template else {
+                println!("Usage: status-line [--format TEMPLATE]");
+                return Ok(());
+            };
+            let mut line = Counts::from_file(filename)?.render(template);
+            if template.contains("{focus}") || template.contains("{pomodoro}") {
+                let focus = focus::load_focus(&focus::focus_filename(filename))?;
+                let title = match &focus {
+                    Some(focus) => focus::focus_title(filename, focus)?,
+                    None => None,
+                };
+                // A pomodoro on a todo completed meanwhile is not shown either.
+                let left = focus.filter(|_| title.is_some()).and_then(|focus| focus.pomodoro_left(Local::now()));
+                line = line.replace("{focus}", &title.unwrap_or_default()).replace("{pomodoro}", &left.unwrap_or_default());
+            }
+            println!("{}", line);
+        },
+        "matrix" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            for (n, (quadrant, todos)) in matrix::buckets(todo_list.todos(), config.escalate_after_days, Local::now()).into_iter().enumerate() {
+                if n > 0 {
+                    println!();
+                }
+                heading(quadrant.title(), config.accessible);
+                if todos.is_empty() {
+                    println!("Nothing here.");
+                } else {
+                    print_todos(&todos.into_iter().cloned().collect::<Vec<Todo>>(), config);
+                }
+            }
+        },
+        "pick" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let today = Local::now().date_naive();
+            let tags = &args[1..];
+            let candidates: Vec<&Todo> = todo_list
+                .todos()
+                .iter()
+                .filter(|todo| todo_list.is_actionable(todo, today))
+                .filter(|todo| tags.iter().all(|tag| todo.tags.iter().any(|t| t.as_str() == tag)))
+                .collect();
+            if candidates.is_empty() {
+                println!("Nothing to pick: no pending todo can be worked on right now{}.",
+                    if tags.is_empty() { String::new() } else { format!(" tagged {}", tags.join(", ")) });
+                return Ok(());
+            }
+            let todo = candidates[random_below(candidates.len())];
+            print_todos(std::slice::from_ref(todo), config);
+        },
+        "plan" => {
+            let new = match args.get(1).map(String::as_str) {
+                None => false,
+                Some("--new") => true,
+                _ => {
+                    println!("Usage: plan [--new]");
+                    return Ok(());
+                }
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let today = Local::now().date_naive();
+            match plan::load_plan(&plan::plan_filename(filename), today)? {
+                Some(accepted) if !new => {
+                    heading(&format!("Plan for {}", today), config.accessible);
+                    let capacity = config.daily_capacity().unwrap_or(DEFAULT_CAPACITY_MINUTES);
+                    planner::show(&todo_list, &accepted.ids, capacity, config.accessible);
+                },
+                _ => planner::run(&todo_list, filename, config)?,
+            }
+        },
+        "serve" if args.get(1).is_some_and(|arg| arg == "token") => run_token_command(&args[2..], filename)?,
+        "serve" => {
+            let port = match (args.get(1).map(String::as_str), args.get(2).map(String::as_str), args.get(3)) {
+                (Some("--sync"), None, _) => Some(DEFAULT_SYNC_PORT),
+                (Some("--sync"), Some("--port"), Some(port)) if args.len() == 4 => port.parse::<u16>().ok(),
+                _ => None,
+            };
+            let Some(port) = port else {
+                println!("Usage: serve --sync [--port N]");
+                return Ok(());
+            };
+            server::run(filename, port, &config.server)?;
+        },
+        "user" => run_user_command(&args[1..], filename)?,
+        "sync" => {
+            let state_file = sync::sync_filename(filename);
+            let saved = sync::load_state(&state_file)?;
+            let mut invite = None;
+            let mut token = None;
+            let mut valid = true;
+            let mut options = args.iter().skip(3);
+            while let Some(option) = options.next() {
+                match (option.as_str(), options.next()) {
+                    ("--invite", Some(code)) => invite = Some(code.as_str()),
+                    ("--token", Some(value)) => token = Some(value.clone()),
+                    _ => valid = false,
+                }
+            }
+            let mut state = match (args.get(1).map(String::as_str), args.get(2), saved) {
+                (Some("users"), None, Some(state)) => {
+                    for user in sync::users(&state)? {
+                        println!("{} ({}, {} devices)", user.name, user.role, user.devices);
+                    }
+                    return Ok(());
+                },
+                // A new token for the server already synced with. A device
+                // registered before there were secrets registers again.
+                (Some("remote"), Some(remote), Some(mut state)) if valid && invite.is_none() && state.remote == *remote && state.secret.is_some() => {
+                    if token.is_some() {
+                        state.token = token;
+                    }
+                    state
+                },
+                (Some("remote"), Some(remote), _) if valid => {
+                    let Some(passphrase) = sync_passphrase() else {
+                        return Ok(());
+                    };
+                    let registered = sync::register(remote, invite, token.as_deref())?;
+                    let key = crypto::master_key(&passphrase, &registered.salt)?;
+                    println!("Registered with {} as device {}.", remote, registered.label);
+                    let state = SyncState {
+                        remote: remote.clone(),
+                        device: registered.device,
+                        secret: Some(registered.secret),
+                        label: Some(registered.label),
+                        key: crypto::to_hex(&key),
+                        seq: 0,
+                        synced: Vec::new(),
+                        token,
+                        // What was done before registering stays out of
+                        // the activity feed.
+                        history_len: history_len(filename),
+                    };
+                    // Kept even if the first sync fails, as the invite is
+                    // used up.
+                    sync::save_state(&state_file, &state)?;
+                    state
+                },
+                (None, _, Some(state)) => state,
+                (None | Some("users"), _, None) => {
+                    println!("No sync server set up yet. Run `sync remote URL` first.");
+                    return Ok(());
+                },
+                _ => {
+                    println!("Usage: sync [remote URL [--invite CODE] [--token TOKEN] | users]");
+                    return Ok(());
+                },
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let changes = history::load_events_after(&history::history_filename(filename), state.history_len)?;
+            let report = sync::sync(&mut todo_list, &mut state, &changes)?;
+            todo_list.save_to_file(filename)?;
+            state.history_len = history_len(filename);
+            sync::save_state(&state_file, &state)?;
+            for (old, new) in &report.pulled.renumbered {
+                println!("Todo {} is now todo {}: another device used its ID first.", old, new);
+            }
+            let pulled = &report.pulled;
+            println!(
+                "Synced with {}: {} added, {} updated and {} deleted here, {} changes sent.",
+                state.remote, pulled.added, pulled.updated, pulled.deleted, report.pushed
+            );
+        },
+        "activity" => {
+            let count = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(20),
+                (Some("-n"), Some(n)) => n.parse::<usize>().ok().filter(|&n| n > 0),
+                _ => None,
+            };
+            let Some(count) = count else {
+                println!("Usage: activity [-n N]");
+                return Ok(());
+            };
+            let Some(state) = sync::load_state(&sync::sync_filename(filename))? else {
+                println!("No sync server set up yet. Run `sync remote URL` first, or see `log` for the changes made here.");
+                return Ok(());
+            };
+            let activity = sync::activity(&state, count)?;
+            if activity.is_empty() {
+                println!("No changes sent to {} yet.", state.remote);
+                return Ok(());
+            }
+            for entry in &activity {
+                // The server knows the user behind a device only once it has
+                // users; before that the name the change was signed with is
+                // the best there is.
+                let device = || format!("device {}", entry.device);
+                match &entry.op {
+                    Op::Put { events, .. } | Op::Delete { events, .. } if !events.is_empty() => {
+                        for event in events {
+                            let who = entry.user.clone().or_else(|| event.by.clone()).unwrap_or_else(device);
+                            let detail = event.detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default();
+                            println!("{}  {:<12} {:<10} #{} {}{}",
+                                event.at.format("%Y-%m-%d %H:%M"),
+                                who,
+                                event.kind.to_string(),
+                                event.todo_id,
+                                event.title,
+                                detail
+                            );
+                        }
+                    },
+                    // Sent by a device from before changes carried their
+                    // history.
+                    op => {
+                        let who = entry.user.clone().unwrap_or_else(device);
+                        let at = entry.at.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "?".repeat(16));
+                        let (kind, title) = match op {
+                            Op::Put { todo, .. } => ("sent", todo.title.as_str()),
+                            Op::Delete { .. } => ("deleted", ""),
+                        };
+                        println!("{}  {:<12} {:<10} #{} {}", at, who, kind, op.todo_id(), title);
+                    },
+                }
+            }
+        },
+        "timeline" => {
+            let weeks = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(4),
+                (Some("--weeks"), Some(n)) => n.parse::<usize>().ok().filter(|&n| n > 0),
+                _ => None,
+            };
+            let Some(weeks) = weeks else {
+                println!("Usage: timeline [--weeks N]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let today = Local::now().date_naive();
+            let days = weeks * 7;
+            let entries = timeline::entries(todo_list.todos(), today, days);
+            if entries.is_empty() {
+                println!("Nothing is scheduled or due in the next {} weeks.", weeks);
+                return Ok(());
+            }
+            if config.accessible {
+                for (todo, start, end) in &entries {
+                    if start == end {
+                        println!("Todo {}. {}: on {}.", todo.id, todo.title, start);
+                    } else {
+                        println!("Todo {}. {}: from {} to {}.", todo.id, todo.title, start, end);
+                    }
+                }
+            } else {
+                print!("{}", timeline::render(&entries, today, days));
+            }
+            let load = timeline::load(&entries, today, days);
+            if let Some((d, &most)) = load.iter().enumerate().max_by_key(|&(d, count)| (count, Reverse(d))) {
+                let day = today + Duration::days(d as i64);
+                println!("\nBusiest day: {} {} with {} todos.", day.format("%A"), day, most);
+            }
+        },
+        "estimate" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let minutes = match args.get(2).map(String::as_str) {
+                Some("--clear") => Some(None),
+                Some(time) => parse_minutes(time).map(Some),
+                None => None,
+            };
+            let (Some(id), Some(minutes)) = (id, minutes) else {
+                println!("Usage: estimate ID TIME|--clear");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.set_estimate(id, minutes) {
+                match minutes {
+                    Some(m) => println!("Todo {} should take {}.", id, format_minutes(m)),
+                    None => println!("Estimate cleared for todo {}.", id),
+                }
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "track" => {
+            let id = args.get(1).and_then(|a| a.parse::<usize>().ok());
+            let minutes = args.get(2).and_then(|time| parse_minutes(time)).filter(|&m| m > 0);
+            let (Some(id), Some(minutes)) = (id, minutes) else {
+                println!("Usage: track ID TIME");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if todo_list.add_spent(id, minutes) {
+                let spent = todo_list.get_todo(id).map_or(0, |todo| todo.spent);
+                println!("Logged {} on todo {}, {} in total.", format_minutes(minutes), id, format_minutes(spent));
+                todo_list.save_to_file(filename)?;
+            } else {
+                println!("Todo with ID {} not found.", id);
+            }
+        },
+        "focus" => run_focus_command(&args[1..], filename, config)?,
+        "holidays" => run_holidays_command(&args[1..], filename, config)?,
+        "estimates" => {
+            let todo_list = TodoList::load_from_file(filename)?;
+            let archived = archive::load_archive(&archive::archive_filename(filename))?;
+            let todos: Vec<&Todo> = todo_list.todos().iter().chain(&archived).collect();
+            let rows = estimates::by_todo(todos.iter().copied());
+            if rows.is_empty() {
+                println!("No todo has both an estimate and logged time yet; see `estimate` and `track`.");
+                return Ok(());
+            }
+            let ratio = |accuracy: &estimates::Accuracy| format!("{:.1}x", accuracy.ratio());
+
+            if config.accessible {
+                for (todo, accuracy) in &rows {
+                    println!("Todo {}. {}: estimated {}, took {}, {} the estimate.", todo.id, todo.title,
+                        format_minutes(accuracy.estimated), format_minutes(accuracy.spent), ratio(accuracy));
+                }
+            } else {
+                println!("{:<5} {:<30} {:<9} {:<9} RATIO", "ID", "TITLE", "ESTIMATE", "SPENT");
+                println!("{}", "-".repeat(62));
+                for (todo, accuracy) in &rows {
+                    println!("{:<5} {:<30} {:<9} {:<9} {}", todo.id, truncate(&todo.title, 30),
+                        format_minutes(accuracy.estimated), format_minutes(accuracy.spent), ratio(accuracy));
+                }
+            }
+
+            let tags = estimates::by_tag(todos.iter().copied());
+            let name = |tag: &str| if tag.is_empty() { "(untagged)".to_string() } else { tag.to_string() };
+            println!();
+            if config.accessible {
+                for (tag, accuracy) in &tags {
+                    println!("Tag {}: estimated {}, took {}, {} the estimate.", name(tag),
+                        format_minutes(accuracy.estimated), format_minutes(accuracy.spent), ratio(accuracy));
+                }
+            } else {
+                println!("{:<36} {:<9} {:<9} RATIO", "TAG", "ESTIMATE", "SPENT");
+                println!("{}", "-".repeat(62));
+                for (tag, accuracy) in &tags {
+                    println!("{:<36} {:<9} {:<9} {}", truncate(&name(tag), 36),
+                        format_minutes(accuracy.estimated), format_minutes(accuracy.spent), ratio(accuracy));
+                }
+            }
+            // Off by a quarter or more either way.
+            let worst: Vec<String> = tags
+                .iter()
+                .filter(|(_, accuracy)| accuracy.error() >= 1.25f64.ln())
+                .take(3)
+                .map(|(tag, accuracy)| format!("{} ({})", name(tag), ratio(accuracy)))
+                .collect();
+            if !worst.is_empty() {
+                println!("\nWorst estimated: {}", worst.join(", "));
+            }
+        },
+        "velocity" => {
+            let weeks = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(8),
+                (Some("--weeks"), Some(n)) => n.parse::<usize>().ok().filter(|&n| n > 0),
+                _ => None,
+            };
+            let Some(weeks) = weeks else {
+                println!("Usage: velocity [--weeks N]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            let archived = archive::load_archive(&archive::archive_filename(filename))?;
+            let today = Local::now().date_naive();
+            let totals = velocity::weekly_points(todo_list.todos().iter().chain(&archived), today, weeks);
+
+            if !config.accessible {
+                println!("{:<10} {:<6}", "WEEK", "POINTS");
+                println!("{}", "-".repeat(40));
+            }
+            for (monday, points) in &totals {
+                let week = monday.iso_week();
+                let week = format!("{}-W{:02}", week.year(), week.week());
+                if config.accessible {
+                    println!("Week {}: {} points.", week, points);
+                } else {
+                    println!("{:<10} {:<6} {}", week, points, "#".repeat(*points as usize));
+                }
+            }
+            let sum: u32 = totals.iter().map(|(_, points)| points).sum();
+            println!("\nAverage velocity: {:.1} points/week", f64::from(sum) / totals.len() as f64);
+        },
+        "fsck" => {
+            let fix = match args.get(1).map(String::as_str) {
+                None => false,
+                Some("--fix") if args.len() == 2 => true,
+                _ => {
+                    println!("Usage: fsck [--fix]");
+                    return Ok(());
+                }
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let problems = if fix { fsck::repair(&mut todo_list) } else { fsck::check(&todo_list) };
+            if problems.is_empty() {
+                println!("{}: no problems found.", filename);
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if fix {
+                todo_list.save_to_file(filename)?;
+                println!("\nFixed {} problems.", problems.len());
+            } else {
+                println!("\n{} problems found. Run `fsck --fix` to repair them.", problems.len());
+                process::exit(1);
+            }
+        },
+        "recover" => {
+            match TodoList::load_from_file(filename) {
+                Ok(_) => {
+                    println!("{} is fine, there is nothing to recover.", filename);
+                    return Ok(());
+                },
+                Err(e) if recover::is_corrupt(&e) => println!("{} is damaged: {}", filename, e),
+                Err(e) => return Err(e),
+            }
+            let recovery = recover::recover(filename)?;
+            recovery.todo_list.save_to_file(filename)?;
+            println!("Recovered {} todos. The damaged file was moved to {}.", recovery.salvaged, recovery.backup);
+        },
+        "gc" => {
+            let Some(days) = config.archive_completed_after_days else {
+                println!("No archival policy configured. Set archive_completed_after_days in {}.", CONFIG_FILENAME);
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            let archived = archive::archive_completed(&mut todo_list, filename, days)?;
+            if archived > 0 {
+                todo_list.save_to_file(filename)?;
+            }
+            println!("Archived {} completed todos older than {} days to {}.", archived, days, archive::archive_filename(filename));
+        },
+        "clone" => {
+            let Some(id) = args.get(1).and_then(|a| a.parse::<usize>().ok()) else {
+                println!("Usage: clone ID [TITLE]");
+                return Ok(());
+            };
+            let title = Some(args[2..].join(" ")).filter(|title| !title.is_empty());
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.set_limits(config.limits);
+            match todo_list.clone_todo(id, title) {
+                Ok(Some(copy)) => {
+                    println!("Todo {} cloned as todo {}.", id, copy);
+                    todo_list.save_to_file(filename)?;
+                },
+                Ok(None) => println!("Todo with ID {} not found.", id),
+                Err(e) => println!("{}", e),
+            }
+        },
+        "split" => {
+            let Some(id) = args.get(1).and_then(|a| a.parse::<usize>().ok()).filter(|_| args.len() == 2) else {
+                println!("Usage: split ID");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.set_limits(config.limits);
+            let Some(todo) = todo_list.get_todo(id) else {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            };
+            if todo.completed || todo.habit {
+                println!("Only pending todos that are not habits can be split.");
+                return Ok(());
+            }
+            println!("Splitting #{} {}. Enter one subtask per line, an empty line to finish.", todo.id, todo.title);
+            let mut titles = Vec::new();
+            loop {
+                let title = get_input(&format!("Subtask {}:", titles.len() + 1));
+                if title.is_empty() {
+                    break;
+                }
+                titles.push(title);
+            }
+            if titles.is_empty() {
+                println!("No subtasks entered; todo {} is unchanged.", id);
+                return Ok(());
+            }
+            match todo_list.split(id, &titles) {
+                Ok(children) => {
+                    let children: Vec<String> = children.iter().map(|child| format!("#{}", child)).collect();
+                    println!("Todo {} now has the subtasks {} and waits for them.", id, children.join(", "));
+                    todo_list.save_to_file(filename)?;
+                },
+                Err(e) => println!("{}", e),
+            }
+        },
+        "show" => {
+            let Some(id) = args.get(1).and_then(|a| a.parse::<usize>().ok()) else {
+                println!("Usage: show ID");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            match todo_list.get_todo(id) {
+                Some(todo) => print_details(todo, &todo_list, config),
+                None => println!("Todo with ID {} not found.", id),
+            }
+        },
+        "relate" | "unrelate" => {
+            let ids: Vec<usize> = args[1..].iter().filter_map(|a| a.parse().ok()).collect();
+            if args.len() != 3 || ids.len() != 2 {
+                println!("Usage: {} ID OTHER", args[0]);
+                return Ok(());
+            }
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if let Some(&missing) = ids.iter().find(|&&id| todo_list.get_todo(id).is_none()) {
+                println!("Todo with ID {} not found.", missing);
+                return Ok(());
+            }
+            if args[0] == "relate" {
+                if !todo_list.relate(ids[0], ids[1]) {
+                    println!("A todo cannot be related to itself.");
+                    return Ok(());
+                }
+                println!("Todos {} and {} are now related.", ids[0], ids[1]);
+            } else if todo_list.unrelate(ids[0], ids[1]) {
+                println!("Todos {} and {} are no longer related.", ids[0], ids[1]);
+            } else {
+                println!("Todos {} and {} are not related.", ids[0], ids[1]);
+                return Ok(());
+            }
+            todo_list.save_to_file(filename)?;
+        },
+        "depend" | "undepend" => {
+            let ids: Vec<usize> = args[1..].iter().filter_map(|a| a.parse().ok()).collect();
+            if args.len() != 3 || ids.len() != 2 {
+                println!("Usage: {} ID ON", args[0]);
+                return Ok(());
+            }
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            if args[0] == "depend" {
+                match todo_list.add_dependency(ids[0], ids[1]) {
+                    Ok(()) => println!("Todo {} now depends on todo {}.", ids[0], ids[1]),
+                    Err(e) => {
+                        println!("{}", e);
+                        return Ok(());
+                    }
+                }
+            } else if todo_list.remove_dependency(ids[0], ids[1]) {
+                println!("Todo {} no longer depends on todo {}.", ids[0], ids[1]);
+            } else {
+                println!("Todo {} does not depend on todo {}.", ids[0], ids[1]);
+                return Ok(());
+            }
+            todo_list.save_to_file(filename)?;
+        },
+        "graph" => {
+            let format = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(GraphFormat::Dot),
+                (Some("--format"), Some(name)) => GraphFormat::parse(name),
+                _ => None,
+            };
+            let Some(format) = format else {
+                println!("Usage: graph [--format dot|mermaid]");
+                return Ok(());
+            };
+            let todo_list = TodoList::load_from_file(filename)?;
+            print!("{}", graph::render(&todo_list, format));
+        },
+        "review" => {
+            let days = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => Some(7),
+                (Some("--days"), Some(n)) => n.parse::<u32>().ok(),
+                _ => None,
+            };
+            let Some((days, cutoff)) = days.and_then(|days| Some((days, days_before(Local::now(), days)?))) else {
+                println!("Usage: review [--days N]");
+                return Ok(());
+            };
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            todo_list.set_days_off(Calendar::days_off(config, filename)?);
+            review::run(&mut todo_list, filename, days, cutoff, config)?;
+        },
+        "triage" => {
+            if args.len() > 1 {
+                println!("Usage: triage");
+                return Ok(());
+            }
+            let mut todo_list = TodoList::load_from_file(filename)?;
+            triage::run(&mut todo_list, filename, config)?;
+        },
+        "keys" => {
+            println!("Interactive menu:");
+            for &action in MenuAction::ALL {
+                println!("  {:<6} {:<10} {}", config.keys.menu_key(action), action.name(), action.description());
+            }
+            println!("Review:");
+            for &action in ReviewAction::ALL {
+                println!("  {:<6} {:<10} {}", config.keys.review_key(action), action.name(), action.description());
+            }
+            for conflict in config.keys.conflicts() {
+                println!("Warning: {}.", conflict);
+            }
+        },
+        "doctor" => {
+            if doctor::run(filename, config, config.accessible) {
+                process::exit(1);
+            }
+        },
+        "init" => {
+            init::run(config)?;
+        },
+        "self-update" => run_self_update(&args[1..], config)?,
+        "backup" => match (args.get(1).map(String::as_str), &args[2.min(args.len())..]) {
+            (Some("create"), [target]) => backup::create(target, filename)?,
+            (Some("restore"), [source]) => backup::restore(source, config, false)?,
+            (Some("restore"), [source, flag]) if flag == "--force" => backup::restore(source, config, true)?,
+            _ => println!("Usage: backup create FILE|- | backup restore FILE|- [--force]"),
+        },
+        "help" | "--help" | "-h" => print_usage(),
+        other => {
+            println!("Unknown command: {}", other);
+            print_usage();
+        }
+    }
+    Ok(())
+}
+
+// "[#####-----]" for half of `width` cells done.
+fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done * width).checked_div(total).unwrap_or(0);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+// Goes to the webhooks and the notifiers of this environment. Failures are
+// reported but never fail the command itself. Returns how many of how many
+// it reached.
+fn send_notification(config: &Config, notification: &Notification) -> (usize, usize) {
+    let backends = notifiers::backends(config);
+    let mut targets: Vec<&dyn Notifier> = config.webhooks.iter().map(|webhook| webhook as &dyn Notifier).collect();
+    targets.extend(backends.iter().map(|backend| backend as &dyn Notifier));
+    let errors = notify::send_all(&targets, notification);
+    for e in &errors {
+        println!("Could not send notification: {}", e);
+    }
+    (targets.len() - errors.len(), targets.len())
+}
+
+// The optional "--note TEXT" after a command's ID; None if anything else is
+// there.
+fn parse_note(args: &[String]) -> Option<Option<String>> {
+    match args.split_first() {
+        None => Some(None),
+        Some((flag, text)) if flag == "--note" && !text.is_empty() => Some(Some(text.join(" "))),
+        _ => None,
+    }
+}
+
+// "+ Buy milk [Groceries] (due 2026-10-15, done)", a todo an import would
+// add.
+fn print_import_preview(todo: &Todo) {
+    let mut notes = Vec::new();
+    if let Some(due) = todo.due {
+        notes.push(format!("due {}", due));
+    }
+    if let Some(priority) = todo.priority {
+        notes.push(priority.to_string());
+    }
+    if !todo.tags.is_empty() {
+        notes.push(todo.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>().join(", "));
+    }
+    if todo.completed {
+        notes.push("done".to_string());
+    }
+    let project = todo.project.as_ref().map(|project| format!(" [{}]", project)).unwrap_or_default();
+    let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+    println!("+ {}{}{}", todo.title, project, notes);
+}
+
+// For recurring todos that moved on instead of being completed.
+pub fn print_next_occurrence(todo_list: &TodoList, id: usize) {
+    if let Some(todo) = todo_list.get_todo(id).filter(|t| t.recurrence.is_some() && !t.completed) {
+        println!("It repeats; next due {}.", format_due(todo));
+    }
+}
+
+// Called after a completion toggle; nothing is sent if the todo was reopened
+// or is a habit.
+pub fn notify_completed(todo_list: &TodoList, id: usize, config: &Config) {
+    if let Some(todo) = todo_list.get_todo(id).filter(|t| t.completed && !t.habit) {
+        send_notification(config, &Notification::Completed(todo));
+    }
+}
+
+fn run_goal_command(args: &[String], filename: &str) -> io::Result<()> {
+    let ids: Vec<usize> = args.iter().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let mut todo_list = TodoList::load_from_file(filename)?;
+    match (args.first().map(String::as_str), ids.as_slice()) {
+        (Some("add"), _) if args.len() > 1 => {
+            let id = todo_list.add_goal(args[1..].join(" "));
+            println!("Goal {} created.", id);
+        },
+        (Some("rm"), &[goal]) => {
+            if !todo_list.delete_goal(goal) {
+                println!("Goal with ID {} not found.", goal);
+                return Ok(());
+            }
+            println!("Goal {} deleted.", goal);
+        },
+        (Some("link"), &[id, goal]) => {
+            if todo_list.get_goal(goal).is_none() {
+                println!("Goal with ID {} not found.", goal);
+                return Ok(());
+            }
+            if !todo_list.set_goal(id, Some(goal)) {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            }
+            println!("Todo {} linked to goal {}.", id, goal);
+        },
+        (Some("unlink"), &[id]) => {
+            if !todo_list.set_goal(id, None) {
+                println!("Todo with ID {} not found.", id);
+                return Ok(());
+            }
+            println!("Todo {} unlinked from its goal.", id);
+        },
+        _ => {
+            println!("Usage: goal add TITLE | goal rm GOAL | goal link ID GOAL | goal unlink ID");
+            return Ok(());
+        }
+    }
+    todo_list.save_to_file(filename)
+}
+
+enum Due {
+    Day(Option<NaiveDate>),
+    Deadline(Deadline),
+}
+
+// A day, "friday", or a time on a day in a time zone, "friday 9am Berlin".
+// None after saying what is wrong with a time.
+fn parse_due(value: &str) -> Option<Due> {
+    let today = Local::now().date_naive();
+    if let Some(day) = parse_date(value, today) {
+        return Some(Due::Day(Some(day)));
+    }
+    if let Some(deadline) = zones::parse_deadline(value, today) {
+        return Some(Due::Deadline(deadline));
+    }
+    if value.split_whitespace().any(|word| zones::parse_time(word).is_some()) {
+        println!("Could not pin '{}' to a time zone; give one after the time, e.g. 9am Berlin or 17:00 America/New_York.", value);
+    } else {
+        println!("Could not understand the date '{}'.", value);
+    }
+    None
+}
+
+const SET_USAGE: &str = "Usage: set IDS title|description|due|priority|location|tags|tags+=|tags-= VALUE|--clear";
+
+enum Change {
+    Title(String),
+    Description(String),
+    Due(Due),
+    Priority(Option<Priority>),
+    Location(Option<String>),
+    Tags(Vec<String>),
+    AddTags(Vec<String>),
+    RemoveTags(Vec<String>),
+}
+
+const TOKEN_USAGE: &str = "Usage: serve token create --name NAME [--scopes LIST] [--expires OFFSET] | serve token list | serve token revoke NAME";
+
+// Managing the tokens the sync server of this list asks for. The server
+// picks up changes with its next request.
+fn run_token_command(args: &[String], filename: &str) -> io::Result<()> {
+    let tokens_file = tokens::tokens_filename(filename);
+    let mut tokens = Tokens::load(&tokens_file)?;
+    let today = Local::now().date_naive();
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            let mut name = None;
+            let mut scopes = Some(Scope::DEFAULT.to_vec());
+            let mut expires_on = None;
+            let mut options = args[1..].iter();
+            while let Some(option) = options.next() {
+                match (option.as_str(), options.next()) {
+                    ("--name", Some(value)) => name = Some(clean_line(value)).filter(|n| !n.is_empty()),
+                    ("--scopes", Some(value)) => scopes = Scope::parse_list(value).filter(|s| !s.is_empty()),
+                    ("--expires", Some(value)) => match apply_offset(value, today) {
+                        Some(day) => expires_on = Some(day),
+                        None => {
+                            println!("--expires expects an offset such as 30d, 2w or 6m.");
+                            return Ok(());
+                        },
+                    },
+                    _ => {
+                        println!("{}", TOKEN_USAGE);
+                        return Ok(());
+                    },
+                }
+            }
+            let Some(name) = name else {
+                println!("{}", TOKEN_USAGE);
+                return Ok(());
+            };
+            let Some(scopes) = scopes else {
+                let names: Vec<String> = Scope::ALL.iter().map(Scope::to_string).collect();
+                let (last, rest) = names.split_last().expect("there are scopes");
+                println!("Scopes are a comma-separated list of {} and {}.", rest.join(", "), last);
+                return Ok(());
+            };
+            let Some(secret) = tokens.create(&name, scopes, expires_on) else {
+                println!("There is already a token called {}.", name);
+                return Ok(());
+            };
+            tokens.save(&tokens_file)?;
+            println!("Created token {}: {}", name, secret);
+            println!("It is not shown again. Use it with: sync remote URL --token {}", secret);
+            if let Some(day) = expires_on {
+                println!("It works until {}.", day - Duration::days(1));
+            }
+        },
+        Some("list") if args.len() == 1 => {
+            if tokens.is_open() {
+                println!("There are no tokens yet; the sync server answers anyone who can reach it.");
+            } else if tokens.tokens().is_empty() {
+                println!("There are no tokens left; the sync server answers nobody until one is created.");
+            }
+            for token in tokens.tokens() {
+                let scopes: Vec<String> = token.scopes.iter().map(Scope::to_string).collect();
+                let expiry = match token.expires_on {
+                    Some(day) if token.is_expired(today) => format!(", expired {}", day),
+                    Some(day) => format!(", expires {}", day),
+                    None => String::new(),
+                };
+                println!("{} ({}; created {}{})", token.name, scopes.join(", "), token.created_at.format("%Y-%m-%d"), expiry);
+            }
+        },
+        Some("revoke") if args.len() == 2 => {
+            if tokens.revoke(&args[1]) {
+                tokens.save(&tokens_file)?;
+                println!("Revoked token {}.", args[1]);
+                if tokens.tokens().is_empty() {
+                    println!("That was the last token: the sync server answers nobody until one is created.");
+                }
+            } else {
+                println!("There is no token called {}.", args[1]);
+            }
+        },
+        _ => println!("{}", TOKEN_USAGE),
+    }
+    Ok(())
+}
+
+const USER_USAGE: &str = "Usage: user add NAME --role ROLE | user invite NAME | user role NAME ROLE | user rm NAME | user list";
+
+// Managing who may use the sync server of this list. The server picks up
+// changes with its next request.
+fn run_user_command(args: &[String], filename: &str) -> io::Result<()> {
+    let users_file = users::users_filename(filename);
+    let mut users = Users::load(&users_file)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["list"] => {
+            if users.is_open() {
+                println!("There are no users yet; any device that can reach the sync server can use it.");
+            } else if users.users().is_empty() {
+                println!("There are no users left; no device can use the sync server until one is added.");
+            }
+            for user in users.users() {
+                println!("{} ({}, {} devices)", user.name, user.role, user.devices.len());
+            }
+            return Ok(());
+        },
+        ["add", name, "--role", role] => match Role::parse(role) {
+            Some(role) => users.add(name, role).map(|code| {
+                println!("Added {} as {}.", name, role);
+                println!("Register a device of theirs with: sync remote URL --invite {}", code);
+            }),
+            None => {
+                println!("The role must be admin, editor or viewer.");
+                return Ok(());
+            },
+        },
+        ["invite", name] => users.invite(name).map(|code| {
+            println!("Register another device of {} with: sync remote URL --invite {}", name, code);
+            println!("Any unused earlier invite of theirs no longer works.");
+        }),
+        ["role", name, role] => match Role::parse(role) {
+            Some(role) => users.set_role(name, role).map(|()| println!("{} is now {}.", name, role)),
+            None => {
+                println!("The role must be admin, editor or viewer.");
+                return Ok(());
+            },
+        },
+        ["rm", name] => users.remove(name).map(|user| {
+            println!("Removed {} and locked out their {} devices.", user.name, user.devices.len());
+            if users.users().is_empty() {
+                println!("There are no users left; no device can use the sync server until one is added.");
+            }
+        }),
+        _ => {
+            println!("{}", USER_USAGE);
+            return Ok(());
+        },
+    };
+    match result {
+        Ok(()) => users.save(&users_file),
+        Err(e) => {
+            println!("{}", e);
+            Ok(())
+        },
+    }
+}
+
+const FOCUS_USAGE: &str = "Usage: focus [ID] [--pomodoro [TIME]] | focus --clear";
+
+fn run_focus_command(args: &[String], filename: &str, config: &Config) -> io::Result<()> {
+    let focus_file = focus::focus_filename(filename);
+    let id = args.first().and_then(|a| a.parse::<usize>().ok());
+    let options = &args[id.map_or(0, |_| 1)..];
+    let pomodoro = match options {
+        [] => None,
+        [flag] if flag == "--pomodoro" => Some(25),
+        [flag, time] if flag == "--pomodoro" => match parse_minutes(time).filter(|&m| m > 0) {
+            Some(minutes) => Some(minutes),
+            None => {
+                println!("{}", FOCUS_USAGE);
+                return Ok(());
+            },
+        },
+        [flag] if flag == "--clear" && id.is_none() => {
+            if focus::clear_focus(&focus_file)? {
+                println!("Focus cleared.");
+            } else {
+                println!("No focus was set.");
+            }
+            return Ok(());
+        },
+        _ => {
+            println!("{}", FOCUS_USAGE);
+            return Ok(());
+        },
+    };
+
+    let todo_list = TodoList::load_from_file(filename)?;
+    let mut current = match id {
+        Some(id) => {
+            match todo_list.get_todo(id) {
+                None => {
+                    println!("Todo with ID {} not found.", id);
+                    return Ok(());
+                },
+                Some(todo) if todo.completed => {
+                    println!("Todo {} is completed already.", id);
+                    return Ok(());
+                },
+                Some(todo) => println!("Focusing on #{} {}.", id, todo.title),
+            }
+            let focus = Focus { id, since: Local::now(), pomodoro_until: None };
+            focus::save_focus(&focus_file, &focus)?;
+            focus
+        },
+        None => match focus::load_focus(&focus_file)? {
+            Some(focus) if todo_list.get_todo(focus.id).is_some_and(|todo| !todo.completed) => focus,
+            // Done or deleted since; nothing to keep.
+            Some(focus) => {
+                focus::clear_focus(&focus_file)?;
+                println!("No focus set; todo {} is no longer pending. `focus ID` sets a new one.", focus.id);
+                return Ok(());
+            },
+            None => {
+                println!("No focus set. `focus ID` sets one.");
+                return Ok(());
+            },
+        },
+    };
+    let Some(todo) = todo_list.get_todo(current.id) else {
+        return Ok(());
+    };
+
+    let Some(minutes) = pomodoro else {
+        if id.is_some() {
+            return Ok(());
+        }
+        let now = Local::now();
+        let today = now.date_naive();
+        heading("Focus", config.accessible);
+        println!("#{} {}", todo.id, todo.title);
+        if !todo.description.is_empty() {
+            println!("{}", todo.description);
+        }
+        let since = if current.since.date_naive() == today {
+            current.since.format("%H:%M").to_string()
+        } else {
+            config.date_format.timestamp(current.since, today)
+        };
+        let mut details = vec![format!("Since {}", since)];
+        if let Some(due) = todo.due {
+            details.push(format!("due {}", config.date_format.date(due, today)));
+        }
+        if let Some(estimate) = todo.estimate {
+            details.push(format!("{} of {} spent", format_minutes(todo.spent), format_minutes(estimate)));
+        } else if todo.spent > 0 {
+            details.push(format!("{} spent", format_minutes(todo.spent)));
+        }
+        if let Some(left) = current.pomodoro_left(now) {
+            details.push(format!("{} left of the pomodoro", left));
+        }
+        println!("{}.", details.join(", "));
+        return Ok(());
+    };
+    let title = todo.title.clone();
+    let worked = pomodoro::run(&focus_file, &mut current, &title, minutes, config.accessible)?;
+    if worked > 0 {
+        // The list may have been changed elsewhere in the meantime.
+        let mut todo_list = TodoList::load_from_file(filename)?;
+        if todo_list.add_spent(current.id, worked) {
+            println!("Logged {} on todo {}.", format_minutes(worked), current.id);
+            todo_list.save_to_file(filename)?;
+        }
+    }
+    Ok(())
+}
+
+const HOLIDAYS_USAGE: &str = "Usage: holidays [--all] | holidays import FILE|- | holidays add DATE [NAME] | holidays remove DATE | holidays clear";
+
+// The days off besides the weekends, kept next to the list.
+fn run_holidays_command(args: &[String], filename: &str, config: &Config) -> io::Result<()> {
+    let holidays_file = workdays::holidays_filename(filename);
+    let mut holidays = workdays::load_holidays(&holidays_file)?;
+    let today = Local::now().date_naive();
+    match args.first().map(String::as_str) {
+        None | Some("--all") if args.len() <= 1 => {
+            let days: Vec<String> = config.workdays.iter().map(|day| day.to_string()).collect();
+            println!("Workdays: {}.", if days.is_empty() { "none".to_string() } else { days.join(", ") });
+            let shown: Vec<&Holiday> = holidays.iter().filter(|holiday| !args.is_empty() || holiday.day >= today).collect();
+            if shown.is_empty() {
+                println!("No holidays to come; `holidays import FILE` reads them from an iCalendar file.");
+            }
+            for holiday in shown {
+                println!("{}  {}", config.date_format.date(holiday.day, today), holiday.name);
+            }
+            return Ok(());
+        },
+        Some("import") if args.len() == 2 => {
+            let mut text = String::new();
+            if args[1] == "-" {
+                io::stdin().read_to_string(&mut text)?;
+            } else {
+                File::open(&args[1])?.read_to_string(&mut text)?;
+            }
+            let imported = workdays::parse_holidays(&text);
+            if imported.is_empty() {
+                println!("No holidays found in {}; it should be an iCalendar (.ics) file or lines of a date and a name.", args[1]);
+                return Ok(());
+            }
+            let new = imported.iter().filter(|holiday| !holidays.iter().any(|known| known.day == holiday.day)).count();
+            holidays.retain(|known| !imported.iter().any(|holiday| holiday.day == known.day));
+            holidays.extend(imported.iter().cloned());
+            println!("Imported {} holidays from {}, {} of them new.", imported.len(), args[1], new);
+        },
+        Some("add") if args.len() >= 2 => {
+            let Some(day) = parse_date(&args[1], today) else {
+                println!("Could not understand the date '{}'.", args[1]);
+                return Ok(());
+            };
+            let name = clean_line(&args[2..].join(" "));
+            holidays.retain(|holiday| holiday.day != day);
+            println!("{} is a holiday.", day);
+            holidays.push(Holiday { day, name });
+        },
+        Some("remove") if args.len() == 2 => {
+            let Some(day) = parse_date(&args[1], today) else {
+                println!("Could not understand the date '{}'.", args[1]);
+                return Ok(());
+            };
+            let before = holidays.len();
+            holidays.retain(|holiday| holiday.day != day);
+            if holidays.len() == before {
+                println!("{} is not a holiday.", day);
+                return Ok(());
+            }
+            println!("{} is no longer a holiday.", day);
+        },
+        Some("clear") if args.len() == 1 => {
+            println!("Removed {} holidays.", holidays.len());
+            holidays.clear();
+        },
+        _ => {
+            println!("{}", HOLIDAYS_USAGE);
+            return Ok(());
+        },
+    }
+    holidays.sort_by_key(|holiday| holiday.day);
+    workdays::save_holidays(&holidays_file, &holidays)
+}
+
+fn run_self_update(args: &[String], config: &Config) -> io::Result<()> {
+    let check_only = match args {
+        [] => false,
+        [flag] if flag == "--check" => true,
+        _ => {
+            println!("Usage: self-update [--check]");
+            return Ok(());
+        },
+    };
+    if !config.updates.enabled {
+        println!("Update checks are turned off by updates.enabled in {}.", CONFIG_FILENAME);
+        return Ok(());
+    }
+    let repository = config.updates.repository.clone().or_else(|| update::github_repository(env!("CARGO_PKG_REPOSITORY")));
+    let Some(repository) = repository else {
+        println!("No repository to update from; set updates.repository in {} to its owner/name on GitHub.", CONFIG_FILENAME);
+        return Ok(());
+    };
+    let current = env!("CARGO_PKG_VERSION");
+    let release = update::latest_release(&repository)?;
+    if !update::is_newer(release.version(), current) {
+        println!("Version {} is the latest.", current);
+        return Ok(());
+    }
+    println!("Version {} is available; this is {}.", release.version(), current);
+    if check_only {
+        return Ok(());
+    }
+    let name = update::asset_name();
+    let Some(asset) = release.asset(&name) else {
+        println!("Release {} has no {} for this platform; nothing was changed.", release.tag_name, name);
+        return Ok(());
+    };
+    // Without a checksum to compare with, a damaged or replaced download
+    // could not be told apart from the real one.
+    let sums = match release.asset(update::CHECKSUMS) {
+        Some(sums) => String::from_utf8_lossy(&update::download(sums)?).into_owned(),
+        None => {
+            println!("Release {} has no {} to verify {} with; nothing was changed.", release.tag_name, update::CHECKSUMS, name);
+            return Ok(());
+        },
+    };
+    let Some(expected) = update::expected_checksum(&sums, &name) else {
+        println!("{} of release {} lists no checksum for {}; nothing was changed.", update::CHECKSUMS, release.tag_name, name);
+        return Ok(());
+    };
+    let binary = update::download(asset)?;
+    let actual = update::checksum(&binary);
+    if actual != expected {
+        println!("The checksum of the downloaded {} is {}, not {} as {} says; nothing was changed.", name, actual, expected, update::CHECKSUMS);
+        return Ok(());
+    }
+    update::replace_current_exe(&binary)?;
+    println!("Updated to version {}.", release.version());
+    Ok(())
+}
+
+// Pending delegated todos grouped by person, longest delegated first, or
+// only those of `person` (lowercase).
+fn print_waiting_for(todo_list: &TodoList, person: Option<&str>, today: NaiveDate) {
+    let mut by_person: BTreeMap<String, (String, Vec<&Todo>)> = BTreeMap::new();
+    for todo in todo_list.todos().iter().filter(|todo| todo.is_delegated()) {
+        let name = todo.delegated_to.clone().unwrap_or_default();
+        let key = name.to_lowercase();
+        if person.is_some_and(|person| person != key) {
+            continue;
+        }
+        by_person.entry(key).or_insert_with(|| (name, Vec::new())).1.push(todo);
+    }
+    if by_person.is_empty() {
+        match person {
+            Some(person) => println!("Nothing is delegated to {}.", person),
+            None => println!("Nothing is delegated."),
+        }
+        return;
+    }
+    let mut due = 0;
+    for (name, todos) in by_person.values_mut() {
+        todos.sort_by_key(|todo| (todo.delegated_on, todo.id));
+        println!("{}:", name);
+        for todo in todos.iter() {
+            let mut notes = Vec::new();
+            if let Some(on) = todo.delegated_on {
+                notes.push(format!("since {}, {} days", on, (today - on).num_days()));
+            }
+            match todo.follow_up {
+                Some(day) if day < today => notes.push(format!("follow-up overdue since {}", day)),
+                Some(day) if day == today => notes.push("follow up today".to_string()),
+                Some(day) => notes.push(format!("follow up on {}", day)),
+                None => {},
+            }
+            if todo.follow_up_due(today) {
+                due += 1;
+            }
+            let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join("; ")) };
+            println!("  #{} {}{}", todo.id, todo.title, notes);
+        }
+    }
+    if due > 0 {
+        println!("{} to follow up on now.", due);
+    }
+}
+
+// `set IDS FIELD VALUE`, changing a single field without the edit prompts.
+// `tags+=` and `tags-=` may also be written together with their value.
+fn run_set_command(args: &[String], filename: &str, config: &Config) -> io::Result<()> {
+    let (Some(expr), Some(field)) = (args.first(), args.get(1)) else {
+        println!("{}", SET_USAGE);
+        return Ok(());
+    };
+    let mut rest = args[2..].to_vec();
+    let field = match ["tags+=", "tags-="].into_iter().find(|op| field.starts_with(op) && field.len() > op.len()) {
+        Some(op) => {
+            rest.insert(0, field[op.len()..].to_string());
+            op
+        },
+        None => field.as_str(),
+    };
+    let value = rest.join(" ");
+    let clear = value == "--clear";
+    if value.is_empty() && field != "description" {
+        println!("{}", SET_USAGE);
+        return Ok(());
+    }
+    let change = match field {
+        "title" => Change::Title(value),
+        "description" => Change::Description(if clear { String::new() } else { value }),
+        "due" if clear => Change::Due(Due::Day(None)),
+        "due" => match parse_due(&value) {
+            Some(due) => Change::Due(due),
+            None => return Ok(()),
+        },
+        "priority" if clear => Change::Priority(None),
+        "priority" => match Priority::parse(&value) {
+            Some(priority) => Change::Priority(Some(priority)),
+            None => {
+                println!("Priority must be low, medium or high.");
+                return Ok(());
+            },
+        },
+        "location" => Change::Location(Some(clean_line(&value)).filter(|l| !clear && !l.is_empty())),
+        "tags" => Change::Tags(if clear { Vec::new() } else { parse_tags(&value) }),
+        "tags+=" => Change::AddTags(parse_tags(&value)),
+        "tags-=" => Change::RemoveTags(parse_tags(&value)),
+        _ => {
+            println!("{}", SET_USAGE);
+            return Ok(());
+        },
+    };
+
+    let mut todo_list = TodoList::load_from_file(filename)?;
+    todo_list.set_limits(config.limits);
+    let Some(ids) = ids_or_explain(std::slice::from_ref(expr), &todo_list) else {
+        return Ok(());
+    };
+    let mut changed = 0;
+    for id in ids {
+        let Some(todo) = todo_list.get_todo(id).cloned() else {
+            println!("Todo with ID {} not found.", id);
+            continue;
+        };
+        let edited = match &change {
+            Change::Title(title) => todo_list.edit_todo(id, title.clone(), todo.description),
+            Change::Description(description) => todo_list.edit_todo(id, todo.title, description.clone()),
+            _ => Ok(true),
+        };
+        if let Err(e) = edited {
+            println!("{}", e);
+            break;
+        }
+        match &change {
+            Change::Title(_) | Change::Description(_) => {},
+            Change::Due(Due::Day(day)) => {
+                todo_list.set_due(id, *day);
+            },
+            Change::Due(Due::Deadline(deadline)) => {
+                todo_list.set_deadline(id, deadline.clone());
+            },
+            Change::Priority(priority) => {
+                todo_list.set_priority(id, *priority);
+            },
+            Change::Location(location) => {
+                todo_list.set_location(id, location.clone());
+            },
+            Change::Tags(names) => {
+                todo_list.set_tags(id, names);
+            },
+            Change::AddTags(names) => {
+                for name in names {
+                    todo_list.add_tag(id, name);
+                }
+            },
+            Change::RemoveTags(names) => {
+                for name in names {
+                    todo_list.remove_tag(id, name);
+                }
+            },
+        }
+        println!("Updated {} of todo {}.", field.trim_end_matches(['+', '-', '=']), id);
+        changed += 1;
+    }
+    if changed > 0 {
+        todo_list.save_to_file(filename)?;
+    }
+    Ok(())
+}
+
+// The todos named by `args`, an ID expression such as "3-7,last" possibly
+// spread over several arguments, or None after saying what is wrong.
+fn ids_or_explain(args: &[String], todo_list: &TodoList) -> Option<Vec<usize>> {
+    match parse_ids(&args.join(" "), todo_list, Local::now().date_naive()) {
+        Ok(ids) if ids.is_empty() => {
+            println!("No todos match.");
+            None
+        },
+        Ok(ids) => Some(ids),
+        Err(e) => {
+            println!("{}", e);
+            None
+        },
+    }
+}
+
+// A random number below `n`, good enough for picking a todo. The standard
+// library's hasher keys are randomly seeded for every process.
+fn random_below(n: usize) -> usize {
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (random % n as u64) as usize
+}
+
+fn run_tag_command(args: &[String], filename: &str) -> io::Result<()> {
+    let (Some(action), Some(from), Some(into), None) = (args.first(), args.get(1), args.get(2), args.get(3)) else {
+        println!("Usage: tag rename OLD NEW | tag merge FROM INTO");
+        return Ok(());
+    };
+    let mut todo_list = TodoList::load_from_file(filename)?;
+    if !todo_list.has_tag(from) {
+        println!("No todo is tagged {}.", from);
+        return Ok(());
+    }
+    match action.as_str() {
+        "rename" if todo_list.has_tag(into) => {
+            println!("Tag {} already exists; use `tag merge {} {}` to combine them.", into, from, into);
+            return Ok(());
+        },
+        "merge" if !todo_list.has_tag(into) => {
+            println!("No todo is tagged {}; use `tag rename {} {}` instead.", into, from, into);
+            return Ok(());
+        },
+        "rename" | "merge" => {},
+        _ => {
+            println!("Usage: tag rename OLD NEW | tag merge FROM INTO");
+            return Ok(());
+        }
+    }
+    let changed = todo_list.replace_tag(from, into);
+    todo_list.save_to_file(filename)?;
+    println!("Updated {} todos: {} -> {}.", changed, from, into);
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Id,
+    Title,
+    Description,
+    Status,
+    Priority,
+    Due,
+    DueTime,
+    Scheduled,
+    WaitUntil,
+    Tags,
+    Project,
+    Location,
+    DelegatedTo,
+    FollowUp,
+    Progress,
+    Points,
+    Estimate,
+    Spent,
+    Postponed,
+    Goal,
+    Parent,
+    Created,
+    Updated,
+}
+
+impl Column {
+    pub const ALL: [Column; 23] = [
+        Column::Id,
+        Column::Title,
+        Column::Description,
+        Column::Status,
+        Column::Priority,
+        Column::Due,
+        Column::DueTime,
+        Column::Scheduled,
+        Column::WaitUntil,
+        Column::Tags,
+        Column::Project,
+        Column::Location,
+        Column::DelegatedTo,
+        Column::FollowUp,
+        Column::Progress,
+        Column::Points,
+        Column::Estimate,
+        Column::Spent,
+        Column::Postponed,
+        Column::Goal,
+        Column::Parent,
+        Column::Created,
+        Column::Updated,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Title => "title",
+            Column::Description => "description",
+            Column::Status => "status",
+            Column::Priority => "priority",
+            Column::Due => "due",
+            Column::DueTime => "due_time",
+            Column::Scheduled => "scheduled",
+            Column::WaitUntil => "wait_until",
+            Column::Tags => "tags",
+            Column::Project => "project",
+            Column::Location => "location",
+            Column::DelegatedTo => "delegated_to",
+            Column::FollowUp => "follow_up",
+            Column::Progress => "progress",
+            Column::Points => "points",
+            Column::Estimate => "estimate",
+            Column::Spent => "spent",
+            Column::Postponed => "postponed",
+            Column::Goal => "goal",
+            Column::Parent => "parent",
+            Column::Created => "created",
+            Column::Updated => "updated",
+        }
+    }
+
+    pub fn default_width(self) -> usize {
+        match self {
+            Column::Id => 5,
+            Column::Title => 30,
+            Column::Description => 40,
+            Column::Status => 10,
+            Column::Priority | Column::Progress => 9,
+            Column::Due | Column::Scheduled | Column::WaitUntil | Column::FollowUp | Column::Created | Column::Updated => 11,
+            Column::Tags | Column::Location => 20,
+            Column::Project | Column::DelegatedTo => 15,
+            Column::DueTime => 36,
+            Column::Points | Column::Goal | Column::Parent => 6,
+            Column::Estimate | Column::Spent => 8,
+            Column::Postponed => 10,
+        }
+    }
+}
+
+// A column and how many characters wide it is, written "title" or "title:40".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnSpec {
+    pub column: Column,
+    pub width: usize,
+}
+
+impl ColumnSpec {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (name, width) = match spec.trim().split_once(':') {
+            Some((name, width)) => (name, Some(width.parse::<usize>().ok().filter(|&w| w > 0)?)),
+            None => (spec.trim(), None),
+        };
+        let column = Column::ALL.into_iter().find(|column| column.name() == name.to_lowercase())?;
+        Some(ColumnSpec { column, width: width.unwrap_or(column.default_width()) })
+    }
+
+    // A comma-separated list such as "id,title:40,due".
+    pub fn parse_list(specs: &str) -> Option<Vec<Self>> {
+        specs.split(',').filter(|spec| !spec.trim().is_empty()).map(ColumnSpec::parse).collect()
+    }
+}
+
+impl fmt::Display for ColumnSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.column.name(), self.width)
+    }
+}
+
+impl Serialize for ColumnSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColumnSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let spec = String::deserialize(deserializer)?;
+        ColumnSpec::parse(&spec).ok_or_else(|| de::Error::custom(format!("unknown column: {}", spec)))
+    }
+}
+
+// The table `list` has always shown.
+pub fn default_columns() -> Vec<ColumnSpec> {
+    [Column::Id, Column::Title, Column::Description, Column::Status, Column::Priority, Column::Due, Column::Tags]
+        .into_iter()
+        .map(|column| ColumnSpec { column, width: column.default_width() })
+        .collect()
+}
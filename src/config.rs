@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use crate::columns::{default_columns, ColumnSpec};
+use crate::dates::DateFormat;
+use crate::keys::Keymap;
+use crate::notify::Webhook;
+use crate::status::DEFAULT_STATUS_LINE;
+use crate::validate::Limits;
+
+pub const CONFIG_FILENAME: &str = "todo_config.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Changes made within this many milliseconds of the last save are
+    // written together. Set to 0 to save after every change.
+    pub save_delay_ms: u64,
+    // Completed todos older than this many days are moved to the archive
+    // file on startup and by `gc`. Unset disables archiving.
+    pub archive_completed_after_days: Option<u32>,
+    // Pending todos left untouched for this many days are treated as one
+    // priority level higher, again for every further period.
+    pub escalate_after_days: Option<u32>,
+    // Pending todos that others wait on are listed with the priority of the
+    // most urgent of those; see `priority::inherited_priorities`.
+    pub inherit_priority: bool,
+    // Columns of the todo table, e.g. ["id", "title:40", "due"].
+    pub columns: Vec<ColumnSpec>,
+    // How dates are shown: "iso", "short", "relative" or a strftime
+    // pattern. Also set by --date-format.
+    pub date_format: DateFormat,
+    // Relay used by `digest --email`.
+    pub smtp: Option<SmtpConfig>,
+    // Endpoints told about completed and soon-due todos.
+    pub webhooks: Vec<Webhook>,
+    // Where else they are shown: "desktop", "bell", "tmux", "file:PATH" or
+    // "auto", which picks one for the session. TODO_NOTIFIERS, a
+    // comma-separated list, takes its place where it is set.
+    pub notifiers: Vec<String>,
+    // Which operations ask before going ahead.
+    pub confirm: Confirmations,
+    // Plain one-line-per-todo output without decorations or symbols, for
+    // screen readers. Also turned on by --accessible.
+    pub accessible: bool,
+    // Mark the status of each todo with ✅, ⏳ or 🔥 (done, pending,
+    // overdue), or with [x], [ ] and [!] where emoji cannot be shown.
+    pub emoji: bool,
+    // Hours of work that fit into a day; `digest` warns about days with more
+    // estimated work than this. Unset disables the warning.
+    pub daily_capacity_hours: Option<f64>,
+    // The days of the week worked, e.g. ["mon", "tue", "wed", "thu", "fri"],
+    // which business-day offsets such as `postpone 4 3b` count along with
+    // the holidays imported by `holidays import`.
+    pub workdays: Vec<Weekday>,
+    // Recurring todos whose next occurrence is on a day off are due on the
+    // next workday instead; see `workdays::Calendar::move_occurrence`.
+    pub skip_days_off: bool,
+    // What `status-line` prints, with {done}, {pending}, {due_today},
+    // {overdue}, {focus} and {pomodoro} filled in.
+    pub status_line: String,
+    // Where the todos are kept.
+    pub storage: StorageKind,
+    // The directory holding the data files; the working directory if unset.
+    pub data_dir: Option<String>,
+    // The connection string for the postgres storage, e.g.
+    // "postgres://todo@db.example.com/todos".
+    pub database_url: Option<String>,
+    // Keys of the interactive menu and of `review` that differ from the
+    // defaults.
+    pub keys: Keymap,
+    // The name comments are signed with; the login name if unset.
+    pub author: Option<String>,
+    // How `serve` limits and logs requests.
+    pub server: ServerConfig,
+    // Where `self-update` looks for new versions, and whether it may.
+    pub updates: UpdateConfig,
+    // Commands standing for others, e.g. "ls": "list --sort due"; see
+    // `aliases::expand`.
+    pub aliases: BTreeMap<String, String>,
+    // max_title_length and max_description_length, in characters.
+    #[serde(flatten)]
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    Json,
+    // Needs the `sled` feature.
+    Sled,
+    // Needs the `postgres` feature and database_url.
+    Postgres,
+    // Nothing is saved once the process ends; what --ephemeral selects.
+    Memory,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Confirmations {
+    // Deleting a todo from the menu, `delete` or `review`.
+    pub delete: bool,
+    // Deleting the todos removed in `bulk-edit`.
+    pub bulk_delete: bool,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Confirmations { delete: true, bulk_delete: true }
+    }
+}
+
+impl Confirmations {
+    // What `--yes` asks for.
+    pub fn none() -> Self {
+        Confirmations { delete: false, bulk_delete: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    // Requests one token may make per minute, or one address while the
+    // server has no tokens; null or 0 for no limit.
+    pub requests_per_minute: Option<u32>,
+    // A file to append a line of JSON to for every request, or "-" for
+    // standard output. Unset logs nothing.
+    pub request_log: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig { requests_per_minute: Some(120), request_log: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    // False turns `self-update` off, e.g. where packages are managed
+    // centrally.
+    pub enabled: bool,
+    // The GitHub repository releases are taken from, "owner/name"; the
+    // repository in Cargo.toml if unset.
+    pub repository: Option<String>,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        UpdateConfig { enabled: true, repository: None }
+    }
+}
+
+// The relay `digest --email` hands mail to. It is spoken to without STARTTLS
+// or AUTH, so it has to accept unauthenticated mail, as a local relay does.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub from: String,
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            save_delay_ms: 2000,
+            archive_completed_after_days: None,
+            escalate_after_days: None,
+            inherit_priority: false,
+            columns: default_columns(),
+            date_format: DateFormat::Iso,
+            smtp: None,
+            webhooks: Vec::new(),
+            notifiers: Vec::new(),
+            confirm: Confirmations::default(),
+            accessible: false,
+            emoji: false,
+            daily_capacity_hours: None,
+            workdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            skip_days_off: false,
+            status_line: DEFAULT_STATUS_LINE.to_string(),
+            storage: StorageKind::Json,
+            data_dir: None,
+            database_url: None,
+            keys: Keymap::default(),
+            author: None,
+            server: ServerConfig::default(),
+            updates: UpdateConfig::default(),
+            aliases: BTreeMap::new(),
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl Config {
+    // The todo file in `data_dir`; the names of the archive, history and
+    // plan files are derived from it.
+    pub fn todo_filename(&self) -> String {
+        let name = match self.storage {
+            StorageKind::Json => "todos.json",
+            StorageKind::Sled => "todos.sled",
+            StorageKind::Postgres => "todos.postgres",
+            StorageKind::Memory => "todos.memory",
+        };
+        match self.data_dir.as_deref().filter(|dir| !dir.is_empty()) {
+            Some(dir) => Path::new(dir).join(name).to_string_lossy().into_owned(),
+            None => name.to_string(),
+        }
+    }
+
+    // The daily capacity in minutes.
+    pub fn daily_capacity(&self) -> Option<u32> {
+        self.daily_capacity_hours.filter(|hours| hours.is_finite() && *hours >= 0.0).map(|hours| (hours * 60.0).round() as u32)
+    }
+
+    // Who comments made here are by.
+    pub fn author(&self) -> String {
+        self.author
+            .clone()
+            .or_else(|| env::var("USER").ok())
+            .or_else(|| env::var("USERNAME").ok())
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| "anonymous".to_string())
+    }
+
+    pub fn load_from_file(filename: &str) -> io::Result<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Config::default());
+        }
+
+        let mut file = File::open(filename)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let config: Config = serde_json::from_str(&contents)?;
+        Ok(config)
+    }
+}
@@ -0,0 +1,69 @@
+// End-to-end encryption of what is sent to a sync server. Every device of a
+// server derives the same master key from the passphrase and the server's
+// salt, and from it a key per device; a device seals its changes with its
+// own key, and the others derive that key from its label to open them.
+use std::io;
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use crate::sync::Sealed;
+
+pub type Key = [u8; 32];
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+// 128 random bits from the operating system, in hex, for secrets handed
+// out to people such as invite codes, and for what identifies sync devices.
+pub fn random_secret() -> String {
+    let mut bytes = [0; 16];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+// Deliberately slow (Argon2id), to make guessing the passphrase expensive.
+pub fn master_key(passphrase: &str, salt: &str) -> io::Result<Key> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt.as_bytes(), &mut key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    Ok(key)
+}
+
+pub fn device_key(master: &Key, device: &str) -> Key {
+    let mut key = [0; 32];
+    Hkdf::<Sha256>::new(None, master)
+        .expand(format!("todo_app sync device {}", device).as_bytes(), &mut key)
+        .expect("32 bytes is a valid HKDF output length");
+    key
+}
+
+// The device's label is authenticated along with the contents, so the
+// server cannot pass one device's changes off as another's.
+pub fn seal(key: &Key, device: &str, plaintext: &[u8]) -> Sealed {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let data = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: device.as_bytes() })
+        .expect("encrypting into memory cannot fail");
+    Sealed { nonce: to_hex(&nonce), data: to_hex(&data) }
+}
+
+// None if the contents were sealed with another key or were tampered with.
+pub fn open(key: &Key, device: &str, sealed: &Sealed) -> Option<Vec<u8>> {
+    let nonce: [u8; 24] = from_hex(&sealed.nonce)?.try_into().ok()?;
+    let data = from_hex(&sealed.data)?;
+    let cipher = XChaCha20Poly1305::new(key.into());
+    cipher.decrypt(&XNonce::from(nonce), Payload { msg: &data, aad: device.as_bytes() }).ok()
+}
@@ -0,0 +1,211 @@
+use std::fmt::{self, Write};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, Weekday};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use crate::workdays::Calendar;
+
+// Accepts "today", "tomorrow", "yesterday", ISO dates ("2026-03-14"), offsets from today
+// ("3d", "+2w", "1m") and weekdays ("friday", "next-monday"), which always
+// mean the next such day after today.
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        "yesterday" => return Some(today - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Some(date) = apply_offset(&input, today) {
+        return Some(date);
+    }
+    let weekday = input.strip_prefix("next-").unwrap_or(&input);
+    parse_weekday(weekday).map(|weekday| next_weekday(today, weekday))
+}
+
+fn split_offset(offset: &str) -> Option<(u32, &str)> {
+    let offset = offset.strip_prefix('+').unwrap_or(offset);
+    // The unit is the last character, which need not be a single byte.
+    let unit_start = offset.char_indices().last()?.0;
+    if unit_start == 0 {
+        return None;
+    }
+    let (amount, unit) = offset.split_at(unit_start);
+    Some((amount.parse().ok()?, unit))
+}
+
+// Moves `from` by an offset such as "3d", "+2w" or "1m"; None past the
+// last day chrono knows.
+pub fn apply_offset(offset: &str, from: NaiveDate) -> Option<NaiveDate> {
+    let (amount, unit) = split_offset(offset)?;
+    match unit {
+        "d" => from.checked_add_days(Days::new(u64::from(amount))),
+        "w" => from.checked_add_days(Days::new(u64::from(amount) * 7)),
+        "m" => from.checked_add_months(Months::new(amount)),
+        _ => None,
+    }
+}
+
+// Moves `from` back by an offset, e.g. "30d" for the day a month ago; None
+// before the first day chrono knows.
+pub fn offset_before(offset: &str, from: NaiveDate) -> Option<NaiveDate> {
+    let (amount, unit) = split_offset(offset)?;
+    match unit {
+        "d" => from.checked_sub_days(Days::new(u64::from(amount))),
+        "w" => from.checked_sub_days(Days::new(u64::from(amount) * 7)),
+        "m" => from.checked_sub_months(Months::new(amount)),
+        _ => None,
+    }
+}
+
+// The moment `days` days before `now`; None before the first day chrono
+// knows.
+pub fn days_before(now: DateTime<Local>, days: u32) -> Option<DateTime<Local>> {
+    now.checked_sub_signed(Duration::days(i64::from(days)))
+}
+
+// The day `postpone` moves a todo to: offsets such as "1d", "1w" or "3b"
+// (three workdays of `calendar`) count from its due date, or from today if
+// it has none or is overdue; anything else is read as by `parse_date`.
+pub fn postpone_date(input: &str, due: Option<NaiveDate>, today: NaiveDate, calendar: &Calendar) -> Option<NaiveDate> {
+    let from = due.map_or(today, |due| due.max(today));
+    if let Some(days) = business_days(input) {
+        return Some(calendar.add_workdays(from, days));
+    }
+    apply_offset(input.trim(), from).or_else(|| parse_date(input, today))
+}
+
+// "3b", "3bd", "3 business days" or "3 workdays".
+fn business_days(input: &str) -> Option<u32> {
+    let input = input.trim().to_lowercase();
+    let input = input.strip_prefix('+').unwrap_or(&input);
+    let (days, unit) = input.split_at(input.find(|c: char| !c.is_ascii_digit())?);
+    let unit = unit.trim();
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    matches!(unit, "b" | "bd" | "business day" | "workday" | "working day").then(|| days.parse().ok()).flatten()
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { i64::from(ahead) })
+}
+
+// How dates are shown in lists and details: "iso" (2026-10-14), "short"
+// (Oct 14, with the year only when it is not this one), "relative" (today,
+// in 3 days, 2 weeks ago) or a strftime pattern such as "%d.%m.%Y".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum DateFormat {
+    #[default]
+    Iso,
+    Short,
+    Relative,
+    Pattern(String),
+}
+
+impl DateFormat {
+    pub const PRESETS: [&'static str; 3] = ["iso", "short", "relative"];
+
+    // None for a pattern chrono cannot read.
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.trim().to_lowercase().as_str() {
+            "iso" => Some(DateFormat::Iso),
+            "short" => Some(DateFormat::Short),
+            "relative" => Some(DateFormat::Relative),
+            _ if format.contains('%') && !StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) => {
+                Some(DateFormat::Pattern(format.to_string()))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn date(&self, day: NaiveDate, today: NaiveDate) -> String {
+        match self {
+            DateFormat::Iso => day.to_string(),
+            DateFormat::Short if day.year() == today.year() => day.format("%b %-d").to_string(),
+            DateFormat::Short => day.format("%b %-d %Y").to_string(),
+            DateFormat::Relative => relative(day, today),
+            // Time fields of a date read as midnight; fields it cannot have
+            // at all, such as the time zone, fall back to ISO.
+            DateFormat::Pattern(pattern) => {
+                let mut out = String::new();
+                match day.and_hms_opt(0, 0, 0).map(|midnight| write!(out, "{}", midnight.format(pattern))) {
+                    Some(Ok(())) => out,
+                    _ => day.to_string(),
+                }
+            },
+        }
+    }
+
+    // Timestamps such as when a todo was created: the presets show the day,
+    // a pattern may show the time as well.
+    pub fn timestamp(&self, at: DateTime<Local>, today: NaiveDate) -> String {
+        match self {
+            DateFormat::Pattern(pattern) => {
+                let mut out = String::new();
+                match write!(out, "{}", at.format(pattern)) {
+                    Ok(()) => out,
+                    Err(_) => at.date_naive().to_string(),
+                }
+            },
+            preset => preset.date(at.date_naive(), today),
+        }
+    }
+}
+
+fn relative(day: NaiveDate, today: NaiveDate) -> String {
+    let days = (day - today).num_days();
+    let (amount, unit) = match days.abs() {
+        0 => return "today".to_string(),
+        1 if days > 0 => return "tomorrow".to_string(),
+        1 => return "yesterday".to_string(),
+        n if n < 14 => (n, "day"),
+        n if n < 60 => (n / 7, "week"),
+        n if n < 730 => (n / 30, "month"),
+        n => (n / 365, "year"),
+    };
+    let unit = if amount == 1 { unit.to_string() } else { format!("{}s", unit) };
+    if days > 0 {
+        format!("in {} {}", amount, unit)
+    } else {
+        format!("{} {} ago", amount, unit)
+    }
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DateFormat::Iso => write!(f, "iso"),
+            DateFormat::Short => write!(f, "short"),
+            DateFormat::Relative => write!(f, "relative"),
+            DateFormat::Pattern(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+impl Serialize for DateFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DateFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = String::deserialize(deserializer)?;
+        DateFormat::parse(&format).ok_or_else(|| de::Error::custom(format!("unknown date format: {}", format)))
+    }
+}
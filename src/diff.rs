@@ -0,0 +1,54 @@
+use std::collections::BTreeSet;
+use serde_json::Value;
+use crate::todo::{Todo, TodoList};
+
+#[derive(Debug)]
+pub enum TodoChange {
+    Added(Todo),
+    Removed(Todo),
+    Modified {
+        id: usize,
+        title: String,
+        // Field name with its old and new value.
+        fields: Vec<(String, Value, Value)>,
+    },
+}
+
+// Differences between two fields of the same todo, ignoring `updated_at`
+// and `field_times`, which change along with everything else.
+pub fn field_changes(old: &Todo, new: &Todo) -> Vec<(String, Value, Value)> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !matches!(key.as_str(), "updated_at" | "field_times"))
+        .filter_map(|key| {
+            let before = old.get(key).cloned().unwrap_or(Value::Null);
+            let after = new.get(key).cloned().unwrap_or(Value::Null);
+            (before != after).then(|| (key.clone(), before, after))
+        })
+        .collect()
+}
+
+// What changed going from `old` to `new`, matching todos by id.
+pub fn diff(old: &TodoList, new: &TodoList) -> Vec<TodoChange> {
+    let mut changes = Vec::new();
+    for todo in old.todos() {
+        match new.get_todo(todo.id) {
+            None => changes.push(TodoChange::Removed(todo.clone())),
+            Some(other) => {
+                let fields = field_changes(todo, other);
+                if !fields.is_empty() {
+                    changes.push(TodoChange::Modified { id: todo.id, title: other.title.clone(), fields });
+                }
+            }
+        }
+    }
+    for todo in new.todos() {
+        if old.get_todo(todo.id).is_none() {
+            changes.push(TodoChange::Added(todo.clone()));
+        }
+    }
+    changes
+}
@@ -0,0 +1,147 @@
+use chrono::NaiveDate;
+use crate::estimates::{self, format_minutes};
+use crate::todo::{Todo, TodoList};
+
+// Pending todos that need attention on a given day. Habits have no due date
+// in the usual sense and are left out. Todos that are due count as due even
+// when also scheduled.
+pub struct Digest<'a> {
+    pub today: NaiveDate,
+    pub overdue: Vec<&'a Todo>,
+    pub due_today: Vec<&'a Todo>,
+    pub scheduled: Vec<&'a Todo>,
+    // Delegated todos whose follow-up day has come.
+    pub follow_ups: Vec<&'a Todo>,
+    // Every pending todo, whether it needs attention today or not.
+    pub pending: usize,
+    // Days of the coming week with more estimated work than the daily
+    // capacity, once `check_capacity` has been called.
+    pub capacity: Option<u32>,
+    pub over_capacity: Vec<(NaiveDate, u32)>,
+}
+
+impl<'a> Digest<'a> {
+    pub fn collect(todo_list: &'a TodoList, today: NaiveDate) -> Self {
+        let mut overdue: Vec<&Todo> = Vec::new();
+        let mut due_today = Vec::new();
+        let mut scheduled: Vec<&Todo> = Vec::new();
+        let mut follow_ups: Vec<&Todo> = Vec::new();
+        let mut pending = 0;
+        for todo in todo_list.todos().iter().filter(|t| !t.completed && !t.habit) {
+            pending += 1;
+            match todo.due {
+                Some(due) if due < today => overdue.push(todo),
+                Some(due) if due == today => due_today.push(todo),
+                _ if todo.scheduled.is_some_and(|day| day <= today) => scheduled.push(todo),
+                _ => {}
+            }
+            if todo.follow_up_due(today) {
+                follow_ups.push(todo);
+            }
+        }
+        overdue.sort_by_key(|todo| todo.due);
+        scheduled.sort_by_key(|todo| todo.scheduled);
+        follow_ups.sort_by_key(|todo| todo.follow_up);
+        Digest { today, overdue, due_today, scheduled, follow_ups, pending, capacity: None, over_capacity: Vec::new() }
+    }
+
+    // Looks for over-committed days in the week starting today, given how
+    // many minutes of work fit into a day.
+    pub fn check_capacity(&mut self, todo_list: &TodoList, capacity: u32) {
+        self.capacity = Some(capacity);
+        self.over_capacity = estimates::over_capacity(todo_list.todos(), self.today, 7, capacity);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overdue.is_empty()
+            && self.due_today.is_empty()
+            && self.scheduled.is_empty()
+            && self.follow_ups.is_empty()
+            && self.over_capacity.is_empty()
+    }
+
+    pub fn subject(&self) -> String {
+        format!(
+            "Todo digest for {}: {} scheduled, {} due today, {} overdue",
+            self.today,
+            self.scheduled.len(),
+            self.due_today.len(),
+            self.overdue.len()
+        )
+    }
+
+    // One line for a quick look, e.g. "3 overdue, 5 due today, 12 pending".
+    pub fn summary(&self) -> String {
+        if self.pending == 0 {
+            return "Nothing pending.".to_string();
+        }
+        format!("{} overdue, {} due today, {} pending", self.overdue.len(), self.due_today.len(), self.pending)
+    }
+
+    pub fn body(&self) -> String {
+        let mut out = String::new();
+        if self.is_empty() {
+            out.push_str("Nothing is scheduled, due today, overdue or to follow up on.\n");
+            return out;
+        }
+        let section = |out: &mut String, title: &str| {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(title);
+        };
+        if let (Some(capacity), false) = (self.capacity, self.over_capacity.is_empty()) {
+            section(&mut out, &format!("Over capacity ({} a day):\n", format_minutes(capacity)));
+            for (day, minutes) in &self.over_capacity {
+                out.push_str(&format!("  {} {} has {} of estimated work\n", day.format("%A"), day, format_minutes(*minutes)));
+            }
+        }
+        if !self.due_today.is_empty() {
+            section(&mut out, "Due today:\n");
+            for todo in &self.due_today {
+                out.push_str(&format!("  #{} {}\n", todo.id, todo.title));
+            }
+        }
+        if !self.overdue.is_empty() {
+            section(&mut out, "Overdue:\n");
+            for todo in &self.overdue {
+                let due = todo.due.unwrap_or(self.today);
+                out.push_str(&format!("  #{} {} (due {}, {} days ago)\n", todo.id, todo.title, due, (self.today - due).num_days()));
+            }
+        }
+        if !self.scheduled.is_empty() {
+            section(&mut out, "Scheduled to work on:\n");
+            for todo in &self.scheduled {
+                let mut notes = Vec::new();
+                if let Some(day) = todo.scheduled.filter(|&day| day < self.today) {
+                    notes.push(format!("since {}", day));
+                }
+                if let Some(due) = todo.due {
+                    notes.push(format!("due {}", due));
+                }
+                let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+                out.push_str(&format!("  #{} {}{}\n", todo.id, todo.title, notes));
+            }
+        }
+        if !self.follow_ups.is_empty() {
+            section(&mut out, "Follow up with:\n");
+            for todo in &self.follow_ups {
+                let person = todo.delegated_to.as_deref().unwrap_or("");
+                out.push_str(&format!("  #{} {} ({})\n", todo.id, todo.title, person));
+            }
+        }
+        out
+    }
+}
+
+// Pending todos due on or before `last_day`, overdue ones included, soonest
+// first.
+pub fn due_by(todo_list: &TodoList, last_day: NaiveDate) -> Vec<&Todo> {
+    let mut todos: Vec<&Todo> = todo_list
+        .todos()
+        .iter()
+        .filter(|t| !t.completed && !t.habit && t.due.is_some_and(|due| due <= last_day))
+        .collect();
+    todos.sort_by_key(|todo| todo.due);
+    todos
+}
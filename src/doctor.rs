@@ -0,0 +1,307 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+use chrono::Local;
+use serde_json::{Map, Value};
+use todo_app::aliases;
+use todo_app::archive;
+use todo_app::config::{Config, StorageKind, CONFIG_FILENAME};
+use todo_app::fsck;
+use todo_app::history;
+use todo_app::recover;
+use todo_app::storage;
+use todo_app::sync;
+use todo_app::tokens;
+use todo_app::users;
+use crate::notifiers;
+use crate::ui::heading;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Level {
+    Ok,
+    Warning,
+    Problem,
+}
+
+struct Section {
+    title: &'static str,
+    findings: Vec<(Level, String, Option<String>)>,
+}
+
+impl Section {
+    fn new(title: &'static str) -> Self {
+        Section { title, findings: Vec::new() }
+    }
+
+    fn ok(&mut self, what: impl Into<String>) {
+        self.findings.push((Level::Ok, what.into(), None));
+    }
+
+    fn warn(&mut self, what: impl Into<String>, fix: impl Into<String>) {
+        self.findings.push((Level::Warning, what.into(), Some(fix.into())));
+    }
+
+    fn problem(&mut self, what: impl Into<String>, fix: impl Into<String>) {
+        self.findings.push((Level::Problem, what.into(), Some(fix.into())));
+    }
+}
+
+fn check_config(config: &Config) -> Section {
+    let mut section = Section::new("Configuration");
+    let contents = match fs::read_to_string(CONFIG_FILENAME) {
+        Ok(contents) => contents,
+        Err(_) if !Path::new(CONFIG_FILENAME).exists() => {
+            section.ok(format!("No {}; the defaults are used.", CONFIG_FILENAME));
+            return section;
+        },
+        Err(e) => {
+            section.problem(format!("{} cannot be read: {}.", CONFIG_FILENAME, e), format!("Check the permissions of {}.", CONFIG_FILENAME));
+            return section;
+        },
+    };
+    // The same error the settings were dropped for when starting.
+    if let Err(e) = serde_json::from_str::<Config>(&contents) {
+        section.problem(
+            format!("{} is not valid: {}. Every setting is at its default.", CONFIG_FILENAME, e),
+            "Correct the line given, or move the file aside and run `init`.",
+        );
+        return section;
+    }
+    section.ok(format!("{} is valid.", CONFIG_FILENAME));
+    // Misspelt settings are ignored rather than refused.
+    let known = serde_json::to_value(Config::default()).ok().and_then(|value| value.as_object().cloned()).unwrap_or_default();
+    let given = serde_json::from_str::<Map<String, Value>>(&contents).unwrap_or_default();
+    for key in given.keys().filter(|key| !known.contains_key(*key)) {
+        section.warn(format!("Unknown setting \"{}\" is ignored.", key), "Check its spelling against the Configuration section of the README.");
+    }
+    for conflict in config.keys.conflicts() {
+        section.problem(format!("The {}.", conflict), "Give each action its own key under keys.");
+    }
+    for (name, line) in &config.aliases {
+        if aliases::split_words(line).is_none() {
+            section.problem(format!("The alias {} has a quote that is never closed.", name), format!("Close the quote in aliases.{}.", name));
+        }
+    }
+    match config.storage {
+        StorageKind::Sled if !cfg!(feature = "sled") => {
+            section.problem("storage is sled, which this build cannot use.", "Build with `--features sled`, or set storage to json.")
+        },
+        StorageKind::Postgres if !cfg!(feature = "postgres") => {
+            section.problem("storage is postgres, which this build cannot use.", "Build with `--features postgres`, or set storage to json.")
+        },
+        StorageKind::Postgres if config.database_url.is_none() => {
+            section.problem("storage is postgres, but there is no database_url.", "Set database_url, e.g. postgres://todo@db.example.com/todos.")
+        },
+        _ => {},
+    }
+    if let Some(dir) = &config.data_dir {
+        if !Path::new(dir).is_dir() {
+            section.problem(format!("data_dir {} is not a directory.", dir), format!("Create it with `mkdir -p {}`, or run `init`.", dir));
+        }
+    }
+    if config.daily_capacity_hours.is_some() && config.daily_capacity().is_none() {
+        section.warn("daily_capacity_hours is not a positive number, so no day counts as too full.", "Set it to the hours of work in a day, e.g. 6.");
+    }
+    section
+}
+
+// Files others should not be able to read: the sync key, and the hashes
+// the sync server checks invite codes and tokens against.
+fn check_permissions(filename: &str) -> Section {
+    let mut section = Section::new("Permissions");
+    let dir = Path::new(filename).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    match fs::metadata(dir) {
+        Ok(meta) if meta.permissions().readonly() => section.problem(
+            format!("{} is read-only, so no save can replace {}.", dir.display(), filename),
+            format!("Make it writable, e.g. `chmod u+w {}`.", dir.display()),
+        ),
+        Ok(_) => {},
+        Err(e) => section.problem(format!("{} cannot be read: {}.", dir.display(), e), "Create the directory or set data_dir to one that exists."),
+    }
+    if Path::new(filename).is_file() {
+        match OpenOptions::new().append(true).open(filename) {
+            Ok(_) => section.ok(format!("{} can be written.", filename)),
+            Err(e) => section.problem(format!("{} cannot be written: {}.", filename, e), format!("Make it writable, e.g. `chmod u+w {}`.", filename)),
+        }
+    }
+    for secret in [sync::sync_filename(filename), tokens::tokens_filename(filename), users::users_filename(filename)] {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(meta) = fs::metadata(&secret) {
+                if meta.permissions().mode() & 0o077 != 0 {
+                    section.problem(format!("{} can be read by other users.", secret), format!("Run `chmod 600 {}`.", secret));
+                } else {
+                    section.ok(format!("{} is private.", secret));
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = secret;
+    }
+    section
+}
+
+fn check_data(filename: &str) -> Section {
+    let mut section = Section::new("Data");
+    // A save that was cut short before its rename leaves the temporary
+    // file behind, next to a list that is still whole.
+    let tmp = format!("{}.tmp", filename);
+    if Path::new(&tmp).exists() {
+        section.warn(format!("{} was left behind by a save that did not finish.", tmp), format!("{} is complete without it; remove it with `rm {}`.", filename, tmp));
+    }
+    match storage::open(filename).and_then(|store| store.load()) {
+        Ok(todo_list) => {
+            section.ok(format!("{} holds {} todos.", filename, todo_list.todos().len()));
+            let problems = fsck::check(&todo_list);
+            if problems.is_empty() {
+                section.ok("No inconsistencies in the todos.");
+            } else {
+                section.problem(format!("{} inconsistencies in the todos, the first: {}", problems.len(), problems[0]), "Run `fsck` to see them and `fsck --fix` to repair them.");
+            }
+        },
+        Err(e) if recover::is_corrupt(&e) => {
+            section.problem(format!("{} is damaged: {}.", filename, e), "Run `recover` to salvage the todos that can still be read.")
+        },
+        Err(e) if storage::is_sled(filename) => section.problem(
+            format!("{} cannot be opened: {}.", filename, e),
+            "Another todo_app is probably using it; wait for it to finish or stop it.",
+        ),
+        Err(e) => section.problem(format!("{} cannot be opened: {}.", filename, e), "Check database_url and that the database is running."),
+    }
+    if let Err(e) = archive::load_archive(&archive::archive_filename(filename)) {
+        section.problem(format!("The archive cannot be read: {}.", e), format!("Move {} aside; it is not needed to use the list.", archive::archive_filename(filename)));
+    }
+    let history = history::history_filename(filename);
+    if let Err(e) = history::load_events(&history) {
+        section.warn(format!("The history cannot be read: {}.", e), format!("`log` and `activity` need it; move {} aside to start a new one.", history));
+    }
+    section
+}
+
+fn reachable(host: &str, port: u16) -> Result<(), String> {
+    let addr = (host, port).to_socket_addrs().map_err(|e| format!("{} cannot be found: {}", host, e))?.next();
+    let addr = addr.ok_or_else(|| format!("{} has no address", host))?;
+    TcpStream::connect_timeout(&addr, Duration::from_secs(3)).map(|_| ()).map_err(|e| format!("{}:{} does not answer: {}", host, port, e))
+}
+
+// "https://hooks.example.com:8443/x" is hooks.example.com, port 8443.
+fn host_and_port(url: &str) -> Option<(&str, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let default = if scheme == "http" { 80 } else { 443 };
+    // An IPv6 address is written in brackets, as in http://[::1]:8080/.
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, after) = bracketed.split_once(']')?;
+            (host, after.strip_prefix(':'))
+        },
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default,
+    };
+    Some((host, port)).filter(|(host, _)| !host.is_empty())
+}
+
+fn check_connections(filename: &str, config: &Config) -> Section {
+    let mut section = Section::new("Connections");
+    match sync::load_state(&sync::sync_filename(filename)) {
+        Ok(Some(state)) => match sync::server_seq(&state) {
+            Ok(seq) => section.ok(format!("The sync server {} answers; it holds {} changes, {} of them not pulled yet.", state.remote, seq, seq.saturating_sub(state.seq))),
+            Err(e) => section.problem(format!("Syncing fails: {}.", e), "Check that `serve --sync` runs there, or register again with `sync remote URL`."),
+        },
+        Ok(None) => {},
+        Err(e) => section.problem(format!("The sync settings cannot be read: {}.", e), "Register again with `sync remote URL`."),
+    }
+    if let Some(smtp) = &config.smtp {
+        match reachable(&smtp.host, smtp.port) {
+            Ok(()) => section.ok(format!("The SMTP relay {}:{} answers.", smtp.host, smtp.port)),
+            Err(e) => section.problem(format!("`digest --email` cannot send: {}.", e), "Check smtp.host and smtp.port."),
+        }
+    }
+    for webhook in &config.webhooks {
+        let Some((host, port)) = host_and_port(&webhook.url) else {
+            section.problem(format!("The webhook {} is not a URL.", webhook.url), "Give it as https://host/path.");
+            continue;
+        };
+        match reachable(host, port) {
+            Ok(()) => section.ok(format!("The webhook host {} answers.", host)),
+            Err(e) => section.warn(format!("The webhook {} cannot be reached: {}.", webhook.url, e), "Check the URL, or the network if it is usually reachable."),
+        }
+    }
+    for spec in notifiers::specs(config).iter().filter(|spec| notifiers::parse(spec).is_none()) {
+        section.warn(
+            format!("The notifier \"{}\" is not known and is left out.", spec),
+            "Use desktop, bell, tmux, file:PATH or auto, in notifiers or TODO_NOTIFIERS.",
+        );
+    }
+    if section.findings.is_empty() {
+        section.ok("No sync server, SMTP relay or webhooks to reach.");
+    }
+    section
+}
+
+// Local time comes from TZ, or the system's /etc/localtime; a TZ naming no
+// known zone makes every time UTC without a word.
+fn check_time_zone() -> Section {
+    let mut section = Section::new("Time zone");
+    let now = Local::now();
+    let tz = env::var("TZ").ok().filter(|tz| !tz.is_empty());
+    if let Some(tz) = &tz {
+        let name = tz.trim_start_matches(':');
+        let zoneinfo = env::var("TZDIR").unwrap_or_else(|_| "/usr/share/zoneinfo".to_string());
+        let names_zone = name.contains('/') || name.chars().all(|c| c.is_ascii_alphabetic());
+        if cfg!(unix) && names_zone && !Path::new(name).is_absolute() && !Path::new(&zoneinfo).join(name).exists() && name != "UTC" {
+            section.problem(
+                format!("TZ is {}, which names no time zone in {}, so times are in UTC.", tz, zoneinfo),
+                "Set TZ to a zone such as Europe/Berlin, or unset it to use the system's.",
+            );
+            return section;
+        }
+    } else if cfg!(unix) && !Path::new("/etc/localtime").exists() {
+        section.warn("Neither TZ nor /etc/localtime is set, so times are in UTC.", "Set TZ to your time zone, e.g. `export TZ=Europe/Berlin`.");
+        return section;
+    }
+    section.ok(format!("Local time is {} (UTC{}){}.", now.format("%Y-%m-%d %H:%M"), now.format("%:z"), tz.map(|tz| format!(", from TZ={}", tz)).unwrap_or_default()));
+    section
+}
+
+// Looks over the configuration, the data files and what the list connects
+// to, printing what is wrong and how to fix it. Returns whether anything
+// needs fixing; warnings do not count.
+pub fn run(filename: &str, config: &Config, accessible: bool) -> bool {
+    let sections = [check_config(config), check_permissions(filename), check_data(filename), check_connections(filename, config), check_time_zone()];
+    let mut counts = [0; 3];
+    for (n, section) in sections.iter().enumerate() {
+        if n > 0 {
+            println!();
+        }
+        heading(section.title, accessible);
+        for (level, what, fix) in &section.findings {
+            let (label, count) = match level {
+                Level::Ok => ("ok", &mut counts[0]),
+                Level::Warning => ("warning", &mut counts[1]),
+                Level::Problem => ("problem", &mut counts[2]),
+            };
+            *count += 1;
+            println!("{:<8} {}", label, what);
+            if let Some(fix) = fix {
+                println!("{:<8} Fix: {}", "", fix);
+            }
+        }
+    }
+    println!();
+    match counts {
+        [_, 0, 0] => println!("Everything looks fine."),
+        [_, warnings, problems] => println!("Problems: {}, warnings: {}.", problems, warnings),
+    }
+    counts[2] > 0
+}
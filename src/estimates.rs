@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use chrono::{Duration, NaiveDate};
+use crate::todo::Todo;
+
+// Accepts minutes ("45", "45m"), hours ("2h", "1.5h") or both ("1h30m").
+pub fn parse_minutes(input: &str) -> Option<u32> {
+    let input = input.trim().to_lowercase();
+    if let Ok(minutes) = input.parse::<u32>() {
+        return Some(minutes);
+    }
+    let (hours, minutes) = match input.split_once('h') {
+        Some((hours, minutes)) => (hours, minutes),
+        None => ("", input.as_str()),
+    };
+    if hours.is_empty() && minutes.is_empty() {
+        return None;
+    }
+    let hours: f64 = if hours.is_empty() { 0.0 } else { hours.parse().ok()? };
+    let minutes: u32 = match minutes.strip_suffix('m') {
+        Some(minutes) => minutes.parse().ok()?,
+        None if minutes.is_empty() => 0,
+        None => return None,
+    };
+    if !hours.is_finite() || hours < 0.0 {
+        return None;
+    }
+    u32::try_from((hours * 60.0).round() as u64 + u64::from(minutes)).ok()
+}
+
+// "45m", "2h" or "1h30m".
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{:02}m", h, m),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Accuracy {
+    pub estimated: u32,
+    pub spent: u32,
+}
+
+impl Accuracy {
+    // How many times the estimate the work actually took.
+    pub fn ratio(&self) -> f64 {
+        f64::from(self.spent) / f64::from(self.estimated)
+    }
+
+    // How far off the estimate was, the same for taking twice and half as
+    // long.
+    pub fn error(&self) -> f64 {
+        self.ratio().ln().abs()
+    }
+}
+
+fn accuracy(todo: &Todo) -> Option<Accuracy> {
+    let estimated = todo.estimate.filter(|&minutes| minutes > 0)?;
+    (todo.spent > 0).then_some(Accuracy { estimated, spent: todo.spent })
+}
+
+// Every todo with both an estimate and time spent on it, worst estimated
+// first.
+pub fn by_todo<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> Vec<(&'a Todo, Accuracy)> {
+    let mut rows: Vec<(&Todo, Accuracy)> = todos.into_iter().filter_map(|todo| Some((todo, accuracy(todo)?))).collect();
+    rows.sort_by(|a, b| b.1.error().total_cmp(&a.1.error()));
+    rows
+}
+
+// The same totalled per tag, with untagged todos under "". A todo with
+// several tags counts towards each of them.
+pub fn by_tag<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> Vec<(String, Accuracy)> {
+    let mut totals: BTreeMap<String, Accuracy> = BTreeMap::new();
+    for (todo, accuracy) in by_todo(todos) {
+        let names: Vec<String> = if todo.tags.is_empty() {
+            vec![String::new()]
+        } else {
+            todo.tags.iter().map(|tag| tag.to_string()).collect()
+        };
+        for name in names {
+            let total = totals.entry(name).or_default();
+            total.estimated += accuracy.estimated;
+            total.spent += accuracy.spent;
+        }
+    }
+    let mut rows: Vec<(String, Accuracy)> = totals.into_iter().collect();
+    rows.sort_by(|a, b| b.1.error().total_cmp(&a.1.error()));
+    rows
+}
+
+// Estimated minutes still to go on a pending todo.
+pub fn remaining(todo: &Todo) -> u32 {
+    todo.estimate.unwrap_or(0).saturating_sub(todo.spent)
+}
+
+// Estimated work left for each of the `days` days from today. A todo's work
+// falls on its scheduled day, or on its due day if it is not scheduled; work
+// from days already past falls on today.
+pub fn daily_load<'a>(todos: impl IntoIterator<Item = &'a Todo>, today: NaiveDate, days: usize) -> Vec<(NaiveDate, u32)> {
+    let mut load: Vec<(NaiveDate, u32)> = (0..days).map(|d| (today + Duration::days(d as i64), 0)).collect();
+    for todo in todos.into_iter().filter(|todo| !todo.completed && !todo.habit) {
+        let Some(day) = todo.scheduled.or(todo.due) else {
+            continue;
+        };
+        let d = (day - today).num_days().max(0) as usize;
+        if let Some((_, minutes)) = load.get_mut(d) {
+            *minutes += remaining(todo);
+        }
+    }
+    load
+}
+
+// The days among those with more estimated work than `capacity` minutes.
+pub fn over_capacity<'a>(todos: impl IntoIterator<Item = &'a Todo>, today: NaiveDate, days: usize, capacity: u32) -> Vec<(NaiveDate, u32)> {
+    daily_load(todos, today, days).into_iter().filter(|&(_, minutes)| minutes > capacity).collect()
+}
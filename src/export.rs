@@ -0,0 +1,354 @@
+use std::io;
+use crate::todo::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnippetFormat {
+    Json,
+    Markdown,
+}
+
+impl SnippetFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(SnippetFormat::Json),
+            "markdown" | "md" => Some(SnippetFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+// A single todo in a form that can be pasted elsewhere. The JSON form can be
+// read back with `parse_snippet`.
+pub fn todo_snippet(todo: &Todo, format: SnippetFormat) -> io::Result<String> {
+    match format {
+        SnippetFormat::Json => Ok(serde_json::to_string_pretty(todo)? + "\n"),
+        SnippetFormat::Markdown => Ok(todo_markdown(todo)),
+    }
+}
+
+fn todo_markdown(todo: &Todo) -> String {
+    let mark = match (todo.completed, todo.cancelled) {
+        (_, true) => "-",
+        (true, false) => "x",
+        (false, false) => " ",
+    };
+    let mut out = format!("## [{}] {}\n\n", mark, todo.title);
+    if !todo.description.is_empty() {
+        out.push_str(&format!("{}\n\n", todo.description));
+    }
+    match (&todo.note, todo.cancelled) {
+        (Some(note), true) => out.push_str(&format!("- Cancelled: {}\n", note)),
+        (None, true) => out.push_str("- Cancelled\n"),
+        (Some(note), false) => out.push_str(&format!("- Note: {}\n", note)),
+        (None, false) => {}
+    }
+    if let Some(priority) = todo.priority {
+        out.push_str(&format!("- Priority: {}\n", priority));
+    }
+    match (todo.due, &todo.deadline) {
+        (_, Some(deadline)) => out.push_str(&format!("- Due: {} {}\n", deadline.at.date_naive(), deadline)),
+        (Some(due), None) => out.push_str(&format!("- Due: {}\n", due)),
+        (None, None) => {}
+    }
+    if let Some(points) = todo.points {
+        out.push_str(&format!("- Points: {}\n", points));
+    }
+    if let Some(recurrence) = &todo.recurrence {
+        out.push_str(&format!("- Repeats: {}\n", recurrence.rule));
+    }
+    if let Some(day) = todo.scheduled {
+        out.push_str(&format!("- Scheduled: {}\n", day));
+    }
+    if let Some(day) = todo.wait_until {
+        out.push_str(&format!("- Waiting until: {}\n", day));
+    }
+    if let Some(progress) = todo.progress {
+        out.push_str(&format!("- Progress: {}%\n", progress));
+    }
+    if let Some(project) = &todo.project {
+        out.push_str(&format!("- Project: {}\n", project));
+    }
+    if let Some(location) = &todo.location {
+        out.push_str(&format!("- Location: {}\n", location));
+    }
+    if let Some(person) = &todo.delegated_to {
+        match todo.delegated_on {
+            Some(on) => out.push_str(&format!("- Delegated to: {} on {}\n", person, on)),
+            None => out.push_str(&format!("- Delegated to: {}\n", person)),
+        }
+    }
+    if let Some(day) = todo.follow_up {
+        out.push_str(&format!("- Follow up: {}\n", day));
+    }
+    if !todo.related.is_empty() {
+        let related: Vec<String> = todo.related.iter().map(|id| format!("#{}", id)).collect();
+        out.push_str(&format!("- Related to: {}\n", related.join(", ")));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<&str> = todo.tags.iter().map(|tag| tag.as_str()).collect();
+        out.push_str(&format!("- Tags: {}\n", tags.join(", ")));
+    }
+    out.push_str(&format!("- Created: {}\n", todo.created_at.format("%Y-%m-%d %H:%M")));
+    for comment in &todo.comments {
+        out.push_str(&format!("- Comment by {} on {}: {}\n", comment.author, comment.at.format("%Y-%m-%d %H:%M"), comment.text));
+    }
+    out
+}
+
+// The compact JSON form, small enough to fit in a QR code for most todos.
+pub fn todo_compact_json(todo: &Todo) -> io::Result<String> {
+    Ok(serde_json::to_string(todo)?)
+}
+
+// Accepts a single exported todo or a JSON array of them.
+pub fn parse_snippet(input: &str) -> io::Result<Vec<Todo>> {
+    if input.trim_start().starts_with('[') {
+        Ok(serde_json::from_str(input)?)
+    } else {
+        Ok(vec![serde_json::from_str(input)?])
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::export_sqlite;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::fs::{self, File};
+    use std::io::{self, Read};
+    use std::path::Path;
+    use chrono::{DateTime, Local, SecondsFormat};
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode};
+    use sqlx::{Connection, Executor};
+    use crate::history::Event;
+    use crate::todo::{Todo, TodoList};
+
+    // One row per todo, with a table of its own for each list a todo has.
+    // Times are ISO 8601 text with the offset, which SQLite's date and time
+    // functions understand.
+    const SCHEMA: &str = "
+        CREATE TABLE todos (
+            id INTEGER PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            status TEXT NOT NULL,
+            archived INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            completed_at TEXT,
+            due TEXT,
+            due_at TEXT,
+            due_zone TEXT,
+            scheduled TEXT,
+            wait_until TEXT,
+            priority TEXT,
+            important INTEGER NOT NULL,
+            project TEXT,
+            location TEXT,
+            goal_id INTEGER,
+            parent_id INTEGER,
+            points INTEGER,
+            estimate_minutes INTEGER,
+            spent_minutes INTEGER NOT NULL,
+            progress INTEGER,
+            habit INTEGER NOT NULL,
+            recurrence TEXT,
+            inbox INTEGER NOT NULL,
+            note TEXT,
+            delegated_to TEXT,
+            delegated_on TEXT,
+            follow_up TEXT
+        );
+        CREATE TABLE tags (todo_id INTEGER NOT NULL, tag TEXT NOT NULL);
+        CREATE TABLE dependencies (todo_id INTEGER NOT NULL, depends_on INTEGER NOT NULL);
+        CREATE TABLE related (todo_id INTEGER NOT NULL, related_id INTEGER NOT NULL);
+        CREATE TABLE habit_log (todo_id INTEGER NOT NULL, day TEXT NOT NULL);
+        CREATE TABLE comments (todo_id INTEGER NOT NULL, at TEXT NOT NULL, author TEXT NOT NULL, text TEXT NOT NULL, reply_to TEXT);
+        CREATE TABLE goals (id INTEGER PRIMARY KEY, title TEXT NOT NULL, created_at TEXT NOT NULL);
+        CREATE TABLE deleted (todo_id INTEGER NOT NULL, created_at TEXT NOT NULL, deleted_at TEXT NOT NULL);
+        CREATE TABLE events (
+            seq INTEGER PRIMARY KEY,
+            at TEXT NOT NULL,
+            todo_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            detail TEXT,
+            author TEXT
+        );
+        CREATE INDEX tags_by_todo ON tags (todo_id);
+        CREATE INDEX events_by_todo ON events (todo_id, at);
+    ";
+
+    fn db_error(e: sqlx::Error) -> io::Error {
+        io::Error::other(e)
+    }
+
+    fn time(at: DateTime<Local>) -> String {
+        at.to_rfc3339_opts(SecondsFormat::Millis, false)
+    }
+
+    // Whether `path` holds something other than an SQLite database, which
+    // is then not replaced.
+    fn is_other_file(path: &str) -> io::Result<bool> {
+        if !Path::new(path).exists() {
+            return Ok(false);
+        }
+        let mut header = [0u8; 16];
+        match File::open(path)?.read_exact(&mut header) {
+            Ok(()) => Ok(&header != b"SQLite format 3\0"),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(fs::metadata(path)?.len() > 0),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn insert_todo(db: &mut SqliteConnection, todo: &Todo, archived: bool) -> Result<bool, sqlx::Error> {
+        let status = if todo.cancelled {
+            "cancelled"
+        } else if todo.completed {
+            "completed"
+        } else {
+            "pending"
+        };
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO todos VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(todo.id as i64)
+        .bind(&todo.title)
+        .bind(&todo.description)
+        .bind(status)
+        .bind(archived)
+        .bind(time(todo.created_at))
+        .bind(time(todo.updated_at))
+        .bind(todo.completed_at.map(time))
+        .bind(todo.due.map(|day| day.to_string()))
+        .bind(todo.deadline.as_ref().map(|deadline| deadline.at.to_rfc3339_opts(SecondsFormat::Secs, false)))
+        .bind(todo.deadline.as_ref().map(|deadline| deadline.zone.clone()))
+        .bind(todo.scheduled.map(|day| day.to_string()))
+        .bind(todo.wait_until.map(|day| day.to_string()))
+        .bind(todo.priority.map(|priority| priority.to_string()))
+        .bind(todo.important)
+        .bind(&todo.project)
+        .bind(&todo.location)
+        .bind(todo.goal.map(|id| id as i64))
+        .bind(todo.parent.map(|id| id as i64))
+        .bind(todo.points)
+        .bind(todo.estimate)
+        .bind(todo.spent)
+        .bind(todo.progress.map(i64::from))
+        .bind(todo.habit)
+        .bind(todo.recurrence.as_ref().map(|recurrence| recurrence.rule.to_string()))
+        .bind(todo.inbox)
+        .bind(&todo.note)
+        .bind(&todo.delegated_to)
+        .bind(todo.delegated_on.map(|day| day.to_string()))
+        .bind(todo.follow_up.map(|day| day.to_string()))
+        .execute(&mut *db)
+        .await?;
+        // A todo archived and then restored, or archived twice, is exported
+        // once, as it is in the list.
+        if inserted.rows_affected() == 0 {
+            return Ok(false);
+        }
+        let id = todo.id as i64;
+        for tag in &todo.tags {
+            sqlx::query("INSERT INTO tags VALUES (?, ?)").bind(id).bind(tag.as_str()).execute(&mut *db).await?;
+        }
+        for &other in &todo.depends_on {
+            sqlx::query("INSERT INTO dependencies VALUES (?, ?)").bind(id).bind(other as i64).execute(&mut *db).await?;
+        }
+        for &other in &todo.related {
+            sqlx::query("INSERT INTO related VALUES (?, ?)").bind(id).bind(other as i64).execute(&mut *db).await?;
+        }
+        for day in &todo.habit_log {
+            sqlx::query("INSERT INTO habit_log VALUES (?, ?)").bind(id).bind(day.to_string()).execute(&mut *db).await?;
+        }
+        for comment in &todo.comments {
+            sqlx::query("INSERT INTO comments VALUES (?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(time(comment.at))
+                .bind(&comment.author)
+                .bind(&comment.text)
+                .bind(comment.reply_to.map(time))
+                .execute(&mut *db)
+                .await?;
+        }
+        Ok(true)
+    }
+
+    async fn write(db: &mut SqliteConnection, todo_list: &TodoList, archived: &[Todo], events: &[Event]) -> Result<usize, sqlx::Error> {
+        let mut tx = db.begin().await?;
+        tx.execute(SCHEMA).await?;
+        let mut count = 0;
+        for todo in todo_list.todos() {
+            count += usize::from(insert_todo(&mut tx, todo, false).await?);
+        }
+        for todo in archived {
+            count += usize::from(insert_todo(&mut tx, todo, true).await?);
+        }
+        for goal in &todo_list.goals {
+            sqlx::query("INSERT OR IGNORE INTO goals VALUES (?, ?, ?)")
+                .bind(goal.id as i64)
+                .bind(&goal.title)
+                .bind(time(goal.created_at))
+                .execute(&mut *tx)
+                .await?;
+        }
+        for tombstone in &todo_list.tombstones {
+            sqlx::query("INSERT INTO deleted VALUES (?, ?, ?)")
+                .bind(tombstone.id as i64)
+                .bind(time(tombstone.created_at))
+                .bind(time(tombstone.deleted_at))
+                .execute(&mut *tx)
+                .await?;
+        }
+        for event in events {
+            sqlx::query("INSERT INTO events (at, todo_id, title, kind, detail, author) VALUES (?, ?, ?, ?, ?, ?)")
+                .bind(time(event.at))
+                .bind(event.todo_id as i64)
+                .bind(&event.title)
+                .bind(event.kind.to_string())
+                .bind(&event.detail)
+                .bind(&event.by)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(count)
+    }
+
+    // Writes the list, the `archived` todos and the history to a new SQLite
+    // database at `path`, replacing an earlier export there. Returns how
+    // many todos were written.
+    pub fn export_sqlite(path: &str, todo_list: &TodoList, archived: &[Todo], events: &[Event]) -> io::Result<usize> {
+        if is_other_file(path)? {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not an SQLite database", path)));
+        }
+        // Written next to it first, so a failed export leaves an earlier one
+        // intact.
+        let partial = format!("{}.partial", path);
+        let _ = fs::remove_file(&partial);
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        let count = runtime
+            .block_on(async {
+                let options = SqliteConnectOptions::new()
+                    .filename(&partial)
+                    .create_if_missing(true)
+                    .journal_mode(SqliteJournalMode::Delete);
+                let mut db = SqliteConnection::connect_with(&options).await?;
+                let count = write(&mut db, todo_list, archived, events).await?;
+                db.close().await?;
+                Ok(count)
+            })
+            .map_err(db_error);
+        match count {
+            Ok(count) => {
+                fs::rename(&partial, path)?;
+                Ok(count)
+            },
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                Err(e)
+            },
+        }
+    }
+}
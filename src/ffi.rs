@@ -0,0 +1,109 @@
+// A C interface to the todo engine, declared in include/todo_core.h. Lists
+// are opaque pointers owned by the caller until passed to todo_list_free;
+// strings returned to C are owned by the caller until passed to
+// todo_string_free. Null pointers are accepted everywhere and treated as an
+// empty or failed value.
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use crate::todo::TodoList;
+
+// Borrows a C string, None for null or invalid UTF-8.
+unsafe fn borrow_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: the caller passes a valid, NUL-terminated string.
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+#[no_mangle]
+pub extern "C" fn todo_list_new() -> *mut TodoList {
+    Box::into_raw(Box::new(TodoList::new()))
+}
+
+/// # Safety
+/// `list` must be null or come from this library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_free(list: *mut TodoList) {
+    if !list.is_null() {
+        // SAFETY: the pointer came from Box::into_raw and is freed once.
+        drop(unsafe { Box::from_raw(list) });
+    }
+}
+
+/// Reads a list in the format of the todo file; null if it cannot be read.
+///
+/// # Safety
+/// `json` must be null or a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_from_json(json: *const c_char) -> *mut TodoList {
+    match unsafe { borrow_str(json) }.map(TodoList::from_json) {
+        Some(Ok(list)) => Box::into_raw(Box::new(list)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// The list in the format of the todo file, to be freed with
+/// todo_string_free; null on failure.
+///
+/// # Safety
+/// `list` must be null or a live list from this library.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_to_json(list: *const TodoList) -> *mut c_char {
+    // SAFETY: the caller passes a live list.
+    match unsafe { list.as_ref() }.map(TodoList::to_json) {
+        Some(Ok(json)) => into_c_string(json),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Adds a todo and returns its ID, or 0 if the title or description is
+/// invalid. A null description counts as empty.
+///
+/// # Safety
+/// `list` must be null or a live list from this library, `title` and
+/// `description` null or valid, NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_add(list: *mut TodoList, title: *const c_char, description: *const c_char) -> usize {
+    // SAFETY: the caller passes a live list and valid strings.
+    let (Some(list), Some(title)) = (unsafe { list.as_mut() }, unsafe { borrow_str(title) }) else {
+        return 0;
+    };
+    let description = unsafe { borrow_str(description) }.unwrap_or("");
+    list.add_todo(title.to_string(), description.to_string()).unwrap_or(0)
+}
+
+/// Marks a todo as done; false if there is no such todo.
+///
+/// # Safety
+/// `list` must be null or a live list from this library.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_complete(list: *mut TodoList, id: usize) -> bool {
+    // SAFETY: the caller passes a live list.
+    unsafe { list.as_mut() }.is_some_and(|list| list.complete_todo(id))
+}
+
+/// The number of todos in the list.
+///
+/// # Safety
+/// `list` must be null or a live list from this library.
+#[no_mangle]
+pub unsafe extern "C" fn todo_list_len(list: *const TodoList) -> usize {
+    // SAFETY: the caller passes a live list.
+    unsafe { list.as_ref() }.map_or(0, |list| list.todos().len())
+}
+
+/// # Safety
+/// `s` must be null or a string returned by this library, not used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn todo_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the pointer came from CString::into_raw and is freed once.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
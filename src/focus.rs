@@ -0,0 +1,75 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::todo::TodoList;
+
+// The one todo being worked on now, and the end of the pomodoro running on
+// it, if any. It belongs to this device and is not synced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Focus {
+    pub id: usize,
+    pub since: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pomodoro_until: Option<DateTime<Local>>,
+}
+
+impl Focus {
+    // "12:34" left of a running pomodoro.
+    pub fn pomodoro_left(&self, now: DateTime<Local>) -> Option<String> {
+        let left = (self.pomodoro_until? - now).num_seconds();
+        (left > 0).then(|| format!("{}:{:02}", left / 60, left % 60))
+    }
+}
+
+// "todos.json" keeps its focus in "todos.focus.json".
+pub fn focus_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.focus.json", stem),
+        None => format!("{}.focus.json", filename),
+    }
+}
+
+pub fn load_focus(filename: &str) -> io::Result<Option<Focus>> {
+    if !Path::new(filename).exists() {
+        return Ok(None);
+    }
+
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let focus: Focus = serde_json::from_str(&contents)?;
+    Ok(Some(focus))
+}
+
+pub fn save_focus(filename: &str, focus: &Focus) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(focus)?;
+    let mut file = File::create(filename)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+// Returns whether there was a focus.
+pub fn clear_focus(filename: &str) -> io::Result<bool> {
+    match fs::remove_file(filename) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// The title of the focused todo for the status line, reading the list todo
+// by todo; None once it is completed or gone.
+pub fn focus_title(filename: &str, focus: &Focus) -> io::Result<Option<String>> {
+    let mut title = None;
+    TodoList::for_each_in_file(filename, |todo| {
+        if todo.id == focus.id {
+            title = Some(todo.title).filter(|_| !todo.completed);
+            return false;
+        }
+        true
+    })?;
+    Ok(title)
+}
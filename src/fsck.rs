@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::fmt;
+use chrono::Local;
+use crate::todo::TodoList;
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    DuplicateId(usize),
+    NextIdTooLow { next_id: usize, max_id: usize },
+    NextGoalIdTooLow { next_id: usize, max_id: usize },
+    SelfDependency(usize),
+    DanglingDependency { id: usize, on: usize },
+    DanglingGoal { id: usize, goal: usize },
+    DanglingParent { id: usize, parent: usize },
+    DanglingRelation { id: usize, other: usize },
+    MissingCompletionTime(usize),
+    StrayCompletionTime(usize),
+    InFuture { id: usize, field: &'static str },
+    BeforeCreation { id: usize, field: &'static str },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::DuplicateId(id) => write!(f, "ID {} is used by more than one todo.", id),
+            Problem::NextIdTooLow { next_id, max_id } => write!(f, "next_id is {} but the highest todo ID is {}.", next_id, max_id),
+            Problem::NextGoalIdTooLow { next_id, max_id } => write!(f, "next_goal_id is {} but the highest goal ID is {}.", next_id, max_id),
+            Problem::SelfDependency(id) => write!(f, "Todo {} depends on itself.", id),
+            Problem::DanglingDependency { id, on } => write!(f, "Todo {} depends on todo {}, which does not exist.", id, on),
+            Problem::DanglingGoal { id, goal } => write!(f, "Todo {} is linked to goal {}, which does not exist.", id, goal),
+            Problem::DanglingParent { id, parent } => write!(f, "Todo {} is a subtask of todo {}, which does not exist.", id, parent),
+            Problem::DanglingRelation { id, other } => write!(f, "Todo {} is related to todo {}, which does not exist.", id, other),
+            Problem::MissingCompletionTime(id) => write!(f, "Todo {} is completed but has no completion time.", id),
+            Problem::StrayCompletionTime(id) => write!(f, "Todo {} has a completion time but is not completed.", id),
+            Problem::InFuture { id, field } => write!(f, "Todo {} has its {} in the future.", id, field),
+            Problem::BeforeCreation { id, field } => write!(f, "Todo {} has its {} before its creation time.", id, field),
+        }
+    }
+}
+
+pub fn check(todo_list: &TodoList) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut ids = HashSet::new();
+    let mut duplicates = HashSet::new();
+    for todo in &todo_list.todos {
+        if !ids.insert(todo.id) && duplicates.insert(todo.id) {
+            problems.push(Problem::DuplicateId(todo.id));
+        }
+    }
+    let max_id = todo_list.todos.iter().map(|todo| todo.id).max().unwrap_or(0);
+    if todo_list.next_id <= max_id {
+        problems.push(Problem::NextIdTooLow { next_id: todo_list.next_id, max_id });
+    }
+    let max_goal_id = todo_list.goals.iter().map(|goal| goal.id).max().unwrap_or(0);
+    if todo_list.next_goal_id <= max_goal_id {
+        problems.push(Problem::NextGoalIdTooLow { next_id: todo_list.next_goal_id, max_id: max_goal_id });
+    }
+
+    let now = Local::now();
+    for todo in &todo_list.todos {
+        for &on in &todo.depends_on {
+            if on == todo.id {
+                problems.push(Problem::SelfDependency(todo.id));
+            } else if !ids.contains(&on) {
+                problems.push(Problem::DanglingDependency { id: todo.id, on });
+            }
+        }
+        if let Some(goal) = todo.goal.filter(|&goal| todo_list.get_goal(goal).is_none()) {
+            problems.push(Problem::DanglingGoal { id: todo.id, goal });
+        }
+        if let Some(parent) = todo.parent.filter(|&parent| parent == todo.id || !ids.contains(&parent)) {
+            problems.push(Problem::DanglingParent { id: todo.id, parent });
+        }
+        for &other in todo.related.iter().filter(|&&other| other == todo.id || !ids.contains(&other)) {
+            problems.push(Problem::DanglingRelation { id: todo.id, other });
+        }
+        match (todo.completed, todo.completed_at) {
+            (true, None) => problems.push(Problem::MissingCompletionTime(todo.id)),
+            (false, Some(_)) => problems.push(Problem::StrayCompletionTime(todo.id)),
+            _ => {}
+        }
+        let times = [("created_at", Some(todo.created_at)), ("updated_at", Some(todo.updated_at)), ("completed_at", todo.completed_at)];
+        for (field, at) in times {
+            let Some(at) = at else {
+                continue;
+            };
+            if at > now {
+                problems.push(Problem::InFuture { id: todo.id, field });
+            } else if at < todo.created_at && field != "created_at" {
+                problems.push(Problem::BeforeCreation { id: todo.id, field });
+            }
+        }
+    }
+    problems
+}
+
+// Fixes every problem `check` reports and returns them:
+// - later todos sharing an ID get fresh IDs,
+// - next_id and next_goal_id are moved past the highest ID in use,
+// - dependencies, goals, parents and related todos that point nowhere are
+//   dropped,
+// - completion times are filled in from updated_at or removed,
+// - timestamps in the future become now, and ones before the creation time
+//   become the creation time.
+pub fn repair(todo_list: &mut TodoList) -> Vec<Problem> {
+    let problems = check(todo_list);
+    if problems.is_empty() {
+        return problems;
+    }
+
+    let max_id = todo_list.todos.iter().map(|todo| todo.id).max().unwrap_or(0);
+    let mut next_id = todo_list.next_id.max(max_id + 1);
+    let mut seen = HashSet::new();
+    for todo in &mut todo_list.todos {
+        if !seen.insert(todo.id) {
+            todo.id = next_id;
+            next_id += 1;
+        }
+    }
+    todo_list.next_id = next_id;
+    let max_goal_id = todo_list.goals.iter().map(|goal| goal.id).max().unwrap_or(0);
+    todo_list.next_goal_id = todo_list.next_goal_id.max(max_goal_id + 1);
+
+    let goals: HashSet<usize> = todo_list.goals.iter().map(|goal| goal.id).collect();
+    let now = Local::now();
+    for todo in &mut todo_list.todos {
+        let id = todo.id;
+        todo.depends_on.retain(|on| *on != id && seen.contains(on));
+        if todo.goal.is_some_and(|goal| !goals.contains(&goal)) {
+            todo.goal = None;
+        }
+        if todo.parent.is_some_and(|parent| parent == id || !seen.contains(&parent)) {
+            todo.parent = None;
+        }
+        todo.related.retain(|other| *other != id && seen.contains(other));
+        todo.created_at = todo.created_at.min(now);
+        todo.updated_at = todo.updated_at.clamp(todo.created_at, now);
+        todo.completed_at = match (todo.completed, todo.completed_at) {
+            (true, None) => Some(todo.updated_at),
+            (true, Some(at)) => Some(at.clamp(todo.created_at, now)),
+            (false, _) => None,
+        };
+    }
+    todo_list.reindex();
+    problems
+}
@@ -0,0 +1,36 @@
+// Matching as fuzzy finders do: "mlk" finds "Buy milk", as long as the
+// letters come in order.
+
+// How well `query` matches `text`, ignoring case: every character of the
+// query must appear in the text in order. Matches at the start of a word and
+// runs of adjacent characters score higher, so "bm" prefers "Buy milk" to
+// "Submit". None if the text does not match; an empty query matches
+// everything with 0.
+pub fn score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+    // Tried from every place the first character occurs, keeping the best.
+    (0..text.len()).filter(|&start| text[start] == first).filter_map(|start| score_from(&query, &text, start)).max()
+}
+
+fn score_from(query: &[char], text: &[char], start: usize) -> Option<u32> {
+    let mut score = 0;
+    let mut at = start;
+    let mut previous: Option<usize> = None;
+    for &wanted in query {
+        let found = (at..text.len()).find(|&i| text[i] == wanted)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 10;
+        }
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        previous = Some(found);
+        at = found + 1;
+    }
+    Some(score)
+}
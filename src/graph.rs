@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use crate::todo::{Todo, TodoList};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "mermaid" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+// The longest chain of unfinished dependencies, ordered from the first task
+// that has to be done to the last one waiting on it.
+pub fn critical_path(todo_list: &TodoList) -> Vec<usize> {
+    fn depth(todo_list: &TodoList, id: usize, memo: &mut HashMap<usize, (usize, Option<usize>)>) -> usize {
+        if let Some(&(d, _)) = memo.get(&id) {
+            return d;
+        }
+        let mut best = (1, None);
+        if let Some(todo) = todo_list.get_todo(id) {
+            for &dep in &todo.depends_on {
+                if todo_list.get_todo(dep).is_some_and(|t| !t.completed) {
+                    let d = depth(todo_list, dep, memo) + 1;
+                    if d > best.0 {
+                        best = (d, Some(dep));
+                    }
+                }
+            }
+        }
+        memo.insert(id, best);
+        best.0
+    }
+
+    let mut memo = HashMap::new();
+    let mut start = None;
+    let mut longest = 1;
+    for todo in todo_list.todos().iter().filter(|t| !t.completed) {
+        let d = depth(todo_list, todo.id, &mut memo);
+        if d > longest {
+            longest = d;
+            start = Some(todo.id);
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = start;
+    while let Some(id) = current {
+        path.push(id);
+        current = memo.get(&id).and_then(|&(_, next)| next);
+    }
+    path.reverse();
+    path
+}
+
+struct Critical {
+    nodes: HashSet<usize>,
+    edges: HashSet<(usize, usize)>,
+}
+
+pub fn render(todo_list: &TodoList, format: GraphFormat) -> String {
+    let mut involved: HashSet<usize> = HashSet::new();
+    let mut edges = Vec::new();
+    for todo in todo_list.todos() {
+        for &dep in &todo.depends_on {
+            if todo_list.get_todo(dep).is_some() {
+                involved.insert(todo.id);
+                involved.insert(dep);
+                edges.push((dep, todo.id));
+            }
+        }
+    }
+    let nodes: Vec<&Todo> = todo_list.todos().iter().filter(|t| involved.contains(&t.id)).collect();
+    let path = critical_path(todo_list);
+    let critical = Critical {
+        nodes: path.iter().copied().collect(),
+        edges: path.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+    };
+
+    match format {
+        GraphFormat::Dot => render_dot(todo_list, &nodes, &edges, &critical),
+        GraphFormat::Mermaid => render_mermaid(todo_list, &nodes, &edges, &critical),
+    }
+}
+
+// When a todo can be worked on at the earliest, in minutes of work from
+// now, and how long it can slip without delaying the last of the todos it
+// was scheduled with. Those with no slack make up the critical path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    pub id: usize,
+    pub minutes: u32,
+    pub start: u32,
+    pub finish: u32,
+    pub slack: u32,
+}
+
+// Schedules `todos` by the critical path method: each todo starts as soon
+// as the todos among them it depends on are finished and takes `minutes` of
+// it, with everything else running in parallel. Dependencies on todos not
+// given, such as those of another project, are left out. In order of the
+// earliest start.
+pub fn schedule(todos: &[&Todo], minutes: impl Fn(&Todo) -> u32) -> Vec<Slot> {
+    let by_id: HashMap<usize, &Todo> = todos.iter().map(|todo| (todo.id, *todo)).collect();
+    // Only dependencies within the todos given; a cycle, which
+    // `add_dependency` refuses anyway, is cut where it closes.
+    // Each todo is done after the todos it depends on, so `done` ends up in
+    // an order where those come first.
+    fn finish(
+        id: usize,
+        by_id: &HashMap<usize, &Todo>,
+        minutes: &dyn Fn(&Todo) -> u32,
+        memo: &mut HashMap<usize, (u32, u32)>,
+        done: &mut Vec<usize>,
+        visiting: &mut HashSet<usize>,
+    ) -> u32 {
+        if let Some(&(_, finish)) = memo.get(&id) {
+            return finish;
+        }
+        visiting.insert(id);
+        let todo = by_id[&id];
+        let mut start = 0;
+        for dep in &todo.depends_on {
+            if by_id.contains_key(dep) && !visiting.contains(dep) {
+                start = start.max(finish(*dep, by_id, minutes, memo, done, visiting));
+            }
+        }
+        visiting.remove(&id);
+        let end = start + minutes(todo);
+        memo.insert(id, (start, end));
+        done.push(id);
+        end
+    }
+
+    let mut memo = HashMap::new();
+    let mut done = Vec::new();
+    for todo in todos {
+        finish(todo.id, &by_id, &minutes, &mut memo, &mut done, &mut HashSet::new());
+    }
+    let length = memo.values().map(|&(_, finish)| finish).max().unwrap_or(0);
+    // The latest a todo may finish is the latest start of whatever waits on
+    // it, so going backwards settles every todo after those waiting on it.
+    let mut latest_finish: HashMap<usize, u32> = HashMap::new();
+    for &id in done.iter().rev() {
+        let latest = *latest_finish.entry(id).or_insert(length);
+        let latest_start = latest - minutes(by_id[&id]);
+        for dep in &by_id[&id].depends_on {
+            if memo.contains_key(dep) {
+                let entry = latest_finish.entry(*dep).or_insert(length);
+                *entry = (*entry).min(latest_start);
+            }
+        }
+    }
+    let mut slots: Vec<Slot> = memo
+        .iter()
+        .map(|(&id, &(start, finish))| Slot { id, minutes: finish - start, start, finish, slack: latest_finish[&id] - finish })
+        .collect();
+    slots.sort_by_key(|slot| (slot.start, slot.slack, slot.id));
+    slots
+}
+
+fn render_dot(todo_list: &TodoList, nodes: &[&Todo], edges: &[(usize, usize)], critical: &Critical) -> String {
+    let mut out = String::from("digraph todos {\n    rankdir=LR;\n    node [shape=box, style=filled, fillcolor=white];\n");
+    for todo in nodes {
+        let label = format!("#{} {}", todo.id, todo.title).replace('\\', "\\\\").replace('"', "\\\"");
+        let mut attrs = vec![format!("label=\"{}\"", label)];
+        if todo.completed {
+            attrs.push("fillcolor=lightgray, fontcolor=gray40".to_string());
+        } else if todo_list.is_blocked(todo) {
+            attrs.push("fillcolor=orange".to_string());
+        }
+        if critical.nodes.contains(&todo.id) {
+            attrs.push("color=red, penwidth=2".to_string());
+        }
+        out.push_str(&format!("    t{} [{}];\n", todo.id, attrs.join(", ")));
+    }
+    for &(from, to) in edges {
+        let style = if critical.edges.contains(&(from, to)) { " [color=red, penwidth=2]" } else { "" };
+        out.push_str(&format!("    t{} -> t{}{};\n", from, to, style));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(todo_list: &TodoList, nodes: &[&Todo], edges: &[(usize, usize)], critical: &Critical) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for todo in nodes {
+        let label = format!("#{} {}", todo.id, todo.title).replace('"', "#quot;");
+        out.push_str(&format!("    t{}[\"{}\"]\n", todo.id, label));
+    }
+    for &(from, to) in edges {
+        out.push_str(&format!("    t{} --> t{}\n", from, to));
+    }
+    out.push_str("    classDef done fill:#e9ecef,color:#868e96\n");
+    out.push_str("    classDef blocked fill:#ffd8a8\n");
+    out.push_str("    classDef critical stroke:#e03131,stroke-width:3px\n");
+    for todo in nodes {
+        if todo.completed {
+            out.push_str(&format!("    class t{} done\n", todo.id));
+        } else if todo_list.is_blocked(todo) {
+            out.push_str(&format!("    class t{} blocked\n", todo.id));
+        }
+        if critical.nodes.contains(&todo.id) {
+            out.push_str(&format!("    class t{} critical\n", todo.id));
+        }
+    }
+    out
+}
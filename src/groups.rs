@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use chrono::{Datelike, Duration, NaiveDate};
+use crate::todo::Todo;
+
+// What `list --group-by` splits the list by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Tag,
+    Project,
+    Status,
+    DueWeek,
+}
+
+impl GroupBy {
+    pub const NAMES: [&'static str; 4] = ["tag", "project", "status", "due-week"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "tag" => Some(GroupBy::Tag),
+            "project" => Some(GroupBy::Project),
+            "status" => Some(GroupBy::Status),
+            "due-week" => Some(GroupBy::DueWeek),
+            _ => None,
+        }
+    }
+}
+
+// Where a group goes among the others: named groups in the order of their
+// names, with those that have no name for the todo, such as "No tag", last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Rank(u8),
+    Day(NaiveDate),
+    Name(String),
+    None,
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+fn keys(todo: &Todo, by: GroupBy, today: NaiveDate) -> Vec<Key> {
+    match by {
+        // A todo with several tags is listed under each of them.
+        GroupBy::Tag if todo.tags.is_empty() => vec![Key::None],
+        GroupBy::Tag => todo.tags.iter().map(|tag| Key::Name(tag.as_str().to_string())).collect(),
+        GroupBy::Project => vec![todo.project.clone().map_or(Key::None, Key::Name)],
+        GroupBy::Status => vec![Key::Rank(if todo.cancelled {
+            4
+        } else if todo.completed {
+            3
+        } else if todo.is_waiting(today) {
+            2
+        } else if todo.in_inbox() {
+            0
+        } else {
+            1
+        })],
+        GroupBy::DueWeek => vec![match todo.due {
+            Some(due) if due < today && !todo.completed && !todo.habit => Key::Rank(0),
+            Some(due) => Key::Day(week_start(due)),
+            None => Key::None,
+        }],
+    }
+}
+
+fn title(key: &Key, by: GroupBy, today: NaiveDate) -> String {
+    let this_week = week_start(today);
+    match (key, by) {
+        (Key::Rank(rank), GroupBy::Status) => ["Inbox", "Pending", "Waiting", "Completed", "Cancelled"][*rank as usize].to_string(),
+        (Key::Rank(_), _) => "Overdue".to_string(),
+        (Key::Day(week), _) if *week == this_week => "This week".to_string(),
+        (Key::Day(week), _) if *week == this_week + Duration::days(7) => "Next week".to_string(),
+        (Key::Day(week), _) => format!("Week of {}", week),
+        (Key::Name(name), _) => name.clone(),
+        (Key::None, GroupBy::Tag) => "No tag".to_string(),
+        (Key::None, GroupBy::Project) => "No project".to_string(),
+        (Key::None, _) => "No due date".to_string(),
+    }
+}
+
+// The todos in sections, each titled and in the order the todos came in.
+// Only sections with todos in them are returned.
+pub fn group(todos: &[Todo], by: GroupBy, today: NaiveDate) -> Vec<(String, Vec<&Todo>)> {
+    let mut groups: BTreeMap<Key, Vec<&Todo>> = BTreeMap::new();
+    for todo in todos {
+        for key in keys(todo, by, today) {
+            groups.entry(key).or_default().push(todo);
+        }
+    }
+    groups.into_iter().map(|(key, todos)| (title(&key, by, today), todos)).collect()
+}
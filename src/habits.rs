@@ -0,0 +1,57 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use crate::todo::Todo;
+
+// Number of consecutive days, ending today (or yesterday if today is still
+// open), on which the habit was kept.
+pub fn current_streak(todo: &Todo, today: NaiveDate) -> usize {
+    let mut day = if todo.done_on(today) { today } else { today - Duration::days(1) };
+    let mut streak = 0;
+    while todo.done_on(day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+// A calendar heatmap of the last `weeks` weeks: one row per weekday, one
+// column per week, '#' for days the habit was kept and '.' for missed days.
+pub fn heatmap(todo: &Todo, today: NaiveDate, weeks: usize) -> String {
+    let first_monday = first_monday(today, weeks);
+    let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+    let mut out = String::new();
+    for (row, name) in names.iter().enumerate() {
+        out.push_str(name);
+        out.push(' ');
+        for week in 0..weeks {
+            let day = first_monday + Duration::weeks(week as i64) + Duration::days(row as i64);
+            let cell = if day > today {
+                ' '
+            } else if todo.done_on(day) {
+                '#'
+            } else {
+                '.'
+            };
+            out.push(cell);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// The same weeks as the heatmap, as (monday, days kept, days so far).
+pub fn weekly_counts(todo: &Todo, today: NaiveDate, weeks: usize) -> Vec<(NaiveDate, usize, usize)> {
+    let first_monday = first_monday(today, weeks);
+    (0..weeks)
+        .map(|week| {
+            let monday = first_monday + Duration::weeks(week as i64);
+            let days: Vec<NaiveDate> = (0..7).map(|d| monday + Duration::days(d)).filter(|&day| day <= today).collect();
+            (monday, days.iter().filter(|&&day| todo.done_on(day)).count(), days.len())
+        })
+        .collect()
+}
+
+fn first_monday(today: NaiveDate, weeks: usize) -> NaiveDate {
+    let this_monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    this_monday - Duration::weeks(weeks.saturating_sub(1) as i64)
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Created,
+    Edited,
+    Completed,
+    Reopened,
+    Cancelled,
+    Touched,
+    Deleted,
+    Archived,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EventKind::Created => "created",
+            EventKind::Edited => "edited",
+            EventKind::Completed => "completed",
+            EventKind::Reopened => "reopened",
+            EventKind::Cancelled => "cancelled",
+            EventKind::Touched => "touched",
+            EventKind::Deleted => "deleted",
+            EventKind::Archived => "archived",
+        };
+        f.write_str(name)
+    }
+}
+
+// One change to one todo. The title is captured at the time of the change so
+// the history still reads well after the todo is renamed or deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Local>,
+    pub todo_id: usize,
+    pub title: String,
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    // Who made the change, if known; see `set_author`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+}
+
+static AUTHOR: Mutex<Option<String>> = Mutex::new(None);
+
+// Signs the changes made from now on, so that the activity of a list shared
+// through a sync server tells who made them.
+pub fn set_author(author: Option<String>) {
+    *AUTHOR.lock().unwrap_or_else(PoisonError::into_inner) = author;
+}
+
+pub(crate) fn author() -> Option<String> {
+    AUTHOR.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+// "todos.json" keeps its history in "todos.history.jsonl", one event per line.
+pub fn history_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.history.jsonl", stem),
+        None => format!("{}.history.jsonl", filename),
+    }
+}
+
+pub fn append_events(filename: &str, events: &[Event]) -> io::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(filename)?;
+    let mut buf = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut buf, event)?;
+        buf.push(b'\n');
+    }
+    file.write_all(&buf)
+}
+
+// Like append_events, but first cuts the file back to `len` bytes, dropping
+// what an earlier, interrupted append may have left.
+pub fn append_events_after(filename: &str, len: u64, events: &[Event]) -> io::Result<()> {
+    if Path::new(filename).exists() && fs::metadata(filename)?.len() > len {
+        OpenOptions::new().write(true).open(filename)?.set_len(len)?;
+    }
+    append_events(filename, events)
+}
+
+pub fn load_events(filename: &str) -> io::Result<Vec<Event>> {
+    load_events_after(filename, 0)
+}
+
+// The events appended after the file was `offset` bytes long.
+pub fn load_events_after(filename: &str, offset: u64) -> io::Result<Vec<Event>> {
+    if !Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+    let mut file = File::open(filename)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            events.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(events)
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use chrono::NaiveDate;
+use crate::todo::{Todo, TodoList};
+
+// Shorthands standing for every todo in a given state.
+pub const FILTERS: [&str; 7] = ["@pending", "@completed", "@cancelled", "@overdue", "@waiting", "@blocked", "@inbox"];
+
+#[derive(Debug, PartialEq)]
+pub enum IdError {
+    Empty,
+    Invalid(String),
+}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdError::Empty => write!(f, "No todo IDs given."),
+            IdError::Invalid(term) => write!(
+                f,
+                "'{}' is not an ID, a range such as 3-7, `last` or one of {}.",
+                term,
+                FILTERS.join(", ")
+            ),
+        }
+    }
+}
+
+fn matches(filter: &str, todo: &Todo, todo_list: &TodoList, today: NaiveDate) -> bool {
+    match filter {
+        "@pending" => !todo.completed,
+        "@completed" => todo.completed,
+        "@cancelled" => todo.cancelled,
+        "@overdue" => !todo.completed && !todo.habit && todo.due.is_some_and(|due| due < today),
+        "@waiting" => !todo.completed && todo.is_waiting(today),
+        "@blocked" => todo_list.is_blocked(todo),
+        "@inbox" => todo.in_inbox(),
+        _ => false,
+    }
+}
+
+// Commands whose first argument names one or more todos.
+pub const ID_COMMANDS: [&str; 32] = [
+    "cancel", "clone", "comment", "delegate", "delete", "depend", "done", "due", "estimate", "export", "follow-up", "habit", "important",
+    "location", "points", "postpone", "priority", "progress", "project", "qr", "relate", "repeat", "schedule", "set", "show", "split",
+    "today", "touch", "track", "undepend", "unrelate", "wait",
+];
+
+// Whether `args`, a command and its arguments, is one of ID_COMMANDS given
+// without the todo IDs it needs. `export` followed by a word, such as
+// `export xlsx FILE`, is a format of its own that needs none.
+pub fn lacks_ids(args: &[String], today: NaiveDate) -> bool {
+    let Some((command, rest)) = args.split_first() else {
+        return false;
+    };
+    if !ID_COMMANDS.contains(&command.as_str()) {
+        return false;
+    }
+    match rest.first() {
+        Some(arg) if parse_ids(arg, &TodoList::new(), today).is_ok() => false,
+        Some(arg) => command != "export" || arg.starts_with('-'),
+        None => true,
+    }
+}
+
+// Reads todo IDs written as single IDs ("4"), lists ("4,7"), ranges ("3-7"),
+// `last` for the newest todo, or filters such as "@completed". Terms can be
+// separated by commas or whitespace, and each ID comes out once, in the order
+// first named. ID ranges and filters only yield todos that exist, but a
+// single ID is passed through so the caller can report it as not found.
+pub fn parse_ids(expr: &str, todo_list: &TodoList, today: NaiveDate) -> Result<Vec<usize>, IdError> {
+    let mut ids: Vec<usize> = Vec::new();
+    let mut push = |id: usize| {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    };
+    let mut any = false;
+    for term in expr.split(|c: char| c == ',' || c.is_whitespace()).filter(|term| !term.is_empty()) {
+        any = true;
+        let term = term.to_lowercase();
+        if let Ok(id) = term.parse::<usize>() {
+            push(id);
+        } else if term == "last" {
+            if let Some(id) = todo_list.todos().iter().map(|todo| todo.id).max() {
+                push(id);
+            }
+        } else if FILTERS.contains(&term.as_str()) {
+            for todo in todo_list.todos().iter().filter(|todo| matches(&term, todo, todo_list, today)) {
+                push(todo.id);
+            }
+        } else if let Some((from, to)) = term.split_once('-') {
+            let (Ok(from), Ok(to)) = (from.parse::<usize>(), to.parse::<usize>()) else {
+                return Err(IdError::Invalid(term));
+            };
+            if from > to {
+                return Err(IdError::Invalid(term));
+            }
+            let mut in_range: Vec<usize> =
+                todo_list.todos().iter().map(|todo| todo.id).filter(|id| (from..=to).contains(id)).collect();
+            in_range.sort_unstable();
+            in_range.into_iter().for_each(&mut push);
+        } else {
+            return Err(IdError::Invalid(term));
+        }
+    }
+    if !any {
+        return Err(IdError::Empty);
+    }
+    Ok(ids)
+}
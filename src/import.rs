@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Local, NaiveDate};
+use serde_json::Value;
+use crate::priority::Priority;
+use crate::tags::Tag;
+use crate::todo::{Todo, TodoList};
+use crate::validate::ValidationError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportStrategy {
+    // Keep the local version of todos present on both sides.
+    SkipDuplicates,
+    // Replace local todos with the imported version.
+    Overwrite,
+    // Keep whichever version was updated last, combining their tags.
+    Merge,
+}
+
+impl ImportStrategy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "skip-duplicates" => Some(ImportStrategy::SkipDuplicates),
+            "overwrite" => Some(ImportStrategy::Overwrite),
+            "merge" => Some(ImportStrategy::Merge),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+// Ids are only unique within one file, so a todo is recognised as already
+// present when its creation time matches, preferring the local todo with the
+// same id if several were created at the same instant. New todos get fresh ids, and their
+// dependencies are remapped onto the ids they ended up with here.
+pub fn import_todos(todo_list: &mut TodoList, incoming: &[Todo], strategy: ImportStrategy) -> ImportSummary {
+    let mut importer = Importer::new(todo_list, strategy);
+    for todo in incoming {
+        importer.push(todo);
+    }
+    importer.finish()
+}
+
+// `import_todos` one todo at a time, for sources too large to hold in memory.
+// Dependencies are remapped in `finish`, once every todo has been seen.
+pub struct Importer<'a> {
+    todo_list: &'a mut TodoList,
+    strategy: ImportStrategy,
+    by_created: HashMap<DateTime<Local>, Vec<usize>>,
+    id_map: HashMap<usize, usize>,
+    added: Vec<usize>,
+    summary: ImportSummary,
+}
+
+impl<'a> Importer<'a> {
+    pub fn new(todo_list: &'a mut TodoList, strategy: ImportStrategy) -> Self {
+        let mut by_created: HashMap<_, Vec<usize>> = HashMap::new();
+        for todo in todo_list.todos() {
+            by_created.entry(todo.created_at).or_default().push(todo.id);
+        }
+        Importer {
+            todo_list,
+            strategy,
+            by_created,
+            id_map: HashMap::new(),
+            added: Vec::new(),
+            summary: ImportSummary::default(),
+        }
+    }
+
+    pub fn push(&mut self, todo: &Todo) {
+        let local_id = self.by_created.get_mut(&todo.created_at).and_then(|ids| {
+            let pos = ids.iter().position(|&id| id == todo.id).unwrap_or(0);
+            (!ids.is_empty()).then(|| ids.remove(pos))
+        });
+        let Some(local_id) = local_id else {
+            let mut todo = todo.clone();
+            todo.goal = None;
+            let new_id = self.todo_list.insert_todo(todo.clone());
+            self.id_map.insert(todo.id, new_id);
+            self.added.push(new_id);
+            self.summary.added += 1;
+            return;
+        };
+        self.id_map.insert(todo.id, local_id);
+        let local = self.todo_list.get_todo(local_id).expect("indexed above").clone();
+        let replacement = match self.strategy {
+            ImportStrategy::SkipDuplicates => None,
+            ImportStrategy::Overwrite => Some(todo.clone()),
+            ImportStrategy::Merge if todo.updated_at > local.updated_at => {
+                let mut merged = todo.clone();
+                for tag in &local.tags {
+                    if !merged.tags.contains(tag) {
+                        merged.tags.push(tag.clone());
+                    }
+                }
+                Some(merged)
+            },
+            ImportStrategy::Merge => None,
+        };
+        match replacement {
+            Some(mut todo) => {
+                todo.id = local_id;
+                todo.goal = local.goal;
+                todo.depends_on = local.depends_on.clone();
+                self.todo_list.replace_todo(todo);
+                self.summary.updated += 1;
+            },
+            None => self.summary.skipped += 1,
+        }
+    }
+
+    pub fn finish(self) -> ImportSummary {
+        for id in self.added {
+            let todo = self.todo_list.get_todo_mut(id).expect("just added");
+            todo.depends_on = todo.depends_on.iter().filter_map(|dep| self.id_map.get(dep).copied()).collect();
+        }
+        self.summary
+    }
+}
+
+fn todo_txt_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn start_of(day: NaiveDate) -> Option<DateTime<Local>> {
+    day.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()
+}
+
+// Reads the todo.txt format (todotxt.org), one task per line:
+//
+//     x 2026-03-02 2026-02-20 (A) Call the bank +Finances @phone due:2026-03-05
+//
+// A leading "x" marks a completed task, followed by the day it was done;
+// the day it was created may come next, after the priority for pending
+// ones. (A) is high priority, (B) medium and anything lower low. The first
+// +project becomes the project, @contexts become tags, due: the due date and
+// t: the day to wait for; every other word stays in the title. The todos
+// come out numbered from 1, ready for `import_todos`. Err gives the line of
+// a task that cannot be a todo.
+pub fn parse_todo_txt(text: &str) -> Result<TodoList, (usize, ValidationError)> {
+    let mut parsed = TodoList::new();
+    for (n, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace().peekable();
+        let completed = words.next_if_eq(&"x").is_some();
+        let completed_on = if completed { words.next_if(|word| todo_txt_date(word).is_some()).and_then(todo_txt_date) } else { None };
+        let priority = words
+            .next_if(|word| word.len() == 3 && word.starts_with('(') && word.ends_with(')') && word.as_bytes()[1].is_ascii_uppercase())
+            .map(|word| match word.as_bytes()[1] {
+                b'A' => Priority::High,
+                b'B' => Priority::Medium,
+                _ => Priority::Low,
+            });
+        let created_on = words.next_if(|word| todo_txt_date(word).is_some()).and_then(todo_txt_date);
+        let (mut title, mut project, mut tags, mut due, mut wait_until) = (Vec::new(), None, Vec::new(), None, None);
+        for word in words {
+            if let Some(day) = word.strip_prefix("due:").and_then(todo_txt_date) {
+                due = Some(day);
+            } else if let Some(day) = word.strip_prefix("t:").and_then(todo_txt_date) {
+                wait_until = Some(day);
+            } else if word.len() > 1 && word.starts_with('@') {
+                tags.push(Tag::from(word));
+            } else if word.len() > 1 && word.starts_with('+') && project.is_none() {
+                project = Some(word[1..].to_string());
+            } else {
+                title.push(word);
+            }
+        }
+        if title.is_empty() && project.is_none() && tags.is_empty() {
+            continue;
+        }
+        let title = if title.is_empty() { project.clone().unwrap_or_else(|| line.trim().to_string()) } else { title.join(" ") };
+        parsed
+            .add_todo_with(title, String::new(), |todo| {
+                if let Some(created) = created_on.and_then(start_of) {
+                    todo.created_at = created;
+                    todo.updated_at = created;
+                }
+                todo.completed = completed;
+                todo.completed_at = if completed { completed_on.and_then(start_of).or(Some(todo.updated_at)) } else { None };
+                todo.priority = priority;
+                todo.project = project;
+                todo.tags = tags;
+                todo.due = due;
+                todo.wait_until = wait_until;
+            })
+            .map_err(|e| (n + 1, e))?;
+    }
+    Ok(parsed)
+}
+
+// Reminders and Microsoft To Do have no export of their own; the tools and
+// shortcuts that make one write JSON or CSV with names of their choosing, so
+// fields are recognised by any of several names, ignoring case, spaces and
+// punctuation.
+const TITLE: [&str; 3] = ["title", "name", "subject"];
+const NOTES: [&str; 4] = ["notes", "note", "body", "content"];
+const LIST: [&str; 6] = ["list", "listname", "listtitle", "displayname", "folder", "calendar"];
+const DUE: [&str; 5] = ["duedate", "due", "duedatetime", "dueon", "duetime"];
+const COMPLETED: [&str; 5] = ["completed", "iscompleted", "done", "status", "state"];
+const COMPLETED_ON: [&str; 5] = ["completiondate", "completeddate", "completeddatetime", "completedat", "completedon"];
+const CREATED: [&str; 5] = ["creationdate", "createddate", "createddatetime", "createdat", "created"];
+const PRIORITY: [&str; 2] = ["priority", "importance"];
+const TAGS: [&str; 3] = ["tags", "categories", "hashtags"];
+const FLAGGED: [&str; 3] = ["flagged", "isflagged", "isimportant"];
+// Arrays of reminders inside a list.
+const ITEMS: [&str; 6] = ["tasks", "reminders", "items", "todos", "value", "lists"];
+
+fn field_key(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+type Record = HashMap<String, Value>;
+
+fn get<'r>(record: &'r Record, names: &[&str]) -> Option<&'r Value> {
+    names.iter().filter_map(|name| record.get(*name)).find(|value| !value.is_null() && value.as_str() != Some(""))
+}
+
+// Text of a field, or of the `content` or `dateTime` of one that is an
+// object, as Microsoft's are.
+fn field_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Object(fields) => fields.iter().find(|(key, _)| matches!(field_key(key).as_str(), "content" | "datetime" | "date")).and_then(|(_, value)| field_text(value)),
+        _ => None,
+    }
+}
+
+// A moment with an offset is taken in local time; one without, such as
+// "2026-10-14T00:00:00.0000000" from Microsoft, or a plain day, as written.
+// Slashes are read month first, as the apps write them in English.
+fn export_date(text: &str) -> Option<(NaiveDate, Option<DateTime<Local>>)> {
+    let text = text.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(text).or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S %z")) {
+        let at = at.with_timezone(&Local);
+        return Some((at.date_naive(), Some(at)));
+    }
+    let day = text.split(['T', ' ']).next().and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+    let day = day
+        .or_else(|| text.split(' ').next().and_then(|day| NaiveDate::parse_from_str(day, "%m/%d/%Y").ok()))
+        .or_else(|| {
+            // "Oct 14, 2026 at 9:00 AM", as Shortcuts writes dates.
+            let day = text.split(" at ").next()?;
+            NaiveDate::parse_from_str(day, "%b %d, %Y").or_else(|_| NaiveDate::parse_from_str(day, "%B %d, %Y")).ok()
+        })?;
+    Some((day, None))
+}
+
+fn is_yes(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        value => field_text(value).is_some_and(|text| matches!(text.to_lowercase().as_str(), "yes" | "true" | "1" | "x" | "completed" | "complete" | "done")),
+    }
+}
+
+// Reminders numbers priorities 1 (high) to 9 (low), 0 being none;
+// Microsoft To Do calls them importance, normal being none.
+fn export_priority(value: &Value) -> Option<Priority> {
+    let text = field_text(value)?.to_lowercase();
+    match text.parse::<u32>() {
+        Ok(0) => None,
+        Ok(1..=4) => Some(Priority::High),
+        Ok(5) => Some(Priority::Medium),
+        Ok(_) => Some(Priority::Low),
+        Err(_) => match text.as_str() {
+            "high" | "!!!" => Some(Priority::High),
+            "medium" | "!!" => Some(Priority::Medium),
+            "low" | "!" => Some(Priority::Low),
+            _ => None,
+        },
+    }
+}
+
+fn export_tags(value: &Value) -> Vec<Tag> {
+    let names: Vec<String> = match value {
+        Value::Array(values) => values.iter().filter_map(field_text).collect(),
+        value => field_text(value).map(|text| text.split([',', ' ']).map(str::to_string).collect()).unwrap_or_default(),
+    };
+    names
+        .iter()
+        .map(|name| name.trim().trim_start_matches('#'))
+        .filter(|name| !name.is_empty())
+        .map(|name| Tag::from(name.replace(' ', "-").as_str()))
+        .collect()
+}
+
+fn records_from_json(value: &Value, list: Option<&str>, out: &mut Vec<(Record, Option<String>)>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                records_from_json(value, list, out);
+            }
+        },
+        Value::Object(fields) => {
+            let record: Record = fields.iter().map(|(key, value)| (field_key(key), value.clone())).collect();
+            let items: Vec<&Value> = ITEMS.iter().filter_map(|name| record.get(*name)).filter(|value| value.is_array()).collect();
+            if items.is_empty() {
+                if get(&record, &TITLE).is_some() {
+                    out.push((record, list.map(str::to_string)));
+                }
+                return;
+            }
+            let name = get(&record, &LIST).or_else(|| get(&record, &TITLE)).and_then(field_text);
+            for items in items {
+                records_from_json(items, name.as_deref().or(list), out);
+            }
+        },
+        _ => {},
+    }
+}
+
+// Fields of a CSV line, with quotes around those that hold the separator,
+// a quote or a line break.
+fn csv_rows(text: &str, separator: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field, mut quoted) = (Vec::new(), String::new(), false);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if c == separator && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {},
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            },
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+fn records_from_csv(text: &str) -> Vec<(Record, Option<String>)> {
+    let header_line = text.lines().next().unwrap_or("");
+    let separator = [',', ';', '\t'].into_iter().max_by_key(|&c| header_line.matches(c).count()).unwrap_or(',');
+    let mut rows = csv_rows(text, separator).into_iter();
+    let header: Vec<String> = rows.next().unwrap_or_default().iter().map(|name| field_key(name)).collect();
+    rows.map(|row| {
+        let record: Record = header.iter().cloned().zip(row.into_iter().map(Value::String)).collect();
+        (record, None)
+    })
+    .collect()
+}
+
+// Reads an export of Apple Reminders or Microsoft To Do, as JSON (an array
+// of reminders, or of lists holding them under `tasks`, `reminders` or
+// `items`) or as CSV with a header line. Lists become projects, and the
+// title, notes, due day, completion with its day, creation time, priority,
+// tags and flag are taken over. The todos come out numbered from 1, ready
+// for `import_todos`; Err tells why the file or one of its reminders cannot
+// be imported.
+pub fn parse_app_export(text: &str) -> Result<TodoList, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let records = if text.trim_start().starts_with(['[', '{']) {
+        let value: Value = serde_json::from_str(text).map_err(|e| format!("The file is not valid JSON: {}.", e))?;
+        let mut records = Vec::new();
+        records_from_json(&value, None, &mut records);
+        records
+    } else {
+        records_from_csv(text)
+    };
+    let mut parsed = TodoList::new();
+    for (n, (record, list)) in records.iter().enumerate() {
+        let Some(title) = get(record, &TITLE).and_then(field_text) else {
+            continue;
+        };
+        let mut description = get(record, &NOTES).and_then(field_text).unwrap_or_default();
+        if description.starts_with('<') {
+            description = strip_html(&description);
+        }
+        let list = get(record, &LIST).and_then(field_text).or_else(|| list.clone());
+        let completed = get(record, &COMPLETED).is_some_and(is_yes) || get(record, &COMPLETED_ON).is_some();
+        let completed_on = get(record, &COMPLETED_ON).and_then(field_text).and_then(|text| export_date(&text));
+        let created = get(record, &CREATED).and_then(field_text).and_then(|text| export_date(&text));
+        parsed
+            .add_todo_with(title, description, |todo| {
+                if let Some(created) = created.and_then(|(day, at)| at.or_else(|| start_of(day))) {
+                    todo.created_at = created;
+                    todo.updated_at = created;
+                }
+                todo.completed = completed;
+                todo.completed_at = completed.then(|| completed_on.and_then(|(day, at)| at.or_else(|| start_of(day))).unwrap_or(todo.updated_at));
+                todo.due = get(record, &DUE).and_then(field_text).and_then(|text| export_date(&text)).map(|(day, _)| day);
+                todo.priority = get(record, &PRIORITY).and_then(export_priority);
+                todo.tags = get(record, &TAGS).map(export_tags).unwrap_or_default();
+                todo.important = get(record, &FLAGGED).is_some_and(is_yes);
+                todo.project = list.filter(|list| !list.is_empty());
+            })
+            .map_err(|e| format!("Reminder {} ({}) cannot be imported: {}", n + 1, get(record, &TITLE).and_then(field_text).unwrap_or_default(), e))?;
+    }
+    Ok(parsed)
+}
+
+// Notes Microsoft keeps as HTML, as plain text.
+fn strip_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            },
+            c if !in_tag => out.push(c),
+            _ => {},
+        }
+    }
+    let out = out.replace("&nbsp;", " ").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&");
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
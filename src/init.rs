@@ -0,0 +1,131 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde_json::{Map, Value};
+use todo_app::config::{Config, StorageKind, CONFIG_FILENAME};
+use todo_app::import::{self, ImportStrategy};
+use todo_app::storage;
+use todo_app::TodoList;
+use crate::input;
+use crate::ui::get_input;
+
+// Nothing is set up here yet: there is neither a configuration nor a list.
+pub fn is_first_run(config: &Config) -> bool {
+    !Path::new(CONFIG_FILENAME).exists() && !Path::new(&config.todo_filename()).exists()
+}
+
+fn ask_storage() -> Option<(StorageKind, Option<String>)> {
+    loop {
+        let answer = get_input("Keep the todos in json (a file), sled (an embedded database) or postgres? [json]:").to_lowercase();
+        if input::at_eof() {
+            return None;
+        }
+        match answer.as_str() {
+            "" | "json" => return Some((StorageKind::Json, None)),
+            "sled" if cfg!(feature = "sled") => return Some((StorageKind::Sled, None)),
+            "postgres" if cfg!(feature = "postgres") => {
+                let url = get_input("Connection string, e.g. postgres://todo@db.example.com/todos:");
+                if url.is_empty() {
+                    return None;
+                }
+                return Some((StorageKind::Postgres, Some(url)));
+            },
+            "sled" | "postgres" => println!("This build cannot use {0}; build with `--features {0}` for it.", answer),
+            _ => println!("Please enter json, sled or postgres."),
+        }
+    }
+}
+
+// The todos of a todos.json or todo.txt file, told apart by the name.
+fn read_import(source: &str) -> io::Result<Option<TodoList>> {
+    if source.ends_with(".json") {
+        return TodoList::load_from_file(source).map(Some);
+    }
+    match import::parse_todo_txt(&fs::read_to_string(source)?) {
+        Ok(incoming) => Ok(Some(incoming)),
+        Err((line, e)) => {
+            println!("Line {} of {} cannot be imported: {}", line, source, e);
+            Ok(None)
+        },
+    }
+}
+
+// Writes the answers into the configuration file, leaving every other
+// setting in it as it was.
+fn write_config(config: &Config) -> io::Result<()> {
+    let mut settings = match fs::read_to_string(CONFIG_FILENAME) {
+        Ok(contents) => serde_json::from_str::<Map<String, Value>>(&contents)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+        Err(e) => return Err(e),
+    };
+    settings.insert("storage".to_string(), serde_json::to_value(config.storage)?);
+    for (key, value) in [("data_dir", &config.data_dir), ("database_url", &config.database_url)] {
+        match value {
+            Some(value) => settings.insert(key.to_string(), Value::String(value.clone())),
+            None => settings.remove(key),
+        };
+    }
+    fs::write(CONFIG_FILENAME, serde_json::to_string_pretty(&settings)? + "\n")
+}
+
+// Asks where and how to keep the todos, writes todo_config.json and imports
+// an existing list if there is one. Returns the configuration as it now is,
+// or None if the user gave up.
+pub fn run(config: &Config) -> io::Result<Option<Config>> {
+    if Path::new(CONFIG_FILENAME).exists() {
+        println!("{} exists already; only where the todos are kept is changed.", CONFIG_FILENAME);
+    }
+    let current = config.data_dir.clone().unwrap_or_else(|| ".".to_string());
+    let dir = get_input(&format!("Directory to keep the todos in [{}]:", current));
+    if input::at_eof() {
+        return Ok(None);
+    }
+    let dir = if dir.is_empty() { current } else { dir };
+    let Some((storage, database_url)) = ask_storage() else {
+        println!("Nothing was changed.");
+        return Ok(None);
+    };
+    let mut new = Config::load_from_file(CONFIG_FILENAME).unwrap_or_default();
+    new.data_dir = Some(dir.clone()).filter(|dir| dir != ".");
+    new.storage = storage;
+    new.database_url = database_url.or(new.database_url);
+    if let Some(url) = &new.database_url {
+        storage::set_database_url(url);
+    }
+
+    // An existing todos.json here is the likeliest thing to bring along.
+    let filename = new.todo_filename();
+    let suggested = ["todos.json", "todo.txt"].into_iter().find(|name| Path::new(name).exists() && *name != filename);
+    let prompt = match suggested {
+        Some(name) => format!("Import todos from a todos.json or todo.txt file? Enter its path, or - for none [{}]:", name),
+        None => "Import todos from a todos.json or todo.txt file? Enter its path, or nothing for none:".to_string(),
+    };
+    let source = get_input(&prompt);
+    let source = match (source.as_str(), suggested) {
+        ("", Some(name)) => Some(name.to_string()),
+        ("" | "-", _) => None,
+        (path, _) => Some(path.to_string()),
+    };
+    let incoming = match &source {
+        Some(source) => match read_import(source)? {
+            Some(incoming) => Some(incoming),
+            None => {
+                println!("Nothing was changed.");
+                return Ok(None);
+            },
+        },
+        None => None,
+    };
+
+    fs::create_dir_all(&dir)?;
+    write_config(&new)?;
+    println!("Wrote {}.", CONFIG_FILENAME);
+    let mut todo_list = TodoList::load_from_file(&filename)?;
+    if let (Some(source), Some(incoming)) = (source, incoming) {
+        let summary = import::import_todos(&mut todo_list, incoming.todos(), ImportStrategy::SkipDuplicates);
+        println!("Imported from {}: {} added, {} skipped.", source, summary.added, summary.skipped);
+    }
+    todo_list.save_to_file(&filename)?;
+    println!("Your todos are kept in {}. Run todo_app without a command for the menu, or `todo_app help` for the commands.", filename);
+    Ok(Some(new))
+}
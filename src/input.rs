@@ -0,0 +1,168 @@
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use todo_app::TodoList;
+use crate::interrupt;
+
+// Completes the word under the cursor with known tag names, or the whole line
+// with the title of a pending todo.
+#[derive(Default)]
+struct InputHelper {
+    tags: Vec<String>,
+    titles: Vec<String>,
+}
+
+impl Completer for InputHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c == ',' || c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &before[start..];
+
+        if !word.is_empty() {
+            let tags: Vec<String> = self.tags.iter().filter(|tag| tag.starts_with(word)).cloned().collect();
+            if !tags.is_empty() {
+                return Ok((start, tags));
+            }
+        }
+        if !before.is_empty() {
+            let lower = before.to_lowercase();
+            let titles = self.titles.iter().filter(|title| title.to_lowercase().starts_with(&lower)).cloned().collect();
+            return Ok((0, titles));
+        }
+        Ok((start, Vec::new()))
+    }
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Validator for InputHelper {}
+
+impl Helper for InputHelper {}
+
+thread_local! {
+    static EDITOR: RefCell<Option<Editor<InputHelper, DefaultHistory>>> = RefCell::new(
+        Editor::new().ok().map(|mut editor| {
+            editor.set_helper(Some(InputHelper::default()));
+            editor
+        })
+    );
+    static AT_EOF: Cell<bool> = const { Cell::new(false) };
+}
+
+// Set while read_secret has turned echo off.
+static ECHO_OFF: AtomicBool = AtomicBool::new(false);
+// Set while key_by_key has the terminal hand over single keys.
+static KEY_BY_KEY: AtomicBool = AtomicBool::new(false);
+
+fn stty(args: &[&str]) -> bool {
+    process::Command::new("stty").args(args).stderr(process::Stdio::null()).status().is_ok_and(|status| status.success())
+}
+
+// Turns echo back on if it was turned off, and line editing if key_by_key
+// turned it off; safe to call from the Ctrl-C handler.
+pub fn restore_terminal() {
+    if ECHO_OFF.swap(false, Ordering::SeqCst) {
+        stty(&["echo"]);
+    }
+    if KEY_BY_KEY.swap(false, Ordering::SeqCst) {
+        stty(&["icanon", "echo"]);
+    }
+}
+
+// Has the terminal pass on every key as it is pressed, without showing
+// it, and a read wait at most a tenth of a second for one. False if the
+// terminal cannot; restore_terminal undoes it.
+pub fn key_by_key() -> bool {
+    let on = stty(&["-icanon", "-echo", "min", "0", "time", "1"]);
+    KEY_BY_KEY.store(on, Ordering::SeqCst);
+    on
+}
+
+// True once input has run out, e.g. when stdin is a finished pipe.
+pub fn at_eof() -> bool {
+    AT_EOF.with(Cell::get)
+}
+
+// Refreshes the completion candidates from the current list.
+pub fn set_completions(todo_list: &TodoList) {
+    let tags = todo_list.tag_counts().into_iter().map(|(tag, _)| tag.to_string()).collect();
+    let titles = todo_list.todos().iter().filter(|t| !t.completed).map(|t| t.title.clone()).collect();
+    EDITOR.with(|editor| {
+        if let Some(editor) = editor.borrow_mut().as_mut() {
+            editor.set_helper(Some(InputHelper { tags, titles }));
+        }
+    });
+}
+
+// Reads a line, e.g. a passphrase, without showing it on a terminal or
+// keeping it in the history.
+pub fn read_secret() -> String {
+    let hidden = stty(&["-echo"]);
+    ECHO_OFF.store(hidden, Ordering::SeqCst);
+    let mut input = String::new();
+    let read = io::stdin().read_line(&mut input);
+    if hidden {
+        restore_terminal();
+        println!();
+    }
+    if read.is_ok_and(|n| n == 0) || interrupt::requested() {
+        AT_EOF.with(|eof| eof.set(true));
+    }
+    input.trim_end_matches(['\r', '\n']).to_string()
+}
+
+// Reads one line with editing, history and completion. Falls back to plain
+// stdin when no line editor is available. End of input reads as "" and
+// sets at_eof, and so does Ctrl-C, which ends the session with its changes
+// saved.
+pub fn read_line() -> String {
+    if interrupt::requested() {
+        AT_EOF.with(|eof| eof.set(true));
+        return String::new();
+    }
+    EDITOR.with(|editor| match editor.borrow_mut().as_mut() {
+        Some(editor) => match editor.readline("> ") {
+            Ok(line) => {
+                let line = line.trim().to_string();
+                if !line.is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                line
+            }
+            Err(ReadlineError::Interrupted) => {
+                interrupt::request();
+                AT_EOF.with(|eof| eof.set(true));
+                String::new()
+            }
+            Err(_) => {
+                AT_EOF.with(|eof| eof.set(true));
+                String::new()
+            }
+        },
+        None => {
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).expect("Failed to read input") == 0 {
+                AT_EOF.with(|eof| eof.set(true));
+            }
+            input.trim().to_string()
+        }
+    })
+}
@@ -0,0 +1,56 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use todo_app::TodoList;
+use crate::input;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+// How many callers are ready to wind down on their own, see `Winding`.
+static WINDING: AtomicUsize = AtomicUsize::new(0);
+
+// Without a handler Ctrl-C kills the process on the spot, possibly halfway
+// through a save and with the terminal left in whatever state it was put in.
+// The line editor reads Ctrl-C as a key instead and reports it through
+// `request`.
+pub fn install() {
+    if let Err(e) = ctrlc::set_handler(handle) {
+        eprintln!("Warning: Ctrl-C will not save pending changes: {}", e);
+    }
+}
+
+fn handle() {
+    input::restore_terminal();
+    if WINDING.load(Ordering::SeqCst) > 0 && !REQUESTED.swap(true, Ordering::SeqCst) {
+        eprintln!("\nInterrupted, saving what is done so far. Press Ctrl-C again to quit at once.");
+        return;
+    }
+    // Lets a save in progress finish; none starts after.
+    let _saving = TodoList::block_saves();
+    eprintln!();
+    process::exit(130);
+}
+
+// Asks whoever is winding down to stop, as Ctrl-C does.
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+// While one is held, Ctrl-C only sets `requested` and the holder is trusted
+// to save pending changes and stop; a second Ctrl-C still quits at once.
+pub struct Winding(());
+
+impl Winding {
+    pub fn start() -> Self {
+        WINDING.fetch_add(1, Ordering::SeqCst);
+        Winding(())
+    }
+}
+
+impl Drop for Winding {
+    fn drop(&mut self) {
+        WINDING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Local, NaiveDate};
+use crate::history::{Event, EventKind};
+use crate::todo::Todo;
+
+// One todo completed on the day, e.g. for a standup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Done {
+    pub at: DateTime<Local>,
+    pub id: usize,
+    pub title: String,
+    pub project: Option<String>,
+    pub note: Option<String>,
+}
+
+// What was completed on `day`, in the order it was done. The history knows
+// the time of every completion, including those of recurring todos and
+// habits, which stay pending; a completion undone the same day does not
+// count. Todos completed without a trace in the history, such as imported
+// ones, are taken from `todos`, which may include the archive.
+pub fn done_on(todos: &[Todo], events: &[Event], day: NaiveDate) -> Vec<Done> {
+    let by_id: HashMap<usize, &Todo> = todos.iter().map(|todo| (todo.id, todo)).collect();
+    let mut last: HashMap<usize, &Event> = HashMap::new();
+    for event in events.iter().filter(|event| event.at.date_naive() == day) {
+        if matches!(event.kind, EventKind::Completed | EventKind::Reopened) {
+            last.insert(event.todo_id, event);
+        }
+    }
+    let mut done: Vec<Done> = last
+        .into_values()
+        .filter(|event| event.kind == EventKind::Completed)
+        .map(|event| {
+            let todo = by_id.get(&event.todo_id);
+            Done {
+                at: event.at,
+                id: event.todo_id,
+                title: todo.map_or_else(|| event.title.clone(), |todo| todo.title.clone()),
+                project: todo.and_then(|todo| todo.project.clone()),
+                note: event.detail.clone(),
+            }
+        })
+        .collect();
+    for todo in todos {
+        let Some(at) = todo.completed_on().filter(|at| at.date_naive() == day && !todo.cancelled) else {
+            continue;
+        };
+        if !done.iter().any(|done| done.id == todo.id) {
+            done.push(Done { at, id: todo.id, title: todo.title.clone(), project: todo.project.clone(), note: todo.note.clone() });
+        }
+    }
+    done.sort_by_key(|done| (done.at, done.id));
+    done
+}
+
+// A list to paste into a standup or a journal.
+pub fn markdown(day: NaiveDate, done: &[Done]) -> String {
+    let mut out = format!("## Done on {}\n\n", day);
+    if done.is_empty() {
+        out.push_str("Nothing.\n");
+    }
+    for done in done {
+        out.push_str(&format!("- {} {}", done.at.format("%H:%M"), done.title));
+        if let Some(project) = &done.project {
+            out.push_str(&format!(" ({})", project));
+        }
+        if let Some(note) = &done.note {
+            out.push_str(&format!(": {}", note));
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+// Something a key can be bound to in one of the interactive prompts.
+pub trait Action: Copy + Ord + 'static {
+    const ALL: &'static [Self];
+
+    fn name(self) -> &'static str;
+    fn default_key(self) -> &'static str;
+    fn description(self) -> &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuAction {
+    List,
+    Add,
+    Edit,
+    Toggle,
+    Delete,
+    Exit,
+}
+
+impl Action for MenuAction {
+    const ALL: &'static [Self] = &[
+        MenuAction::List,
+        MenuAction::Add,
+        MenuAction::Edit,
+        MenuAction::Toggle,
+        MenuAction::Delete,
+        MenuAction::Exit,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            MenuAction::List => "list",
+            MenuAction::Add => "add",
+            MenuAction::Edit => "edit",
+            MenuAction::Toggle => "toggle",
+            MenuAction::Delete => "delete",
+            MenuAction::Exit => "exit",
+        }
+    }
+
+    fn default_key(self) -> &'static str {
+        match self {
+            MenuAction::List => "1",
+            MenuAction::Add => "2",
+            MenuAction::Edit => "3",
+            MenuAction::Toggle => "4",
+            MenuAction::Delete => "5",
+            MenuAction::Exit => "0",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            MenuAction::List => "List all todos",
+            MenuAction::Add => "Add a new todo",
+            MenuAction::Edit => "Edit a todo",
+            MenuAction::Toggle => "Toggle todo completion status",
+            MenuAction::Delete => "Delete a todo",
+            MenuAction::Exit => "Exit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewAction {
+    Complete,
+    Reschedule,
+    Delegate,
+    Delete,
+    Skip,
+    Quit,
+}
+
+impl Action for ReviewAction {
+    const ALL: &'static [Self] = &[
+        ReviewAction::Complete,
+        ReviewAction::Reschedule,
+        ReviewAction::Delegate,
+        ReviewAction::Delete,
+        ReviewAction::Skip,
+        ReviewAction::Quit,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ReviewAction::Complete => "complete",
+            ReviewAction::Reschedule => "reschedule",
+            ReviewAction::Delegate => "delegate",
+            ReviewAction::Delete => "delete",
+            ReviewAction::Skip => "skip",
+            ReviewAction::Quit => "quit",
+        }
+    }
+
+    fn default_key(self) -> &'static str {
+        match self {
+            ReviewAction::Complete => "c",
+            ReviewAction::Reschedule => "r",
+            ReviewAction::Delegate => "d",
+            ReviewAction::Delete => "x",
+            ReviewAction::Skip => "s",
+            ReviewAction::Quit => "q",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ReviewAction::Complete => "Complete the todo",
+            ReviewAction::Reschedule => "Give it a new due date",
+            ReviewAction::Delegate => "Tag it with the person it was handed to",
+            ReviewAction::Delete => "Delete it",
+            ReviewAction::Skip => "Leave it as it is",
+            ReviewAction::Quit => "Stop the review",
+        }
+    }
+}
+
+// Keys that differ from the defaults, e.g. {"menu": {"add": "a"}}. Keys are
+// matched ignoring case.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub menu: BTreeMap<MenuAction, String>,
+    pub review: BTreeMap<ReviewAction, String>,
+}
+
+fn key<A: Action>(bindings: &BTreeMap<A, String>, action: A) -> &str {
+    bindings
+        .get(&action)
+        .map(|key| key.trim())
+        .filter(|key| !key.is_empty())
+        .unwrap_or(action.default_key())
+}
+
+fn action<A: Action>(bindings: &BTreeMap<A, String>, input: &str) -> Option<A> {
+    A::ALL.iter().copied().find(|&action| key(bindings, action).eq_ignore_ascii_case(input.trim()))
+}
+
+fn conflicts<A: Action>(bindings: &BTreeMap<A, String>) -> Vec<(String, Vec<A>)> {
+    let mut by_key: BTreeMap<String, Vec<A>> = BTreeMap::new();
+    for &action in A::ALL {
+        by_key.entry(key(bindings, action).to_lowercase()).or_default().push(action);
+    }
+    by_key.into_iter().filter(|(_, actions)| actions.len() > 1).collect()
+}
+
+impl Keymap {
+    pub fn menu_key(&self, action: MenuAction) -> &str {
+        key(&self.menu, action)
+    }
+
+    pub fn menu_action(&self, input: &str) -> Option<MenuAction> {
+        action(&self.menu, input)
+    }
+
+    pub fn review_key(&self, action: ReviewAction) -> &str {
+        key(&self.review, action)
+    }
+
+    pub fn review_action(&self, input: &str) -> Option<ReviewAction> {
+        action(&self.review, input)
+    }
+
+    // Keys bound to more than one action, described for the user. Only the
+    // first action listed for such a key can be reached.
+    pub fn conflicts(&self) -> Vec<String> {
+        let describe = |prompt: &str, key: String, names: Vec<&str>| {
+            format!("{} key '{}' is bound to {}", prompt, key, names.join(" and "))
+        };
+        let menu = conflicts(&self.menu)
+            .into_iter()
+            .map(|(key, actions)| describe("Menu", key, actions.iter().map(|a| a.name()).collect()));
+        let review = conflicts(&self.review)
+            .into_iter()
+            .map(|(key, actions)| describe("Review", key, actions.iter().map(|a| a.name()).collect()));
+        menu.chain(review).collect()
+    }
+}
@@ -0,0 +1,60 @@
+pub mod aliases;
+pub mod archive;
+pub mod columns;
+pub mod config;
+#[cfg(feature = "net")]
+pub mod crypto;
+pub mod dates;
+pub mod diff;
+pub mod digest;
+pub mod estimates;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod focus;
+pub mod fsck;
+pub mod fuzzy;
+pub mod graph;
+pub mod groups;
+pub mod habits;
+pub mod history;
+pub mod ids;
+pub mod import;
+pub mod journal;
+pub mod keys;
+#[cfg(feature = "net")]
+pub mod mail;
+pub mod matrix;
+pub mod notify;
+pub mod paper;
+pub mod plan;
+pub mod priority;
+#[cfg(feature = "python")]
+mod python;
+pub mod recover;
+pub mod rrule;
+pub mod status;
+pub mod storage;
+pub mod sync;
+mod tags;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod timeline;
+mod todo;
+#[cfg(feature = "net")]
+pub mod tokens;
+#[cfg(feature = "net")]
+pub mod update;
+#[cfg(feature = "net")]
+pub mod users;
+pub mod validate;
+pub mod velocity;
+pub mod wal;
+pub mod workdays;
+pub mod zones;
+
+pub use priority::Priority;
+pub use tags::{parse_tags, Tag, TagTable};
+pub use todo::{Comment, DependencyError, Goal, Todo, TodoList, Tombstone};
+pub use zones::Deadline;
@@ -0,0 +1,85 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use chrono::Local;
+use crate::config::SmtpConfig;
+
+// Delivers a plain-text message through an SMTP relay. There is no TLS or
+// authentication; point it at a local relay (postfix, msmtpd, ...) that
+// forwards the mail, or pipe `digest --stdout` into another mailer.
+pub fn send(smtp: &SmtpConfig, to: &str, subject: &str, body: &str) -> io::Result<()> {
+    for (what, text) in [("address", to), ("sender address", smtp.from.as_str()), ("subject", subject)] {
+        check_header(what, text)?;
+    }
+    let stream = TcpStream::connect((smtp.host.as_str(), smtp.port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    expect(&mut reader, 220)?;
+    command(&mut writer, &mut reader, &format!("HELO {}", hostname()), 250)?;
+    command(&mut writer, &mut reader, &format!("MAIL FROM:<{}>", smtp.from), 250)?;
+    command(&mut writer, &mut reader, &format!("RCPT TO:<{}>", to), 250)?;
+    command(&mut writer, &mut reader, "DATA", 354)?;
+
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        smtp.from,
+        to,
+        subject,
+        Local::now().to_rfc2822()
+    );
+    for line in body.lines() {
+        // Dot-stuffing, so a line holding a single "." does not end the message.
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    writer.write_all(message.as_bytes())?;
+    expect(&mut reader, 250)?;
+
+    command(&mut writer, &mut reader, "QUIT", 221)
+}
+
+// A line break would end the SMTP command or header early and let the
+// rest pass for commands or headers of its own.
+fn check_header(what: &str, text: &str) -> io::Result<()> {
+    if text.chars().any(char::is_control) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("the {} may not contain control characters", what)));
+    }
+    Ok(())
+}
+
+fn command(writer: &mut TcpStream, reader: &mut BufReader<TcpStream>, line: &str, code: u16) -> io::Result<()> {
+    writer.write_all(format!("{}\r\n", line).as_bytes())?;
+    expect(reader, code)
+}
+
+// Reads a possibly multi-line reply ("250-..." continued, "250 ..." last).
+fn expect(reader: &mut BufReader<TcpStream>, code: u16) -> io::Result<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "SMTP server closed the connection"));
+        }
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        return match line.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(got) if got == code => Ok(()),
+            // 530: the relay wants STARTTLS or AUTH, neither of which is spoken here.
+            Some(530) => Err(io::Error::other(format!(
+                "the SMTP relay requires TLS or a login, which is not supported; use an unauthenticated local relay ({})",
+                line.trim_end()
+            ))),
+            _ => Err(io::Error::other(format!("unexpected SMTP reply: {}", line.trim_end()))),
+        };
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string())
+}
@@ -1,201 +1,288 @@
-use std::fs::{File, OpenOptions};
-use std::io::{self, Read, Write};
-use std::path::Path;
-use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Local};
+mod backup;
+mod bulk_edit;
+mod cli;
+mod doctor;
+mod init;
+mod input;
+mod interrupt;
+mod metrics;
+mod notifiers;
+mod picker;
+mod planner;
+mod pomodoro;
+mod review;
+mod server;
+#[cfg(feature = "tray")]
+mod tray;
+mod triage;
+mod ui;
+mod watch;
+mod wizard;
+mod xlsx;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Todo {
-    id: usize,
-    title: String,
-    description: String,
-    completed: bool,
-    created_at: DateTime<Local>,
-    updated_at: DateTime<Local>,
-}
+use std::env;
+use std::io::{self, IsTerminal};
+use std::process;
+use std::time::{Duration, Instant};
+use todo_app::aliases;
+use todo_app::archive;
+use todo_app::digest::Digest;
+use todo_app::focus;
+use todo_app::recover;
+use todo_app::history;
+use todo_app::storage;
+use todo_app::wal;
+use todo_app::workdays::Calendar;
+use todo_app::config::{Config, Confirmations, StorageKind, CONFIG_FILENAME};
+use todo_app::dates::DateFormat;
+use todo_app::keys::{Action, Keymap, MenuAction};
+use chrono::Local;
+use todo_app::{parse_tags, Todo, TodoList};
+use cli::{notify_completed, print_next_occurrence, run_command};
+use ui::{format_progress, format_tags, get_confirmation, get_input, heading, parse_progress, print_todos};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TodoList {
-    todos: Vec<Todo>,
-    next_id: usize,
+// Coalesces saves in the interactive session: a change is written right away
+// unless the previous save happened less than `delay` ago, in which case it is
+// held back until the window has passed or the session ends.
+struct SaveScheduler {
+    filename: String,
+    delay: Duration,
+    last_save: Option<Instant>,
+    dirty: bool,
 }
 
-impl TodoList {
-    fn new() -> Self {
-        TodoList {
-            todos: Vec::new(),
-            next_id: 1,
+impl SaveScheduler {
+    fn new(filename: &str, delay: Duration) -> Self {
+        SaveScheduler {
+            filename: filename.to_string(),
+            delay,
+            last_save: None,
+            dirty: false,
         }
     }
 
-    fn add_todo(&mut self, title: String, description: String) {
-        let now = Local::now();
-        let todo = Todo {
-            id: self.next_id,
-            title,
-            description,
-            completed: false,
-            created_at: now,
-            updated_at: now,
-        };
-        self.todos.push(todo);
-        self.next_id += 1;
+    fn window_elapsed(&self) -> bool {
+        self.last_save.is_none_or(|at| at.elapsed() >= self.delay)
     }
 
-    fn get_todo(&self, id: usize) -> Option<&Todo> {
-        self.todos.iter().find(|todo| todo.id == id)
+    fn changed(&mut self, todo_list: &TodoList) -> io::Result<()> {
+        self.dirty = true;
+        if self.window_elapsed() {
+            self.flush(todo_list)?;
+        }
+        Ok(())
     }
 
-    fn get_todo_mut(&mut self, id: usize) -> Option<&mut Todo> {
-        self.todos.iter_mut().find(|todo| todo.id == id)
+    fn tick(&mut self, todo_list: &TodoList) -> io::Result<()> {
+        if self.dirty && self.window_elapsed() {
+            self.flush(todo_list)?;
+        }
+        Ok(())
     }
 
-    fn edit_todo(&mut self, id: usize, title: String, description: String) -> bool {
-        if let Some(todo) = self.get_todo_mut(id) {
-            todo.title = title;
-            todo.description = description;
-            todo.updated_at = Local::now();
-            true
-        } else {
-            false
+    fn flush(&mut self, todo_list: &TodoList) -> io::Result<()> {
+        if self.dirty {
+            todo_list.save_to_file(&self.filename)?;
+            self.dirty = false;
+            self.last_save = Some(Instant::now());
         }
+        Ok(())
     }
+}
 
-    fn delete_todo(&mut self, id: usize) -> bool {
-        let position = self.todos.iter().position(|todo| todo.id == id);
-        if let Some(pos) = position {
-            self.todos.remove(pos);
-            true
-        } else {
-            false
-        }
+fn display_menu(keys: &Keymap, accessible: bool) {
+    if accessible {
+        println!("\nTODO APP menu:");
+    } else {
+        println!("\n===== TODO APP =====");
+    }
+    for &action in MenuAction::ALL {
+        println!("{}. {}", keys.menu_key(action), action.description());
     }
+    if !accessible {
+        println!("====================");
+    }
+}
 
-    fn toggle_completed(&mut self, id: usize) -> bool {
-        if let Some(todo) = self.get_todo_mut(id) {
-            todo.completed = !todo.completed;
-            todo.updated_at = Local::now();
-            true
+fn main() -> io::Result<()> {
+    interrupt::install();
+    let mut config = Config::load_from_file(CONFIG_FILENAME).unwrap_or_else(|_| {
+        println!("Could not read {}, using default settings.", CONFIG_FILENAME);
+        Config::default()
+    });
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Before anything else is read, so that an alias can stand for any
+    // arguments, --yes and the other flags included.
+    let mut args = match aliases::expand(&config.aliases, &args) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        },
+    };
+    // -y/--yes may appear anywhere and answers yes to every confirmation,
+    // --accessible, --date-format and --ephemeral likewise anywhere.
+    let before = args.len();
+    args.retain(|arg| arg != "-y" && arg != "--yes");
+    if args.len() != before {
+        config.confirm = Confirmations::none();
+    }
+    let before = args.len();
+    args.retain(|arg| arg != "--accessible");
+    if args.len() != before {
+        config.accessible = true;
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--date-format") {
+        let format = args.get(at + 1).and_then(|format| DateFormat::parse(format));
+        let Some(format) = format else {
+            println!("--date-format expects {} or a strftime pattern such as %d.%m.%Y.", DateFormat::PRESETS.join(", "));
+            return Ok(());
+        };
+        config.date_format = format;
+        args.drain(at..at + 2);
+    }
+    let before = args.len();
+    args.retain(|arg| arg != "--ephemeral");
+    if args.len() != before {
+        config.storage = StorageKind::Memory;
+        println!("Ephemeral session: starting from an empty list, nothing is saved.");
+    }
+    history::set_author(Some(config.author()));
+    // The menu in a directory without a list offers to set one up rather
+    // than starting an empty one without a word.
+    if args.is_empty() && config.storage != StorageKind::Memory && init::is_first_run(&config) && io::stdin().is_terminal() {
+        println!("There is no todo list here yet.");
+        if get_confirmation("Set one up now?") {
+            if let Some(new) = init::run(&config)? {
+                config.storage = new.storage;
+                config.data_dir = new.data_dir;
+                config.database_url = new.database_url;
+            }
         } else {
-            false
+            println!("Starting an empty list in {}; `todo_app init` sets one up later.", config.todo_filename());
         }
     }
-
-    fn list_todos(&self) {
-        if self.todos.is_empty() {
-            println!("No todos found.");
-            return;
+    let filename = &config.todo_filename();
+    if let Some(url) = &config.database_url {
+        storage::set_database_url(url);
+    }
+    if wal::recover(filename)? {
+        println!("Finished a save that was interrupted last time.");
+    }
+    if !args.is_empty() {
+        let result = run_command(&args, filename, &config);
+        if result.as_ref().is_err_and(recover::is_corrupt)
+            && TodoList::load_from_file(filename).is_err_and(|e| recover::is_corrupt(&e))
+        {
+            eprintln!("{} is damaged. Run `todo_app recover` to salvage the todos that can still be read.", filename);
         }
+        return result;
+    }
 
-        println!("{:<5} {:<30} {:<50} {:<10}", "ID", "TITLE", "DESCRIPTION", "STATUS");
-        println!("{}", "-".repeat(100));
-
-        for todo in &self.todos {
-            let status = if todo.completed { "Completed" } else { "Pending" };
-            println!("{:<5} {:<30} {:<50} {:<10}",
-                todo.id,
-                truncate(&todo.title, 27),
-                truncate(&todo.description, 47),
-                status
-            );
+    let mut todo_list = match TodoList::load_from_file(filename) {
+        Ok(todo_list) => todo_list,
+        // Starting over with an empty list would overwrite the damaged file
+        // on the next save, so ask first.
+        Err(e) if recover::is_corrupt(&e) => {
+            println!("{} is damaged: {}", filename, e);
+            if !get_confirmation("Salvage the todos that can still be read and move the damaged file aside?") {
+                println!("Leaving {} untouched. Goodbye!", filename);
+                return Ok(());
+            }
+            let recovery = recover::recover(filename)?;
+            recovery.todo_list.save_to_file(filename)?;
+            println!("Recovered {} todos. The damaged file was moved to {}.", recovery.salvaged, recovery.backup);
+            recovery.todo_list
+        },
+        Err(e) => return Err(e),
+    };
+    todo_list.set_limits(config.limits);
+    todo_list.set_days_off(Calendar::days_off(&config, filename)?);
+    if let Some(days) = config.archive_completed_after_days {
+        let archived = archive::archive_completed(&mut todo_list, filename, days)?;
+        if archived > 0 {
+            println!("Archived {} completed todos older than {} days.", archived, days);
+            todo_list.save_to_file(filename)?;
         }
     }
-
-    fn save_to_file(&self, filename: &str) -> io::Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        let mut file = File::create(filename)?;
-        file.write_all(json.as_bytes())?;
-        Ok(())
+    let mut saver = SaveScheduler::new(filename, Duration::from_millis(config.save_delay_ms));
+    // Ctrl-C ends the session through the Exit action below, so held back
+    // changes are saved.
+    let _winding = interrupt::Winding::start();
+    for conflict in config.keys.conflicts() {
+        println!("Warning: {}.", conflict);
     }
-
-    fn load_from_file(filename: &str) -> io::Result<Self> {
-        if !Path::new(filename).exists() {
-            return Ok(TodoList::new());
-        }
-
-        let mut file = File::open(filename)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-
-        let todo_list: TodoList = serde_json::from_str(&contents)?;
-        Ok(todo_list)
+    println!("{}", Digest::collect(&todo_list, Local::now().date_naive()).summary());
+    let inbox = todo_list.todos().iter().filter(|todo| todo.in_inbox()).count();
+    if inbox > 0 {
+        println!("{} todos in the inbox; `triage` files them.", inbox);
     }
-}
-
-fn truncate(s: &str, max_chars: usize) -> String {
-    if s.len() <= max_chars {
-        s.to_string()
-    } else {
-        format!("{}...", &s[0..max_chars-3])
+    let today = Local::now().date_naive();
+    let follow_ups = todo_list.todos().iter().filter(|todo| todo.follow_up_due(today)).count();
+    if follow_ups > 0 {
+        println!("{} delegated todos to follow up on; `waiting-for` lists them.", follow_ups);
     }
-}
-
-fn get_input(prompt: &str) -> String {
-    println!("{}", prompt);
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).expect("Failed to read input");
-    input.trim().to_string()
-}
-
-fn get_confirmation(prompt: &str) -> bool {
-    loop {
-        let input = get_input(&format!("{} (y/n): ", prompt)).to_lowercase();
-        match input.as_str() {
-            "y" | "yes" => return true,
-            "n" | "no" => return false,
-            _ => println!("Please enter 'y' or 'n'"),
+    if let Ok(Some(focus)) = focus::load_focus(&focus::focus_filename(filename)) {
+        if let Some(todo) = todo_list.get_todo(focus.id).filter(|todo| !todo.completed) {
+            println!("Focus: #{} {}", todo.id, todo.title);
         }
     }
-}
-
-fn display_menu() {
-    println!("\n===== TODO APP =====");
-    println!("1. List all todos");
-    println!("2. Add a new todo");
-    println!("3. Edit a todo");
-    println!("4. Toggle todo completion status");
-    println!("5. Delete a todo");
-    println!("0. Exit");
-    println!("====================");
-}
-
-fn main() -> io::Result<()> {
-    const FILENAME: &str = "todos.json";
-    let mut todo_list = TodoList::load_from_file(FILENAME).unwrap_or_else(|_| {
-        println!("Creating new todo list.");
-        TodoList::new()
-    });
 
     loop {
-        display_menu();
+        display_menu(&config.keys, config.accessible);
+        input::set_completions(&todo_list);
         let choice = get_input("Enter your choice:");
+        saver.tick(&todo_list)?;
+        // Running out of input ends the session like choosing to exit.
+        let action = if input::at_eof() { Some(MenuAction::Exit) } else { config.keys.menu_action(&choice) };
 
-        match choice.as_str() {
-            "1" => {
-                println!("\n--- All Todos ---");
-                todo_list.list_todos();
+        match action {
+            Some(MenuAction::List) => {
+                println!();
+                heading("All Todos", config.accessible);
+                let today = Local::now().date_naive();
+                let (waiting, visible): (Vec<Todo>, Vec<Todo>) =
+                    todo_list.todos().iter().cloned().partition(|todo| todo.is_waiting(today));
+                print_todos(&visible, &config);
+                if !waiting.is_empty() {
+                    println!("\n{} waiting todos hidden; `list --all` shows them.", waiting.len());
+                }
             },
-            "2" => {
-                let title = get_input("Enter todo title:");
-                let description = get_input("Enter todo description:");
-                todo_list.add_todo(title, description);
-                println!("Todo added successfully!");
-                todo_list.save_to_file(FILENAME)?;
+            Some(MenuAction::Add) => {
+                if wizard::run(&mut todo_list).is_some() {
+                    println!("Todo added successfully!");
+                    saver.changed(&todo_list)?;
+                }
             },
-            "3" => {
-                todo_list.list_todos();
-                let id_str = get_input("Enter the ID of the todo to edit:");
+            Some(MenuAction::Edit) => {
+                print_todos(todo_list.todos(), &config);
+                let id_str = picker::ask_id("Enter the ID of the todo to edit:", todo_list.todos(), &config);
                 if let Ok(id) = id_str.parse::<usize>() {
                     if let Some(todo) = todo_list.get_todo(id) {
                         println!("Editing todo: {}", todo.title);
                         let title = get_input(&format!("Enter new title (current: {}):", todo.title));
                         let description = get_input(&format!("Enter new description (current: {}):", todo.description));
+                        let tags = parse_tags(&get_input(&format!("Enter new tags (current: {}):", format_tags(todo))));
+                        let progress = loop {
+                            let current = if todo.progress.is_some() { format_progress(todo) } else { "none".to_string() };
+                            let input = get_input(&format!("Enter progress in % (current: {}; empty keeps it, - clears it):", current));
+                            match input.as_str() {
+                                "" => break todo.progress,
+                                "-" => break None,
+                                _ => match parse_progress(&input) {
+                                    Some(p) => break Some(p),
+                                    None => println!("Progress must be a number from 0 to 100."),
+                                },
+                            }
+                        };
 
-                        if todo_list.edit_todo(id, title, description) {
-                            println!("Todo updated successfully!");
-                            todo_list.save_to_file(FILENAME)?;
-                        } else {
-                            println!("Failed to update todo.");
+                        match todo_list.edit_todo(id, title, description) {
+                            Ok(true) if todo_list.set_tags(id, &tags) && todo_list.set_progress(id, progress) => {
+                                println!("Todo updated successfully!");
+                                saver.changed(&todo_list)?;
+                            },
+                            Ok(_) => println!("Failed to update todo."),
+                            Err(e) => println!("{}", e),
                         }
                     } else {
                         println!("Todo with ID {} not found.", id);
@@ -204,13 +291,18 @@ fn main() -> io::Result<()> {
                     println!("Invalid ID format.");
                 }
             },
-            "4" => {
-                todo_list.list_todos();
-                let id_str = get_input("Enter the ID of the todo to toggle completion status:");
+            Some(MenuAction::Toggle) => {
+                print_todos(todo_list.todos(), &config);
+                let id_str = picker::ask_id("Enter the ID of the todo to toggle completion status:", todo_list.todos(), &config);
                 if let Ok(id) = id_str.parse::<usize>() {
-                    if todo_list.toggle_completed(id) {
+                    // Only completing asks for a note, not reopening or keeping a habit.
+                    let completing = todo_list.get_todo(id).is_some_and(|todo| !todo.completed && !todo.habit);
+                    let note = if completing { Some(get_input("Note (optional):")) } else { None };
+                    if todo_list.toggle_with_note(id, note) {
                         println!("Todo status toggled successfully!");
-                        todo_list.save_to_file(FILENAME)?;
+                        print_next_occurrence(&todo_list, id);
+                        saver.changed(&todo_list)?;
+                        notify_completed(&todo_list, id, &config);
                     } else {
                         println!("Todo with ID {} not found.", id);
                     }
@@ -218,19 +310,19 @@ fn main() -> io::Result<()> {
                     println!("Invalid ID format.");
                 }
             },
-            "5" => {
-                todo_list.list_todos();
-                let id_str = get_input("Enter the ID of the todo to delete:");
+            Some(MenuAction::Delete) => {
+                print_todos(todo_list.todos(), &config);
+                let id_str = picker::ask_id("Enter the ID of the todo to delete:", todo_list.todos(), &config);
                 if let Ok(id) = id_str.parse::<usize>() {
                     if let Some(todo) = todo_list.get_todo(id) {
                         println!("You are about to delete the following todo:");
                         println!("Title: {}", todo.title);
                         println!("Description: {}", todo.description);
 
-                        if get_confirmation("Are you sure you want to delete this todo?") {
+                        if !config.confirm.delete || get_confirmation("Are you sure you want to delete this todo?") {
                             if todo_list.delete_todo(id) {
                                 println!("Todo deleted successfully!");
-                                todo_list.save_to_file(FILENAME)?;
+                                saver.changed(&todo_list)?;
                             } else {
                                 println!("Failed to delete todo.");
                             }
@@ -244,13 +336,17 @@ fn main() -> io::Result<()> {
                     println!("Invalid ID format.");
                 }
             },
-            "0" => {
+            Some(MenuAction::Exit) => {
+                saver.flush(&todo_list)?;
                 println!("Exiting. Goodbye!");
                 break;
             },
-            _ => println!("Invalid choice. Please try again."),
+            None => println!("Invalid choice. Please try again."),
         }
     }
 
+    if interrupt::requested() {
+        process::exit(130);
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use crate::priority::{effective_priority, Priority};
+use crate::todo::Todo;
+
+// A todo due within this many days, or overdue, is urgent.
+pub const URGENT_WITHIN_DAYS: i64 = 2;
+
+// The four quadrants of the Eisenhower matrix, named for what to do with
+// the todos in them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quadrant {
+    Do,
+    Schedule,
+    Delegate,
+    Drop,
+}
+
+impl Quadrant {
+    pub const ALL: [Quadrant; 4] = [Quadrant::Do, Quadrant::Schedule, Quadrant::Delegate, Quadrant::Drop];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Quadrant::Do => "Do: urgent and important",
+            Quadrant::Schedule => "Schedule: important, not urgent",
+            Quadrant::Delegate => "Delegate: urgent, not important",
+            Quadrant::Drop => "Drop: neither urgent nor important",
+        }
+    }
+}
+
+pub fn is_urgent(todo: &Todo, today: NaiveDate) -> bool {
+    todo.due.is_some_and(|due| due <= today + Duration::days(URGENT_WITHIN_DAYS))
+}
+
+// Flagged as important, or of high priority once aging is applied.
+pub fn is_important(todo: &Todo, escalate_after_days: Option<u32>, now: DateTime<Local>) -> bool {
+    todo.important || effective_priority(todo, escalate_after_days, now) == Some(Priority::High)
+}
+
+pub fn quadrant(todo: &Todo, today: NaiveDate, escalate_after_days: Option<u32>, now: DateTime<Local>) -> Quadrant {
+    match (is_urgent(todo, today), is_important(todo, escalate_after_days, now)) {
+        (true, true) => Quadrant::Do,
+        (false, true) => Quadrant::Schedule,
+        (true, false) => Quadrant::Delegate,
+        (false, false) => Quadrant::Drop,
+    }
+}
+
+// Pending todos that are not waiting, in their quadrants, soonest due first
+// within each.
+pub fn buckets<'a>(todos: impl IntoIterator<Item = &'a Todo>, escalate_after_days: Option<u32>, now: DateTime<Local>) -> Vec<(Quadrant, Vec<&'a Todo>)> {
+    let today = now.date_naive();
+    let mut buckets: Vec<(Quadrant, Vec<&Todo>)> = Quadrant::ALL.into_iter().map(|q| (q, Vec::new())).collect();
+    for todo in todos.into_iter().filter(|todo| !todo.completed && !todo.habit && !todo.is_waiting(today)) {
+        let q = quadrant(todo, today, escalate_after_days, now);
+        if let Some((_, bucket)) = buckets.iter_mut().find(|(quadrant, _)| *quadrant == q) {
+            bucket.push(todo);
+        }
+    }
+    for (_, bucket) in &mut buckets {
+        bucket.sort_by_key(|todo| (todo.due.is_none(), todo.due, todo.id));
+    }
+    buckets
+}
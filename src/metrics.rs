@@ -0,0 +1,135 @@
+// What `serve` reports at /metrics, in the Prometheus text format. The
+// counters run from when the server started; the todo counts are read from
+// the list next to the server at every scrape.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+use chrono::Local;
+use todo_app::sync::Server;
+use todo_app::TodoList;
+
+// Upper bounds of the latency buckets, in seconds.
+const BUCKETS: [f64; 10] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0];
+
+#[derive(Default)]
+struct Histogram {
+    // Cumulative, as Prometheus expects.
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    // By method, endpoint and status.
+    requests: BTreeMap<(&'static str, &'static str, String), u64>,
+    latencies: BTreeMap<&'static str, Histogram>,
+    // By direction and outcome.
+    syncs: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+// Anything else is one label value, so that scanners probing random paths
+// do not add a series each.
+fn endpoint(path: &str) -> &'static str {
+    match path {
+        "/register" => "/register",
+        "/ops" => "/ops",
+        "/activity" => "/activity",
+        "/users" => "/users",
+        "/metrics" => "/metrics",
+        _ => "other",
+    }
+}
+
+fn method(method: &str) -> &'static str {
+    match method {
+        "GET" => "GET",
+        "POST" => "POST",
+        _ => "other",
+    }
+}
+
+impl Metrics {
+    pub fn observe(&mut self, request_method: &str, path: &str, status: Option<u16>, outcome: &'static str, elapsed: Duration) {
+        let (request_method, path) = (method(request_method), endpoint(path));
+        let status = status.map_or_else(|| "none".to_string(), |status| status.to_string());
+        *self.requests.entry((request_method, path, status)).or_default() += 1;
+        let seconds = elapsed.as_secs_f64();
+        let histogram = self.latencies.entry(path).or_default();
+        for (count, &bound) in histogram.buckets.iter_mut().zip(&BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+        let direction = match (request_method, path) {
+            ("GET", "/ops") => "pull",
+            ("POST", "/ops") => "push",
+            _ => return,
+        };
+        *self.syncs.entry((direction, outcome)).or_default() += 1;
+    }
+
+    pub fn render(&self, server: &Server, filename: &str) -> String {
+        let mut out = String::new();
+        let today = Local::now().date_naive();
+        let (mut pending, mut completed, mut cancelled, mut overdue) = (0, 0, 0, 0);
+        // A list that cannot be read leaves the todo counts out rather than
+        // failing the scrape.
+        let read = TodoList::for_each_in_file(filename, |todo| {
+            if todo.cancelled {
+                cancelled += 1;
+            } else if todo.completed {
+                completed += 1;
+            } else {
+                pending += 1;
+                if todo.due.is_some_and(|due| due < today) {
+                    overdue += 1;
+                }
+            }
+            true
+        });
+        if read.is_ok() {
+            let _ = writeln!(out, "# HELP todo_todos Todos in the list next to the server, by status.");
+            let _ = writeln!(out, "# TYPE todo_todos gauge");
+            for (status, count) in [("pending", pending), ("completed", completed), ("cancelled", cancelled)] {
+                let _ = writeln!(out, "todo_todos{{status=\"{}\"}} {}", status, count);
+            }
+            let _ = writeln!(out, "# HELP todo_todos_overdue Pending todos past their due date.");
+            let _ = writeln!(out, "# TYPE todo_todos_overdue gauge");
+            let _ = writeln!(out, "todo_todos_overdue {}", overdue);
+        }
+
+        let _ = writeln!(out, "# HELP todo_sync_ops_total Changes stored by the server.");
+        let _ = writeln!(out, "# TYPE todo_sync_ops_total counter");
+        let _ = writeln!(out, "todo_sync_ops_total {}", server.seq());
+        let _ = writeln!(out, "# HELP todo_sync_devices Devices registered with the server.");
+        let _ = writeln!(out, "# TYPE todo_sync_devices gauge");
+        let _ = writeln!(out, "todo_sync_devices {}", server.device_count());
+
+        let _ = writeln!(out, "# HELP todo_syncs_total Pulls and pushes by outcome.");
+        let _ = writeln!(out, "# TYPE todo_syncs_total counter");
+        for ((direction, outcome), count) in &self.syncs {
+            let _ = writeln!(out, "todo_syncs_total{{direction=\"{}\",outcome=\"{}\"}} {}", direction, outcome, count);
+        }
+
+        let _ = writeln!(out, "# HELP todo_http_requests_total Requests by method, endpoint and status.");
+        let _ = writeln!(out, "# TYPE todo_http_requests_total counter");
+        for ((request_method, path, status), count) in &self.requests {
+            let _ = writeln!(out, "todo_http_requests_total{{method=\"{}\",endpoint=\"{}\",status=\"{}\"}} {}", request_method, path, status, count);
+        }
+
+        let _ = writeln!(out, "# HELP todo_http_request_duration_seconds Time taken to answer a request, by endpoint.");
+        let _ = writeln!(out, "# TYPE todo_http_request_duration_seconds histogram");
+        for (path, histogram) in &self.latencies {
+            for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(out, "todo_http_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}", path, bound, count);
+            }
+            let _ = writeln!(out, "todo_http_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}", path, histogram.count);
+            let _ = writeln!(out, "todo_http_request_duration_seconds_sum{{endpoint=\"{}\"}} {}", path, histogram.sum);
+            let _ = writeln!(out, "todo_http_request_duration_seconds_count{{endpoint=\"{}\"}} {}", path, histogram.count);
+        }
+        out
+    }
+}
@@ -0,0 +1,157 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use todo_app::config::Config;
+use todo_app::notify::{self, Notification, Notifier, WebhookFormat};
+
+// How long a FIFO nobody reads from is waited on.
+const FIFO_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Where notifications are shown besides the webhooks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backend {
+    // notify-send on Linux and the BSDs, osascript on macOS.
+    Desktop,
+    // The terminal bell and the headline on standard error.
+    Bell,
+    // The status line of the tmux session the app runs in.
+    Tmux,
+    // A line of JSON appended to a file, or written to a FIFO for a script
+    // to read.
+    File(String),
+}
+
+// "desktop", "bell", "tmux", "file:PATH" or "auto".
+pub fn parse(spec: &str) -> Option<Backend> {
+    match spec.trim() {
+        "auto" => Some(auto()),
+        "desktop" => Some(Backend::Desktop),
+        "bell" => Some(Backend::Bell),
+        "tmux" => Some(Backend::Tmux),
+        spec => spec.strip_prefix("file:").filter(|path| !path.is_empty()).map(|path| Backend::File(path.to_string())),
+    }
+}
+
+fn is_set(name: &str) -> bool {
+    env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+// The desktop where there is one to show it on (a session over SSH has
+// none, even with a DISPLAY forwarded), else tmux if the app runs in it,
+// else the bell.
+pub fn auto() -> Backend {
+    let remote = is_set("SSH_CONNECTION") || is_set("SSH_TTY");
+    let desktop = if cfg!(target_os = "macos") { true } else { is_set("DISPLAY") || is_set("WAYLAND_DISPLAY") };
+    if desktop && !remote {
+        Backend::Desktop
+    } else if is_set("TMUX") {
+        Backend::Tmux
+    } else {
+        Backend::Bell
+    }
+}
+
+// The notifiers as given: by TODO_NOTIFIERS, so that each environment the
+// same configuration is used in can choose its own, or in the configuration.
+pub fn specs(config: &Config) -> Vec<String> {
+    match env::var("TODO_NOTIFIERS") {
+        Ok(list) => list.split(',').map(str::trim).filter(|spec| !spec.is_empty()).map(str::to_string).collect(),
+        Err(_) => config.notifiers.clone(),
+    }
+}
+
+// Those that are understood; `doctor` points out the rest.
+pub fn backends(config: &Config) -> Vec<Backend> {
+    specs(config).iter().filter_map(|spec| parse(spec)).collect()
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("{} could not be run: {}", program, e)))?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed with {}", program, status)));
+    }
+    Ok(())
+}
+
+// AppleScript string literal.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn is_fifo(path: &str) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+fn append(path: &str, line: &str) -> io::Result<()> {
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
+
+// Opening a FIFO waits for a reader, so it is written from a thread that is
+// given up on if none comes.
+fn write_fifo(path: &str, line: String) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let owned = path.to_string();
+    thread::spawn(move || {
+        let _ = tx.send(OpenOptions::new().write(true).open(&owned).and_then(|mut fifo| fifo.write_all(line.as_bytes())));
+    });
+    rx.recv_timeout(FIFO_TIMEOUT)
+        .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, format!("nothing reads from {}", path))))
+}
+
+impl Notifier for Backend {
+    fn name(&self) -> String {
+        match self {
+            Backend::Desktop => "desktop".to_string(),
+            Backend::Bell => "bell".to_string(),
+            Backend::Tmux => "tmux".to_string(),
+            Backend::File(path) => format!("file {}", path),
+        }
+    }
+
+    fn send(&self, notification: &Notification) -> io::Result<()> {
+        let headline = notification.headline();
+        match self {
+            Backend::Desktop if cfg!(target_os = "macos") => {
+                let script = format!("display notification {} with title {}", quoted(&notification.body()), quoted(&headline));
+                run("osascript", &["-e", &script])
+            },
+            Backend::Desktop => run("notify-send", &["--app-name=todo_app", &headline, &notification.body()]),
+            Backend::Bell => {
+                let mut err = io::stderr().lock();
+                let bell = if err.is_terminal() { "\x07" } else { "" };
+                writeln!(err, "{}{}", bell, headline)
+            },
+            Backend::Tmux if !is_set("TMUX") => Err(io::Error::other("not running inside tmux")),
+            // tmux expands the message as a format, where `#(...)` runs a
+            // command; `##` is a literal `#`. (`-l` would do too, but only
+            // from tmux 3.3 on.)
+            Backend::Tmux => run("tmux", &["display-message", &headline.replace('#', "##")]),
+            Backend::File(path) => {
+                let line = format!("{}\n", notify::payload(WebhookFormat::Generic, notification));
+                if is_fifo(path) {
+                    write_fifo(path, line)
+                } else {
+                    append(path, &line)
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::io;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::todo::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    // The notification as plain JSON, for scripts and automation services.
+    #[default]
+    Generic,
+    Slack,
+    Discord,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+pub enum Notification<'a> {
+    Completed(&'a Todo),
+    DueSoon(Vec<&'a Todo>),
+}
+
+impl Notification<'_> {
+    fn event(&self) -> &'static str {
+        match self {
+            Notification::Completed(_) => "completed",
+            Notification::DueSoon(_) => "due_soon",
+        }
+    }
+
+    pub fn todos(&self) -> Vec<&Todo> {
+        match self {
+            Notification::Completed(todo) => vec![todo],
+            Notification::DueSoon(todos) => todos.clone(),
+        }
+    }
+
+    pub fn headline(&self) -> String {
+        match self {
+            Notification::Completed(todo) => format!("Completed: {}", todo.title),
+            Notification::DueSoon(todos) if todos.len() == 1 => "1 todo is due soon".to_string(),
+            Notification::DueSoon(todos) => format!("{} todos are due soon", todos.len()),
+        }
+    }
+
+    // The todos by ID and title, one per line, for the body of a
+    // notification on the desktop or in a file.
+    pub fn body(&self) -> String {
+        let lines: Vec<String> = self.todos().iter().map(|todo| format!("#{} {}", todo.id, todo.title)).collect();
+        lines.join("\n")
+    }
+}
+
+// Somewhere notifications go: a webhook, or one of the app's own backends
+// such as the desktop or tmux, chosen with `notifiers` in the configuration.
+pub trait Notifier {
+    // How errors refer to it, e.g. its URL.
+    fn name(&self) -> String;
+    fn send(&self, notification: &Notification) -> io::Result<()>;
+}
+
+#[cfg(feature = "net")]
+impl Notifier for Webhook {
+    fn name(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn send(&self, notification: &Notification) -> io::Result<()> {
+        send(self, notification)
+    }
+}
+
+// Short "due/priority/tags" summary used by the chat formats.
+fn details(todo: &Todo) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    if let Some(due) = todo.due {
+        fields.push(("Due", due.to_string()));
+    }
+    if let Some(priority) = todo.priority {
+        fields.push(("Priority", priority.to_string()));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<&str> = todo.tags.iter().map(|tag| tag.as_str()).collect();
+        fields.push(("Tags", tags.join(", ")));
+    }
+    fields
+}
+
+pub fn payload(format: WebhookFormat, notification: &Notification) -> Value {
+    match format {
+        WebhookFormat::Generic => json!({
+            "event": notification.event(),
+            "todos": notification.todos(),
+        }),
+        WebhookFormat::Slack => slack_payload(notification),
+        WebhookFormat::Discord => discord_payload(notification),
+    }
+}
+
+// `text` cut to at most `max` characters, ending in "…" if it was longer.
+fn clip(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(max - 1).collect();
+    clipped.push('…');
+    clipped
+}
+
+// Block Kit message: a header, then one section per todo with its details
+// underneath as context. `text` is the fallback shown in push notifications.
+// Slack drops messages of more than 50 blocks, headers longer than 150
+// characters and texts longer than 3000, so only the first 10 todos are
+// shown, followed by how many more there are.
+fn slack_payload(notification: &Notification) -> Value {
+    const TODOS: usize = 10;
+    const HEADER: usize = 150;
+    const TEXT: usize = 3000;
+    let headline = notification.headline();
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": clip(&headline, HEADER) },
+    })];
+    let todos = notification.todos();
+    for todo in todos.iter().take(TODOS) {
+        let mut text = format!("*#{}* {}", todo.id, todo.title);
+        if !todo.description.is_empty() {
+            text.push_str(&format!("\n{}", todo.description));
+        }
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": clip(&text, TEXT) },
+        }));
+        let fields = details(todo);
+        if !fields.is_empty() {
+            let elements: Vec<Value> = fields
+                .iter()
+                .map(|(name, value)| json!({ "type": "mrkdwn", "text": clip(&format!("*{}:* {}", name, value), TEXT) }))
+                .collect();
+            blocks.push(json!({ "type": "context", "elements": elements }));
+        }
+    }
+    if todos.len() > TODOS {
+        let more = format!("…and {} more", todos.len() - TODOS);
+        blocks.push(json!({ "type": "context", "elements": [{ "type": "mrkdwn", "text": more }] }));
+    }
+    json!({ "text": headline, "blocks": blocks })
+}
+
+// One embed per todo, green for completed and amber for due soon. Discord
+// allows at most 10 embeds per message.
+fn discord_payload(notification: &Notification) -> Value {
+    let color = match notification {
+        Notification::Completed(_) => 0x2ecc71,
+        Notification::DueSoon(_) => 0xf1c40f,
+    };
+    let embeds: Vec<Value> = notification
+        .todos()
+        .into_iter()
+        .take(10)
+        .map(|todo| {
+            let fields: Vec<Value> = details(todo)
+                .into_iter()
+                .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+                .collect();
+            let mut embed = json!({
+                "title": format!("#{} {}", todo.id, todo.title),
+                "color": color,
+                "fields": fields,
+            });
+            // Discord rejects empty descriptions.
+            if !todo.description.is_empty() {
+                embed["description"] = json!(todo.description);
+            }
+            embed
+        })
+        .collect();
+    json!({ "content": notification.headline(), "embeds": embeds })
+}
+
+// A webhook that does not answer must not hold up the command that
+// notifies it.
+#[cfg(feature = "net")]
+pub fn send(webhook: &Webhook, notification: &Notification) -> io::Result<()> {
+    let config = ureq::Agent::config_builder()
+        .timeout_connect(Some(std::time::Duration::from_secs(5)))
+        .timeout_global(Some(std::time::Duration::from_secs(10)))
+        .build();
+    ureq::Agent::new_with_config(config)
+        .post(&webhook.url)
+        .send_json(payload(webhook.format, notification))
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+// Sends to every notifier, carrying on past failures; returns the errors.
+pub fn send_all(notifiers: &[&dyn Notifier], notification: &Notification) -> Vec<io::Error> {
+    notifiers
+        .iter()
+        .filter_map(|notifier| notifier.send(notification).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", notifier.name(), e))).err())
+        .collect()
+}
@@ -0,0 +1,338 @@
+use chrono::NaiveDate;
+use crate::digest::Digest;
+use crate::estimates::{self, format_minutes};
+use crate::todo::{Todo, TodoList};
+
+// A day's agenda laid out for paper: headed sections of todos to tick off.
+pub struct Page {
+    pub title: String,
+    pub subtitle: String,
+    pub sections: Vec<Section>,
+}
+
+pub struct Section {
+    pub heading: String,
+    pub items: Vec<String>,
+}
+
+// Lines left under "Notes" to write on.
+const NOTE_LINES: usize = 6;
+
+// "#12 Send the report (due 17:00, high, 1h30m)"
+fn item(todo: &Todo, mut notes: Vec<String>) -> String {
+    if let Some(priority) = todo.priority {
+        notes.push(priority.to_string());
+    }
+    if todo.estimate.is_some() && estimates::remaining(todo) > 0 {
+        notes.push(format_minutes(estimates::remaining(todo)));
+    }
+    let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+    format!("#{} {}{}", todo.id, todo.title, notes)
+}
+
+// What `digest` lists for `day`, the todos due at a time of day in the order
+// they are due, and the habits not yet kept that day.
+pub fn agenda(todo_list: &TodoList, day: NaiveDate) -> Page {
+    let digest = Digest::collect(todo_list, day);
+    let mut sections = Vec::new();
+    let mut section = |heading: &str, items: Vec<String>| {
+        if !items.is_empty() {
+            sections.push(Section { heading: heading.to_string(), items });
+        }
+    };
+    let mut due_today = digest.due_today.clone();
+    due_today.sort_by_key(|todo| (todo.deadline.is_none(), todo.deadline.as_ref().map(|deadline| deadline.at)));
+    section(
+        "Due today",
+        due_today
+            .iter()
+            .map(|todo| item(todo, todo.deadline.iter().map(|deadline| format!("due {}", deadline.local().format("%H:%M"))).collect()))
+            .collect(),
+    );
+    section(
+        "Overdue",
+        digest.overdue.iter().map(|todo| item(todo, todo.due.iter().map(|due| format!("due {}", due)).collect())).collect(),
+    );
+    section(
+        "Scheduled",
+        digest
+            .scheduled
+            .iter()
+            .map(|todo| item(todo, todo.due.iter().map(|due| format!("due {}", due)).collect()))
+            .collect(),
+    );
+    section(
+        "Follow up",
+        digest.follow_ups.iter().map(|todo| item(todo, todo.delegated_to.iter().map(|person| format!("with {}", person)).collect())).collect(),
+    );
+    section(
+        "Habits",
+        todo_list.todos().iter().filter(|todo| todo.habit && !todo.done_on(day)).map(|todo| format!("#{} {}", todo.id, todo.title)).collect(),
+    );
+    Page { title: format!("Agenda for {}", day.format("%A, %-d %B %Y")), subtitle: digest.summary(), sections }
+}
+
+// Splits `text` into lines for which `fits` holds, between words where it
+// can and inside a word too long for a line of its own.
+fn wrap(text: &str, fits: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let joined = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if fits(&joined) {
+            line = joined;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        let mut rest = word;
+        while !fits(rest) {
+            let end = rest.char_indices().map(|(i, c)| i + c.len_utf8()).take_while(|&end| fits(&rest[..end])).last();
+            let end = end.unwrap_or_else(|| rest.chars().next().map_or(rest.len(), char::len_utf8));
+            lines.push(rest[..end].to_string());
+            rest = &rest[end..];
+        }
+        line = rest.to_string();
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+impl Page {
+    // For a printer fed plain text, or `lp`, at `width` columns.
+    pub fn text(&self, width: usize) -> String {
+        let width = width.max(20);
+        let mut out = format!("{}\n{}\n", self.title, self.subtitle);
+        if self.sections.is_empty() {
+            out.push_str("\nNothing is scheduled, due today, overdue or to follow up on.\n");
+        }
+        for section in &self.sections {
+            out.push_str(&format!("\n{}\n", section.heading));
+            for item in &section.items {
+                for (n, line) in wrap(item, |line| line.chars().count() + 6 <= width).iter().enumerate() {
+                    let mark = if n == 0 { "  [ ] " } else { "      " };
+                    out.push_str(&format!("{}{}\n", mark, line));
+                }
+            }
+        }
+        out.push_str("\nNotes\n");
+        for _ in 0..NOTE_LINES {
+            out.push_str(&format!("\n  {}\n", "_".repeat(width - 2)));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaperSize {
+    A4,
+    Letter,
+}
+
+impl PaperSize {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "a4" => Some(PaperSize::A4),
+            "letter" => Some(PaperSize::Letter),
+            _ => None,
+        }
+    }
+
+    // Width and height in points.
+    fn points(self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (595.0, 842.0),
+            PaperSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+// Widths of the printable ASCII characters in Helvetica, in thousandths of
+// the font size, as its metrics give them.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278,
+    584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944,
+    667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500,
+    278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+fn text_width(text: &str, size: f64) -> f64 {
+    let units: u32 = text.chars().map(|c| if (' '..='~').contains(&c) { u32::from(HELVETICA[c as usize - 32]) } else { 556 }).sum();
+    f64::from(units) * size / 1000.0
+}
+
+// A PDF string in the standard fonts' WinAnsi encoding, which covers Latin-1
+// and a few punctuation marks; anything else becomes "?".
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                c as u8
+            },
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        };
+        out.push(byte);
+    }
+    out.push(b')');
+    out
+}
+
+const MARGIN: f64 = 56.0;
+
+enum Line {
+    Title(String),
+    Subtitle(String),
+    Heading(String),
+    // A todo, with a box in front for its first line.
+    Item(String, bool),
+    Rule,
+    Gap,
+}
+
+impl Line {
+    fn height(&self) -> f64 {
+        match self {
+            Line::Title(_) => 26.0,
+            Line::Subtitle(_) => 16.0,
+            Line::Heading(_) => 22.0,
+            Line::Item(..) => 16.0,
+            Line::Rule => 24.0,
+            Line::Gap => 8.0,
+        }
+    }
+}
+
+// The text operator for `text` at `size` points in font F1 (regular) or F2
+// (bold).
+fn show(out: &mut Vec<u8>, font: u8, size: f64, x: f64, y: f64, text: &str) {
+    out.extend_from_slice(format!("BT /F{} {} Tf {:.1} {:.1} Td ", font, size, x, y).as_bytes());
+    out.extend_from_slice(&pdf_string(text));
+    out.extend_from_slice(b" Tj ET\n");
+}
+
+impl Page {
+    // A PDF of one or more pages, set in Helvetica, which every PDF reader
+    // has, with a box to tick in front of each todo.
+    pub fn pdf(&self, size: PaperSize) -> Vec<u8> {
+        let (width, height) = size.points();
+        let indent = 18.0;
+        let text_right = width - MARGIN;
+
+        let mut lines = vec![Line::Title(self.title.clone()), Line::Subtitle(self.subtitle.clone())];
+        if self.sections.is_empty() {
+            lines.push(Line::Gap);
+            lines.push(Line::Subtitle("Nothing is scheduled, due today, overdue or to follow up on.".to_string()));
+        }
+        for section in &self.sections {
+            lines.push(Line::Gap);
+            lines.push(Line::Heading(section.heading.clone()));
+            for item in &section.items {
+                let wrapped = wrap(item, |line| MARGIN + indent + text_width(line, 11.0) <= text_right);
+                lines.extend(wrapped.into_iter().enumerate().map(|(n, line)| Line::Item(line, n == 0)));
+            }
+        }
+        lines.push(Line::Gap);
+        lines.push(Line::Heading("Notes".to_string()));
+        lines.extend((0..NOTE_LINES).map(|_| Line::Rule));
+
+        // Broken into pages, keeping a heading with the line after it.
+        let bottom = MARGIN + 20.0;
+        let mut pages: Vec<Vec<(f64, &Line)>> = vec![Vec::new()];
+        let mut y = height - MARGIN;
+        for (n, line) in lines.iter().enumerate() {
+            let next = if matches!(line, Line::Heading(_) | Line::Gap) { lines.get(n + 1).map_or(0.0, Line::height) } else { 0.0 };
+            if y - line.height() - next < bottom && pages.last().is_some_and(|page| !page.is_empty()) {
+                pages.push(Vec::new());
+                y = height - MARGIN;
+                if matches!(line, Line::Gap) {
+                    continue;
+                }
+            }
+            y -= line.height();
+            if let Some(page) = pages.last_mut() {
+                page.push((y, line));
+            }
+        }
+
+        let mut contents = Vec::new();
+        for (number, page) in pages.iter().enumerate() {
+            let mut out = Vec::new();
+            for &(y, line) in page {
+                match line {
+                    Line::Title(text) => show(&mut out, 2, 18.0, MARGIN, y, text),
+                    Line::Subtitle(text) => show(&mut out, 1, 11.0, MARGIN, y, text),
+                    Line::Heading(text) => show(&mut out, 2, 13.0, MARGIN, y, text),
+                    Line::Item(text, first) => {
+                        if *first {
+                            out.extend_from_slice(format!("0.8 w {:.1} {:.1} 9 9 re S\n", MARGIN, y - 1.0).as_bytes());
+                        }
+                        show(&mut out, 1, 11.0, MARGIN + indent, y, text);
+                    },
+                    Line::Rule => out.extend_from_slice(format!("0.5 w {:.1} {:.1} m {:.1} {:.1} l S\n", MARGIN, y, text_right, y).as_bytes()),
+                    Line::Gap => {},
+                }
+            }
+            if pages.len() > 1 {
+                let label = format!("Page {} of {}", number + 1, pages.len());
+                show(&mut out, 1, 9.0, text_right - text_width(&label, 9.0), MARGIN, &label);
+            }
+            contents.push(out);
+        }
+
+        // Objects 1 to 4 are the catalog, the page tree and the two fonts;
+        // each page and its contents follow.
+        let kids: Vec<String> = (0..pages.len()).map(|n| format!("{} 0 R", 5 + 2 * n)).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        for (n, content) in contents.into_iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    width,
+                    height,
+                    6 + 2 * n
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend_from_slice(&content);
+            stream.extend_from_slice(b"endstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (n, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", n + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes());
+        pdf
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::{self, IsTerminal, Read, Write};
+use todo_app::config::Config;
+use todo_app::fuzzy;
+use todo_app::Todo;
+use crate::input;
+use crate::ui::{get_input, truncate};
+
+// Matches shown at once; the arrow keys move through the rest.
+const SHOWN: usize = 10;
+
+enum Key {
+    Char(char),
+    Backspace,
+    ClearQuery,
+    Up,
+    Down,
+    Enter,
+    Cancel,
+    // Nothing was pressed in time, or a key without a use here.
+    Other,
+}
+
+fn read_byte(stdin: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    Ok((stdin.read(&mut byte)? == 1).then_some(byte[0]))
+}
+
+fn read_key(stdin: &mut impl Read) -> io::Result<Key> {
+    let Some(byte) = read_byte(stdin)? else {
+        return Ok(Key::Other);
+    };
+    Ok(match byte {
+        b'\r' | b'\n' => Key::Enter,
+        // Esc on its own; arrow keys arrive as Esc [ A and Esc [ B.
+        0x1b => match read_byte(stdin)? {
+            None => Key::Cancel,
+            Some(b'[' | b'O') => match read_byte(stdin)? {
+                Some(b'A') => Key::Up,
+                Some(b'B') => Key::Down,
+                _ => Key::Other,
+            },
+            Some(_) => Key::Other,
+        },
+        // Ctrl-D
+        0x04 => Key::Cancel,
+        0x7f | 0x08 => Key::Backspace,
+        // Ctrl-U
+        0x15 => Key::ClearQuery,
+        // Ctrl-P, Ctrl-N and Tab
+        0x10 => Key::Up,
+        0x0e | b'\t' => Key::Down,
+        byte if byte < 0x20 => Key::Other,
+        byte if byte < 0x80 => Key::Char(byte as char),
+        // The rest of a character outside ASCII.
+        lead => {
+            let len = match lead {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                _ => 2,
+            };
+            let mut bytes = vec![lead];
+            for _ in 1..len {
+                bytes.extend(read_byte(stdin)?);
+            }
+            String::from_utf8(bytes).ok().and_then(|text| text.chars().next()).map_or(Key::Other, Key::Char)
+        },
+    })
+}
+
+// The todos whose titles match `query`, best first and pending before
+// completed ones that match as well.
+fn matches<'a>(todos: &'a [Todo], query: &str) -> Vec<&'a Todo> {
+    let mut scored: Vec<(u32, &Todo)> = todos.iter().filter_map(|todo| Some((fuzzy::score(query, &todo.title)?, todo))).collect();
+    scored.sort_by_key(|&(score, todo)| (std::cmp::Reverse(score), todo.completed, todo.id));
+    scored.into_iter().map(|(_, todo)| todo).collect()
+}
+
+// Whether there is a terminal to pick on. Accessible mode asks for IDs
+// instead, as the redrawn list would not read well.
+pub fn available(config: &Config) -> bool {
+    !config.accessible && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+// Asks for the ID of a todo, where an empty answer opens the picker if
+// there is a terminal for it.
+pub fn ask_id(prompt: &str, todos: &[Todo], config: &Config) -> String {
+    if todos.is_empty() || !available(config) {
+        return get_input(prompt);
+    }
+    let input = get_input(&format!("{} (Enter to search by title):", prompt.trim_end_matches(':')));
+    if !input.is_empty() || input::at_eof() {
+        return input;
+    }
+    pick(todos, config).ok().flatten().map(|id| id.to_string()).unwrap_or_default()
+}
+
+// Lets the user find a todo by typing a few characters of its title, the
+// best matches listed above the query and redrawn with every key. Enter
+// picks the highlighted one, Esc gives up. None if nothing was picked or
+// the terminal does not allow it.
+pub fn pick(todos: &[Todo], config: &Config) -> io::Result<Option<usize>> {
+    if todos.is_empty() || !available(config) || !input::key_by_key() {
+        return Ok(None);
+    }
+    let picked = run(todos);
+    input::restore_terminal();
+    picked
+}
+
+fn run(todos: &[Todo]) -> io::Result<Option<usize>> {
+    let mut stdin = io::stdin().lock();
+    let mut out = io::stdout().lock();
+    let mut query = String::new();
+    let mut found = matches(todos, &query);
+    let mut selected: usize = 0;
+    // Lines above the query drawn last time.
+    let mut drawn = 0;
+    loop {
+        let first = selected.saturating_sub(SHOWN - 1);
+        let shown = &found[first..found.len().min(first + SHOWN)];
+        if drawn > 0 {
+            write!(out, "\x1b[{}A", drawn)?;
+        }
+        write!(out, "\r\x1b[J")?;
+        // The best match nearest the query, as in fzf.
+        for (n, todo) in shown.iter().enumerate().rev() {
+            let marker = if first + n == selected { ">" } else { " " };
+            let done = if todo.completed { " (done)" } else { "" };
+            write!(out, "{} #{} {}{}\r\n", marker, todo.id, truncate(&todo.title, 60), done)?;
+        }
+        write!(out, "  {}/{}\r\n> {}", found.len(), todos.len(), query)?;
+        out.flush()?;
+        drawn = shown.len() + 1;
+
+        let key = loop {
+            match read_key(&mut stdin)? {
+                Key::Other => continue,
+                key => break key,
+            }
+        };
+        match key {
+            Key::Char(c) => query.push(c),
+            Key::Backspace => {
+                query.pop();
+            },
+            Key::ClearQuery => query.clear(),
+            Key::Up => selected = (selected + 1).min(found.len().saturating_sub(1)),
+            Key::Down => selected = selected.saturating_sub(1),
+            Key::Enter | Key::Cancel => {
+                write!(out, "\x1b[{}A\r\x1b[J", drawn)?;
+                out.flush()?;
+                return Ok(match key {
+                    Key::Enter => found.get(selected).map(|todo| todo.id),
+                    _ => None,
+                });
+            },
+            Key::Other => {},
+        }
+        if matches!(key, Key::Char(_) | Key::Backspace | Key::ClearQuery) {
+            found = matches(todos, &query);
+            selected = 0;
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use crate::estimates;
+use crate::priority::effective_priority;
+use crate::todo::{Todo, TodoList};
+
+// Time set aside for a todo without an estimate.
+pub const UNESTIMATED_MINUTES: u32 = 30;
+
+// Capacity used when no daily capacity is configured.
+pub const DEFAULT_CAPACITY_MINUTES: u32 = 8 * 60;
+
+// The todos accepted as the plan for one day, in the order to work on them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub day: NaiveDate,
+    pub ids: Vec<usize>,
+}
+
+// "todos.json" keeps its plan in "todos.plan.json".
+pub fn plan_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.plan.json", stem),
+        None => format!("{}.plan", filename),
+    }
+}
+
+// The plan accepted for `today`, if any; an older plan does not count.
+pub fn load_plan(filename: &str, today: NaiveDate) -> io::Result<Option<Plan>> {
+    if !Path::new(filename).exists() {
+        return Ok(None);
+    }
+
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let plan: Plan = serde_json::from_str(&contents)?;
+    Ok(Some(plan).filter(|plan| plan.day == today))
+}
+
+pub fn save_plan(filename: &str, plan: &Plan) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(plan)?;
+    let mut file = File::create(filename)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+// The time a todo takes out of the day: what is left of its estimate.
+pub fn minutes(todo: &Todo) -> u32 {
+    match todo.estimate {
+        Some(_) => estimates::remaining(todo),
+        None => UNESTIMATED_MINUTES,
+    }
+}
+
+// Overdue first, then due today, then scheduled for today or earlier, then
+// everything else; within each group by priority and then due date.
+fn urgency(todo: &Todo, today: NaiveDate, escalate_after_days: Option<u32>, now: DateTime<Local>) -> impl Ord {
+    let group = match todo.due {
+        Some(due) if due < today => 0,
+        Some(due) if due == today => 1,
+        _ if todo.scheduled.is_some_and(|day| day <= today) => 2,
+        _ => 3,
+    };
+    (group, Reverse(effective_priority(todo, escalate_after_days, now)), todo.due.is_none(), todo.due, todo.id)
+}
+
+// The most urgent actionable todos that fit into `capacity` minutes, most
+// urgent first. A todo too long for what is left of the day is passed over
+// for shorter ones, except that the plan always has at least one todo.
+pub fn propose(todo_list: &TodoList, today: NaiveDate, escalate_after_days: Option<u32>, capacity: u32) -> Vec<usize> {
+    let now = Local::now();
+    let mut candidates: Vec<&Todo> = todo_list
+        .todos()
+        .iter()
+        .filter(|todo| todo_list.is_actionable(todo, today))
+        .collect();
+    candidates.sort_by_cached_key(|todo| urgency(todo, today, escalate_after_days, now));
+
+    let mut left = capacity;
+    let mut ids = Vec::new();
+    for todo in candidates {
+        let needed = minutes(todo);
+        if needed <= left || ids.is_empty() {
+            left = left.saturating_sub(needed);
+            ids.push(todo.id);
+        }
+    }
+    ids
+}
@@ -0,0 +1,88 @@
+use std::io;
+use chrono::Local;
+use todo_app::config::Config;
+use todo_app::estimates::format_minutes;
+use todo_app::plan::{self, Plan, DEFAULT_CAPACITY_MINUTES};
+use todo_app::TodoList;
+use crate::input;
+use crate::ui::{format_due, get_input, heading, truncate};
+
+// Prints the todos of a plan in order, with the time each takes and how
+// much of the day they fill.
+pub fn show(todo_list: &TodoList, ids: &[usize], capacity: u32, accessible: bool) {
+    let mut total = 0;
+    for (n, &id) in ids.iter().enumerate() {
+        let Some(todo) = todo_list.get_todo(id) else {
+            continue;
+        };
+        let minutes = plan::minutes(todo);
+        total += minutes;
+        let due = if todo.due.is_some() { format!("due {}", format_due(todo)) } else { String::new() };
+        if accessible {
+            let status = if todo.completed { "Done" } else { "Pending" };
+            let due = if due.is_empty() { String::new() } else { format!(", {}", due) };
+            println!("{}. Todo {}. {}: {}, {}{}.", n + 1, todo.id, todo.title, status, format_minutes(minutes), due);
+        } else {
+            let mark = if todo.completed { "[x]" } else { "[ ]" };
+            let line = format!("{:>2}. {} #{:<4} {:<30} {:<7} {}", n + 1, mark, todo.id, truncate(&todo.title, 30), format_minutes(minutes), due);
+            println!("{}", line.trim_end());
+        }
+    }
+    println!("\n{} of {} planned.", format_minutes(total), format_minutes(capacity));
+}
+
+// Proposes a plan for today and lets the user adjust it before it is saved
+// as today's plan: "+ID" adds a todo, "-ID" drops one, "ID N" moves a todo
+// to position N. Enter accepts; "q" or the end of input discards it.
+pub fn run(todo_list: &TodoList, filename: &str, config: &Config) -> io::Result<()> {
+    let today = Local::now().date_naive();
+    let capacity = config.daily_capacity().unwrap_or(DEFAULT_CAPACITY_MINUTES);
+    let mut ids = plan::propose(todo_list, today, config.escalate_after_days, capacity);
+    if ids.is_empty() {
+        println!("Nothing to plan: no pending todo can be worked on today.");
+        return Ok(());
+    }
+
+    loop {
+        println!();
+        heading(&format!("Proposed plan for {}", today), config.accessible);
+        show(todo_list, &ids, capacity, config.accessible);
+        let answer = get_input("Enter to accept, +ID to add, -ID to remove, ID N to move, q to discard:");
+        if input::at_eof() || answer.eq_ignore_ascii_case("q") {
+            println!("Plan discarded.");
+            return Ok(());
+        }
+        if answer.is_empty() {
+            break;
+        }
+
+        let parsed = |s: &str| s.parse::<usize>().ok();
+        let mut words = answer.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(word), None, _) if word.starts_with('+') => match parsed(&word[1..]) {
+                Some(id) if ids.contains(&id) => println!("Todo {} is already planned.", id),
+                Some(id) if todo_list.get_todo(id).is_some_and(|todo| !todo.completed) => ids.push(id),
+                Some(id) => println!("No pending todo with ID {}.", id),
+                None => println!("Could not understand '{}'.", answer),
+            },
+            (Some(word), None, _) if word.starts_with('-') => match parsed(&word[1..]) {
+                Some(id) if ids.contains(&id) => ids.retain(|&planned| planned != id),
+                Some(id) => println!("Todo {} is not in the plan.", id),
+                None => println!("Could not understand '{}'.", answer),
+            },
+            (Some(id), Some(pos), None) => match (parsed(id), parsed(pos)) {
+                (Some(id), Some(pos)) if ids.contains(&id) && pos > 0 => {
+                    ids.retain(|&planned| planned != id);
+                    ids.insert((pos - 1).min(ids.len()), id);
+                },
+                (Some(id), Some(_)) if !ids.contains(&id) => println!("Todo {} is not in the plan.", id),
+                _ => println!("Could not understand '{}'.", answer),
+            },
+            _ => println!("Could not understand '{}'.", answer),
+        }
+    }
+
+    plan::save_plan(&plan::plan_filename(filename), &Plan { day: today, ids })?;
+    println!("Plan for {} saved; `plan` shows it for the rest of the day.", today);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::Local;
+use todo_app::estimates::format_minutes;
+use todo_app::focus::{self, Focus};
+use crate::interrupt;
+
+// Runs a pomodoro of `minutes` on the focused todo, with the time left on
+// one redrawn line. Its end is kept in the focus file meanwhile, for
+// `status-line`. Ctrl-C stops it early. Returns the whole minutes worked.
+pub fn run(focus_file: &str, focus: &mut Focus, title: &str, minutes: u32, accessible: bool) -> io::Result<u32> {
+    let _winding = interrupt::Winding::start();
+    let length = Duration::from_secs(u64::from(minutes) * 60);
+    let start = Instant::now();
+    focus.pomodoro_until = Some(Local::now() + chrono::Duration::minutes(i64::from(minutes)));
+    focus::save_focus(focus_file, focus)?;
+    println!("Pomodoro of {} on #{} {}; press Ctrl-C to stop it early.", format_minutes(minutes), focus.id, title);
+
+    let redraw = !accessible && io::stdout().is_terminal();
+    let mut out = io::stdout();
+    while start.elapsed() < length && !interrupt::requested() {
+        if redraw {
+            let left = (length - start.elapsed()).as_secs();
+            write!(out, "\r\x1b[K{}:{:02} left", left / 60, left % 60)?;
+            out.flush()?;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    if redraw {
+        write!(out, "\r\x1b[K")?;
+    }
+
+    focus.pomodoro_until = None;
+    focus::save_focus(focus_file, focus)?;
+    let worked = ((start.elapsed().as_secs() / 60) as u32).min(minutes);
+    if interrupt::requested() {
+        println!("Pomodoro stopped after {}.", format_minutes(worked));
+    } else {
+        // The terminal bell, for whoever is looking elsewhere.
+        println!("\x07Pomodoro done; time for a break.");
+    }
+    Ok(worked)
+}
@@ -0,0 +1,106 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::todo::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "l" | "low" => Some(Priority::Low),
+            "m" | "medium" => Some(Priority::Medium),
+            "h" | "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    fn raised(level: Option<Priority>) -> Priority {
+        match level {
+            None => Priority::Low,
+            Some(Priority::Low) => Priority::Medium,
+            Some(Priority::Medium) | Some(Priority::High) => Priority::High,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        };
+        f.write_str(name)
+    }
+}
+
+// The priority a todo is treated as having once aging is applied: every
+// `escalate_after_days` days a pending todo sits without updates, its
+// priority goes up one level, up to high.
+pub fn effective_priority(todo: &Todo, escalate_after_days: Option<u32>, now: DateTime<Local>) -> Option<Priority> {
+    let Some(step) = escalate_after_days.filter(|&days| days > 0) else {
+        return todo.priority;
+    };
+    if todo.completed || todo.habit {
+        return todo.priority;
+    }
+
+    let idle_days = (now - todo.updated_at).num_days().max(0) as u64;
+    let mut level = todo.priority;
+    for _ in 0..idle_days / u64::from(step) {
+        if level == Some(Priority::High) {
+            break;
+        }
+        level = Some(Priority::raised(level));
+    }
+    level
+}
+
+// With `inherit_priority`, a pending todo that others wait on is treated as
+// urgent as the most urgent of them, directly or through a chain of
+// dependencies, so prerequisites are not buried under the work they block.
+// Returns, for every todo raised this way, the priority it takes on and the
+// chain of IDs from it to the todo it takes that priority from.
+pub fn inherited_priorities(todos: &[Todo], escalate_after_days: Option<u32>, now: DateTime<Local>) -> HashMap<usize, (Priority, Vec<usize>)> {
+    let pending: HashMap<usize, &Todo> = todos.iter().filter(|todo| !todo.completed).map(|todo| (todo.id, todo)).collect();
+    let own = |todo: &Todo| effective_priority(todo, escalate_after_days, now);
+    let mut sources: Vec<(Priority, usize)> = pending.values().filter_map(|todo| Some((own(todo)?, todo.id))).collect();
+    // The most urgent first, so that a todo keeps the first priority it gets.
+    sources.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut inherited: HashMap<usize, (Priority, Vec<usize>)> = HashMap::new();
+    for (priority, source) in sources {
+        // Breadth first, for the shortest chain to each blocker.
+        let mut came_from: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([source]);
+        while let Some(id) = queue.pop_front() {
+            for &blocker in &pending[&id].depends_on {
+                if blocker == source || came_from.contains_key(&blocker) {
+                    continue;
+                }
+                let Some(todo) = pending.get(&blocker) else {
+                    continue;
+                };
+                came_from.insert(blocker, id);
+                queue.push_back(blocker);
+                if own(todo).is_some_and(|own| own >= priority) || inherited.contains_key(&blocker) {
+                    continue;
+                }
+                let mut chain = vec![blocker];
+                while let Some(&next) = came_from.get(chain.last().unwrap()) {
+                    chain.push(next);
+                }
+                inherited.insert(blocker, (priority, chain));
+            }
+        }
+    }
+    inherited
+}
@@ -0,0 +1,144 @@
+// The `todo_rust` Python module. Todos handed to Python are copies; changes
+// go through the methods of TodoList.
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use crate::todo;
+
+#[pyclass(name = "Todo", module = "todo_rust", frozen, get_all)]
+struct Todo {
+    id: usize,
+    title: String,
+    description: String,
+    completed: bool,
+    cancelled: bool,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    completed_at: Option<DateTime<FixedOffset>>,
+    tags: Vec<String>,
+    project: Option<String>,
+    location: Option<String>,
+    priority: Option<String>,
+    important: bool,
+    due: Option<NaiveDate>,
+    scheduled: Option<NaiveDate>,
+    wait_until: Option<NaiveDate>,
+    habit: bool,
+    points: Option<u32>,
+    // In minutes.
+    estimate: Option<u32>,
+    spent: u32,
+    depends_on: Vec<usize>,
+    goal: Option<usize>,
+}
+
+impl From<&todo::Todo> for Todo {
+    fn from(todo: &todo::Todo) -> Self {
+        Todo {
+            id: todo.id,
+            title: todo.title.clone(),
+            description: todo.description.clone(),
+            completed: todo.completed,
+            cancelled: todo.cancelled,
+            created_at: todo.created_at.fixed_offset(),
+            updated_at: todo.updated_at.fixed_offset(),
+            completed_at: todo.completed_on().map(|at| at.fixed_offset()),
+            tags: todo.tags.iter().map(|tag| tag.as_str().to_string()).collect(),
+            project: todo.project.clone(),
+            location: todo.location.clone(),
+            priority: todo.priority.map(|priority| priority.to_string()),
+            important: todo.important,
+            due: todo.due,
+            scheduled: todo.scheduled,
+            wait_until: todo.wait_until,
+            habit: todo.habit,
+            points: todo.points,
+            estimate: todo.estimate,
+            spent: todo.spent,
+            depends_on: todo.depends_on.clone(),
+            goal: todo.goal,
+        }
+    }
+}
+
+#[pymethods]
+impl Todo {
+    fn __repr__(&self) -> String {
+        let completed = if self.completed { "True" } else { "False" };
+        format!("Todo(id={}, title={:?}, completed={})", self.id, self.title, completed)
+    }
+}
+
+#[pyclass(name = "TodoList", module = "todo_rust")]
+struct TodoList {
+    list: todo::TodoList,
+}
+
+#[pymethods]
+impl TodoList {
+    #[new]
+    fn new() -> Self {
+        TodoList { list: todo::TodoList::new() }
+    }
+
+    // A missing file gives an empty list, as in the app.
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        Ok(TodoList { list: todo::TodoList::load_from_file(path)? })
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        Ok(self.list.save_to_file(path)?)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let list = todo::TodoList::from_json(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(TodoList { list })
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        self.list.to_json().map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[pyo3(signature = (title, description = String::new()))]
+    fn add(&mut self, title: String, description: String) -> PyResult<usize> {
+        self.list.add_todo(title, description).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn complete(&mut self, id: usize) -> bool {
+        self.list.complete_todo(id)
+    }
+
+    #[pyo3(signature = (id, note = None))]
+    fn cancel(&mut self, id: usize, note: Option<String>) -> bool {
+        self.list.cancel_todo(id, note)
+    }
+
+    fn delete(&mut self, id: usize) -> bool {
+        self.list.delete_todo(id)
+    }
+
+    fn get(&self, id: usize) -> Option<Todo> {
+        self.list.get_todo(id).map(Todo::from)
+    }
+
+    fn todos(&self) -> Vec<Todo> {
+        self.list.todos().iter().map(Todo::from).collect()
+    }
+
+    fn search(&self, query: &str) -> Vec<Todo> {
+        self.list.search(query).into_iter().map(Todo::from).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.list.todos().len()
+    }
+}
+
+#[pymodule]
+fn todo_rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Todo>()?;
+    m.add_class::<TodoList>()?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::io;
+use chrono::Local;
+use crate::fsck;
+use crate::todo::{Todo, TodoList};
+
+// True for errors meaning the file was read but is not a valid todo list,
+// as opposed to it being unreadable.
+pub fn is_corrupt(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof)
+}
+
+// Every todo object in `contents` that still parses. Each `{` is tried as the
+// start of a todo; after a hit, scanning resumes behind it, so the objects
+// around a damaged spot are kept.
+pub fn salvage_todos(contents: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = contents[pos..].find('{') {
+        let start = pos + offset;
+        let mut stream = serde_json::Deserializer::from_str(&contents[start..]).into_iter::<Todo>();
+        match stream.next() {
+            Some(Ok(todo)) => {
+                todos.push(todo);
+                pos = start + stream.byte_offset();
+            },
+            _ => pos = start + 1,
+        }
+    }
+    todos
+}
+
+pub struct Recovery {
+    pub todo_list: TodoList,
+    // Where the damaged file was moved to.
+    pub backup: String,
+    pub salvaged: usize,
+}
+
+// Rebuilds a list from whatever todos in `filename` can still be read and
+// moves the damaged file aside, so nothing is lost when the rebuilt list is
+// saved in its place. Goals are not recovered; links to them are dropped.
+pub fn recover(filename: &str) -> io::Result<Recovery> {
+    let contents = String::from_utf8_lossy(&fs::read(filename)?).into_owned();
+    let todos = salvage_todos(&contents);
+    let salvaged = todos.len();
+    let mut todo_list = TodoList::from_todos(todos);
+    fsck::repair(&mut todo_list);
+
+    let backup = format!("{}.corrupt-{}", filename, Local::now().format("%Y%m%d-%H%M%S"));
+    fs::rename(filename, &backup)?;
+    Ok(Recovery { todo_list, backup, salvaged })
+}
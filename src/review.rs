@@ -0,0 +1,104 @@
+use std::io;
+use chrono::{DateTime, Local};
+use todo_app::dates::parse_date;
+use todo_app::config::Config;
+use todo_app::keys::{Action, ReviewAction};
+use todo_app::TodoList;
+use crate::cli::notify_completed;
+use crate::input;
+use crate::ui::{format_progress, format_tags, get_confirmation, get_input, heading};
+
+// Walks through pending todos that have not been touched for `days` days,
+// since `cutoff`, and asks what to do with each one. Every decision is saved
+// immediately.
+pub fn run(todo_list: &mut TodoList, filename: &str, days: u32, cutoff: DateTime<Local>, config: &Config) -> io::Result<()> {
+    let stale: Vec<usize> = todo_list
+        .todos()
+        .iter()
+        .filter(|todo| !todo.completed && todo.updated_at < cutoff)
+        .map(|todo| todo.id)
+        .collect();
+
+    if stale.is_empty() {
+        println!("Nothing to review: every pending todo was updated in the last {} days.", days);
+        return Ok(());
+    }
+
+    let prompt = ReviewAction::ALL
+        .iter()
+        .map(|&action| format!("[{}] {}", config.keys.review_key(action), action.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    for (n, &id) in stale.iter().enumerate() {
+        let Some(todo) = todo_list.get_todo(id) else {
+            continue;
+        };
+        let idle = (Local::now() - todo.updated_at).num_days();
+        println!();
+        heading(&format!("Review {} of {}", n + 1, stale.len()), config.accessible);
+        println!("#{} {}", todo.id, todo.title);
+        println!("Description: {}", todo.description);
+        println!("Tags: {}", format_tags(todo));
+        let today = Local::now().date_naive();
+        println!("Due: {}", todo.due.map(|day| config.date_format.date(day, today)).unwrap_or_default());
+        if let Some(day) = todo.scheduled {
+            println!("Scheduled: {}", config.date_format.date(day, today));
+        }
+        if todo.progress.is_some() {
+            println!("Progress: {}", format_progress(todo));
+        }
+        println!("Last updated {} days ago.", idle);
+
+        loop {
+            let choice = get_input(&format!("{}:", prompt));
+            // Running out of input stops the review like quitting.
+            let action = if input::at_eof() { Some(ReviewAction::Quit) } else { config.keys.review_action(&choice) };
+            let changed = match action {
+                Some(ReviewAction::Complete) => todo_list.toggle_completed(id),
+                Some(ReviewAction::Reschedule) => {
+                    let input = get_input("New due date (e.g. 2026-05-01, tomorrow, 1w, friday; empty to clear):");
+                    if input.is_empty() {
+                        todo_list.set_due(id, None)
+                    } else if let Some(due) = parse_date(&input, Local::now().date_naive()) {
+                        todo_list.set_due(id, Some(due))
+                    } else {
+                        println!("Could not understand that date.");
+                        continue;
+                    }
+                },
+                Some(ReviewAction::Delegate) => {
+                    let person = get_input("Delegate to:");
+                    if person.is_empty() {
+                        continue;
+                    }
+                    todo_list.set_delegated_to(id, Some(person), Local::now().date_naive())
+                },
+                Some(ReviewAction::Delete) => {
+                    if config.confirm.delete && !get_confirmation("Are you sure you want to delete this todo?") {
+                        continue;
+                    }
+                    todo_list.delete_todo(id)
+                },
+                Some(ReviewAction::Skip) => false,
+                Some(ReviewAction::Quit) => {
+                    println!("Review stopped.");
+                    return Ok(());
+                },
+                None => {
+                    println!("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+            if changed {
+                todo_list.save_to_file(filename)?;
+                if action == Some(ReviewAction::Complete) {
+                    notify_completed(todo_list, id, config);
+                }
+            }
+            break;
+        }
+    }
+
+    println!("\nReview finished.");
+    Ok(())
+}
@@ -0,0 +1,438 @@
+use std::collections::VecDeque;
+use std::fmt;
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+// Recurrence rules in the RRULE syntax of RFC 5545, e.g.
+// "FREQ=MONTHLY;BYDAY=2TU" for every second Tuesday of the month. Todos only
+// have dates, so the time-of-day parts (BYHOUR, ...) and sub-daily
+// frequencies are not supported, nor is BYWEEKNO.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+    // Weekdays, optionally numbered within the month or year: 2TU, -1FR.
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_year_day: Vec<i32>,
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RuleError {
+    MissingFrequency,
+    UnknownPart(String),
+    Unsupported(String),
+    InvalidValue { part: String, value: String },
+    NotWithFrequency(&'static str),
+    CountAndUntil,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::MissingFrequency => write!(f, "The rule needs a FREQ, e.g. FREQ=WEEKLY."),
+            RuleError::UnknownPart(part) => write!(f, "Unknown rule part {}.", part),
+            RuleError::Unsupported(part) => write!(f, "{} is not supported; todos only have dates.", part),
+            RuleError::InvalidValue { part, value } => write!(f, "Invalid value for {}: {:?}.", part, value),
+            RuleError::NotWithFrequency(part) => write!(f, "{} cannot be used with this FREQ.", part),
+            RuleError::CountAndUntil => write!(f, "A rule cannot have both COUNT and UNTIL."),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+// Periods in a row without a single occurrence after which a rule is taken
+// to have none left, e.g. FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30.
+const MAX_EMPTY_PERIODS: u32 = 10_000;
+
+impl Rule {
+    // Accepts the rule with or without a leading "RRULE:"; part names and
+    // values are not case sensitive.
+    pub fn parse(text: &str) -> Result<Self, RuleError> {
+        let text = text.trim();
+        let text = match text.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &text[6..],
+            _ => text,
+        };
+
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_year_day: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+        for part in text.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=').unwrap_or((part, ""));
+            let name = name.trim().to_uppercase();
+            let value = value.trim().to_uppercase();
+            let invalid = || RuleError::InvalidValue { part: name.clone(), value: value.clone() };
+            match name.as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        "HOURLY" | "MINUTELY" | "SECONDLY" => return Err(RuleError::Unsupported(format!("FREQ={}", value))),
+                        _ => return Err(invalid()),
+                    })
+                },
+                "INTERVAL" => rule.interval = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?,
+                "COUNT" => rule.count = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?),
+                "UNTIL" => rule.until = Some(parse_until(&value).ok_or_else(invalid)?),
+                "BYDAY" => rule.by_day = parse_list(&value, parse_weekday_num).ok_or_else(invalid)?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(&value, |v| parse_signed(v, 31)).ok_or_else(invalid)?,
+                "BYYEARDAY" => rule.by_year_day = parse_list(&value, |v| parse_signed(v, 366)).ok_or_else(invalid)?,
+                "BYSETPOS" => rule.by_set_pos = parse_list(&value, |v| parse_signed(v, 366)).ok_or_else(invalid)?,
+                "BYMONTH" => {
+                    rule.by_month = parse_list(&value, |v| v.parse().ok().filter(|m| (1..=12).contains(m))).ok_or_else(invalid)?
+                },
+                "WKST" => rule.week_start = parse_weekday(&value).ok_or_else(invalid)?,
+                "BYWEEKNO" | "BYHOUR" | "BYMINUTE" | "BYSECOND" => return Err(RuleError::Unsupported(name)),
+                _ => return Err(RuleError::UnknownPart(name)),
+            }
+        }
+
+        rule.frequency = frequency.ok_or(RuleError::MissingFrequency)?;
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err(RuleError::CountAndUntil);
+        }
+        // Numbered weekdays only make sense within a month or year, and
+        // RFC 5545 rules out these combinations too.
+        let numbered = rule.by_day.iter().any(|(n, _)| n.is_some());
+        if numbered && matches!(rule.frequency, Frequency::Daily | Frequency::Weekly) {
+            return Err(RuleError::NotWithFrequency("BYDAY with numbered weekdays"));
+        }
+        if !rule.by_month_day.is_empty() && rule.frequency == Frequency::Weekly {
+            return Err(RuleError::NotWithFrequency("BYMONTHDAY"));
+        }
+        if !rule.by_year_day.is_empty() && rule.frequency != Frequency::Yearly {
+            return Err(RuleError::NotWithFrequency("BYYEARDAY"));
+        }
+        Ok(rule)
+    }
+
+    // Every date the rule produces from `start` on, in order. As in most
+    // implementations, `start` itself only counts if it fits the rule.
+    pub fn occurrences(&self, start: NaiveDate) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            start,
+            period: 0,
+            pending: VecDeque::new(),
+            emitted: 0,
+            empty_periods: 0,
+            done: false,
+        }
+    }
+
+    // The first occurrence after `after` of the series starting at `start`.
+    pub fn next_after(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        self.occurrences(start).find(|&day| day > after)
+    }
+
+    // The first day of the n-th period counted from the one containing
+    // `start`.
+    fn period_start(&self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        let steps = n.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_signed(Duration::days(i64::from(steps))),
+            Frequency::Weekly => {
+                let back = (7 + start.weekday().num_days_from_monday() - self.week_start.num_days_from_monday()) % 7;
+                (start - Duration::days(i64::from(back))).checked_add_signed(Duration::weeks(i64::from(steps)))
+            },
+            Frequency::Monthly => start.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => NaiveDate::from_ymd_opt(start.year().checked_add(i32::try_from(steps).ok()?)?, 1, 1),
+        }
+    }
+
+    // The occurrences within the period starting at `first`, sorted.
+    fn period_dates(&self, first: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = match self.frequency {
+            Frequency::Daily => vec![first],
+            Frequency::Weekly => {
+                let week: Vec<NaiveDate> = (0..7).map(|d| first + Duration::days(d)).collect();
+                if self.by_day.is_empty() {
+                    week.into_iter().filter(|day| day.weekday() == start.weekday()).collect()
+                } else {
+                    week
+                }
+            },
+            Frequency::Monthly => {
+                if self.by_month_day.is_empty() && self.by_day.is_empty() {
+                    first.with_day(start.day()).into_iter().collect()
+                } else {
+                    month_days(first.year(), first.month())
+                }
+            },
+            Frequency::Yearly => {
+                let expands = !self.by_year_day.is_empty() || !self.by_month_day.is_empty() || !self.by_day.is_empty();
+                if expands {
+                    let year: Vec<NaiveDate> = first.iter_days().take_while(|day| day.year() == first.year()).collect();
+                    if self.by_year_day.is_empty() {
+                        year
+                    } else {
+                        select(&year, &self.by_year_day)
+                    }
+                } else {
+                    let months = if self.by_month.is_empty() { vec![start.month()] } else { self.by_month.clone() };
+                    months
+                        .into_iter()
+                        .filter_map(|month| NaiveDate::from_ymd_opt(first.year(), month, start.day()))
+                        .collect()
+                }
+            },
+        };
+
+        if !self.by_month.is_empty() {
+            dates.retain(|day| self.by_month.contains(&day.month()));
+        }
+        if !self.by_month_day.is_empty() {
+            dates.retain(|day| {
+                let last = last_day_of_month(day.year(), day.month()) as i32;
+                self.by_month_day.iter().any(|&n| n == day.day() as i32 || n == day.day() as i32 - last - 1)
+            });
+        }
+        if !self.by_day.is_empty() {
+            dates.retain(|&day| self.fits_by_day(day));
+        }
+        if !self.by_set_pos.is_empty() {
+            dates = select(&dates, &self.by_set_pos);
+        }
+        dates
+    }
+
+    // Numbered weekdays count within the month for monthly rules and for
+    // yearly rules with BYMONTH, otherwise within the year.
+    fn fits_by_day(&self, day: NaiveDate) -> bool {
+        self.by_day.iter().any(|&(n, weekday)| {
+            if day.weekday() != weekday {
+                return false;
+            }
+            let Some(n) = n else {
+                return true;
+            };
+            let in_month = self.frequency == Frequency::Monthly || !self.by_month.is_empty();
+            let (index, total) = if in_month {
+                let last = last_day_of_month(day.year(), day.month());
+                ((day.day() - 1) / 7, (last - 1 - (day.day() - 1) % 7) / 7 + 1)
+            } else {
+                let days_in_year = if NaiveDate::from_ymd_opt(day.year(), 2, 29).is_some() { 366 } else { 365 };
+                ((day.ordinal() - 1) / 7, (days_in_year - 1 - (day.ordinal() - 1) % 7) / 7 + 1)
+            };
+            let index = index as i32;
+            if n > 0 { index == n - 1 } else { index == total as i32 + n }
+        })
+    }
+}
+
+// Picks the 1-based positions `positions` out of `dates`, negative ones
+// counting from the end.
+fn select(dates: &[NaiveDate], positions: &[i32]) -> Vec<NaiveDate> {
+    let len = dates.len() as i32;
+    let mut picked: Vec<NaiveDate> = positions
+        .iter()
+        .filter_map(|&n| {
+            let index = if n > 0 { n - 1 } else { len + n };
+            usize::try_from(index).ok().and_then(|i| dates.get(i).copied())
+        })
+        .collect();
+    picked.sort();
+    picked.dedup();
+    picked
+}
+
+fn month_days(year: i32, month: u32) -> Vec<NaiveDate> {
+    (1..=last_day_of_month(year, month)).filter_map(|day| NaiveDate::from_ymd_opt(year, month, day)).collect()
+}
+
+fn last_day_of_month(year: i32, month: u32) -> u32 {
+    (28..=31).rev().find(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some()).unwrap_or(28)
+}
+
+fn parse_list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    let items: Option<Vec<T>> = value.split(',').map(|item| parse(item.trim())).collect();
+    items.filter(|items| !items.is_empty())
+}
+
+// A non-zero number from -max to max, e.g. "-1" or "+3".
+fn parse_signed(value: &str, max: i32) -> Option<i32> {
+    let n: i32 = value.strip_prefix('+').unwrap_or(value).parse().ok()?;
+    (n != 0 && n.abs() <= max).then_some(n)
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_weekday_num(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = value.len().checked_sub(2)?;
+    let weekday = parse_weekday(value.get(split..)?)?;
+    match &value[..split] {
+        "" => Some((None, weekday)),
+        n => Some((Some(parse_signed(n, 53)?), weekday)),
+    }
+}
+
+// 20261231, 20261231T235959Z or 2026-12-31; only the date is used.
+fn parse_until(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok())
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn join<T>(items: &[T], show: impl Fn(&T) -> String) -> String {
+    items.iter().map(show).collect::<Vec<_>>().join(",")
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        if !self.by_month.is_empty() {
+            write!(f, ";BYMONTH={}", join(&self.by_month, u32::to_string))?;
+        }
+        if !self.by_year_day.is_empty() {
+            write!(f, ";BYYEARDAY={}", join(&self.by_year_day, i32::to_string))?;
+        }
+        if !self.by_month_day.is_empty() {
+            write!(f, ";BYMONTHDAY={}", join(&self.by_month_day, i32::to_string))?;
+        }
+        if !self.by_day.is_empty() {
+            let day = |&(n, weekday): &(Option<i32>, Weekday)| {
+                format!("{}{}", n.map(|n| n.to_string()).unwrap_or_default(), weekday_name(weekday))
+            };
+            write!(f, ";BYDAY={}", join(&self.by_day, day))?;
+        }
+        if !self.by_set_pos.is_empty() {
+            write!(f, ";BYSETPOS={}", join(&self.by_set_pos, i32::to_string))?;
+        }
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_name(self.week_start))?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Rule::parse(&text).map_err(de::Error::custom)
+    }
+}
+
+pub struct Occurrences<'a> {
+    rule: &'a Rule,
+    start: NaiveDate,
+    period: u32,
+    pending: VecDeque<NaiveDate>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = NaiveDate;
+
+    fn next(&mut self) -> Option<NaiveDate> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if let Some(day) = self.pending.pop_front() {
+                if self.rule.until.is_some_and(|until| day > until) || self.rule.count.is_some_and(|n| self.emitted >= n) {
+                    self.done = true;
+                    return None;
+                }
+                self.emitted += 1;
+                return Some(day);
+            }
+            if self.empty_periods >= MAX_EMPTY_PERIODS {
+                self.done = true;
+                continue;
+            }
+            let Some(first) = self.rule.period_start(self.start, self.period) else {
+                self.done = true;
+                continue;
+            };
+            if self.rule.until.is_some_and(|until| first > until) {
+                self.done = true;
+                continue;
+            }
+            self.period += 1;
+            let start = self.start;
+            self.pending.extend(self.rule.period_dates(first, start).into_iter().filter(|&day| day >= start));
+            self.empty_periods = if self.pending.is_empty() { self.empty_periods + 1 } else { 0 };
+        }
+    }
+}
+
+// A todo's rule together with the day the series started, which COUNT and
+// INTERVAL are counted from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub rule: Rule,
+    pub start: NaiveDate,
+}
@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use serde_json::json;
+use todo_app::config::ServerConfig;
+use crate::metrics::Metrics;
+use todo_app::sync::{self, ActivityEntry, PushError, PushRequest, PushResponse, Server, UserInfo};
+use chrono::{Local, SecondsFormat};
+use todo_app::tokens::{self, Denied, Scope, Tokens};
+use todo_app::users::{self, Role, Users};
+
+// Bodies larger than this are refused.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+// Nor are longer request or header lines, or more headers, accepted: a
+// client must not make the server hold on to unbounded amounts of memory,
+// nor keep it busy, as it answers one request at a time.
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+// For the same reason a client gets this long to send its whole request,
+// and again to take the whole response, however it spreads the bytes out.
+const TIME_LIMIT: Duration = Duration::from_secs(10);
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    // From an `Authorization: Bearer` header.
+    token: Option<String>,
+    // From an `X-Device-Secret` header.
+    secret: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what.to_string())
+}
+
+// The time from now until `until`, for a socket timeout, which cannot be
+// zero.
+fn time_left(until: Instant) -> io::Result<Duration> {
+    Some(until.saturating_duration_since(Instant::now()))
+        .filter(|left| !left.is_zero())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "the client took too long"))
+}
+
+// Reads from `stream` until `until` at the latest.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(time_left(self.until)?))?;
+        self.stream.read(buf)
+    }
+}
+
+// Reads one line of at most MAX_LINE bytes into `line`.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<()> {
+    line.clear();
+    reader.take(MAX_LINE).read_line(line)?;
+    if !line.ends_with('\n') {
+        return Err(invalid("request line or header too long, or cut short"));
+    }
+    Ok(())
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    let mut reader = BufReader::new(Deadline { stream, until: Instant::now() + TIME_LIMIT });
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let mut request = Request { method: method.to_string(), path: path.to_string(), query, token: None, secret: None, body: Vec::new() };
+
+    let mut length = 0;
+    for count in 0.. {
+        read_line(&mut reader, &mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| invalid("bad Content-Length"))?;
+            } else if name.eq_ignore_ascii_case("authorization") {
+                request.token = value.trim().strip_prefix("Bearer ").map(|token| token.trim().to_string());
+            } else if name.eq_ignore_ascii_case("x-device-secret") {
+                request.secret = Some(value.trim().to_string());
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+// Returns the status, for the request log.
+fn respond(stream: &TcpStream, status: u16, body: &impl Serialize) -> io::Result<u16> {
+    respond_with(stream, status, "", body)
+}
+
+// `headers` are extra header lines, each ending in CRLF.
+fn respond_with(stream: &TcpStream, status: u16, headers: &str, body: &impl Serialize) -> io::Result<u16> {
+    send(stream, status, &format!("Content-Type: application/json\r\n{}", headers), &serde_json::to_vec(body)?)
+}
+
+fn send(mut stream: &TcpStream, status: u16, headers: &str, body: &[u8]) -> io::Result<u16> {
+    let until = Instant::now() + TIME_LIMIT;
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        _ => "Internal Server Error",
+    };
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        headers,
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    let mut rest = &response[..];
+    while !rest.is_empty() {
+        stream.set_write_timeout(Some(time_left(until)?))?;
+        match stream.write(rest) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => rest = &rest[written..],
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(status)
+}
+
+fn error(message: &str) -> serde_json::Value {
+    json!({ "error": message })
+}
+
+const UNKNOWN_DEVICE: &str = "This device is not registered, or did not send its secret; run `sync remote URL` again.";
+
+// What a device may do, once it has proven with its secret that it is the
+// device it says. Before there are users every device is an editor; after, a
+// device not registered through an invite may do nothing.
+fn role(users: &Users, device: &str) -> Option<Role> {
+    if users.is_open() {
+        return Some(Role::Editor);
+    }
+    users.of_device(device).map(|user| user.role)
+}
+
+struct Files {
+    // The server's own list, counted at /metrics.
+    list: String,
+    users: String,
+    tokens: String,
+}
+
+// A bucket per client holding up to a minute's worth of requests, refilled
+// continuously, so short bursts pass and a steady flood does not.
+struct RateLimiter {
+    per_minute: Option<u32>,
+    buckets: HashMap<String, (f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(per_minute: Option<u32>) -> Self {
+        RateLimiter { per_minute: per_minute.filter(|&n| n > 0), buckets: HashMap::new() }
+    }
+
+    // None if `client` may make another request now, or else the seconds
+    // until it may.
+    fn check(&mut self, client: &str, now: Instant) -> Option<u64> {
+        let capacity = f64::from(self.per_minute?);
+        let rate = capacity / 60.0;
+        // Buckets untouched for a minute are full again and can go.
+        if self.buckets.len() > 10_000 {
+            self.buckets.retain(|_, (_, last)| now.duration_since(*last) < Duration::from_secs(60));
+        }
+        let (left, last) = self.buckets.entry(client.to_string()).or_insert((capacity, now));
+        *left = (*left + now.duration_since(*last).as_secs_f64() * rate).min(capacity);
+        *last = now;
+        if *left >= 1.0 {
+            *left -= 1.0;
+            None
+        } else {
+            Some(((1.0 - *left) / rate).ceil() as u64)
+        }
+    }
+}
+
+// One line of the request log.
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    at: String,
+    peer: &'a str,
+    method: &'a str,
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<&'a str>,
+    // None if no response could be sent.
+    status: Option<u16>,
+    outcome: &'static str,
+    latency_ms: f64,
+}
+
+fn outcome(status: Option<u16>) -> &'static str {
+    match status {
+        Some(200..=299) => "ok",
+        Some(400) => "bad_request",
+        Some(401) => "unauthorized",
+        Some(403) => "forbidden",
+        Some(404) => "not_found",
+        Some(409) => "behind",
+        Some(429) => "rate_limited",
+        Some(_) => "error",
+        None => "failed",
+    }
+}
+
+fn open_log(target: Option<&str>) -> io::Result<Option<Box<dyn Write>>> {
+    Ok(match target {
+        None => None,
+        Some("-") => Some(Box::new(io::stdout())),
+        Some(path) => Some(Box::new(OpenOptions::new().create(true).append(true).open(path)?)),
+    })
+}
+
+// Every sync ends with a push, which tells the device how far it got even
+// when it has nothing to send; such a push only needs `pull`.
+fn scope(request: &Request) -> Option<Scope> {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/register") => Some(Scope::Register),
+        ("GET", "/ops" | "/activity") => Some(Scope::Pull),
+        ("POST", "/ops") => match serde_json::from_slice::<PushRequest>(&request.body) {
+            Ok(push) if push.ops.is_empty() => Some(Scope::Pull),
+            _ => Some(Scope::Push),
+        },
+        ("GET", "/users") => Some(Scope::Users),
+        ("GET", "/metrics") => Some(Scope::Metrics),
+        _ => None,
+    }
+}
+
+// Reads one request, checks its token and the rate limit and answers it,
+// logging it to `log` if there is one.
+fn serve(server: &mut Server, files: &Files, limiter: &mut RateLimiter, metrics: &mut Metrics, log: &mut Option<Box<dyn Write>>, stream: &TcpStream) -> io::Result<()> {
+    let started = Instant::now();
+    let peer = stream.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+    let request = read_request(stream);
+    let mut token_name = None;
+    let result = match &request {
+        Err(e) => respond(stream, 400, &error(&e.to_string())),
+        // Both are read for every request, so that `user` and `serve token`
+        // take effect without a restart.
+        Ok(request) => Tokens::load(&files.tokens).and_then(|tokens| {
+            let checked = scope(request).map(|scope| tokens.check(request.token.as_deref(), scope, Local::now().date_naive()));
+            // Counted per token when there is a valid one, and per address
+            // otherwise, turned-away requests included.
+            let client = match &checked {
+                Some(Ok(Some(name))) => {
+                    token_name = Some(name.to_string());
+                    format!("token {}", name)
+                },
+                _ => format!("address {}", peer),
+            };
+            if let Some(wait) = limiter.check(&client, started) {
+                let message = format!("too many requests; try again in {} seconds", wait);
+                return respond_with(stream, 429, &format!("Retry-After: {}\r\n", wait), &error(&message));
+            }
+            match checked {
+                Some(Err(denied @ Denied::OutOfScope(..))) => respond(stream, 403, &error(&denied.to_string())),
+                Some(Err(denied)) => respond(stream, 401, &error(&denied.to_string())),
+                _ => handle(server, files, metrics, request, stream),
+            }
+        }),
+    };
+    let status = result.as_ref().ok().copied();
+    let elapsed = started.elapsed();
+    if let Ok(request) = &request {
+        metrics.observe(&request.method, &request.path, status, outcome(status), elapsed);
+    }
+    if let Some(log) = log {
+        let request = request.as_ref().ok();
+        let entry = LogEntry {
+            at: Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+            peer: &peer,
+            method: request.map_or("-", |request| request.method.as_str()),
+            path: request.map_or("-", |request| request.path.as_str()),
+            device: request.and_then(|request| request.param("device")),
+            token: token_name.as_deref(),
+            status,
+            outcome: outcome(status),
+            latency_ms: elapsed.as_micros() as f64 / 1000.0,
+        };
+        if let Err(e) = serde_json::to_writer(&mut *log, &entry).map_err(io::Error::from).and_then(|()| writeln!(log)).and_then(|()| log.flush()) {
+            eprintln!("Could not write the request log: {}", e);
+        }
+    }
+    result.map(|_| ())
+}
+
+fn handle(server: &mut Server, files: &Files, metrics: &Metrics, request: &Request, stream: &TcpStream) -> io::Result<u16> {
+    let users_file = files.users.as_str();
+    let mut users = Users::load(users_file)?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/register") => {
+            let invite = request.param("invite").unwrap_or_default();
+            if !users.is_open() && !users.has_invite(invite) {
+                return respond(stream, 403, &error("an invite code from `user add` or `user invite` is needed"));
+            }
+            let registered = server.register()?;
+            match users.redeem(invite, &registered.device) {
+                Some(user) => {
+                    println!("Registered device {} for {} ({}).", registered.label, user.name, user.role);
+                    users.save(users_file)?;
+                },
+                None => println!("Registered device {}.", registered.label),
+            }
+            respond(stream, 200, &registered)
+        },
+        ("GET", "/ops") => {
+            let device = request.param("device").unwrap_or_default();
+            let Some(since) = request.param("since").and_then(|since| since.parse().ok()) else {
+                return respond(stream, 400, &error("since must be a number"));
+            };
+            if !server.authenticate(device, request.secret.as_deref()) {
+                return respond(stream, 403, &error(UNKNOWN_DEVICE));
+            }
+            if role(&users, device).is_none() {
+                return respond(stream, 403, &error("this device belongs to no user"));
+            }
+            respond(stream, 200, &server.pull(device, since))
+        },
+        ("POST", "/ops") => {
+            let push: PushRequest = match serde_json::from_slice(&request.body) {
+                Ok(push) => push,
+                Err(e) => return respond(stream, 400, &error(&e.to_string())),
+            };
+            if !server.authenticate(&push.device, request.secret.as_deref()) {
+                return respond(stream, 403, &error(UNKNOWN_DEVICE));
+            }
+            match role(&users, &push.device) {
+                None => return respond(stream, 403, &error("this device belongs to no user")),
+                Some(role) if !role.can_push() && !push.ops.is_empty() => return respond(stream, 403, &error("viewers cannot send changes")),
+                Some(_) => {},
+            }
+            let count = push.ops.len();
+            let device = push.device.clone();
+            match server.push(push) {
+                Ok(seq) => {
+                    if count > 0 {
+                        println!("Received {} changes from device {}.", count, device);
+                    }
+                    respond(stream, 200, &PushResponse { seq })
+                },
+                Err(PushError::Behind(seq)) => respond(stream, 409, &PushResponse { seq }),
+                Err(PushError::UnknownDevice) => respond(stream, 403, &error(&PushError::UnknownDevice.to_string())),
+                Err(PushError::Io(e)) => {
+                    eprintln!("Could not store changes: {}", e);
+                    respond(stream, 500, &error(&e.to_string()))
+                },
+            }
+        },
+        ("GET", "/activity") => {
+            let device = request.param("device").unwrap_or_default();
+            let limit = request.param("limit").and_then(|limit| limit.parse().ok()).unwrap_or(50);
+            if !server.authenticate(device, request.secret.as_deref()) {
+                return respond(stream, 403, &error(UNKNOWN_DEVICE));
+            }
+            if role(&users, device).is_none() {
+                return respond(stream, 403, &error("this device belongs to no user"));
+            }
+            let entries: Vec<ActivityEntry> = server
+                .recent(limit)
+                .iter()
+                .map(|logged| ActivityEntry { logged: server.public(logged), user: users.of_device(&logged.device).map(|user| user.name.clone()) })
+                .collect();
+            respond(stream, 200, &entries)
+        },
+        ("GET", "/users") => {
+            let device = request.param("device").unwrap_or_default();
+            if !server.authenticate(device, request.secret.as_deref()) || !role(&users, device).is_some_and(Role::can_manage) {
+                return respond(stream, 403, &error("only admins can list users"));
+            }
+            let list: Vec<UserInfo> = users
+                .users()
+                .iter()
+                .map(|user| UserInfo { name: user.name.clone(), role: user.role.to_string(), devices: user.devices.len() })
+                .collect();
+            respond(stream, 200, &list)
+        },
+        ("GET", "/metrics") => {
+            let text = metrics.render(server, &files.list);
+            send(stream, 200, "Content-Type: text/plain; version=0.0.4\r\n", text.as_bytes())
+        },
+        _ => respond(stream, 404, &error("no such endpoint")),
+    }
+}
+
+// Serves sync to any number of devices, one request at a time, until the
+// process is stopped.
+pub fn run(filename: &str, port: u16, config: &ServerConfig) -> io::Result<()> {
+    let log = sync::server_filename(filename);
+    let mut request_log = open_log(config.request_log.as_deref())?;
+    let mut limiter = RateLimiter::new(config.requests_per_minute);
+    let mut metrics = Metrics::default();
+    let files = Files { list: filename.to_string(), users: users::users_filename(filename), tokens: tokens::tokens_filename(filename) };
+    let mut server = Server::open(&log)?;
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("Serving sync on port {} from {} ({} changes so far). Press Ctrl-C to stop.", port, log, server.seq());
+    if Tokens::load(&files.tokens)?.is_open() {
+        println!("There are no tokens, so anyone who can reach the port gets an answer. Create one with `serve token create`.");
+    }
+    if Users::load(&files.users)?.is_open() {
+        println!("There are no users, so any device that can reach the port can sync. Add some with `user add`.");
+    }
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if let Err(e) = serve(&mut server, &files, &mut limiter, &mut metrics, &mut request_log, &stream) {
+            eprintln!("Request failed: {}", e);
+        }
+    }
+    Ok(())
+}
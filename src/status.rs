@@ -0,0 +1,62 @@
+use std::io;
+use chrono::{Local, NaiveDate};
+use crate::todo::{Todo, TodoList};
+
+pub const DEFAULT_STATUS_LINE: &str = "✔{done} ⏳{pending} ⚠{overdue}";
+
+// What a status bar shows. Habits count as done on days they were kept and
+// as pending otherwise, but are never due or overdue.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counts {
+    pub done: usize,
+    pub pending: usize,
+    pub due_today: usize,
+    pub overdue: usize,
+}
+
+impl Counts {
+    pub fn add(&mut self, todo: &Todo, today: NaiveDate) {
+        if todo.habit {
+            if todo.done_on(today) {
+                self.done += 1;
+            } else {
+                self.pending += 1;
+            }
+            return;
+        }
+        if todo.completed {
+            if todo.completed_on().is_some_and(|at| at.date_naive() == today) && !todo.cancelled {
+                self.done += 1;
+            }
+            return;
+        }
+        self.pending += 1;
+        match todo.due {
+            Some(due) if due < today => self.overdue += 1,
+            Some(due) if due == today => self.due_today += 1,
+            _ => {}
+        }
+    }
+
+    // Streams the file instead of loading the whole list, which keeps a
+    // status bar polling every few seconds cheap.
+    pub fn from_file(filename: &str) -> io::Result<Self> {
+        let today = Local::now().date_naive();
+        let mut counts = Counts::default();
+        TodoList::for_each_in_file(filename, |todo| {
+            counts.add(&todo, today);
+            true
+        })?;
+        Ok(counts)
+    }
+
+    // Fills in {done} (completed today), {pending}, {due_today} and
+    // {overdue}; anything else is copied as it is.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{done}", &self.done.to_string())
+            .replace("{pending}", &self.pending.to_string())
+            .replace("{due_today}", &self.due_today.to_string())
+            .replace("{overdue}", &self.overdue.to_string())
+    }
+}
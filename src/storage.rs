@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use crate::todo::{Todo, TodoList};
+
+// Where a todo list is kept: a JSON file, with the `sled` feature a sled
+// database, which reads and writes single todos without going through the
+// whole list, with the `postgres` feature a PostgreSQL database shared by
+// several clients, or only in memory.
+pub trait Storage {
+    // An empty list if nothing has been saved yet.
+    fn load(&self) -> io::Result<TodoList>;
+    fn save(&self, todo_list: &TodoList) -> io::Result<()>;
+    fn get(&self, id: usize) -> io::Result<Option<Todo>>;
+    // Hands the todos to `f` one at a time until it returns false.
+    fn for_each(&self, f: &mut dyn FnMut(Todo) -> bool) -> io::Result<()>;
+}
+
+static DATABASE_URL: OnceLock<String> = OnceLock::new();
+
+// The connection string used for "todos.postgres"; only the first call
+// counts.
+pub fn set_database_url(url: &str) {
+    let _ = DATABASE_URL.set(url.to_string());
+}
+
+// A filename ending in ".sled" names a sled database (a directory), one
+// ending in ".postgres" the PostgreSQL database given to set_database_url,
+// one ending in ".memory" a MemoryStorage kept for the rest of the process,
+// anything else a JSON file.
+pub fn is_memory(filename: &str) -> bool {
+    filename.ends_with(".memory")
+}
+
+pub fn is_sled(filename: &str) -> bool {
+    filename.ends_with(".sled")
+}
+
+pub fn is_postgres(filename: &str) -> bool {
+    filename.ends_with(".postgres")
+}
+
+#[cfg(not(all(feature = "sled", feature = "postgres")))]
+fn unsupported(filename: &str, feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} needs a build with {} support (feature `{}`)", filename, feature, feature),
+    )
+}
+
+static MEMORY: Mutex<Option<HashMap<String, MemoryStorage>>> = Mutex::new(None);
+
+pub fn open(filename: &str) -> io::Result<Box<dyn Storage>> {
+    if is_memory(filename) {
+        let mut stores = MEMORY.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = stores.get_or_insert_with(HashMap::new).entry(filename.to_string()).or_default();
+        return Ok(Box::new(store.clone()));
+    }
+    if is_sled(filename) {
+        #[cfg(feature = "sled")]
+        return Ok(Box::new(SledStore::open(filename)?));
+        #[cfg(not(feature = "sled"))]
+        return Err(unsupported(filename, "sled"));
+    }
+    if is_postgres(filename) {
+        let Some(url) = DATABASE_URL.get() else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "No database_url is configured for the postgres storage."));
+        };
+        #[cfg(feature = "postgres")]
+        return Ok(Box::new(PostgresStore::connect(url)?));
+        #[cfg(not(feature = "postgres"))]
+        {
+            let _ = url;
+            return Err(unsupported(filename, "postgres"));
+        }
+    }
+    Ok(Box::new(JsonFile { filename: filename.to_string() }))
+}
+
+pub struct JsonFile {
+    pub filename: String,
+}
+
+impl Storage for JsonFile {
+    fn load(&self) -> io::Result<TodoList> {
+        TodoList::read_json_file(&self.filename)
+    }
+
+    fn save(&self, todo_list: &TodoList) -> io::Result<()> {
+        todo_list.write_json_file(&self.filename)
+    }
+
+    fn get(&self, id: usize) -> io::Result<Option<Todo>> {
+        let mut found = None;
+        TodoList::stream_json_file(&self.filename, |todo| {
+            if todo.id == id {
+                found = Some(todo);
+            }
+            found.is_none()
+        })?;
+        Ok(found)
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(Todo) -> bool) -> io::Result<()> {
+        TodoList::stream_json_file(&self.filename, f)
+    }
+}
+
+// Keeps the list in memory, as saved, for throwaway sessions and for tests
+// that should not touch the disk. Clones share the same list.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    json: Arc<Mutex<Option<String>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> io::Result<TodoList> {
+        match &*self.json.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(json) => Ok(TodoList::from_json(json)?),
+            None => Ok(TodoList::new()),
+        }
+    }
+
+    fn save(&self, todo_list: &TodoList) -> io::Result<()> {
+        let json = todo_list.to_json()?;
+        *self.json.lock().unwrap_or_else(PoisonError::into_inner) = Some(json);
+        Ok(())
+    }
+
+    fn get(&self, id: usize) -> io::Result<Option<Todo>> {
+        Ok(self.load()?.get_todo(id).cloned())
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(Todo) -> bool) -> io::Result<()> {
+        for todo in self.load()?.todos() {
+            if !f(todo.clone()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+pub use self::sled_store::SledStore;
+
+#[cfg(feature = "sled")]
+mod sled_store {
+    use std::collections::HashMap;
+    use std::io;
+    use serde::{Deserialize, Serialize};
+    use crate::todo::{Goal, Todo, TodoList, Tombstone};
+    use super::Storage;
+
+    // Todos are stored under their ID as 8 big-endian bytes, so they come
+    // back in ID order; everything else in the list under META_KEY.
+    const META_KEY: &[u8] = b"meta";
+
+    #[derive(Serialize, Deserialize)]
+    struct Meta {
+        next_id: usize,
+        goals: Vec<Goal>,
+        next_goal_id: usize,
+        #[serde(default)]
+        tombstones: Vec<Tombstone>,
+    }
+
+    fn key(id: usize) -> [u8; 8] {
+        (id as u64).to_be_bytes()
+    }
+
+    // The database is locked while open, so a second process using the same
+    // database gets an error until the first lets go of it.
+    pub struct SledStore {
+        db: sled::Db,
+    }
+
+    impl SledStore {
+        pub fn open(path: &str) -> io::Result<Self> {
+            Ok(SledStore { db: sled::open(path)? })
+        }
+    }
+
+    impl Storage for SledStore {
+        fn load(&self) -> io::Result<TodoList> {
+            let mut todos = Vec::new();
+            let mut meta = None;
+            for entry in self.db.iter() {
+                let (key, value) = entry?;
+                if &*key == META_KEY {
+                    meta = Some(serde_json::from_slice::<Meta>(&value)?);
+                } else {
+                    todos.push(serde_json::from_slice::<Todo>(&value)?);
+                }
+            }
+            Ok(match meta {
+                Some(meta) => TodoList::from_parts(todos, meta.next_id, meta.goals, meta.next_goal_id, meta.tombstones),
+                None => TodoList::from_todos(todos),
+            })
+        }
+
+        // Writes only the todos that changed, and removes deleted ones, in
+        // a single atomic batch.
+        fn save(&self, todo_list: &TodoList) -> io::Result<()> {
+            let mut stored: HashMap<Vec<u8>, sled::IVec> = HashMap::new();
+            for entry in self.db.iter() {
+                let (key, value) = entry?;
+                stored.insert(key.to_vec(), value);
+            }
+
+            let mut batch = sled::Batch::default();
+            for todo in todo_list.todos() {
+                let key = key(todo.id);
+                let value = serde_json::to_vec(todo)?;
+                if stored.remove(key.as_slice()).is_none_or(|old| *old != *value) {
+                    batch.insert(&key, value);
+                }
+            }
+            stored.remove(META_KEY);
+            for key in stored.into_keys() {
+                batch.remove(key);
+            }
+            let meta = Meta {
+                next_id: todo_list.next_id,
+                goals: todo_list.goals.clone(),
+                next_goal_id: todo_list.next_goal_id,
+                tombstones: todo_list.tombstones.clone(),
+            };
+            batch.insert(META_KEY, serde_json::to_vec(&meta)?);
+
+            self.db.apply_batch(batch)?;
+            self.db.flush()?;
+            Ok(())
+        }
+
+        fn get(&self, id: usize) -> io::Result<Option<Todo>> {
+            match self.db.get(key(id))? {
+                Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+                None => Ok(None),
+            }
+        }
+
+        fn for_each(&self, f: &mut dyn FnMut(Todo) -> bool) -> io::Result<()> {
+            for entry in self.db.iter() {
+                let (key, value) = entry?;
+                if &*key != META_KEY && !f(serde_json::from_slice(&value)?) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub use self::postgres_store::PostgresStore;
+
+#[cfg(feature = "postgres")]
+mod postgres_store {
+    use std::collections::{HashMap, HashSet};
+    use std::future::Future;
+    use std::io;
+    use serde::Deserialize;
+    use serde_json::Value;
+    use sqlx::postgres::{PgPool, PgPoolOptions};
+    use sqlx::Row;
+    use tokio::runtime::Runtime;
+    use crate::history::EventKind;
+    use crate::todo::{Goal, Todo, TodoList, Tombstone};
+    use super::Storage;
+
+    const SCHEMA: [&str; 4] = [
+        "CREATE TABLE IF NOT EXISTS todos (id BIGINT PRIMARY KEY, data JSONB NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS todo_list (
+            id INT PRIMARY KEY CHECK (id = 1),
+            next_id BIGINT NOT NULL,
+            next_goal_id BIGINT NOT NULL,
+            goals JSONB NOT NULL
+        )",
+        "INSERT INTO todo_list VALUES (1, 1, 1, '[]') ON CONFLICT DO NOTHING",
+        "ALTER TABLE todo_list ADD COLUMN IF NOT EXISTS tombstones JSONB NOT NULL DEFAULT '[]'",
+    ];
+
+    fn db_error(e: sqlx::Error) -> io::Error {
+        io::Error::other(e)
+    }
+
+    // Each todo is a row of its own, so clients sharing the database only
+    // write the todos they changed. The single row of todo_list holds the
+    // rest of the list and is locked while a client saves.
+    pub struct PostgresStore {
+        runtime: Runtime,
+        pool: PgPool,
+    }
+
+    impl PostgresStore {
+        pub fn connect(url: &str) -> io::Result<Self> {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            let pool = runtime.block_on(async {
+                let pool = PgPoolOptions::new().max_connections(1).connect(url).await?;
+                for statement in SCHEMA {
+                    sqlx::query(statement).execute(&pool).await?;
+                }
+                Ok::<_, sqlx::Error>(pool)
+            });
+            Ok(PostgresStore { runtime, pool: pool.map_err(db_error)? })
+        }
+
+        fn block_on<T>(&self, future: impl Future<Output = Result<T, sqlx::Error>>) -> io::Result<T> {
+            self.runtime.block_on(future).map_err(db_error)
+        }
+    }
+
+    fn decode(data: Value) -> io::Result<Todo> {
+        Ok(serde_json::from_value(data)?)
+    }
+
+    impl Storage for PostgresStore {
+        fn load(&self) -> io::Result<TodoList> {
+            let (rows, meta) = self.block_on(async {
+                let rows = sqlx::query("SELECT data FROM todos ORDER BY id").fetch_all(&self.pool).await?;
+                let meta = sqlx::query("SELECT next_id, next_goal_id, goals, tombstones FROM todo_list WHERE id = 1")
+                    .fetch_one(&self.pool)
+                    .await?;
+                Ok((rows, meta))
+            })?;
+            let todos = rows
+                .into_iter()
+                .map(|row| decode(row.try_get("data").map_err(db_error)?))
+                .collect::<io::Result<Vec<Todo>>>()?;
+            let next_id: i64 = meta.try_get("next_id").map_err(db_error)?;
+            let next_goal_id: i64 = meta.try_get("next_goal_id").map_err(db_error)?;
+            let goals: Vec<Goal> = serde_json::from_value(meta.try_get("goals").map_err(db_error)?)?;
+            let tombstones: Vec<Tombstone> = serde_json::from_value(meta.try_get("tombstones").map_err(db_error)?)?;
+            Ok(TodoList::from_parts(todos, next_id as usize, goals, next_goal_id as usize, tombstones))
+        }
+
+        // Writes the todos changed since the list was loaded in a single
+        // transaction, keeping what other clients saved in the meantime: a
+        // todo they changed more recently is left as they saved it, a todo
+        // they deleted is not brought back, and a new todo whose ID another
+        // client has taken in the meantime is stored under the next free ID.
+        fn save(&self, todo_list: &TodoList) -> io::Result<()> {
+            let events = todo_list.unsaved_events();
+            let ids_of = |kinds: &[EventKind]| -> HashSet<usize> {
+                events.iter().filter(|event| kinds.contains(&event.kind)).map(|event| event.todo_id).collect()
+            };
+            let created = ids_of(&[EventKind::Created]);
+            let removed = ids_of(&[EventKind::Deleted, EventKind::Archived]);
+            let goals = serde_json::to_value(&todo_list.goals)?;
+
+            self.block_on(async {
+                let mut tx = self.pool.begin().await?;
+                let meta = sqlx::query("SELECT next_id, tombstones FROM todo_list WHERE id = 1 FOR UPDATE")
+                    .fetch_one(&mut *tx)
+                    .await?;
+                let mut tombstones: Vec<Tombstone> =
+                    serde_json::from_value(meta.try_get("tombstones")?).map_err(|e| sqlx::Error::Decode(e.into()))?;
+                for tombstone in &todo_list.tombstones {
+                    if !tombstones.contains(tombstone) {
+                        tombstones.push(tombstone.clone());
+                    }
+                }
+                let mut stored: HashMap<usize, Value> = HashMap::new();
+                for row in sqlx::query("SELECT id, data FROM todos").fetch_all(&mut *tx).await? {
+                    stored.insert(row.try_get::<i64, _>("id")? as usize, row.try_get("data")?);
+                }
+                let mut next_id = [meta.try_get::<i64, _>("next_id")? as usize, todo_list.next_id]
+                    .into_iter()
+                    .chain(stored.keys().map(|id| id + 1))
+                    .max()
+                    .unwrap_or(1);
+
+                for &id in removed.iter().filter(|&&id| todo_list.get_todo(id).is_none()) {
+                    sqlx::query("DELETE FROM todos WHERE id = $1").bind(id as i64).execute(&mut *tx).await?;
+                }
+                for todo in todo_list.todos() {
+                    let data = serde_json::to_value(todo).map_err(|e| sqlx::Error::Encode(e.into()))?;
+                    let old = stored.get(&todo.id).and_then(|old| Todo::deserialize(old).ok().map(|todo| (old, todo)));
+                    let id = match old {
+                        // Not the same todo: the ID was taken by another client.
+                        Some((_, old)) if old.created_at != todo.created_at => {
+                            if !created.contains(&todo.id) {
+                                continue;
+                            }
+                            next_id += 1;
+                            next_id - 1
+                        },
+                        Some((_, old)) if old.updated_at > todo.updated_at => continue,
+                        Some((old, _)) if *old == data => continue,
+                        Some(_) => todo.id,
+                        None if created.contains(&todo.id) => todo.id,
+                        None => continue,
+                    };
+                    let mut data = data;
+                    data["id"] = Value::from(id);
+                    sqlx::query("INSERT INTO todos (id, data) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data")
+                        .bind(id as i64)
+                        .bind(data)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                let tombstones = serde_json::to_value(&tombstones).map_err(|e| sqlx::Error::Encode(e.into()))?;
+                sqlx::query(
+                    "UPDATE todo_list SET next_id = $1, next_goal_id = GREATEST(next_goal_id, $2), goals = $3, tombstones = $4
+                     WHERE id = 1",
+                )
+                .bind(next_id as i64)
+                .bind(todo_list.next_goal_id as i64)
+                .bind(goals)
+                .bind(tombstones)
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await
+            })
+        }
+
+        fn get(&self, id: usize) -> io::Result<Option<Todo>> {
+            let row = self.block_on(
+                sqlx::query("SELECT data FROM todos WHERE id = $1").bind(id as i64).fetch_optional(&self.pool),
+            )?;
+            row.map(|row| decode(row.try_get("data").map_err(db_error)?)).transpose()
+        }
+
+        fn for_each(&self, f: &mut dyn FnMut(Todo) -> bool) -> io::Result<()> {
+            for todo in self.load()?.todos() {
+                if !f(todo.clone()) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+}
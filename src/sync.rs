@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+#[cfg(feature = "net")]
+use std::fs::OpenOptions;
+#[cfg(feature = "net")]
+use std::io::{BufRead, BufReader};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "net")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "net")]
+use crate::crypto::{self, Key};
+use crate::history::Event;
+use crate::todo::{Todo, TodoList};
+
+// One change to the list as exchanged through the sync server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+// `events` are the entries of the sending device's history that led to the
+// change, for the activity feed.
+pub enum Op {
+    Put {
+        todo: Box<Todo>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<Event>,
+    },
+    // `created_at` tells the deleted todo from another one that later got
+    // the same id elsewhere.
+    Delete {
+        id: usize,
+        #[serde(default)]
+        created_at: Option<DateTime<Local>>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<Event>,
+    },
+}
+
+impl Op {
+    pub fn todo_id(&self) -> usize {
+        match self {
+            Op::Put { todo, .. } => todo.id,
+            Op::Delete { id, .. } => *id,
+        }
+    }
+}
+
+// An op encrypted by the device that made it (see crypto), all the server
+// ever gets to see of it. Both fields are hex.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sealed {
+    pub nonce: String,
+    pub data: String,
+}
+
+// An op as kept by the server, numbered in the order it arrived. Devices
+// are sent it with the label of the device it came from in place of that
+// device's id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedOp {
+    pub seq: u64,
+    pub device: String,
+    pub op: Sealed,
+    // When the server received it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<DateTime<Local>>,
+}
+
+// An op in the activity feed, with the user of the device that sent it if
+// the server has users.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    #[serde(flatten)]
+    pub logged: LoggedOp,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+// `salt` is the same for every device of a server and goes into the
+// derivation of the encryption key. The device sends `secret` along with its
+// id in every request, and seals its changes for the others under `label`,
+// which is all they get to know of it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterResponse {
+    pub device: String,
+    pub label: String,
+    pub secret: String,
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PullResponse {
+    pub seq: u64,
+    pub ops: Vec<LoggedOp>,
+}
+
+// Accepted only from a device that has pulled every op up to `since`, so
+// ops are always made on top of everything the server has seen.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushRequest {
+    pub device: String,
+    pub since: u64,
+    pub ops: Vec<Sealed>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushResponse {
+    pub seq: u64,
+}
+
+// What a device remembers about the server it syncs with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncState {
+    pub remote: String,
+    pub device: String,
+    // Proves to the server that this is the device; see RegisterResponse.
+    // Devices registered before there were secrets have neither it nor a
+    // label, and must register again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    // The master key derived from the passphrase, in hex. Whoever has it can
+    // read everything synced, so this file must stay private.
+    pub key: String,
+    // The last op pulled.
+    pub seq: u64,
+    // The todos as of the last sync, to tell local changes from remote ones.
+    pub synced: Vec<Todo>,
+    // Sent with every request if the server asks for one; see `serve token`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    // The length of the history file after the last sync; what was
+    // appended since goes along with the next push.
+    #[serde(default)]
+    pub history_len: u64,
+}
+
+impl SyncState {
+    // What this device's changes are sealed under.
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.device)
+    }
+}
+
+// "todos.json" keeps its sync state in "todos.sync.json".
+pub fn sync_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.sync.json", stem),
+        None => format!("{}.sync.json", filename),
+    }
+}
+
+pub fn load_state(filename: &str) -> io::Result<Option<SyncState>> {
+    if !Path::new(filename).exists() {
+        return Ok(None);
+    }
+
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+// Readable by the owner only, as it holds the key.
+pub fn save_state(filename: &str, state: &SyncState) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(state)?;
+    let mut file = File::create(filename)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+fn same(a: &Todo, b: &Todo) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+// What changed here since the last sync, each op with the events in
+// `history` about its todo. Events of changes pulled from elsewhere are left
+// out.
+pub fn local_ops(todo_list: &TodoList, synced: &[Todo], history: &[Event]) -> Vec<Op> {
+    let events = |id: usize| -> Vec<Event> {
+        history.iter().filter(|event| event.todo_id == id && event.detail.as_deref() != Some("synced")).cloned().collect()
+    };
+    let synced: HashMap<usize, &Todo> = synced.iter().map(|todo| (todo.id, todo)).collect();
+    let mut ops: Vec<Op> = todo_list
+        .todos()
+        .iter()
+        .filter(|todo| synced.get(&todo.id).is_none_or(|old| !same(old, todo)))
+        .map(|todo| Op::Put { todo: Box::new(todo.clone()), events: events(todo.id) })
+        .collect();
+    let mut deleted: Vec<&Todo> = synced.values().copied().filter(|todo| todo_list.get_todo(todo.id).is_none()).collect();
+    deleted.sort_unstable_by_key(|todo| todo.id);
+    ops.extend(deleted.into_iter().map(|todo| Op::Delete { id: todo.id, created_at: Some(todo.created_at), events: events(todo.id) }));
+    ops
+}
+
+// For a todo changed both here and on another device: each field keeps the
+// later of the two changes, and a tie goes to the copy changed last. The
+// comments of both are kept.
+fn merge(local: &Todo, remote: &Todo) -> Todo {
+    let (Ok(Value::Object(mut merged)), Ok(Value::Object(theirs))) = (serde_json::to_value(local), serde_json::to_value(remote)) else {
+        return local.clone();
+    };
+    for (field, value) in theirs {
+        if matches!(field.as_str(), "updated_at" | "field_times" | "comments") {
+            continue;
+        }
+        if (remote.field_time(&field), remote.updated_at) > (local.field_time(&field), local.updated_at) {
+            merged.insert(field, value);
+        }
+    }
+    let mut todo: Todo = serde_json::from_value(Value::Object(merged)).unwrap_or_else(|_| local.clone());
+    todo.updated_at = local.updated_at.max(remote.updated_at);
+    for comment in &remote.comments {
+        if !todo.comments.iter().any(|ours| ours.at == comment.at && ours.author == comment.author) {
+            todo.comments.push(comment.clone());
+        }
+    }
+    todo.comments.sort_by_key(|comment| comment.at);
+    for (field, &at) in &remote.field_times {
+        let time = todo.field_times.entry(field.clone()).or_insert(at);
+        *time = (*time).max(at);
+    }
+    todo
+}
+
+#[derive(Debug, Default)]
+pub struct Applied {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    // Todos added here that moved to a new id because another device had
+    // used theirs first, as (old, new).
+    pub renumbered: Vec<(usize, usize)>,
+}
+
+// Applies ops pulled from the server. A todo changed both here and on
+// another device is merged field by field; a todo deleted on either side
+// stays deleted, however recently the other side changed it. `synced` is
+// updated to what the server now holds.
+pub fn apply(todo_list: &mut TodoList, synced: &mut Vec<Todo>, ops: Vec<Op>) -> Applied {
+    let mut applied = Applied::default();
+    let mut base: HashMap<usize, Todo> = synced.drain(..).map(|todo| (todo.id, todo)).collect();
+    for op in ops {
+        match op {
+            Op::Put { todo, .. } => {
+                let todo = *todo;
+                let id = todo.id;
+                match todo_list.get_todo(id) {
+                    None if base.contains_key(&id) || todo_list.is_deleted(id, todo.created_at) => {},
+                    None => {
+                        todo_list.put_todo(todo.clone(), "synced");
+                        applied.added += 1;
+                    },
+                    // Added here under an id another device took first.
+                    Some(local) if !base.contains_key(&id) && local.created_at != todo.created_at => {
+                        if let Some(new_id) = todo_list.renumber_todo(id) {
+                            applied.renumbered.push((id, new_id));
+                        }
+                        todo_list.put_todo(todo.clone(), "synced");
+                        applied.added += 1;
+                    },
+                    Some(local) => {
+                        let changed_here = base.get(&id).is_none_or(|old| !same(old, local));
+                        let merged = if changed_here { merge(local, &todo) } else { todo.clone() };
+                        if !same(local, &merged) {
+                            todo_list.put_todo(merged, "synced");
+                            applied.updated += 1;
+                        }
+                    },
+                }
+                base.insert(id, todo);
+            },
+            Op::Delete { id, created_at, .. } => {
+                let same_todo = todo_list.get_todo(id).is_some_and(|local| created_at.is_none_or(|at| at == local.created_at));
+                if same_todo && todo_list.delete_todo(id) {
+                    applied.deleted += 1;
+                }
+                base.remove(&id);
+            },
+        }
+    }
+    synced.extend(base.into_values());
+    synced.sort_by_key(|todo| todo.id);
+    // Todos from elsewhere were added at the end; keep every device in the
+    // same order.
+    if applied.added > 0 {
+        todo_list.todos.sort_by_key(|todo| todo.id);
+        todo_list.reindex();
+    }
+    applied
+}
+
+// "todos.json" is served from the op log "todos.server.jsonl".
+pub fn server_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.server.jsonl", stem),
+        None => format!("{}.server.jsonl", filename),
+    }
+}
+
+#[cfg(feature = "net")]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Entry {
+    Salt { salt: String },
+    // Devices registered before there were labels and secrets have neither:
+    // their label is their id, and they cannot make requests any more.
+    Register {
+        device: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+    },
+    Op(LoggedOp),
+}
+
+#[derive(Debug)]
+pub enum PushError {
+    UnknownDevice,
+    // The device has not pulled up to the given op yet.
+    Behind(u64),
+    Io(io::Error),
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PushError::UnknownDevice => write!(f, "This device is not registered."),
+            PushError::Behind(seq) => write!(f, "There are newer changes up to {} to pull first.", seq),
+            PushError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+struct Device {
+    id: String,
+    label: String,
+    // The SHA-256 of the device's secret, in hex.
+    secret: Option<String>,
+}
+
+// The server side of sync: registered devices and every op pushed, kept in
+// memory and appended to a log file that is read back on start.
+#[cfg(feature = "net")]
+pub struct Server {
+    filename: String,
+    salt: String,
+    devices: Vec<Device>,
+    ops: Vec<LoggedOp>,
+}
+
+#[cfg(feature = "net")]
+fn hash(secret: &str) -> String {
+    crypto::to_hex(&Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(feature = "net")]
+impl Server {
+    pub fn open(filename: &str) -> io::Result<Self> {
+        let mut server = Server { filename: filename.to_string(), salt: String::new(), devices: Vec::new(), ops: Vec::new() };
+        if Path::new(filename).exists() {
+            for line in BufReader::new(File::open(filename)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line)? {
+                    Entry::Salt { salt } => server.salt = salt,
+                    Entry::Register { device, label, secret } => {
+                        let label = label.unwrap_or_else(|| device.clone());
+                        server.devices.push(Device { id: device, label, secret });
+                    },
+                    Entry::Op(op) => server.ops.push(op),
+                }
+            }
+        }
+        if server.salt.is_empty() {
+            let salt = crypto::random_secret();
+            server.append(&[Entry::Salt { salt: salt.clone() }])?;
+            server.salt = salt;
+        }
+        Ok(server)
+    }
+
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    // The number of the last op.
+    pub fn seq(&self) -> u64 {
+        self.ops.last().map_or(0, |op| op.seq)
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_registered(&self, device: &str) -> bool {
+        self.devices.iter().any(|known| known.id == device)
+    }
+
+    // Whether `secret` is the one `device` was given when it registered.
+    pub fn authenticate(&self, device: &str, secret: Option<&str>) -> bool {
+        let Some(secret) = secret else {
+            return false;
+        };
+        let hashed = hash(secret);
+        self.devices.iter().any(|known| known.id == device && known.secret.as_deref() == Some(hashed.as_str()))
+    }
+
+    // `op` as other devices get to see it, with the label of the device it
+    // came from.
+    pub fn public(&self, op: &LoggedOp) -> LoggedOp {
+        let label = self.devices.iter().find(|known| known.id == op.device).map_or("", |known| known.label.as_str());
+        LoggedOp { device: label.to_string(), ..op.clone() }
+    }
+
+    fn append(&self, entries: &[Entry]) -> io::Result<()> {
+        let mut buf = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut buf, entry)?;
+            buf.push(b'\n');
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.filename)?;
+        file.write_all(&buf)?;
+        file.sync_all()
+    }
+
+    pub fn register(&mut self) -> io::Result<RegisterResponse> {
+        let device = crypto::random_secret();
+        let label = crypto::random_secret();
+        let secret = crypto::random_secret();
+        self.append(&[Entry::Register { device: device.clone(), label: Some(label.clone()), secret: Some(hash(&secret)) }])?;
+        self.devices.push(Device { id: device.clone(), label: label.clone(), secret: Some(hash(&secret)) });
+        Ok(RegisterResponse { device, label, secret, salt: self.salt.clone() })
+    }
+
+    // The ops after `since` that came from other devices.
+    pub fn pull(&self, device: &str, since: u64) -> PullResponse {
+        let ops = self
+            .ops
+            .iter()
+            .filter(|op| op.seq > since && op.device != device)
+            .map(|op| self.public(op))
+            .collect();
+        PullResponse { seq: self.seq(), ops }
+    }
+
+    // The last `limit` ops, from every device, newest last, with the ids of
+    // the devices; see `public`.
+    pub fn recent(&self, limit: usize) -> &[LoggedOp] {
+        &self.ops[self.ops.len().saturating_sub(limit)..]
+    }
+
+    pub fn push(&mut self, request: PushRequest) -> Result<u64, PushError> {
+        if !self.is_registered(&request.device) {
+            return Err(PushError::UnknownDevice);
+        }
+        if request.since < self.seq() {
+            return Err(PushError::Behind(self.seq()));
+        }
+        let first = self.seq() + 1;
+        let logged: Vec<LoggedOp> = request
+            .ops
+            .into_iter()
+            .zip(first..)
+            .map(|(op, seq)| LoggedOp { seq, device: request.device.clone(), op, at: Some(Local::now()) })
+            .collect();
+        let entries: Vec<Entry> = logged.iter().cloned().map(Entry::Op).collect();
+        self.append(&entries).map_err(PushError::Io)?;
+        self.ops.extend(logged);
+        Ok(self.seq())
+    }
+}
+
+#[cfg(feature = "net")]
+fn with_token<B>(request: ureq::RequestBuilder<B>, token: Option<&str>) -> ureq::RequestBuilder<B> {
+    match token {
+        Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+        None => request,
+    }
+}
+
+// With the token and the device's secret.
+#[cfg(feature = "net")]
+fn as_device<B>(request: ureq::RequestBuilder<B>, state: &SyncState) -> ureq::RequestBuilder<B> {
+    let request = with_token(request, state.token.as_deref());
+    match &state.secret {
+        Some(secret) => request.header("X-Device-Secret", secret),
+        None => request,
+    }
+}
+
+// `doing` is e.g. "syncing with", followed by the server.
+#[cfg(feature = "net")]
+fn request_failed(remote: &str, doing: &str, e: ureq::Error) -> io::Error {
+    match e {
+        ureq::Error::StatusCode(401) => io::Error::other(format!("{} wants a valid token: sync remote URL --token TOKEN", remote)),
+        ureq::Error::StatusCode(429) => io::Error::other(format!("{} is getting too many requests from this device; try again in a minute", remote)),
+        e => io::Error::other(format!("{} {} failed: {}", doing, remote, e)),
+    }
+}
+
+// An op from the activity feed, decrypted. `device` is the label of the
+// device that sent it.
+#[derive(Debug)]
+pub struct Activity {
+    pub seq: u64,
+    pub at: Option<DateTime<Local>>,
+    pub device: String,
+    pub user: Option<String>,
+    pub op: Op,
+}
+
+// How many changes the server holds, asked for as a pull that fetches
+// none of them, to see that it can be reached and still lets this device
+// in. Gives up after a few seconds.
+#[cfg(feature = "net")]
+pub fn server_seq(state: &SyncState) -> io::Result<u64> {
+    let remote = state.remote.trim_end_matches('/');
+    let url = format!("{}/ops?since={}&device={}", remote, u64::MAX, state.device);
+    let agent = ureq::Agent::new_with_config(ureq::Agent::config_builder().timeout_global(Some(std::time::Duration::from_secs(5))).build());
+    let pulled: PullResponse = as_device(agent.get(&url), state)
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| match e {
+            ureq::Error::StatusCode(403) => io::Error::other(format!(
+                "{} does not know this device, or it belongs to no user; if it was registered before devices had secrets, run `sync remote URL` again",
+                remote
+            )),
+            e => request_failed(remote, "reaching", e),
+        })?;
+    Ok(pulled.seq)
+}
+
+// The last `limit` changes sent to the server by any device, this one
+// included, oldest first.
+#[cfg(feature = "net")]
+pub fn activity(state: &SyncState, limit: usize) -> io::Result<Vec<Activity>> {
+    let remote = state.remote.trim_end_matches('/');
+    let url = format!("{}/activity?device={}&limit={}", remote, state.device, limit);
+    let entries: Vec<ActivityEntry> = as_device(ureq::get(&url), state)
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| match e {
+            ureq::Error::StatusCode(403) => io::Error::other(format!("{} does not show its activity to this device", remote)),
+            e => request_failed(remote, "fetching the activity of", e),
+        })?;
+    let master: Key = crypto::from_hex(&state.key)
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the sync key is damaged; run `sync remote URL` again"))?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let op = open_op(&master, &entry.logged)?;
+            Ok(Activity { seq: entry.logged.seq, at: entry.logged.at, device: entry.logged.device, user: entry.user, op })
+        })
+        .collect()
+}
+
+// Registers with the server at `remote` as a new device, with an invite
+// code if the server has users and a token if it has tokens.
+#[cfg(feature = "net")]
+pub fn register(remote: &str, invite: Option<&str>, token: Option<&str>) -> io::Result<RegisterResponse> {
+    let mut url = format!("{}/register", remote.trim_end_matches('/'));
+    if let Some(invite) = invite {
+        url.push_str(&format!("?invite={}", invite));
+    }
+    with_token(ureq::post(&url), token).send_empty().and_then(|mut response| response.body_mut().read_json()).map_err(|e| match e {
+        ureq::Error::StatusCode(403) => io::Error::other(format!(
+            "{} needs a valid invite code (sync remote URL --invite CODE), or a token allowing register",
+            remote
+        )),
+        e => request_failed(remote, "registering with", e),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub name: String,
+    pub role: String,
+    pub devices: usize,
+}
+
+// The users of the server, which only admins may see.
+#[cfg(feature = "net")]
+pub fn users(state: &SyncState) -> io::Result<Vec<UserInfo>> {
+    let remote = state.remote.trim_end_matches('/');
+    let url = format!("{}/users?device={}", remote, state.device);
+    as_device(ureq::get(&url), state).call().and_then(|mut response| response.body_mut().read_json()).map_err(|e| match e {
+        ureq::Error::StatusCode(403) => io::Error::other(format!("only admins with a token allowing users can list the users of {}", remote)),
+        e => request_failed(remote, "listing the users of", e),
+    })
+}
+
+#[cfg(feature = "net")]
+fn open_op(master: &Key, logged: &LoggedOp) -> io::Result<Op> {
+    let plaintext = crypto::open(&crypto::device_key(master, &logged.device), &logged.device, &logged.op).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cannot decrypt the changes of device {}: was it set up with another passphrase?", logged.device),
+        )
+    })?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub pulled: Applied,
+    pub pushed: usize,
+}
+
+// Pulls what other devices changed, applies it and pushes what changed
+// here, along with the `history` of those changes, retrying when another
+// device pushed in between.
+#[cfg(feature = "net")]
+pub fn sync(todo_list: &mut TodoList, state: &mut SyncState, history: &[Event]) -> io::Result<Report> {
+    const ATTEMPTS: usize = 5;
+    let remote = state.remote.trim_end_matches('/').to_string();
+    let failed = |e: ureq::Error| match e {
+        ureq::Error::StatusCode(403) => io::Error::other(format!(
+            "{} refused this device: it is not registered for a user, its user is a viewer and cannot send changes, its token does not allow this, or it was registered before devices had secrets and needs `sync remote URL` again",
+            remote
+        )),
+        e => request_failed(&remote, "syncing with", e),
+    };
+    let master: Key = crypto::from_hex(&state.key)
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the sync key is damaged; run `sync remote URL` again"))?;
+    let own_key = crypto::device_key(&master, state.label());
+    let mut report = Report::default();
+    for _ in 0..ATTEMPTS {
+        let url = format!("{}/ops?since={}&device={}", remote, state.seq, state.device);
+        let pulled: PullResponse =
+            as_device(ureq::get(&url), state).call().and_then(|mut response| response.body_mut().read_json()).map_err(failed)?;
+        let ops = pulled.ops.iter().map(|logged| open_op(&master, logged)).collect::<io::Result<Vec<Op>>>()?;
+        let applied = apply(todo_list, &mut state.synced, ops);
+        report.pulled.added += applied.added;
+        report.pulled.updated += applied.updated;
+        report.pulled.deleted += applied.deleted;
+        report.pulled.renumbered.extend(applied.renumbered);
+        state.seq = pulled.seq;
+
+        let ops = local_ops(todo_list, &state.synced, history)
+            .iter()
+            .map(|op| Ok(crypto::seal(&own_key, state.label(), &serde_json::to_vec(op)?)))
+            .collect::<io::Result<Vec<Sealed>>>()?;
+        let pushed = ops.len();
+        let request = PushRequest { device: state.device.clone(), since: state.seq, ops };
+        match as_device(ureq::post(format!("{}/ops", remote)), state).send_json(&request) {
+            Ok(mut response) => {
+                let response: PushResponse = response.body_mut().read_json().map_err(failed)?;
+                state.seq = response.seq;
+                state.synced = todo_list.todos().to_vec();
+                report.pushed = pushed;
+                return Ok(report);
+            },
+            // Someone pushed since the pull: pull again.
+            Err(ureq::Error::StatusCode(409)) => continue,
+            Err(e) => return Err(failed(e)),
+        }
+    }
+    Err(io::Error::other(format!("{} kept changing; try again", remote)))
+}
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// A tag name shared between every todo carrying it. Tags whose name starts
+// with '@' are contexts ("@home", "@computer").
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct Tag(Arc<str>);
+
+impl Tag {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_context(&self) -> bool {
+        self.0.starts_with('@')
+    }
+}
+
+// A tag not yet shared with anything; TodoList interns it when stored.
+impl From<&str> for Tag {
+    fn from(name: &str) -> Self {
+        Tag(Arc::from(name))
+    }
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Hash for Tag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Deref for Tag {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Tag(Arc::from(name)))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TagTable {
+    names: HashSet<Arc<str>>,
+}
+
+impl TagTable {
+    pub fn intern(&mut self, name: &str) -> Tag {
+        if let Some(existing) = self.names.get(name) {
+            return Tag(Arc::clone(existing));
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(Arc::clone(&name));
+        Tag(name)
+    }
+}
+
+// Splits user input such as "work, @home urgent" into distinct tag names.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let name = name.trim();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
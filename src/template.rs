@@ -0,0 +1,66 @@
+use std::fmt;
+use crate::columns::Column;
+
+// Output templates such as "{{id}} {{title}} [{{due}}]". Fields are the
+// column names of the todo table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Field(Column),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TemplateError {
+    UnknownField(String),
+    Unclosed,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::UnknownField(name) => write!(f, "Unknown template field {{{{{}}}}}.", name),
+            TemplateError::Unclosed => write!(f, "A template field is missing its closing }}}}."),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut pieces = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                pieces.push(Piece::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or(TemplateError::Unclosed)?;
+            let name = after[..end].trim();
+            let column = Column::ALL
+                .into_iter()
+                .find(|column| column.name() == name)
+                .ok_or_else(|| TemplateError::UnknownField(name.to_string()))?;
+            pieces.push(Piece::Field(column));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest.to_string()));
+        }
+        Ok(Template { pieces })
+    }
+
+    pub fn render(&self, mut value: impl FnMut(Column) -> String) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Field(column) => out.push_str(&value(*column)),
+            }
+        }
+        out
+    }
+}
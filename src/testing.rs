@@ -0,0 +1,206 @@
+// proptest strategies for the model, for checking that storage backends and
+// format changes keep every todo intact:
+//
+//     proptest! {
+//         #[test]
+//         fn survives_my_backend(todo_list in any::<TodoList>()) { ... }
+//     }
+//
+// The generated values are valid as far as serialization goes, not as a
+// list: IDs are unique, but dependencies, goals and parents may point at
+// todos that do not exist, as in a hand-edited file.
+use std::collections::BTreeMap;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
+use proptest::collection::{btree_map, btree_set, vec};
+use proptest::prelude::*;
+use crate::priority::Priority;
+use crate::rrule::{Recurrence, Rule};
+use crate::tags::Tag;
+use crate::todo::{Comment, Goal, Todo, TodoList};
+use crate::zones::Deadline;
+
+// A time between 1970 and 2100, to the nanosecond.
+pub fn datetime() -> impl Strategy<Value = DateTime<Local>> {
+    (0i64..4_102_444_800, 0u32..1_000_000_000).prop_map(|(secs, nanos)| {
+        Local.timestamp_opt(secs, nanos).single().expect("a timestamp maps to a single local time")
+    })
+}
+
+pub fn date() -> impl Strategy<Value = NaiveDate> {
+    (0i32..47_482).prop_map(|days| NaiveDate::from_ymd_opt(1970, 1, 1).expect("valid date") + chrono::Days::new(days as u64))
+}
+
+// At an offset in quarter hours, as zones have them, in a zone named
+// anything.
+pub fn deadline() -> impl Strategy<Value = Deadline> {
+    (datetime(), -48i32..=56, text(30)).prop_map(|(at, quarters, zone)| Deadline {
+        at: at.with_timezone(&FixedOffset::east_opt(quarters * 900).expect("a valid offset")),
+        zone,
+    })
+}
+
+pub fn priority() -> impl Strategy<Value = Priority> {
+    prop_oneof![Just(Priority::Low), Just(Priority::Medium), Just(Priority::High)]
+}
+
+pub fn tag() -> impl Strategy<Value = Tag> {
+    "@?[a-z][a-z0-9-]{0,11}".prop_map(|name| Tag::from(name.as_str()))
+}
+
+const RULES: [&str; 6] = [
+    "FREQ=DAILY",
+    "FREQ=DAILY;INTERVAL=3",
+    "FREQ=WEEKLY;BYDAY=MO,WE,FR",
+    "FREQ=MONTHLY;BYDAY=2TU",
+    "FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=12",
+    "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+];
+
+pub fn recurrence() -> impl Strategy<Value = Recurrence> {
+    (proptest::sample::select(&RULES[..]), date()).prop_map(|(rule, start)| Recurrence {
+        rule: Rule::parse(rule).expect("the sample rules are valid"),
+        start,
+    })
+}
+
+// Names of fields that are edited, for `field_times`.
+const FIELDS: [&str; 6] = ["title", "description", "due", "priority", "tags", "completed"];
+
+fn field_times() -> impl Strategy<Value = BTreeMap<String, DateTime<Local>>> {
+    btree_map(proptest::sample::select(&FIELDS[..]).prop_map(String::from), datetime(), 0..4)
+}
+
+// Any text, including control characters and characters outside the BMP.
+fn text(max: usize) -> impl Strategy<Value = String> {
+    proptest::string::string_regex(&format!("(?s).{{0,{}}}", max)).expect("valid regex")
+}
+
+// Replies may refer to comments that are not there, as after a bad merge.
+pub fn comment() -> impl Strategy<Value = Comment> {
+    (text(20), datetime(), text(200), prop::option::of(datetime())).prop_map(|(author, at, text, reply_to)| Comment { author, at, text, reply_to })
+}
+
+pub fn todo() -> impl Strategy<Value = Todo> {
+    let basics = (1usize..10_000, text(60), text(200), any::<bool>(), datetime(), datetime());
+    let links = (vec(tag(), 0..4), vec(1usize..10_000, 0..3), vec(1usize..10_000, 0..3), prop::option::of(1usize..100));
+    let dates = (
+        prop::option::of(datetime()),
+        prop::option::of(date()),
+        prop::option::of(deadline()),
+        prop::option::of(date()),
+        prop::option::of(date()),
+    );
+    let habit = (any::<bool>(), btree_set(date(), 0..5), prop::option::of(recurrence()));
+    let effort = (prop::option::of(0u32..100), prop::option::of(0u32..10_000), 0u32..10_000, prop::option::of(0u8..=100), 0u32..20);
+    let labels = (
+        prop::option::of(priority()),
+        any::<bool>(),
+        prop::option::of(text(20)),
+        prop::option::of(text(20)),
+        any::<bool>(),
+        prop::option::of(text(40)),
+        prop::option::of(1usize..10_000),
+        field_times(),
+        any::<bool>(),
+    );
+    let delegation = (prop::option::of(text(20)), prop::option::of(date()), prop::option::of(date()));
+    let comments = vec(comment(), 0..3).prop_map(|mut comments| {
+        comments.sort_by_key(|comment| comment.at);
+        comments
+    });
+    (basics, links, dates, habit, effort, labels, delegation, comments).prop_map(
+        |(
+            (id, title, description, completed, created_at, updated_at),
+            (tags, depends_on, related, goal),
+            (completed_at, due, deadline, scheduled, wait_until),
+            (habit, habit_log, recurrence),
+            (points, estimate, spent, progress, postponed),
+            (priority, important, project, location, cancelled, note, parent, field_times, inbox),
+            (delegated_to, delegated_on, follow_up),
+            comments,
+        )| Todo {
+            id,
+            title,
+            description,
+            completed,
+            created_at,
+            updated_at,
+            tags,
+            completed_at,
+            depends_on,
+            related,
+            due,
+            deadline,
+            postponed,
+            scheduled,
+            habit,
+            habit_log,
+            goal,
+            points,
+            estimate,
+            spent,
+            priority,
+            important,
+            project,
+            location,
+            progress,
+            wait_until,
+            recurrence,
+            cancelled,
+            note,
+            parent,
+            field_times,
+            inbox,
+            delegated_to,
+            delegated_on,
+            follow_up,
+            comments,
+        },
+    )
+}
+
+pub fn goal() -> impl Strategy<Value = Goal> {
+    (1usize..100, text(40), datetime()).prop_map(|(id, title, created_at)| Goal { id, title, created_at })
+}
+
+// Up to `max` todos, and a few goals, with IDs unique within the list.
+pub fn todo_list(max: usize) -> impl Strategy<Value = TodoList> {
+    (vec(todo(), 0..=max), vec(goal(), 0..4)).prop_map(|(mut todos, mut goals)| {
+        todos.sort_by_key(|todo| todo.id);
+        todos.dedup_by_key(|todo| todo.id);
+        goals.sort_by_key(|goal| goal.id);
+        goals.dedup_by_key(|goal| goal.id);
+        let mut todo_list = TodoList::from_todos(todos);
+        todo_list.next_goal_id = goals.last().map_or(1, |goal| goal.id + 1);
+        todo_list.goals = goals;
+        todo_list
+    })
+}
+
+impl Arbitrary for Todo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Todo>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        todo().boxed()
+    }
+}
+
+impl Arbitrary for Goal {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Goal>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        goal().boxed()
+    }
+}
+
+// Lists of up to 30 todos; todo_list picks another size.
+impl Arbitrary for TodoList {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<TodoList>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        todo_list(30).boxed()
+    }
+}
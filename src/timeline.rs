@@ -0,0 +1,90 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use crate::todo::Todo;
+
+const LABEL_WIDTH: usize = 28;
+
+// A pending todo's stretch from its scheduled to its due date; a todo with
+// only one of the two takes up that one day.
+pub fn span(todo: &Todo) -> Option<(NaiveDate, NaiveDate)> {
+    match (todo.scheduled, todo.due) {
+        (Some(a), Some(b)) => Some((a.min(b), a.max(b))),
+        (day, other) => day.or(other).map(|day| (day, day)),
+    }
+}
+
+// Pending todos whose span overlaps the `days` days from today, earliest
+// first.
+pub fn entries<'a>(todos: impl IntoIterator<Item = &'a Todo>, today: NaiveDate, days: usize) -> Vec<(&'a Todo, NaiveDate, NaiveDate)> {
+    let last = today + Duration::days(days as i64 - 1);
+    let mut entries: Vec<(&Todo, NaiveDate, NaiveDate)> = todos
+        .into_iter()
+        .filter(|todo| !todo.completed && !todo.habit)
+        .filter_map(|todo| span(todo).map(|(start, end)| (todo, start, end)))
+        .filter(|&(_, start, end)| end >= today && start <= last)
+        .collect();
+    entries.sort_by_key(|&(todo, start, end)| (start, end, todo.id));
+    entries
+}
+
+// How many of the entries are running on each of the days.
+pub fn load(entries: &[(&Todo, NaiveDate, NaiveDate)], today: NaiveDate, days: usize) -> Vec<usize> {
+    (0..days)
+        .map(|d| today + Duration::days(d as i64))
+        .map(|day| entries.iter().filter(|&&(_, start, end)| start <= day && day <= end).count())
+        .collect()
+}
+
+// One row per todo with a column per day: '=' while it runs, '|' on its due
+// day, and '<' or '>' where it continues past the edge of the chart. The
+// last row counts the todos running on each day.
+pub fn render(entries: &[(&Todo, NaiveDate, NaiveDate)], today: NaiveDate, days: usize) -> String {
+    let mut dates = String::new();
+    let mut weekdays = String::new();
+    for d in 0..days {
+        let day = today + Duration::days(d as i64);
+        if d % 7 == 0 {
+            dates.push_str(&format!("{:<7}", day.format("%m-%d")));
+        }
+        weekdays.push(day.weekday().to_string().chars().next().unwrap_or(' '));
+    }
+    let mut out = format!("{:w$}{}\n{:w$}{}\n", "", dates.trim_end(), "", weekdays, w = LABEL_WIDTH);
+
+    for &(todo, start, end) in entries {
+        let label = format!("#{} {}", todo.id, todo.title);
+        let label: String = if label.chars().count() > LABEL_WIDTH - 2 {
+            format!("{}...", label.chars().take(LABEL_WIDTH - 5).collect::<String>())
+        } else {
+            label
+        };
+        out.push_str(&format!("{:<w$}", label, w = LABEL_WIDTH));
+        for d in 0..days {
+            let day = today + Duration::days(d as i64);
+            let cell = if day < start || day > end {
+                ' '
+            } else if d == 0 && start < today {
+                '<'
+            } else if d == days - 1 && end > day {
+                '>'
+            } else if todo.due == Some(day) {
+                '|'
+            } else {
+                '='
+            };
+            out.push(cell);
+        }
+        let trimmed = out.trim_end_matches(' ').len();
+        out.truncate(trimmed);
+        out.push('\n');
+    }
+
+    out.push_str(&format!("{:<w$}", "todos per day", w = LABEL_WIDTH));
+    for count in load(entries, today, days) {
+        out.push(match count {
+            0 => '.',
+            1..=9 => char::from_digit(count as u32, 10).unwrap_or('+'),
+            _ => '+',
+        });
+    }
+    out.push('\n');
+    out
+}
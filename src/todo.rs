@@ -0,0 +1,1260 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{DateTime, Local, NaiveDate};
+use crate::diff;
+use crate::history::{self, Event, EventKind};
+use crate::priority::Priority;
+use crate::rrule::Recurrence;
+use crate::storage;
+use crate::tags::{Tag, TagTable};
+use crate::validate::{clean_line, validate, Limits, ValidationError};
+use crate::wal;
+use crate::workdays::Calendar;
+use crate::zones::Deadline;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Todo {
+    pub id: usize,
+    pub title: String,
+    pub description: String,
+    pub completed: bool,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+    // Todos this one refers to, without waiting for them. The link is kept
+    // on one side only but shows on both.
+    #[serde(default)]
+    pub related: Vec<usize>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    // Set when the todo is due at a time in a time zone, "9:00 in
+    // Europe/Berlin", rather than on a day wherever it is looked at; `due`
+    // is then its day in that zone.
+    #[serde(default)]
+    pub deadline: Option<Deadline>,
+    // How often the due date was moved later with `postpone` or `today`.
+    #[serde(default)]
+    pub postponed: u32,
+    // The day work on the todo is planned to start, as opposed to the day
+    // it has to be finished by.
+    #[serde(default)]
+    pub scheduled: Option<NaiveDate>,
+    // Habits recur every day; instead of a completed flag they keep the
+    // days on which they were done.
+    #[serde(default)]
+    pub habit: bool,
+    #[serde(default)]
+    pub habit_log: BTreeSet<NaiveDate>,
+    #[serde(default)]
+    pub goal: Option<usize>,
+    #[serde(default)]
+    pub points: Option<u32>,
+    // Minutes the todo is expected to take, and the minutes logged on it.
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    #[serde(default)]
+    pub spent: u32,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    // Important regardless of priority, for the Eisenhower matrix.
+    #[serde(default)]
+    pub important: bool,
+    #[serde(default)]
+    pub project: Option<String>,
+    // Where the todo can be done, e.g. "hardware store".
+    #[serde(default)]
+    pub location: Option<String>,
+    // Percent complete, 0 to 100, for todos that are done bit by bit.
+    #[serde(default)]
+    pub progress: Option<u8>,
+    // Kept out of the default lists until this day.
+    #[serde(default)]
+    pub wait_until: Option<NaiveDate>,
+    // Completing a recurring todo moves it to its next occurrence instead.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    // A completed todo that was given up on rather than done.
+    #[serde(default)]
+    pub cancelled: bool,
+    // Why it was completed or cancelled, e.g. "fixed in PR #42".
+    #[serde(default)]
+    pub note: Option<String>,
+    // The todo this one was split off from.
+    #[serde(default)]
+    pub parent: Option<usize>,
+    // When each field was last changed, so that edits made on different
+    // devices can be merged field by field. Fields never changed since
+    // creation are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_times: BTreeMap<String, DateTime<Local>>,
+    // Added without a project, a priority or a due date and not given one
+    // since; see `triage`.
+    #[serde(default)]
+    pub inbox: bool,
+    // Handed off to someone else, on the day given, with a day to check on
+    // how it is going; see `waiting-for`.
+    #[serde(default)]
+    pub delegated_to: Option<String>,
+    #[serde(default)]
+    pub delegated_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub follow_up: Option<NaiveDate>,
+    // Oldest first. Comments are only ever added, so those made on
+    // different devices are all kept when syncing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+}
+
+impl Todo {
+    // Applies `change` as one edit made at `now`, stamping the fields it
+    // changed.
+    pub(crate) fn edit<R>(&mut self, now: DateTime<Local>, change: impl FnOnce(&mut Todo) -> R) -> R {
+        let before = self.clone();
+        let result = change(self);
+        if self.inbox && self.is_sorted() {
+            self.inbox = false;
+        }
+        self.updated_at = now;
+        for (field, _, _) in diff::field_changes(&before, self) {
+            self.field_times.insert(field, now);
+        }
+        result
+    }
+
+    fn is_sorted(&self) -> bool {
+        self.project.is_some() || self.priority.is_some() || self.due.is_some()
+    }
+
+    // Still waiting to be triaged.
+    pub fn in_inbox(&self) -> bool {
+        self.inbox && !self.completed
+    }
+
+    // Pending and in someone else's hands.
+    pub fn is_delegated(&self) -> bool {
+        self.delegated_to.is_some() && !self.completed
+    }
+
+    pub fn follow_up_due(&self, today: NaiveDate) -> bool {
+        self.is_delegated() && self.follow_up.is_some_and(|day| day <= today)
+    }
+
+    // When `field` was last changed.
+    pub fn field_time(&self, field: &str) -> DateTime<Local> {
+        self.field_times.get(field).copied().unwrap_or(self.created_at)
+    }
+
+    pub fn done_on(&self, day: NaiveDate) -> bool {
+        self.habit_log.contains(&day)
+    }
+
+    // Whichever of the scheduled and the due date comes first.
+    pub fn next_date(&self) -> Option<NaiveDate> {
+        match (self.scheduled, self.due) {
+            (Some(scheduled), Some(due)) => Some(scheduled.min(due)),
+            (scheduled, due) => scheduled.or(due),
+        }
+    }
+
+    pub fn next_occurrence(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let recurrence = self.recurrence.as_ref()?;
+        let after = self.due.map_or(today, |due| due.max(today));
+        recurrence.rule.next_after(recurrence.start, after)
+    }
+
+    pub fn is_waiting(&self, today: NaiveDate) -> bool {
+        !self.completed && self.wait_until.is_some_and(|day| day > today)
+    }
+
+    // Todos saved before completion times were recorded fall back to the
+    // last update.
+    pub fn completed_on(&self) -> Option<DateTime<Local>> {
+        if !self.completed {
+            return None;
+        }
+        Some(self.completed_at.unwrap_or(self.updated_at))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Goal {
+    pub id: usize,
+    pub title: String,
+    pub created_at: DateTime<Local>,
+}
+
+// Comments are numbered from 1 in the order they were made. A reply refers
+// to the time of the comment it answers, which unlike its number stays the
+// same when comments from another device are merged in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Comment {
+    pub author: String,
+    pub at: DateTime<Local>,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<DateTime<Local>>,
+}
+
+// What is left of a deleted todo, so that a copy still around elsewhere is
+// not brought back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Tombstone {
+    pub id: usize,
+    pub created_at: DateTime<Local>,
+    pub deleted_at: DateTime<Local>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DependencyError {
+    NotFound(usize),
+    SelfReference,
+    Cycle,
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DependencyError::NotFound(id) => write!(f, "Todo with ID {} not found.", id),
+            DependencyError::SelfReference => write!(f, "A todo cannot depend on itself."),
+            DependencyError::Cycle => write!(f, "That dependency would create a cycle."),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TodoList {
+    pub(crate) todos: Vec<Todo>,
+    pub(crate) next_id: usize,
+    #[serde(default)]
+    pub(crate) goals: Vec<Goal>,
+    #[serde(default = "first_id")]
+    pub(crate) next_goal_id: usize,
+    #[serde(default)]
+    pub(crate) tombstones: Vec<Tombstone>,
+    #[serde(skip)]
+    index: HashMap<usize, usize>,
+    #[serde(skip)]
+    tag_table: TagTable,
+    #[serde(skip)]
+    limits: Limits,
+    // Occurrences of recurring todos on a day off move to the next workday
+    // of this calendar; on no calendar they stay where the rule puts them.
+    #[serde(skip)]
+    days_off: Option<Calendar>,
+    // Changes not yet appended to the history file; written by save_to_file.
+    #[serde(skip)]
+    journal: Mutex<Vec<Event>>,
+}
+
+// Held while a list is being saved.
+static SAVING: Mutex<()> = Mutex::new(());
+
+fn first_id() -> usize {
+    1
+}
+
+impl Default for TodoList {
+    fn default() -> Self {
+        TodoList::new()
+    }
+}
+
+impl TodoList {
+    pub fn new() -> Self {
+        TodoList {
+            todos: Vec::new(),
+            next_id: 1,
+            goals: Vec::new(),
+            next_goal_id: 1,
+            tombstones: Vec::new(),
+            index: HashMap::new(),
+            tag_table: TagTable::default(),
+            limits: Limits::default(),
+            days_off: None,
+            journal: Mutex::new(Vec::new()),
+        }
+    }
+
+    // A list holding `todos` as they are, e.g. salvaged from a damaged file.
+    pub fn from_todos(todos: Vec<Todo>) -> Self {
+        let mut todo_list = TodoList::new();
+        todo_list.next_id = todos.iter().map(|todo| todo.id).max().unwrap_or(0) + 1;
+        todo_list.todos = todos;
+        todo_list.rebuild_index(0);
+        todo_list.intern_tags();
+        todo_list
+    }
+
+    fn record(&mut self, kind: EventKind, todo_id: usize, title: &str, detail: Option<String>) {
+        let event = Event { at: Local::now(), todo_id, title: title.to_string(), kind, detail, by: history::author() };
+        self.journal.get_mut().expect("journal lock poisoned").push(event);
+    }
+
+    // Applies a change to one field of a todo, bumping updated_at and
+    // recording it in the history.
+    fn update(&mut self, id: usize, field: &str, change: impl FnOnce(&mut Todo)) -> bool {
+        let Some(todo) = self.get_todo_mut(id) else {
+            return false;
+        };
+        todo.edit(Local::now(), change);
+        let title = todo.title.clone();
+        self.record(EventKind::Edited, id, &title, Some(field.to_string()));
+        true
+    }
+
+    fn rebuild_index(&mut self, from: usize) {
+        for (pos, todo) in self.todos.iter().enumerate().skip(from) {
+            self.index.insert(todo.id, pos);
+        }
+    }
+
+    // For code changing `todos` directly.
+    pub(crate) fn reindex(&mut self) {
+        self.index.clear();
+        self.rebuild_index(0);
+    }
+
+    // Makes todos carrying the same tag share a single allocation.
+    fn intern_tags(&mut self) {
+        for todo in &mut self.todos {
+            for tag in &mut todo.tags {
+                *tag = self.tag_table.intern(tag);
+            }
+        }
+    }
+
+    // Bounds for titles and descriptions given to add_todo and edit_todo.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_days_off(&mut self, calendar: Option<Calendar>) {
+        self.days_off = calendar;
+    }
+
+    pub fn add_todo(&mut self, title: String, description: String) -> Result<usize, ValidationError> {
+        self.add_todo_with(title, description, |_| {})
+    }
+
+    // Like add_todo, with the other fields set by `fill` before the todo is
+    // stored, so the history records a single creation.
+    pub fn add_todo_with(&mut self, title: String, description: String, fill: impl FnOnce(&mut Todo)) -> Result<usize, ValidationError> {
+        let (title, description) = validate(&title, &description, &self.limits)?;
+        let now = Local::now();
+        let mut todo = Todo {
+            id: self.next_id,
+            title,
+            description,
+            completed: false,
+            created_at: now,
+            updated_at: now,
+            tags: Vec::new(),
+            completed_at: None,
+            depends_on: Vec::new(),
+            related: Vec::new(),
+            due: None,
+            deadline: None,
+            postponed: 0,
+            habit: false,
+            habit_log: BTreeSet::new(),
+            goal: None,
+            points: None,
+            estimate: None,
+            spent: 0,
+            important: false,
+            priority: None,
+            project: None,
+            location: None,
+            progress: None,
+            wait_until: None,
+            scheduled: None,
+            recurrence: None,
+            cancelled: false,
+            note: None,
+            parent: None,
+            field_times: BTreeMap::new(),
+            inbox: false,
+            delegated_to: None,
+            delegated_on: None,
+            follow_up: None,
+            comments: Vec::new(),
+        };
+        fill(&mut todo);
+        todo.inbox = !todo.is_sorted();
+        for tag in &mut todo.tags {
+            *tag = self.tag_table.intern(tag);
+        }
+        let (id, title) = (todo.id, todo.title.clone());
+        self.index.insert(id, self.todos.len());
+        self.todos.push(todo);
+        self.next_id += 1;
+        self.record(EventKind::Created, id, &title, None);
+        Ok(id)
+    }
+
+    // Adds a todo coming from another list under a fresh id.
+    pub fn insert_todo(&mut self, mut todo: Todo) -> usize {
+        todo.id = self.next_id;
+        for tag in &mut todo.tags {
+            *tag = self.tag_table.intern(tag);
+        }
+        let (id, title) = (todo.id, todo.title.clone());
+        self.index.insert(id, self.todos.len());
+        self.todos.push(todo);
+        self.next_id += 1;
+        self.record(EventKind::Created, id, &title, Some("imported".to_string()));
+        id
+    }
+
+    // Replaces the todo with the same id as `todo` wholesale.
+    pub fn replace_todo(&mut self, mut todo: Todo) -> bool {
+        let Some(&pos) = self.index.get(&todo.id) else {
+            return false;
+        };
+        for tag in &mut todo.tags {
+            *tag = self.tag_table.intern(tag);
+        }
+        let (id, title) = (todo.id, todo.title.clone());
+        self.todos[pos] = todo;
+        self.record(EventKind::Edited, id, &title, Some("imported".to_string()));
+        true
+    }
+
+    // Stores `todo` under its own id, replacing the todo there if any.
+    pub(crate) fn put_todo(&mut self, mut todo: Todo, detail: &str) {
+        for tag in &mut todo.tags {
+            *tag = self.tag_table.intern(tag);
+        }
+        let (id, title) = (todo.id, todo.title.clone());
+        self.next_id = self.next_id.max(id + 1);
+        let kind = match self.index.get(&id) {
+            Some(&pos) => {
+                self.todos[pos] = todo;
+                EventKind::Edited
+            },
+            None => {
+                self.index.insert(id, self.todos.len());
+                self.todos.push(todo);
+                EventKind::Created
+            },
+        };
+        self.record(kind, id, &title, Some(detail.to_string()));
+    }
+
+    // Moves a todo to the next free id, along with the references to it.
+    pub(crate) fn renumber_todo(&mut self, id: usize) -> Option<usize> {
+        let new_id = self.next_id;
+        let pos = self.index.remove(&id)?;
+        self.todos[pos].id = new_id;
+        self.index.insert(new_id, pos);
+        self.next_id += 1;
+        let rename = |other: &mut usize| {
+            if *other == id {
+                *other = new_id;
+            }
+        };
+        for todo in &mut self.todos {
+            todo.depends_on.iter_mut().for_each(rename);
+            todo.related.iter_mut().for_each(rename);
+            todo.parent.iter_mut().for_each(rename);
+        }
+        let title = self.todos[pos].title.clone();
+        self.record(EventKind::Edited, new_id, &title, Some(format!("id, was {}", id)));
+        Some(new_id)
+    }
+
+    // Whether the todo created at `created_at` under `id` was deleted here.
+    pub fn is_deleted(&self, id: usize, created_at: DateTime<Local>) -> bool {
+        self.tombstones.iter().any(|tombstone| tombstone.id == id && tombstone.created_at == created_at)
+    }
+
+    pub fn todos(&self) -> &[Todo] {
+        &self.todos
+    }
+
+    pub fn get_todo(&self, id: usize) -> Option<&Todo> {
+        let pos = *self.index.get(&id)?;
+        Some(&self.todos[pos])
+    }
+
+    pub(crate) fn get_todo_mut(&mut self, id: usize) -> Option<&mut Todo> {
+        let pos = *self.index.get(&id)?;
+        Some(&mut self.todos[pos])
+    }
+
+    // Ok(false) if there is no todo with this id.
+    pub fn edit_todo(&mut self, id: usize, title: String, description: String) -> Result<bool, ValidationError> {
+        let (title, description) = validate(&title, &description, &self.limits)?;
+        Ok(self.update(id, "title, description", |todo| {
+            todo.title = title;
+            todo.description = description;
+        }))
+    }
+
+    pub fn set_tags(&mut self, id: usize, names: &[String]) -> bool {
+        if self.get_todo(id).is_none() {
+            return false;
+        }
+        let tags = names.iter().map(|name| self.tag_table.intern(name)).collect();
+        self.update(id, "tags", |todo| todo.tags = tags)
+    }
+
+    pub fn add_tag(&mut self, id: usize, name: &str) -> bool {
+        if self.get_todo(id).is_none() {
+            return false;
+        }
+        let tag = self.tag_table.intern(name);
+        self.update(id, "tags", |todo| {
+            if !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        })
+    }
+
+    pub fn remove_tag(&mut self, id: usize, name: &str) -> bool {
+        self.update(id, "tags", |todo| todo.tags.retain(|tag| tag.as_str() != name))
+    }
+
+    // Every tag in use with the number of todos carrying it, most used first.
+    pub fn tag_counts(&self) -> Vec<(Tag, usize)> {
+        let mut counts: HashMap<Tag, usize> = HashMap::new();
+        for tag in self.todos.iter().flat_map(|todo| &todo.tags) {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+        let mut counts: Vec<(Tag, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.todos.iter().any(|todo| todo.tags.iter().any(|tag| tag.as_str() == name))
+    }
+
+    // Replaces tag `from` with `into` on every todo, keeping each todo's tags
+    // distinct. Used both for renaming and for merging two tags. Returns the
+    // number of todos changed.
+    pub fn replace_tag(&mut self, from: &str, into: &str) -> usize {
+        let into = self.tag_table.intern(into);
+        let affected: Vec<usize> = self
+            .todos
+            .iter()
+            .filter(|todo| todo.tags.iter().any(|tag| tag.as_str() == from))
+            .map(|todo| todo.id)
+            .collect();
+        for &id in &affected {
+            self.update(id, "tags", |todo| {
+                let pos = todo.tags.iter().position(|tag| tag.as_str() == from).expect("filtered above");
+                if todo.tags.contains(&into) {
+                    todo.tags.remove(pos);
+                } else {
+                    todo.tags[pos] = into.clone();
+                }
+            });
+        }
+        affected.len()
+    }
+
+    pub fn set_points(&mut self, id: usize, points: Option<u32>) -> bool {
+        self.update(id, "points", |todo| todo.points = points)
+    }
+
+    pub fn set_estimate(&mut self, id: usize, minutes: Option<u32>) -> bool {
+        self.update(id, "estimate", |todo| todo.estimate = minutes)
+    }
+
+    pub fn add_spent(&mut self, id: usize, minutes: u32) -> bool {
+        self.update(id, "spent", |todo| todo.spent = todo.spent.saturating_add(minutes))
+    }
+
+    pub fn set_priority(&mut self, id: usize, priority: Option<Priority>) -> bool {
+        self.update(id, "priority", |todo| todo.priority = priority)
+    }
+
+    // A due date alone is a day wherever the todo is looked at, so it
+    // replaces a deadline.
+    pub fn set_due(&mut self, id: usize, due: Option<NaiveDate>) -> bool {
+        self.update(id, "due", |todo| {
+            todo.due = due;
+            todo.deadline = None;
+        })
+    }
+
+    pub fn set_deadline(&mut self, id: usize, deadline: Deadline) -> bool {
+        self.update(id, "due", |todo| {
+            todo.due = Some(deadline.at.date_naive());
+            todo.deadline = Some(deadline);
+        })
+    }
+
+    // Sets the due date, counting a move to a later day as a postponement.
+    // A deadline keeps its time of day.
+    pub fn postpone(&mut self, id: usize, due: NaiveDate) -> bool {
+        self.update(id, "due", |todo| {
+            if todo.due.is_some_and(|old| due > old) {
+                todo.postponed += 1;
+            }
+            todo.due = Some(due);
+            todo.deadline = todo.deadline.take().map(|deadline| deadline.on(due));
+        })
+    }
+
+    pub fn set_scheduled(&mut self, id: usize, day: Option<NaiveDate>) -> bool {
+        self.update(id, "scheduled", |todo| todo.scheduled = day)
+    }
+
+    // Also moves the due date to the first occurrence of the rule.
+    pub fn set_recurrence(&mut self, id: usize, recurrence: Option<Recurrence>) -> bool {
+        let first = recurrence.as_ref().and_then(|r| {
+            let first = r.rule.occurrences(r.start).next()?;
+            Some(self.days_off.as_ref().map_or(first, |calendar| calendar.move_occurrence(first, &r.rule)))
+        });
+        self.update(id, "recurrence", |todo| {
+            if let Some(first) = first {
+                todo.due = Some(first);
+                todo.deadline = todo.deadline.take().map(|deadline| deadline.on(first));
+            }
+            todo.recurrence = recurrence;
+        })
+    }
+
+    pub fn set_wait_until(&mut self, id: usize, day: Option<NaiveDate>) -> bool {
+        self.update(id, "wait until", |todo| todo.wait_until = day)
+    }
+
+    // Values over 100 are capped.
+    pub fn set_progress(&mut self, id: usize, progress: Option<u8>) -> bool {
+        self.update(id, "progress", |todo| todo.progress = progress.map(|p| p.min(100)))
+    }
+
+    pub fn set_project(&mut self, id: usize, project: Option<String>) -> bool {
+        self.update(id, "project", |todo| todo.project = project)
+    }
+
+    pub fn set_location(&mut self, id: usize, location: Option<String>) -> bool {
+        self.update(id, "location", |todo| todo.location = location)
+    }
+
+    // Delegating to someone new starts the clock again; taking the todo
+    // back drops the follow-up too.
+    pub fn set_delegated_to(&mut self, id: usize, person: Option<String>, today: NaiveDate) -> bool {
+        self.update(id, "delegated to", |todo| {
+            if person.is_none() {
+                todo.delegated_on = None;
+                todo.follow_up = None;
+            } else if todo.delegated_to != person {
+                todo.delegated_on = Some(today);
+            }
+            todo.delegated_to = person;
+        })
+    }
+
+    pub fn set_follow_up(&mut self, id: usize, day: Option<NaiveDate>) -> bool {
+        self.update(id, "follow up", |todo| todo.follow_up = day)
+    }
+
+    // Adds a comment, optionally replying to comment number `reply_to`, and
+    // returns its number. None if the todo or the comment replied to do not
+    // exist.
+    pub fn add_comment(&mut self, id: usize, author: String, text: String, reply_to: Option<usize>) -> Option<usize> {
+        let todo = self.get_todo(id)?;
+        let reply_to = match reply_to {
+            Some(number) => Some(todo.comments.get(number.checked_sub(1)?)?.at),
+            None => None,
+        };
+        let mut number = 0;
+        self.update(id, "comments", |todo| {
+            todo.comments.push(Comment { author, at: Local::now(), text, reply_to });
+            number = todo.comments.len();
+        });
+        Some(number)
+    }
+
+    pub fn delete_todo(&mut self, id: usize) -> bool {
+        if let Some(pos) = self.index.remove(&id) {
+            let removed = self.todos.remove(pos);
+            self.rebuild_index(pos);
+            let now = Local::now();
+            self.tombstones.push(Tombstone { id, created_at: removed.created_at, deleted_at: now });
+            // Dropping the links is an edit like any other, so sync sees it as
+            // newer than the links another device still holds.
+            for todo in &mut self.todos {
+                if todo.depends_on.contains(&id) || todo.related.contains(&id) || todo.parent == Some(id) {
+                    todo.edit(now, |todo| {
+                        todo.depends_on.retain(|&dep| dep != id);
+                        todo.related.retain(|&other| other != id);
+                        if todo.parent == Some(id) {
+                            todo.parent = None;
+                        }
+                    });
+                }
+            }
+            self.record(EventKind::Deleted, id, &removed.title, None);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn add_dependency(&mut self, id: usize, on: usize) -> Result<(), DependencyError> {
+        if id == on {
+            return Err(DependencyError::SelfReference);
+        }
+        for check in [id, on] {
+            if self.get_todo(check).is_none() {
+                return Err(DependencyError::NotFound(check));
+            }
+        }
+        if self.depends_transitively(on, id) {
+            return Err(DependencyError::Cycle);
+        }
+
+        if !self.get_todo(id).is_some_and(|todo| todo.depends_on.contains(&on)) {
+            self.update(id, "dependencies", |todo| todo.depends_on.push(on));
+        }
+        Ok(())
+    }
+
+    // Adds a fresh copy of a todo, optionally under a new title: pending, with
+    // new timestamps and without its progress, note, habit log,
+    // dependencies or parent. Ok(None) if there is no such todo.
+    pub fn clone_todo(&mut self, id: usize, title: Option<String>) -> Result<Option<usize>, ValidationError> {
+        let Some(original) = self.get_todo(id).cloned() else {
+            return Ok(None);
+        };
+        let title = title.unwrap_or_else(|| original.title.clone());
+        let id = self.add_todo_with(title, original.description.clone(), |todo| {
+            todo.tags = original.tags;
+            todo.due = original.due;
+            todo.deadline = original.deadline;
+            todo.scheduled = original.scheduled;
+            todo.wait_until = original.wait_until;
+            todo.habit = original.habit;
+            todo.goal = original.goal;
+            todo.points = original.points;
+            todo.estimate = original.estimate;
+            todo.priority = original.priority;
+            todo.important = original.important;
+            todo.project = original.project;
+            todo.location = original.location;
+            todo.recurrence = original.recurrence;
+        })?;
+        Ok(Some(id))
+    }
+
+    // Adds a subtask for each title, carrying over the parent's tags,
+    // priority, project, goal and dates, and makes the parent depend on all
+    // of them. Nothing is added if any title is invalid.
+    pub fn split(&mut self, id: usize, titles: &[String]) -> Result<Vec<usize>, ValidationError> {
+        for title in titles {
+            validate(title, "", &self.limits)?;
+        }
+        let Some(parent) = self.get_todo(id).cloned() else {
+            return Ok(Vec::new());
+        };
+        let mut children = Vec::new();
+        for title in titles {
+            let child = self.add_todo_with(title.clone(), String::new(), |todo| {
+                todo.tags = parent.tags.clone();
+                todo.priority = parent.priority;
+                todo.project = parent.project.clone();
+                todo.location = parent.location.clone();
+                todo.goal = parent.goal;
+                todo.due = parent.due;
+                todo.deadline = parent.deadline.clone();
+                todo.scheduled = parent.scheduled;
+                todo.wait_until = parent.wait_until;
+                todo.parent = Some(id);
+            })?;
+            children.push(child);
+        }
+        self.update(id, "dependencies", |todo| todo.depends_on.extend(&children));
+        Ok(children)
+    }
+
+    // False if either todo does not exist or they are the same.
+    pub fn relate(&mut self, id: usize, other: usize) -> bool {
+        if id == other || self.get_todo(other).is_none() {
+            return false;
+        }
+        if self.related(id).contains(&other) {
+            return self.get_todo(id).is_some();
+        }
+        self.update(id, "related", |todo| todo.related.push(other))
+    }
+
+    // Removes the link whichever of the two it was made from.
+    pub fn unrelate(&mut self, id: usize, other: usize) -> bool {
+        for (from, to) in [(id, other), (other, id)] {
+            if self.get_todo(from).is_some_and(|todo| todo.related.contains(&to)) {
+                return self.update(from, "related", |todo| todo.related.retain(|&r| r != to));
+            }
+        }
+        false
+    }
+
+    // Every todo linked to this one, in either direction.
+    pub fn related(&self, id: usize) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.get_todo(id).map(|todo| todo.related.clone()).unwrap_or_default();
+        ids.extend(self.todos.iter().filter(|todo| todo.related.contains(&id)).map(|todo| todo.id));
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn remove_dependency(&mut self, id: usize, on: usize) -> bool {
+        if !self.get_todo(id).is_some_and(|todo| todo.depends_on.contains(&on)) {
+            return false;
+        }
+        self.update(id, "dependencies", |todo| todo.depends_on.retain(|&dep| dep != on))
+    }
+
+    pub fn depends_transitively(&self, id: usize, on: usize) -> bool {
+        let mut stack = vec![id];
+        let mut seen = Vec::new();
+        while let Some(current) = stack.pop() {
+            if current == on {
+                return true;
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            if let Some(todo) = self.get_todo(current) {
+                stack.extend(&todo.depends_on);
+            }
+        }
+        false
+    }
+
+    // A pending todo is blocked while any of its dependencies is unfinished.
+    pub fn is_blocked(&self, todo: &Todo) -> bool {
+        !todo.completed
+            && todo
+                .depends_on
+                .iter()
+                .any(|&dep| self.get_todo(dep).is_some_and(|dep| !dep.completed))
+    }
+
+    // Something that can be worked on right now: pending, not a habit, not
+    // waiting and not blocked.
+    pub fn is_actionable(&self, todo: &Todo, today: NaiveDate) -> bool {
+        !todo.completed && !todo.habit && !todo.is_waiting(today) && !self.is_blocked(todo)
+    }
+
+    pub fn set_important(&mut self, id: usize, important: bool) -> bool {
+        self.update(id, "important", |todo| todo.important = important)
+    }
+
+    pub fn set_habit(&mut self, id: usize, habit: bool) -> bool {
+        self.update(id, "habit", |todo| {
+            todo.habit = habit;
+            todo.completed = false;
+            todo.completed_at = None;
+        })
+    }
+
+    pub fn complete_todo(&mut self, id: usize) -> bool {
+        self.complete_with_note(id, None)
+    }
+
+    pub fn complete_with_note(&mut self, id: usize, note: Option<String>) -> bool {
+        match self.get_todo(id) {
+            Some(todo) if todo.habit && todo.done_on(Local::now().date_naive()) => true,
+            Some(todo) if !todo.habit && todo.completed => true,
+            Some(_) => self.toggle_with_note(id, note),
+            None => false,
+        }
+    }
+
+    pub fn toggle_completed(&mut self, id: usize) -> bool {
+        self.toggle_with_note(id, None)
+    }
+
+    // For habits this toggles whether the habit was kept today. A pending
+    // recurring todo moves on to its next occurrence after today (or after
+    // its due date, if that is later) and stays pending, with its progress
+    // reset; it is only really completed once the rule has no occurrences
+    // left. The note goes into the history when completing, and is kept on
+    // the todo if it ends up completed; reopening drops it.
+    pub fn toggle_with_note(&mut self, id: usize, note: Option<String>) -> bool {
+        let note = note.map(|note| clean_line(&note)).filter(|note| !note.is_empty());
+        let days_off = self.days_off.clone();
+        let Some(todo) = self.get_todo_mut(id) else {
+            return false;
+        };
+        let now = Local::now();
+        let done = todo.edit(now, |todo| {
+            if todo.habit {
+                let today = now.date_naive();
+                if !todo.habit_log.remove(&today) {
+                    todo.habit_log.insert(today);
+                }
+                todo.done_on(today)
+            } else if let Some(next) = todo.next_occurrence(now.date_naive()).filter(|_| !todo.completed) {
+                let next = match (&days_off, &todo.recurrence) {
+                    (Some(calendar), Some(recurrence)) => calendar.move_occurrence(next, &recurrence.rule),
+                    _ => next,
+                };
+                // The scheduled date keeps its distance to the due date.
+                if let (Some(scheduled), Some(due)) = (todo.scheduled, todo.due) {
+                    todo.scheduled = Some(next - (due - scheduled));
+                }
+                todo.due = Some(next);
+                todo.deadline = todo.deadline.take().map(|deadline| deadline.on(next));
+                todo.progress = None;
+                true
+            } else {
+                todo.completed = !todo.completed;
+                todo.completed_at = if todo.completed { Some(now) } else { None };
+                todo.cancelled = false;
+                todo.note = if todo.completed { note.clone() } else { None };
+                todo.completed
+            }
+        });
+        let title = todo.title.clone();
+        let (kind, note) = if done { (EventKind::Completed, note) } else { (EventKind::Reopened, None) };
+        self.record(kind, id, &title, note);
+        true
+    }
+
+    // Marks a todo as looked at now without changing anything else, which also
+    // restarts its priority aging.
+    pub fn touch_todo(&mut self, id: usize) -> bool {
+        let Some(todo) = self.get_todo_mut(id) else {
+            return false;
+        };
+        todo.updated_at = Local::now();
+        let title = todo.title.clone();
+        self.record(EventKind::Touched, id, &title, None);
+        true
+    }
+
+    // Closes a todo without it having been done. Recurring todos and habits
+    // stop for good.
+    pub fn cancel_todo(&mut self, id: usize, note: Option<String>) -> bool {
+        let note = note.map(|note| clean_line(&note)).filter(|note| !note.is_empty());
+        let Some(todo) = self.get_todo_mut(id) else {
+            return false;
+        };
+        let now = Local::now();
+        todo.edit(now, |todo| {
+            todo.habit = false;
+            todo.completed = true;
+            todo.completed_at = Some(now);
+            todo.cancelled = true;
+            todo.note = note.clone();
+        });
+        let title = todo.title.clone();
+        self.record(EventKind::Cancelled, id, &title, note);
+        true
+    }
+
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    pub fn get_goal(&self, id: usize) -> Option<&Goal> {
+        self.goals.iter().find(|goal| goal.id == id)
+    }
+
+    pub fn add_goal(&mut self, title: String) -> usize {
+        let id = self.next_goal_id;
+        self.goals.push(Goal { id, title, created_at: Local::now() });
+        self.next_goal_id += 1;
+        id
+    }
+
+    pub fn delete_goal(&mut self, id: usize) -> bool {
+        let Some(pos) = self.goals.iter().position(|goal| goal.id == id) else {
+            return false;
+        };
+        self.goals.remove(pos);
+        let linked: Vec<usize> = self.todos.iter().filter(|todo| todo.goal == Some(id)).map(|todo| todo.id).collect();
+        for todo_id in linked {
+            self.update(todo_id, "goal", |todo| todo.goal = None);
+        }
+        true
+    }
+
+    pub fn set_goal(&mut self, id: usize, goal: Option<usize>) -> bool {
+        if goal.is_some_and(|goal| self.get_goal(goal).is_none()) {
+            return false;
+        }
+        self.update(id, "goal", |todo| todo.goal = goal)
+    }
+
+    // Completed and total todos linked to a goal. Habits never finish, so
+    // they are left out, as are cancelled todos.
+    pub fn goal_progress(&self, id: usize) -> (usize, usize) {
+        let linked = self.todos.iter().filter(|todo| todo.goal == Some(id) && !todo.habit && !todo.cancelled);
+        linked.fold((0, 0), |(done, total), todo| (done + usize::from(todo.completed), total + 1))
+    }
+
+    // Completed and total todos of every project, by project name. Habits
+    // and cancelled todos are left out as for goals.
+    pub fn project_progress(&self) -> BTreeMap<&str, (usize, usize)> {
+        let mut progress: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for todo in self.todos.iter().filter(|todo| !todo.habit && !todo.cancelled) {
+            if let Some(project) = &todo.project {
+                let (done, total) = progress.entry(project.as_str()).or_default();
+                *done += usize::from(todo.completed);
+                *total += 1;
+            }
+        }
+        progress
+    }
+
+    pub fn remove_completed_before(&mut self, cutoff: DateTime<Local>) -> Vec<Todo> {
+        let (removed, kept) = self
+            .todos
+            .drain(..)
+            .partition(|todo| todo.completed_on().is_some_and(|at| at < cutoff));
+        self.todos = kept;
+        self.reindex();
+        for todo in &removed {
+            self.record(EventKind::Archived, todo.id, &todo.title, None);
+        }
+        removed
+    }
+
+    #[cfg(feature = "generate")]
+    pub fn generate(&mut self, count: usize) {
+        for n in 0..count {
+            let id = self.next_id;
+            self.add_todo(
+                format!("Generated todo {}", id),
+                format!("Synthetic todo number {} for performance testing", n + 1),
+            )
+            .expect("generated todos are valid");
+            if n % 3 == 0 {
+                self.toggle_completed(id);
+            }
+        }
+    }
+
+    pub fn search(&self, query: &str) -> Vec<&Todo> {
+        let query = query.to_lowercase();
+        self.todos
+            .iter()
+            .filter(|todo| {
+                todo.title.to_lowercase().contains(&query)
+                    || todo.description.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    // The list as stored in the todo file, for keeping it somewhere other
+    // than a file, e.g. in a browser.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let mut todo_list: TodoList = serde_json::from_str(json)?;
+        todo_list.rebuild_index(0);
+        todo_list.intern_tags();
+        Ok(todo_list)
+    }
+
+    // A list read back from a store keeping its todos apart from the rest.
+    #[cfg(any(feature = "sled", feature = "postgres"))]
+    pub(crate) fn from_parts(
+        todos: Vec<Todo>,
+        next_id: usize,
+        goals: Vec<Goal>,
+        next_goal_id: usize,
+        tombstones: Vec<Tombstone>,
+    ) -> Self {
+        let mut todo_list = TodoList { todos, next_id, goals, next_goal_id, tombstones, ..TodoList::new() };
+        todo_list.rebuild_index(0);
+        todo_list.intern_tags();
+        todo_list
+    }
+
+    // Changes made since the list was loaded or last saved.
+    pub(crate) fn unsaved_events(&self) -> Vec<Event> {
+        self.journal.lock().expect("journal lock poisoned").clone()
+    }
+
+    // Saves to `filename` in the store its name selects (see storage::open).
+    // Goes through the write-ahead record (see wal), so that a crash at any
+    // point leaves either the old list or the new one, with the history to
+    // match.
+    pub fn save_to_file(&self, filename: &str) -> io::Result<()> {
+        let _saving = Self::block_saves();
+        // Nothing of a list kept in memory may end up on disk, history
+        // included.
+        if storage::is_memory(filename) {
+            storage::open(filename)?.save(self)?;
+            self.journal.lock().expect("journal lock poisoned").clear();
+            return Ok(());
+        }
+        let events = self.unsaved_events();
+        let history_len = fs::metadata(history::history_filename(filename)).map_or(0, |meta| meta.len());
+        wal::begin(filename, self, &events, history_len)?;
+        self.write_out(filename, &events, history_len)?;
+        self.journal.lock().expect("journal lock poisoned").drain(..events.len());
+        wal::finish(filename)
+    }
+
+    // The save itself, which can be repeated: the events go into the history
+    // after its first `history_len` bytes, replacing whatever an earlier try
+    // left there.
+    pub(crate) fn write_out(&self, filename: &str, events: &[Event], history_len: u64) -> io::Result<()> {
+        storage::open(filename)?.save(self)?;
+        history::append_events_after(&history::history_filename(filename), history_len, events)
+    }
+
+    // For replaying a save, whose store may want to know what changed.
+    pub(crate) fn set_unsaved_events(&mut self, events: Vec<Event>) {
+        *self.journal.get_mut().expect("journal lock poisoned") = events;
+    }
+
+    // Waits for a save in progress in any thread to finish, and keeps new
+    // ones from starting until the guard is dropped; for ending the process
+    // without cutting a save short.
+    pub fn block_saves() -> MutexGuard<'static, ()> {
+        SAVING.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn load_from_file(filename: &str) -> io::Result<Self> {
+        wal::recover(filename)?;
+        storage::open(filename)?.load()
+    }
+
+    // Writes to a temporary file first and renames it over the old one, so
+    // an interrupted save never leaves a half-written list behind.
+    pub(crate) fn write_json_file(&self, filename: &str) -> io::Result<()> {
+        let json = self.to_json()?;
+        let tmp = format!("{}.tmp", filename);
+        let mut file = File::create(&tmp)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, filename)?;
+        Ok(())
+    }
+
+    pub(crate) fn read_json_file(filename: &str) -> io::Result<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(TodoList::new());
+        }
+
+        let mut file = File::open(filename)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        Ok(TodoList::from_json(&contents)?)
+    }
+
+    // Reads only as much of the file as is needed to collect the first
+    // `limit` todos for which `keep` holds, so paging through a huge list
+    // stays cheap.
+    pub fn load_page(filename: &str, limit: usize, keep: impl Fn(&Todo) -> bool) -> io::Result<Vec<Todo>> {
+        let mut todos = Vec::new();
+        if limit > 0 {
+            Self::for_each_in_file(filename, |todo| {
+                if keep(&todo) {
+                    todos.push(todo);
+                }
+                todos.len() < limit
+            })?;
+        }
+        Ok(todos)
+    }
+
+    // Hands the todos stored in `filename` to `f` one at a time, without
+    // building the whole list. Stops early when `f` returns false.
+    pub fn for_each_in_file(filename: &str, mut f: impl FnMut(Todo) -> bool) -> io::Result<()> {
+        wal::recover(filename)?;
+        storage::open(filename)?.for_each(&mut f)
+    }
+
+    pub(crate) fn stream_json_file(filename: &str, f: impl FnMut(Todo) -> bool) -> io::Result<()> {
+        if !Path::new(filename).exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(File::open(filename)?);
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let mut stream = TodoStream { f, stopped: false };
+        match (&mut stream).deserialize(&mut deserializer) {
+            Ok(()) => Ok(()),
+            Err(_) if stream.stopped => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+struct TodoStream<F> {
+    f: F,
+    stopped: bool,
+}
+
+impl<'de, F: FnMut(Todo) -> bool> DeserializeSeed<'de> for &mut TodoStream<F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(Todo) -> bool> Visitor<'de> for &mut TodoStream<F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a todo list")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "todos" {
+                map.next_value_seed(TodoStreamItems(&mut *self))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct TodoStreamItems<'a, F>(&'a mut TodoStream<F>);
+
+impl<'de, F: FnMut(Todo) -> bool> DeserializeSeed<'de> for TodoStreamItems<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(Todo) -> bool> Visitor<'de> for TodoStreamItems<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of todos")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let stream = self.0;
+        while let Some(todo) = seq.next_element()? {
+            if !(stream.f)(todo) {
+                // Bail out instead of scanning the rest of the file;
+                // for_each_in_file recognises this case through `stopped`.
+                stream.stopped = true;
+                return Err(de::Error::custom("stopped early"));
+            }
+        }
+        Ok(())
+    }
+}
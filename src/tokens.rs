@@ -0,0 +1,185 @@
+// Tokens a sync server asks for before it answers at all, so that putting it
+// on a network does not open it to everyone there. Until the first token is
+// created the server answers every request, as before; once all are revoked
+// it answers none. Only a hash of each token is kept; the token itself is
+// shown once, when it is created.
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::crypto::{random_secret, to_hex};
+
+// What a token may be used for, one scope per endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    // Fetching changes.
+    Pull,
+    // Sending changes.
+    Push,
+    // Registering a new device.
+    Register,
+    // Listing the users.
+    Users,
+    // Reading /metrics.
+    Metrics,
+}
+
+impl Scope {
+    pub const DEFAULT: [Scope; 3] = [Scope::Pull, Scope::Push, Scope::Register];
+    pub const ALL: [Scope; 5] = [Scope::Pull, Scope::Push, Scope::Register, Scope::Users, Scope::Metrics];
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope.trim().to_lowercase().as_str() {
+            "pull" => Some(Scope::Pull),
+            "push" => Some(Scope::Push),
+            "register" => Some(Scope::Register),
+            "users" => Some(Scope::Users),
+            "metrics" => Some(Scope::Metrics),
+            _ => None,
+        }
+    }
+
+    // A comma-separated list such as "pull,push".
+    pub fn parse_list(scopes: &str) -> Option<Vec<Self>> {
+        scopes.split(',').filter(|scope| !scope.trim().is_empty()).map(Scope::parse).collect()
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Scope::Pull => "pull",
+            Scope::Push => "push",
+            Scope::Register => "register",
+            Scope::Users => "users",
+            Scope::Metrics => "metrics",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub name: String,
+    // The SHA-256 of the token, in hex.
+    hash: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Local>,
+    // The first day the token no longer works, if it expires.
+    #[serde(default)]
+    pub expires_on: Option<NaiveDate>,
+}
+
+impl Token {
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires_on.is_some_and(|day| day <= today)
+    }
+}
+
+// Why a request was turned away.
+#[derive(Debug, PartialEq)]
+pub enum Denied {
+    Missing,
+    Unknown,
+    Expired(String),
+    OutOfScope(String, Scope),
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Denied::Missing => write!(f, "a token is needed"),
+            Denied::Unknown => write!(f, "the token is not valid"),
+            Denied::Expired(name) => write!(f, "the token {} has expired", name),
+            Denied::OutOfScope(name, scope) => write!(f, "the token {} does not allow {}", name, scope),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Tokens {
+    tokens: Vec<Token>,
+    // No token was ever created.
+    #[serde(skip)]
+    open: bool,
+}
+
+// "todos.json" keeps the tokens of its sync server in "todos.tokens.json".
+pub fn tokens_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.tokens.json", stem),
+        None => format!("{}.tokens.json", filename),
+    }
+}
+
+fn hash(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+impl Tokens {
+    pub fn load(filename: &str) -> io::Result<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Tokens { tokens: Vec::new(), open: true });
+        }
+        let mut contents = String::new();
+        File::open(filename)?.read_to_string(&mut contents)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, filename: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut file = File::create(filename)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(json.as_bytes())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    // Returns the new token, or None if the name is taken.
+    pub fn create(&mut self, name: &str, scopes: Vec<Scope>, expires_on: Option<NaiveDate>) -> Option<String> {
+        if self.tokens.iter().any(|token| token.name == name) {
+            return None;
+        }
+        let secret = random_secret();
+        self.open = false;
+        self.tokens.push(Token { name: name.to_string(), hash: hash(&secret), scopes, created_at: Local::now(), expires_on });
+        Some(secret)
+    }
+
+    pub fn revoke(&mut self, name: &str) -> bool {
+        let before = self.tokens.len();
+        self.tokens.retain(|token| token.name != name);
+        self.tokens.len() < before
+    }
+
+    // Whether a request bearing `token` may do what needs `scope`; before
+    // there are tokens every request may. Returns the name of the token used.
+    pub fn check(&self, token: Option<&str>, scope: Scope, today: NaiveDate) -> Result<Option<&str>, Denied> {
+        if self.open {
+            return Ok(None);
+        }
+        let hashed = hash(token.ok_or(Denied::Missing)?);
+        let found = self.tokens.iter().find(|known| known.hash == hashed).ok_or(Denied::Unknown)?;
+        if found.is_expired(today) {
+            return Err(Denied::Expired(found.name.clone()));
+        }
+        if !found.scopes.contains(&scope) {
+            return Err(Denied::OutOfScope(found.name.clone(), scope));
+        }
+        Ok(Some(&found.name))
+    }
+}
@@ -0,0 +1,144 @@
+use std::io;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use chrono::Local;
+use ksni::blocking::TrayMethods;
+use ksni::menu::StandardItem;
+use ksni::{MenuItem, Status, ToolTip};
+use todo_app::digest::Digest;
+use todo_app::validate::Limits;
+use todo_app::TodoList;
+
+const REFRESH: Duration = Duration::from_secs(30);
+
+struct TodoTray {
+    filename: String,
+    limits: Limits,
+    due_today: Vec<String>,
+    overdue: Vec<String>,
+}
+
+impl TodoTray {
+    fn refresh(&mut self) {
+        // A file that cannot be read right now (e.g. mid-sync) keeps the
+        // previous counts until the next refresh.
+        if let Ok(todo_list) = TodoList::load_from_file(&self.filename) {
+            let digest = Digest::collect(&todo_list, Local::now().date_naive());
+            self.due_today = digest.due_today.iter().map(|t| format!("#{} {}", t.id, t.title)).collect();
+            self.overdue = digest.overdue.iter().map(|t| format!("#{} {}", t.id, t.title)).collect();
+        }
+    }
+}
+
+impl ksni::Tray for TodoTray {
+    fn id(&self) -> String {
+        env!("CARGO_PKG_NAME").into()
+    }
+
+    fn title(&self) -> String {
+        format!("Todos: {} due, {} overdue", self.due_today.len(), self.overdue.len())
+    }
+
+    fn icon_name(&self) -> String {
+        "appointment-soon".into()
+    }
+
+    fn attention_icon_name(&self) -> String {
+        "appointment-missed".into()
+    }
+
+    fn status(&self) -> Status {
+        if self.overdue.is_empty() { Status::Active } else { Status::NeedsAttention }
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        let mut lines = Vec::new();
+        lines.extend(self.overdue.iter().map(|t| format!("Overdue: {}", t)));
+        lines.extend(self.due_today.iter().map(|t| format!("Today: {}", t)));
+        ToolTip {
+            title: self.title(),
+            description: lines.join("\n"),
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        quick_add(self.filename.clone(), self.limits);
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        vec![
+            StandardItem {
+                label: "Quick add...".into(),
+                icon_name: "list-add".into(),
+                activate: Box::new(|this: &mut Self| quick_add(this.filename.clone(), this.limits)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: "Refresh".into(),
+                icon_name: "view-refresh".into(),
+                activate: Box::new(|this: &mut Self| this.refresh()),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Quit".into(),
+                icon_name: "application-exit".into(),
+                activate: Box::new(|_| std::process::exit(0)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+}
+
+// Asks for a title with zenity, off the tray's own thread so the icon stays
+// responsive while the dialog is open.
+fn quick_add(filename: String, limits: Limits) {
+    thread::spawn(move || {
+        let output = Command::new("zenity")
+            .args(["--entry", "--title=New todo", "--text=Title:"])
+            .output();
+        let title = match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("Could not open the quick-add dialog (is zenity installed?): {}", e);
+                return;
+            }
+        };
+        if title.is_empty() {
+            return;
+        }
+        let result = TodoList::load_from_file(&filename).and_then(|mut todo_list| {
+            todo_list.set_limits(limits);
+            todo_list.add_todo(title, String::new()).map_err(io::Error::other)?;
+            todo_list.save_to_file(&filename)
+        });
+        if let Err(e) = result {
+            eprintln!("Could not add the todo: {}", e);
+        }
+    });
+}
+
+// Shows the number of todos due today and overdue in the system tray until
+// quit from its menu. The list is re-read every REFRESH.
+pub fn run(filename: &str, limits: Limits) -> io::Result<()> {
+    let mut tray = TodoTray {
+        filename: filename.to_string(),
+        limits,
+        due_today: Vec::new(),
+        overdue: Vec::new(),
+    };
+    tray.refresh();
+    let handle = tray.spawn().map_err(|e| io::Error::other(format!("could not create the tray icon: {}", e)))?;
+    loop {
+        thread::sleep(REFRESH);
+        if handle.update(TodoTray::refresh).is_none() {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+use chrono::Local;
+use todo_app::config::Config;
+use todo_app::dates::parse_date;
+use todo_app::{Priority, TodoList};
+use crate::input;
+use crate::ui::{format_tags, get_input, heading};
+
+// Walks through the inbox, asking for a project, a priority and a due date
+// for each todo. Giving any of them files the todo; leaving all three empty
+// keeps it in the inbox. Every todo is saved as soon as it is filed.
+pub fn run(todo_list: &mut TodoList, filename: &str, config: &Config) -> io::Result<()> {
+    let inbox: Vec<usize> = todo_list.todos().iter().filter(|todo| todo.in_inbox()).map(|todo| todo.id).collect();
+    if inbox.is_empty() {
+        println!("The inbox is empty.");
+        return Ok(());
+    }
+
+    let mut filed = 0;
+    for (n, &id) in inbox.iter().enumerate() {
+        let Some(todo) = todo_list.get_todo(id) else {
+            continue;
+        };
+        println!();
+        heading(&format!("Triage {} of {}", n + 1, inbox.len()), config.accessible);
+        println!("#{} {}", todo.id, todo.title);
+        if !todo.description.is_empty() {
+            println!("Description: {}", todo.description);
+        }
+        if !todo.tags.is_empty() {
+            println!("Tags: {}", format_tags(todo));
+        }
+
+        let project = get_input("Project (empty for none, q to stop):");
+        // Running out of input stops like q.
+        if input::at_eof() || project == "q" {
+            println!("Triage stopped.");
+            return Ok(());
+        }
+        let priority = loop {
+            let input = get_input("Priority (low, medium, high; empty for none):");
+            if input.is_empty() {
+                break None;
+            }
+            match Priority::parse(&input) {
+                Some(priority) => break Some(priority),
+                None => println!("Priority must be low, medium or high."),
+            }
+        };
+        let due = loop {
+            let input = get_input("Due date (e.g. 2026-05-01, tomorrow, 1w, friday; empty for none):");
+            if input.is_empty() {
+                break None;
+            }
+            match parse_date(&input, Local::now().date_naive()) {
+                Some(due) => break Some(due),
+                None => println!("Could not understand that date."),
+            }
+        };
+
+        if project.is_empty() && priority.is_none() && due.is_none() {
+            println!("Left in the inbox.");
+            continue;
+        }
+        if !project.is_empty() {
+            todo_list.set_project(id, Some(project));
+        }
+        if priority.is_some() {
+            todo_list.set_priority(id, priority);
+        }
+        if due.is_some() {
+            todo_list.set_due(id, due);
+        }
+        todo_list.save_to_file(filename)?;
+        filed += 1;
+        println!("Filed.");
+    }
+
+    match inbox.len() - filed {
+        0 => println!("\nThe inbox is empty."),
+        left => println!("\nFiled {} todos; {} left in the inbox.", filed, left),
+    }
+    Ok(())
+}
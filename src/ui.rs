@@ -0,0 +1,326 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+use chrono::{DateTime, Local, NaiveDate};
+use todo_app::columns::{Column, ColumnSpec};
+use todo_app::config::Config;
+use todo_app::dates::DateFormat;
+use todo_app::estimates::format_minutes;
+use todo_app::priority::effective_priority;
+use todo_app::template::Template;
+use todo_app::{Deadline, Todo, TodoList};
+use crate::input;
+
+pub fn print_todos(todos: &[Todo], config: &Config) {
+    print_table(todos, config, &config.columns);
+}
+
+pub fn print_table(todos: &[Todo], config: &Config, columns: &[ColumnSpec]) {
+    if todos.is_empty() {
+        println!("No todos found.");
+        return;
+    }
+    if config.accessible {
+        print_lines(todos, config, columns);
+        return;
+    }
+    // Room for the status glyph and the space after it, and for relative
+    // dates as long as "11 months ago".
+    let relative = config.date_format == DateFormat::Relative;
+    let columns: Vec<ColumnSpec> = columns
+        .iter()
+        .map(|&spec| match spec.column {
+            Column::Status if config.emoji => ColumnSpec { width: spec.width + 4, ..spec },
+            Column::Due | Column::Scheduled | Column::WaitUntil | Column::FollowUp | Column::Created | Column::Updated if relative => {
+                ColumnSpec { width: spec.width + 3, ..spec }
+            },
+            _ => spec,
+        })
+        .collect();
+
+    let header: Vec<String> = columns.iter().map(|spec| pad(&spec.column.name().to_uppercase(), spec.width)).collect();
+    println!("{}", header.join(" ").trim_end());
+    let total: usize = columns.iter().map(|spec| spec.width).sum::<usize>() + columns.len().saturating_sub(1);
+    println!("{}", "-".repeat(total));
+
+    for todo in todos {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|spec| {
+                // Free text leaves a little room before the next column.
+                let room = match spec.column {
+                    Column::Title | Column::Description | Column::Tags | Column::Project | Column::Location | Column::DelegatedTo if spec.width > 3 => spec.width - 3,
+                    _ => spec.width,
+                };
+                pad(&truncate(&format_cell(todo, spec.column, config), room), spec.width)
+            })
+            .collect();
+        println!("{}", cells.join(" ").trim_end());
+    }
+}
+
+// The accessible form of the table: one sentence-like line per todo, fields
+// named instead of placed in columns, empty fields left out and nothing
+// truncated.
+fn print_lines(todos: &[Todo], config: &Config, columns: &[ColumnSpec]) {
+    for todo in todos {
+        let mut line = format!("Todo {}.", todo.id);
+        for spec in columns.iter().filter(|spec| spec.column != Column::Id) {
+            let value = format_cell(todo, spec.column, config);
+            if !value.is_empty() {
+                line.push_str(&format!(" {}: {}.", capitalize(&spec.column.name().replace('_', " ")), value));
+            }
+        }
+        println!("{}", line);
+    }
+}
+
+// Every field of one todo on a line of its own, followed by the todos it is
+// connected to, each with its title so they can be looked up in turn.
+pub fn print_details(todo: &Todo, todo_list: &TodoList, config: &Config) {
+    heading(&format!("Todo {}", todo.id), config.accessible);
+    for column in Column::ALL.into_iter().filter(|&column| column != Column::Id) {
+        let value = format_cell(todo, column, config);
+        if !value.is_empty() {
+            println!("{}: {}", capitalize(&column.name().replace('_', " ")), value);
+        }
+    }
+    if let Some(note) = &todo.note {
+        println!("Note: {}", note);
+    }
+    if !todo.comments.is_empty() {
+        println!("Comments:");
+        print_comments(todo, None, 1);
+    }
+    let blocks: Vec<usize> = todo_list.todos().iter().filter(|t| t.depends_on.contains(&todo.id)).map(|t| t.id).collect();
+    let subtasks: Vec<usize> = todo_list.todos().iter().filter(|t| t.parent == Some(todo.id)).map(|t| t.id).collect();
+    let links = [
+        ("Depends on", todo.depends_on.clone()),
+        ("Blocks", blocks),
+        ("Subtasks", subtasks),
+        ("Related", todo_list.related(todo.id)),
+    ];
+    for (label, ids) in links.iter().filter(|(_, ids)| !ids.is_empty()) {
+        println!("{}:", label);
+        for id in ids {
+            let title = todo_list.get_todo(*id).map(|t| t.title.as_str()).unwrap_or("");
+            println!("  #{} {}", id, title);
+        }
+    }
+}
+
+// The comments replying to `to`, each followed by the replies to it one
+// level further in. A reply to a comment that is not there, or not earlier,
+// is shown at the top level.
+fn print_comments(todo: &Todo, to: Option<DateTime<Local>>, depth: usize) {
+    for (n, comment) in todo.comments.iter().enumerate() {
+        let parent = comment.reply_to.filter(|&at| at < comment.at && todo.comments.iter().any(|other| other.at == at));
+        if parent != to {
+            continue;
+        }
+        println!("{}{}. {}, {}: {}", "  ".repeat(depth), n + 1, comment.author, comment.at.format("%Y-%m-%d %H:%M"), comment.text);
+        print_comments(todo, Some(comment.at), depth + 1);
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+// Screen readers would read out the dashes, so accessible mode uses a colon.
+pub fn heading(title: &str, accessible: bool) {
+    if accessible {
+        println!("{}:", title);
+    } else {
+        println!("--- {} ---", title);
+    }
+}
+
+// One line per todo; nothing at all for an empty list, which suits scripts
+// and status bars.
+pub fn print_formatted(todos: &[Todo], config: &Config, template: &Template) {
+    for todo in todos {
+        println!("{}", template.render(|column| format_cell(todo, column, config)));
+    }
+}
+
+fn format_cell(todo: &Todo, column: Column, config: &Config) -> String {
+    let today = Local::now().date_naive();
+    let date = |day: Option<NaiveDate>| day.map(|day| config.date_format.date(day, today)).unwrap_or_default();
+    match column {
+        Column::Id => todo.id.to_string(),
+        Column::Title => todo.title.clone(),
+        Column::Description => todo.description.clone(),
+        Column::Status => match status_glyph(todo, config) {
+            Some(glyph) => format!("{} {}", glyph, format_status(todo)),
+            None => format_status(todo),
+        },
+        Column::Priority => format_priority(todo, config),
+        Column::Due => date(todo.due),
+        Column::DueTime => todo.deadline.as_ref().map(|deadline| format_deadline(deadline, config)).unwrap_or_default(),
+        Column::Scheduled => date(todo.scheduled),
+        Column::WaitUntil => date(todo.wait_until),
+        Column::Tags => format_tags(todo),
+        Column::Project => todo.project.clone().unwrap_or_default(),
+        Column::Location => todo.location.clone().unwrap_or_default(),
+        Column::DelegatedTo => format_delegated_to(todo, config),
+        Column::FollowUp => date(todo.follow_up),
+        Column::Progress => format_progress(todo),
+        Column::Points => todo.points.map(|p| p.to_string()).unwrap_or_default(),
+        Column::Estimate => todo.estimate.map(format_minutes).unwrap_or_default(),
+        Column::Spent => Some(todo.spent).filter(|&m| m > 0).map(format_minutes).unwrap_or_default(),
+        Column::Postponed => Some(todo.postponed).filter(|&n| n > 0).map(|n| n.to_string()).unwrap_or_default(),
+        Column::Goal => todo.goal.map(|g| g.to_string()).unwrap_or_default(),
+        Column::Parent => todo.parent.map(|p| p.to_string()).unwrap_or_default(),
+        Column::Created => config.date_format.timestamp(todo.created_at, today),
+        Column::Updated => config.date_format.timestamp(todo.updated_at, today),
+    }
+}
+
+// "09:00 Europe/Berlin (03:00 here)", with the day if it is another one
+// here; the local time is left out when the zone is as far from UTC.
+pub fn format_deadline(deadline: &Deadline, config: &Config) -> String {
+    let local = deadline.local();
+    if local.offset().local_minus_utc() == deadline.at.offset().local_minus_utc() {
+        return deadline.to_string();
+    }
+    let here = if local.date_naive() == deadline.at.date_naive() {
+        local.format("%H:%M").to_string()
+    } else {
+        format!("{} {}", config.date_format.date(local.date_naive(), Local::now().date_naive()), local.format("%H:%M"))
+    };
+    format!("{} ({} here)", deadline, here)
+}
+
+// "Sam since 2026-10-01"; the date is left out when not known.
+fn format_delegated_to(todo: &Todo, config: &Config) -> String {
+    match (&todo.delegated_to, todo.delegated_on) {
+        (Some(person), Some(on)) => format!("{} since {}", person, config.date_format.date(on, Local::now().date_naive())),
+        (Some(person), None) => person.clone(),
+        (None, _) => String::new(),
+    }
+}
+
+// Emoji take up two cells in the terminal, everything else we print one.
+fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if EMOJI.contains(&c) { 2 } else { 1 }).sum()
+}
+
+fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(display_width(s))))
+}
+
+const EMOJI: [char; 3] = ['✅', '⏳', '🔥'];
+
+fn status_glyph(todo: &Todo, config: &Config) -> Option<&'static str> {
+    if !config.emoji || config.accessible {
+        return None;
+    }
+    let today = Local::now().date_naive();
+    let glyphs = if emoji_supported() { ["✅", "⏳", "🔥"] } else { ["[x]", "[ ]", "[!]"] };
+    Some(if todo.completed || (todo.habit && todo.done_on(today)) {
+        glyphs[0]
+    } else if todo.due.is_some_and(|due| due < today) {
+        glyphs[2]
+    } else {
+        glyphs[1]
+    })
+}
+
+// Emoji need a UTF-8 locale and a terminal with a font for them; the Linux
+// console and output redirected to a file or pipe get ASCII instead.
+fn emoji_supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        let term = env::var("TERM").unwrap_or_default();
+        io::stdout().is_terminal()
+            && (locale.contains("utf-8") || locale.contains("utf8"))
+            && term != "linux"
+            && term != "dumb"
+    })
+}
+
+// Escalated priorities are marked with a trailing '^', or spelled out in
+// accessible mode.
+pub fn format_priority(todo: &Todo, config: &Config) -> String {
+    match effective_priority(todo, config.escalate_after_days, Local::now()) {
+        Some(priority) if Some(priority) != todo.priority && config.accessible => format!("{} (escalated)", priority),
+        Some(priority) if Some(priority) != todo.priority => format!("{}^", priority),
+        Some(priority) => priority.to_string(),
+        None => String::new(),
+    }
+}
+
+// Pending todos with a progress value show how far along they are instead.
+pub fn format_status(todo: &Todo) -> String {
+    if todo.cancelled {
+        "Cancelled".to_string()
+    } else if todo.habit {
+        if todo.done_on(Local::now().date_naive()) { "Done today" } else { "Pending" }.to_string()
+    } else if todo.completed {
+        "Completed".to_string()
+    } else if todo.in_inbox() {
+        "Inbox".to_string()
+    } else if let Some(progress) = todo.progress {
+        format!("{}% done", progress)
+    } else {
+        "Pending".to_string()
+    }
+}
+
+pub fn format_tags(todo: &Todo) -> String {
+    todo.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+pub fn format_progress(todo: &Todo) -> String {
+    todo.progress.map(|p| format!("{}%", p)).unwrap_or_default()
+}
+
+// "60" or "60%", at most 100.
+pub fn parse_progress(input: &str) -> Option<u8> {
+    input.trim().trim_end_matches('%').parse::<u8>().ok().filter(|&p| p <= 100)
+}
+
+pub fn format_due(todo: &Todo) -> String {
+    match (todo.due, &todo.deadline) {
+        (Some(due), Some(deadline)) => format!("{} {}", due, deadline),
+        (due, _) => due.map(|due| due.to_string()).unwrap_or_default(),
+    }
+}
+
+pub fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else if max_chars < 3 {
+        s.chars().take(max_chars).collect()
+    } else {
+        format!("{}...", s.chars().take(max_chars - 3).collect::<String>())
+    }
+}
+
+pub fn get_input(prompt: &str) -> String {
+    println!("{}", prompt);
+    input::read_line()
+}
+
+// Without any input left the answer is no.
+pub fn get_confirmation(prompt: &str) -> bool {
+    loop {
+        let input = get_input(&format!("{} (y/n): ", prompt)).to_lowercase();
+        if input::at_eof() {
+            return false;
+        }
+        match input.as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => println!("Please enter 'y' or 'n'"),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Updating the binary from the releases of its GitHub repository. A release
+// carries one binary per platform, named as `asset_name` gives, and a
+// SHA256SUMS file listing their checksums as `sha256sum` writes them; a
+// binary whose checksum is missing or differs is never installed.
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::crypto::to_hex;
+
+pub const CHECKSUMS: &str = "SHA256SUMS";
+
+// Larger downloads are refused rather than held in memory.
+const MAX_DOWNLOAD: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    // "v1.2.0" is version 1.2.0.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+// "owner/name" from a repository URL such as https://github.com/owner/name.
+pub fn github_repository(url: &str) -> Option<String> {
+    let path = url.trim_end_matches('/').trim_end_matches(".git");
+    let path = path.strip_prefix("https://github.com/").or_else(|| path.strip_prefix("github.com/")).unwrap_or(path);
+    let (owner, name) = path.split_once('/')?;
+    (!owner.is_empty() && !name.is_empty() && !name.contains('/')).then(|| format!("{}/{}", owner, name))
+}
+
+// The binary for this platform, e.g. "todo_app-x86_64-linux".
+pub fn asset_name() -> String {
+    format!("todo_app-{}-{}{}", env::consts::ARCH, env::consts::OS, env::consts::EXE_SUFFIX)
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version.split(['.', '-', '+']).map_while(|part| part.parse().ok()).collect()
+}
+
+// Compares the numbers of versions such as 0.10.1, so that 0.10 is newer
+// than 0.9.
+pub fn is_newer(version: &str, than: &str) -> bool {
+    parse_version(version) > parse_version(than)
+}
+
+// The checksum `sums` gives for `name`, in lowercase hex.
+pub fn expected_checksum(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        // sha256sum marks files read in binary mode with a '*'.
+        (file.trim().trim_start_matches('*') == name).then(|| hash.to_lowercase())
+    })
+}
+
+pub fn checksum(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+fn failed(doing: &str, e: ureq::Error) -> io::Error {
+    io::Error::other(format!("{} failed: {}", doing, e))
+}
+
+// The newest release of `repository`, "owner/name", that is not a draft or
+// a prerelease.
+pub fn latest_release(repository: &str) -> io::Result<Release> {
+    let url = format!("https://api.github.com/repos/{}/releases/latest", repository);
+    ureq::get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", concat!("todo_app/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| match e {
+            ureq::Error::StatusCode(404) => io::Error::new(io::ErrorKind::NotFound, format!("{} has no releases", repository)),
+            e => failed(&format!("asking GitHub for the releases of {}", repository), e),
+        })
+}
+
+pub fn download(asset: &Asset) -> io::Result<Vec<u8>> {
+    ureq::get(&asset.browser_download_url)
+        .header("User-Agent", concat!("todo_app/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .and_then(|mut response| response.body_mut().with_config().limit(MAX_DOWNLOAD).read_to_vec())
+        .map_err(|e| failed(&format!("downloading {}", asset.name), e))
+}
+
+// Puts `binary` where the running program is. It is written next to it
+// first and renamed over it, so the old binary stays whole if anything
+// fails; Windows, which cannot replace a running program, keeps it as
+// todo_app.old.exe.
+pub fn replace_current_exe(binary: &[u8]) -> io::Result<()> {
+    let current = env::current_exe()?;
+    let new = current.with_extension("new");
+    let mut file = File::create(&new)?;
+    file.write_all(binary)?;
+    file.sync_all()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    fs::rename(&current, current.with_extension("old.exe"))?;
+    fs::rename(&new, &current).inspect_err(|_| {
+        let _ = fs::remove_file(&new);
+    })
+}
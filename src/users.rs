@@ -0,0 +1,182 @@
+// Who may use a sync server, and for what. Until the first user is added
+// the server is open to every device that can reach it, as before; from
+// then on devices register with an invite code handed out for a user and
+// act with that user's role, and removing every user locks everyone out.
+// A server keeps a single list, so a role is server-wide: it applies to that
+// list, and a server for another list has users of its own.
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::crypto::{random_secret, to_hex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    // Pulls changes but cannot send any.
+    Viewer,
+    Editor,
+    // Can also see who has access.
+    Admin,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role.to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "editor" => Some(Role::Editor),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn can_push(self) -> bool {
+        self >= Role::Editor
+    }
+
+    pub fn can_manage(self) -> bool {
+        self == Role::Admin
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub name: String,
+    pub role: Role,
+    // Devices registered with one of the user's invites.
+    #[serde(default)]
+    pub devices: Vec<String>,
+    // The SHA-256 of the invite code not used yet, in hex; the code itself
+    // is only ever shown when it is made.
+    #[serde(default)]
+    invite: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UserError {
+    Exists(String),
+    NotFound(String),
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserError::Exists(name) => write!(f, "There is already a user called {}.", name),
+            UserError::NotFound(name) => write!(f, "There is no user called {}.", name),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Users {
+    users: Vec<User>,
+    // No users were ever added.
+    #[serde(skip)]
+    open: bool,
+}
+
+// "todos.json" keeps the users of its sync server in "todos.users.json".
+pub fn users_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.users.json", stem),
+        None => format!("{}.users.json", filename),
+    }
+}
+
+fn hash(code: &str) -> String {
+    to_hex(&Sha256::digest(code.as_bytes()))
+}
+
+impl Users {
+    pub fn load(filename: &str) -> io::Result<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Users { users: Vec::new(), open: true });
+        }
+        let mut contents = String::new();
+        File::open(filename)?.read_to_string(&mut contents)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // Readable by the owner only: it tells who has access.
+    pub fn save(&self, filename: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut file = File::create(filename)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(json.as_bytes())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn users(&self) -> &[User] {
+        &self.users
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut User, UserError> {
+        self.users.iter_mut().find(|user| user.name == name).ok_or_else(|| UserError::NotFound(name.to_string()))
+    }
+
+    // Returns the user's first invite code.
+    pub fn add(&mut self, name: &str, role: Role) -> Result<String, UserError> {
+        if self.users.iter().any(|user| user.name == name) {
+            return Err(UserError::Exists(name.to_string()));
+        }
+        self.users.push(User { name: name.to_string(), role, devices: Vec::new(), invite: None });
+        self.open = false;
+        self.invite(name)
+    }
+
+    // A new invite code for one more device, replacing any unused one.
+    pub fn invite(&mut self, name: &str) -> Result<String, UserError> {
+        let code = random_secret();
+        self.find_mut(name)?.invite = Some(hash(&code));
+        Ok(code)
+    }
+
+    pub fn set_role(&mut self, name: &str, role: Role) -> Result<(), UserError> {
+        self.find_mut(name)?.role = role;
+        Ok(())
+    }
+
+    // The user's devices lose access along with the user.
+    pub fn remove(&mut self, name: &str) -> Result<User, UserError> {
+        let pos = self.users.iter().position(|user| user.name == name).ok_or_else(|| UserError::NotFound(name.to_string()))?;
+        Ok(self.users.remove(pos))
+    }
+
+    pub fn has_invite(&self, code: &str) -> bool {
+        let hashed = hash(code);
+        self.users.iter().any(|user| user.invite.as_deref() == Some(hashed.as_str()))
+    }
+
+    // Uses up the invite `code` for `device`, returning whose it was.
+    pub fn redeem(&mut self, code: &str, device: &str) -> Option<&User> {
+        let hashed = hash(code);
+        let user = self.users.iter_mut().find(|user| user.invite.as_deref() == Some(hashed.as_str()))?;
+        user.invite = None;
+        user.devices.push(device.to_string());
+        Some(user)
+    }
+
+    pub fn of_device(&self, device: &str) -> Option<&User> {
+        self.users.iter().find(|user| user.devices.iter().any(|known| known == device))
+    }
+}
@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+// Maximum lengths in characters, after cleaning.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub max_title_length: usize,
+    pub max_description_length: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_title_length: 200,
+            max_description_length: 2000,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    EmptyTitle,
+    TitleTooLong { length: usize, max: usize },
+    DescriptionTooLong { length: usize, max: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::EmptyTitle => write!(f, "The title cannot be empty."),
+            ValidationError::TitleTooLong { length, max } => {
+                write!(f, "The title is {} characters long; at most {} are allowed.", length, max)
+            },
+            ValidationError::DescriptionTooLong { length, max } => {
+                write!(f, "The description is {} characters long; at most {} are allowed.", length, max)
+            },
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+// Drops control characters and collapses every run of whitespace into a
+// single space.
+pub fn clean_line(input: &str) -> String {
+    input
+        .split(char::is_whitespace)
+        .map(|word| word.chars().filter(|c| !c.is_control()).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Like clean_line, but line breaks in descriptions are kept.
+pub fn clean_text(input: &str) -> String {
+    input.lines().map(clean_line).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+// Cleans a title and description and checks them against `limits`. Every
+// way of adding or editing a todo goes through here.
+pub fn validate(title: &str, description: &str, limits: &Limits) -> Result<(String, String), ValidationError> {
+    let title = clean_line(title);
+    let description = clean_text(description);
+    if title.is_empty() {
+        return Err(ValidationError::EmptyTitle);
+    }
+    let length = title.chars().count();
+    if length > limits.max_title_length {
+        return Err(ValidationError::TitleTooLong { length, max: limits.max_title_length });
+    }
+    let length = description.chars().count();
+    if length > limits.max_description_length {
+        return Err(ValidationError::DescriptionTooLong { length, max: limits.max_description_length });
+    }
+    Ok((title, description))
+}
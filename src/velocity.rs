@@ -0,0 +1,25 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use crate::todo::Todo;
+
+// Story points completed in each of the last `weeks` ISO weeks, oldest first.
+// Cancelled todos earn no points.
+// The week is identified by its Monday.
+pub fn weekly_points<'a>(todos: impl IntoIterator<Item = &'a Todo>, today: NaiveDate, weeks: usize) -> Vec<(NaiveDate, u32)> {
+    let this_monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let mut totals: Vec<(NaiveDate, u32)> = (0..weeks)
+        .rev()
+        .map(|back| (this_monday - Duration::weeks(back as i64), 0))
+        .collect();
+
+    for todo in todos {
+        let (Some(points), Some(completed), false) = (todo.points, todo.completed_on(), todo.cancelled) else {
+            continue;
+        };
+        let day = completed.date_naive();
+        let monday = day - Duration::days(i64::from(day.weekday().num_days_from_monday()));
+        if let Some(entry) = totals.iter_mut().find(|(week, _)| *week == monday) {
+            entry.1 += points;
+        }
+    }
+    totals
+}
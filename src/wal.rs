@@ -0,0 +1,68 @@
+// A write-ahead record of the save in progress. Before a save touches the
+// store or the history it writes down everything it is about to write; the
+// record is removed once both are done. A record still there on the next
+// start belongs to a save that was cut short and is replayed, and one that
+// cannot be read was itself cut short before the save began, so it is
+// dropped and the list stays as it was.
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::history::Event;
+use crate::todo::TodoList;
+
+#[derive(Serialize)]
+struct Intent<'a> {
+    // The length of the history file before the save appended to it.
+    history_len: u64,
+    events: &'a [Event],
+    list: &'a TodoList,
+}
+
+#[derive(Deserialize)]
+struct SavedIntent {
+    history_len: u64,
+    events: Vec<Event>,
+    list: TodoList,
+}
+
+// "todos.json" keeps its record in "todos.wal.json".
+pub fn wal_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.wal.json", stem),
+        None => format!("{}.wal.json", filename),
+    }
+}
+
+pub(crate) fn begin(filename: &str, todo_list: &TodoList, events: &[Event], history_len: u64) -> io::Result<()> {
+    let json = serde_json::to_vec(&Intent { history_len, events, list: todo_list })?;
+    let mut file = File::create(wal_filename(filename))?;
+    file.write_all(&json)?;
+    file.sync_all()
+}
+
+pub(crate) fn finish(filename: &str) -> io::Result<()> {
+    fs::remove_file(wal_filename(filename))
+}
+
+// Completes or rolls back a save of `filename` that was cut short. Returns
+// whether there was one to replay.
+pub fn recover(filename: &str) -> io::Result<bool> {
+    let wal = wal_filename(filename);
+    if !Path::new(&wal).exists() {
+        return Ok(false);
+    }
+
+    let mut contents = String::new();
+    File::open(&wal)?.read_to_string(&mut contents)?;
+    let Ok(intent) = serde_json::from_str::<SavedIntent>(&contents) else {
+        fs::remove_file(&wal)?;
+        return Ok(false);
+    };
+    let mut todo_list = intent.list;
+    todo_list.reindex();
+    todo_list.set_unsaved_events(intent.events.clone());
+    todo_list.write_out(filename, &intent.events, intent.history_len)?;
+    fs::remove_file(&wal)?;
+    Ok(true)
+}
@@ -0,0 +1,55 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use notify::{RecursiveMode, Watcher};
+
+// Changes arriving this close together are drawn once; a single save can
+// produce several events (write of the temporary file, rename, ...).
+const SETTLE: Duration = Duration::from_millis(100);
+
+// Calls `render` now and again whenever `filename` changes, until the process
+// is interrupted. The directory is watched rather than the file because saves
+// replace the file through a rename.
+pub fn run(filename: &str, mut render: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    let path = Path::new(filename);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = path.file_name().map(|n| n.to_os_string());
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(io::Error::other)?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive).map_err(io::Error::other)?;
+
+    // Access events are ignored, or reading the file to draw it would
+    // trigger the next redraw.
+    let touches_file = |event: &notify::Result<notify::Event>| match event {
+        Ok(event) if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() => {
+            event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == name)
+        },
+        _ => false,
+    };
+
+    redraw(&mut render);
+    while let Ok(event) = rx.recv() {
+        if !touches_file(&event) {
+            continue;
+        }
+        while rx.recv_timeout(SETTLE).is_ok() {}
+        redraw(&mut render);
+    }
+    Ok(())
+}
+
+fn redraw(render: &mut impl FnMut() -> io::Result<()>) {
+    // Clear the screen and move the cursor home.
+    print!("\x1b[2J\x1b[H");
+    if let Err(e) = render() {
+        // The file may be caught mid-write by another tool; the next change
+        // will draw it again.
+        println!("Could not read the list: {}", e);
+    }
+    println!("\nWatching for changes, press Ctrl-C to stop.");
+}
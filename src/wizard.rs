@@ -0,0 +1,135 @@
+use chrono::{Local, NaiveDate};
+use todo_app::dates::parse_date;
+use todo_app::validate::{clean_line, ValidationError};
+use todo_app::{parse_tags, Priority, Tag, TodoList};
+use crate::input;
+use crate::ui::get_input;
+
+#[derive(Default)]
+struct Draft {
+    title: String,
+    description: String,
+    due: Option<NaiveDate>,
+    priority: Option<Priority>,
+    tags: Vec<String>,
+    project: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Title,
+    Description,
+    Due,
+    Priority,
+    Tags,
+    Project,
+}
+
+const STEPS: [Step; 6] = [Step::Title, Step::Description, Step::Due, Step::Priority, Step::Tags, Step::Project];
+
+impl Draft {
+    fn current(&self, step: Step) -> String {
+        match step {
+            Step::Title => self.title.clone(),
+            Step::Description => self.description.clone(),
+            Step::Due => self.due.map(|due| due.to_string()).unwrap_or_default(),
+            Step::Priority => self.priority.map(|p| p.to_string()).unwrap_or_default(),
+            Step::Tags => self.tags.join(", "),
+            Step::Project => self.project.clone().unwrap_or_default(),
+        }
+    }
+
+    // Applies an answer; "-" clears the field. Returns an explanation if the
+    // answer cannot be used.
+    fn set(&mut self, step: Step, input: &str) -> Result<(), String> {
+        let clear = input == "-";
+        match step {
+            Step::Title if clear => return Err("The title cannot be empty.".to_string()),
+            Step::Title => self.title = input.to_string(),
+            Step::Description => self.description = if clear { String::new() } else { input.to_string() },
+            Step::Due if clear => self.due = None,
+            Step::Due => match parse_date(input, Local::now().date_naive()) {
+                Some(due) => self.due = Some(due),
+                None => return Err("Could not understand that date.".to_string()),
+            },
+            Step::Priority if clear => self.priority = None,
+            Step::Priority => match Priority::parse(input) {
+                Some(priority) => self.priority = Some(priority),
+                None => return Err("Priority must be low, medium or high.".to_string()),
+            },
+            Step::Tags => self.tags = if clear { Vec::new() } else { parse_tags(input) },
+            Step::Project if clear => self.project = None,
+            Step::Project => self.project = Some(clean_line(input)).filter(|p| !p.is_empty()),
+        }
+        Ok(())
+    }
+}
+
+fn prompt(step: Step) -> &'static str {
+    match step {
+        Step::Title => "Title",
+        Step::Description => "Description",
+        Step::Due => "Due date (e.g. 2026-05-01, tomorrow, 3d, friday)",
+        Step::Priority => "Priority (low, medium, high)",
+        Step::Tags => "Tags, separated by commas",
+        Step::Project => "Project",
+    }
+}
+
+// Asks for each field of a new todo in turn. Enter keeps the value shown (or
+// skips an empty optional field), "-" clears it and "<" goes back a step.
+// An empty title on the first step cancels. Returns the id of the new todo.
+pub fn run(todo_list: &mut TodoList) -> Option<usize> {
+    println!("Press Enter to skip a field, '-' to clear it, '<' to go back.");
+    let mut draft = Draft::default();
+    let mut pos = 0;
+    loop {
+        while pos < STEPS.len() {
+            let step = STEPS[pos];
+            let current = draft.current(step);
+            let label = if current.is_empty() {
+                format!("{}:", prompt(step))
+            } else {
+                format!("{} [{}]:", prompt(step), current)
+            };
+            let input = get_input(&label);
+            if input == "<" {
+                pos = pos.saturating_sub(1);
+                continue;
+            }
+            if input.is_empty() {
+                if step == Step::Title && draft.title.is_empty() {
+                    println!("Add cancelled.");
+                    return None;
+                }
+                pos += 1;
+                continue;
+            }
+            match draft.set(step, &input) {
+                Ok(()) => pos += 1,
+                Err(e) => println!("{}", e),
+            }
+        }
+
+        let added = todo_list.add_todo_with(draft.title.clone(), draft.description.clone(), |todo| {
+            todo.due = draft.due;
+            todo.priority = draft.priority;
+            todo.tags = draft.tags.iter().map(|tag| Tag::from(tag.as_str())).collect();
+            todo.project = draft.project.clone();
+        });
+        match added {
+            Ok(id) => return Some(id),
+            // Back to the offending field, keeping everything entered so far.
+            Err(e) => {
+                println!("{}", e);
+                if input::at_eof() {
+                    return None;
+                }
+                pos = match e {
+                    ValidationError::DescriptionTooLong { .. } => 1,
+                    _ => 0,
+                };
+            },
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+use crate::rrule::Rule;
+
+// A day off that is not a weekend, e.g. imported from a public holiday
+// calendar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holiday {
+    pub day: NaiveDate,
+    pub name: String,
+}
+
+// "todos.json" keeps its holidays in "todos.holidays.json".
+pub fn holidays_filename(filename: &str) -> String {
+    match filename.strip_suffix(".json") {
+        Some(stem) => format!("{}.holidays.json", stem),
+        None => format!("{}.holidays.json", filename),
+    }
+}
+
+pub fn load_holidays(filename: &str) -> io::Result<Vec<Holiday>> {
+    if !Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let holidays: Vec<Holiday> = serde_json::from_str(&contents)?;
+    Ok(holidays)
+}
+
+pub fn save_holidays(filename: &str, holidays: &[Holiday]) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(holidays)?;
+    let mut file = File::create(filename)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+// Years of a yearly holiday written as a repeating event that are imported.
+const REPEAT_YEARS: u32 = 10;
+
+// Holidays from an iCalendar file, as public holiday calendars are offered
+// for download: a holiday for every day of every event, with those that
+// repeat expanded over the next years. Anything else is read as lines of a
+// date and a name, "2026-12-25 Christmas Day" or "2026-12-25,Christmas Day".
+pub fn parse_holidays(text: &str) -> Vec<Holiday> {
+    let mut holidays = if text.contains("BEGIN:VCALENDAR") { parse_ics(text) } else { parse_lines(text) };
+    holidays.sort_by_key(|holiday| holiday.day);
+    holidays.dedup_by_key(|holiday| holiday.day);
+    holidays
+}
+
+fn parse_lines(text: &str) -> Vec<Holiday> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (day, name) = line.split_once([',', ';', '\t', ' ']).unwrap_or((line, ""));
+            let day = NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d").ok()?;
+            Some(Holiday { day, name: name.trim().trim_matches('"').to_string() })
+        })
+        .collect()
+}
+
+fn parse_ics(text: &str) -> Vec<Holiday> {
+    // Long lines are folded onto the next ones, which start with a space.
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut holidays = Vec::new();
+    let (mut start, mut end, mut name, mut rule) = (None, None, String::new(), None);
+    for line in &lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let date = || NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok();
+        match key.split(';').next().unwrap_or(key) {
+            "BEGIN" if value == "VEVENT" => (start, end, name, rule) = (None, None, String::new(), None),
+            "DTSTART" => start = date(),
+            "DTEND" => end = date(),
+            "SUMMARY" => name = value.replace("\\,", ",").replace("\\;", ";").replace("\\n", " ").replace("\\\\", "\\"),
+            "RRULE" => rule = Rule::parse(value).ok(),
+            "END" if value == "VEVENT" => {
+                let Some(start) = start else {
+                    continue;
+                };
+                // The end is the day after the last one; a week at most.
+                let days = end.map_or(1, |end| (end - start).num_days().clamp(1, 7));
+                let starts: Vec<NaiveDate> = match &rule {
+                    Some(rule) => {
+                        let until = start.with_year(start.year() + REPEAT_YEARS as i32).unwrap_or(start);
+                        rule.occurrences(start).take_while(|&day| day <= until).collect()
+                    },
+                    None => vec![start],
+                };
+                for first in starts {
+                    for n in 0..days {
+                        holidays.push(Holiday { day: first + Duration::days(n), name: name.clone() });
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    holidays
+}
+
+// The days that are worked: the weekdays of the `workdays` setting, except
+// holidays.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    workdays: Vec<Weekday>,
+    holidays: BTreeSet<NaiveDate>,
+}
+
+// Stops a search for a workday in a calendar that has none.
+const MAX_DAYS_OFF: u32 = 3_660;
+
+impl Calendar {
+    pub fn new(workdays: &[Weekday], holidays: &[Holiday]) -> Self {
+        Calendar { workdays: workdays.to_vec(), holidays: holidays.iter().map(|holiday| holiday.day).collect() }
+    }
+
+    // The calendar of the list in `filename`, with its holidays.
+    pub fn load(config: &Config, filename: &str) -> io::Result<Self> {
+        Ok(Calendar::new(&config.workdays, &load_holidays(&holidays_filename(filename))?))
+    }
+
+    // What `TodoList::set_days_off` is given: the calendar if recurring
+    // todos are to skip days off.
+    pub fn days_off(config: &Config, filename: &str) -> io::Result<Option<Self>> {
+        if !config.skip_days_off {
+            return Ok(None);
+        }
+        Calendar::load(config, filename).map(Some)
+    }
+
+    pub fn is_holiday(&self, day: NaiveDate) -> bool {
+        self.holidays.contains(&day)
+    }
+
+    pub fn is_workday(&self, day: NaiveDate) -> bool {
+        self.workdays.contains(&day.weekday()) && !self.is_holiday(day)
+    }
+
+    // `day` if it is a workday, otherwise the next one.
+    pub fn next_workday(&self, day: NaiveDate) -> NaiveDate {
+        (0..MAX_DAYS_OFF).map(|n| day + Duration::days(i64::from(n))).find(|&day| self.is_workday(day)).unwrap_or(day)
+    }
+
+    // The `n`th workday after `from`.
+    pub fn add_workdays(&self, from: NaiveDate, n: u32) -> NaiveDate {
+        (0..n).fold(from, |day, _| self.next_workday(day + Duration::days(1)))
+    }
+
+    // Where an occurrence of `rule` on a day off goes: to the next workday,
+    // unless the rule asks for that weekday itself, as a weekly rule on
+    // Saturdays does. Holidays are moved off in any case.
+    pub fn move_occurrence(&self, day: NaiveDate, rule: &Rule) -> NaiveDate {
+        let asked_for = |day: NaiveDate| !self.is_holiday(day) && rule.by_day.iter().any(|&(_, weekday)| weekday == day.weekday());
+        (0..MAX_DAYS_OFF)
+            .map(|n| day + Duration::days(i64::from(n)))
+            .find(|&day| self.is_workday(day) || asked_for(day))
+            .unwrap_or(day)
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use chrono::{Datelike, Local, NaiveDate, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use todo_app::config::Config;
+use todo_app::{Todo, TodoList};
+use crate::ui::{format_priority, format_status, format_tags};
+
+// Sheets for todos without a project.
+const NO_PROJECT: &str = "No project";
+
+const HEADER: [&str; 6] = ["ID", "Title", "Status", "Due", "Priority", "Tags"];
+const WIDTHS: [u32; 6] = [6, 50, 12, 12, 10, 30];
+
+// Characters XML 1.0 allows, with the markup ones escaped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {},
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// "A1" for row 1 of the first column.
+fn cell_ref(column: usize, row: usize) -> String {
+    format!("{}{}", (b'A' + column as u8) as char, row)
+}
+
+fn text_cell(column: usize, row: usize, text: &str, style: u8) -> String {
+    format!(r#"<c r="{}" s="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#, cell_ref(column, row), style, escape(text))
+}
+
+// Spreadsheets count days from 1899-12-30.
+fn date_serial(day: NaiveDate) -> i64 {
+    (day - NaiveDate::from_ymd_opt(1899, 12, 30).expect("valid date")).num_days()
+}
+
+fn sheet(todos: &[&Todo], config: &Config) -> String {
+    let mut rows = String::new();
+    let header: Vec<String> = HEADER.iter().enumerate().map(|(column, name)| text_cell(column, 1, name, 1)).collect();
+    rows.push_str(&format!(r#"<row r="1">{}</row>"#, header.concat()));
+    for (n, todo) in todos.iter().enumerate() {
+        let row = n + 2;
+        let mut cells = vec![
+            format!(r#"<c r="{}"><v>{}</v></c>"#, cell_ref(0, row), todo.id),
+            text_cell(1, row, &todo.title, 0),
+            text_cell(2, row, &format_status(todo), 0),
+        ];
+        if let Some(due) = todo.due {
+            cells.push(format!(r#"<c r="{}" s="2"><v>{}</v></c>"#, cell_ref(3, row), date_serial(due)));
+        }
+        for (column, text) in [(4, format_priority(todo, config)), (5, format_tags(todo))] {
+            if !text.is_empty() {
+                cells.push(text_cell(column, row, &text, 0));
+            }
+        }
+        rows.push_str(&format!(r#"<row r="{}">{}</row>"#, row, cells.concat()));
+    }
+    let columns: Vec<String> =
+        WIDTHS.iter().enumerate().map(|(n, width)| format!(r#"<col min="{}" max="{}" width="{}" customWidth="1"/>"#, n + 1, n + 1, width)).collect();
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            r#"<sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews>"#,
+            r#"<cols>{}</cols><sheetData>{}</sheetData><autoFilter ref="A1:{}"/></worksheet>"#
+        ),
+        columns.concat(),
+        rows,
+        cell_ref(HEADER.len() - 1, todos.len() + 1)
+    )
+}
+
+// At most 31 characters, none of []:*?/\, and unique ignoring case.
+fn sheet_names(projects: &[&str]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for project in projects {
+        let clean: String = project.chars().map(|c| if "[]:*?/\\".contains(c) { '-' } else { c }).collect();
+        let clean = clean.trim_matches('\'').trim().to_string();
+        let clean = if clean.is_empty() { "Project".to_string() } else { clean };
+        let mut name: String = clean.chars().take(31).collect();
+        let mut n = 2;
+        while names.iter().any(|taken| taken.to_lowercase() == name.to_lowercase()) {
+            let suffix = format!(" ({})", n);
+            name = format!("{}{}", clean.chars().take(31 - suffix.len()).collect::<String>(), suffix);
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+fn workbook(names: &[String]) -> String {
+    let sheets: Vec<String> =
+        names.iter().enumerate().map(|(n, name)| format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape(name), n + 1, n + 1)).collect();
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+            r#"<sheets>{}</sheets></workbook>"#
+        ),
+        sheets.concat()
+    )
+}
+
+fn workbook_rels(sheets: usize) -> String {
+    let mut rels: Vec<String> = (1..=sheets)
+        .map(|n| {
+            format!(r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#, n, n)
+        })
+        .collect();
+    rels.push(format!(r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#, sheets + 1));
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        rels.concat()
+    )
+}
+
+fn content_types(sheets: usize) -> String {
+    let overrides: Vec<String> = (1..=sheets)
+        .map(|n| format!(r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#, n))
+        .collect();
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+            r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+            r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+            r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+            r#"<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+            "{}</Types>"
+        ),
+        overrides.concat()
+    )
+}
+
+const PACKAGE_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+    r#"</Relationships>"#
+);
+
+// Style 0 is plain, 1 the bold header and 2 a date in the reader's short
+// date format.
+const STYLES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+    r#"<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts>"#,
+    r#"<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>"#,
+    r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>"#,
+    r#"<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+    r#"<cellXfs count="3"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>"#,
+    r#"<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>"#,
+    r#"<xf numFmtId="14" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/></cellXfs>"#,
+    r#"<cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles>"#,
+    r#"</styleSheet>"#
+);
+
+// A zip archive, as an xlsx file is, of deflated entries.
+fn write_zip(out: &mut impl Write, files: &[(String, Vec<u8>)]) -> io::Result<()> {
+    let now = Local::now();
+    let time = (now.hour() << 11 | now.minute() << 5 | (now.second() / 2)) as u16;
+    let date = ((now.year().clamp(1980, 2107) - 1980) as u32) << 9 | now.month() << 5 | now.day();
+    let date = date as u16;
+    let mut central = Vec::new();
+    let mut offset = 0u32;
+    for (name, contents) in files {
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        // Version, flags, method, time, date, CRC, sizes and name length.
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&8u16.to_le_bytes());
+        fields.extend_from_slice(&time.to_le_bytes());
+        fields.extend_from_slice(&date.to_le_bytes());
+        fields.extend_from_slice(&crc.sum().to_le_bytes());
+        fields.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+        out.write_all(b"PK\x03\x04")?;
+        out.write_all(&fields)?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&compressed)?;
+
+        central.extend_from_slice(b"PK\x01\x02");
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&fields);
+        // No extra field, comment, disk number or attributes.
+        central.extend_from_slice(&[0; 12]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        offset += (30 + name.len() + compressed.len()) as u32;
+    }
+    out.write_all(&central)?;
+    out.write_all(b"PK\x05\x06")?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&(files.len() as u16).to_le_bytes())?;
+    out.write_all(&(files.len() as u16).to_le_bytes())?;
+    out.write_all(&(central.len() as u32).to_le_bytes())?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&0u16.to_le_bytes())
+}
+
+// Writes the list to `path` as a workbook with one sheet per project, by
+// name, and one for the todos without a project last. Returns the number
+// of sheets.
+pub fn export(path: &str, todo_list: &TodoList, config: &Config) -> io::Result<usize> {
+    let mut projects: BTreeMap<Option<&str>, Vec<&Todo>> = BTreeMap::new();
+    for todo in todo_list.todos() {
+        projects.entry(todo.project.as_deref()).or_default().push(todo);
+    }
+    let mut sheets: Vec<(Option<&str>, Vec<&Todo>)> = projects.into_iter().collect();
+    // No project sorts first.
+    if sheets.first().is_some_and(|(project, _)| project.is_none()) {
+        sheets.rotate_left(1);
+    }
+    if sheets.is_empty() {
+        sheets.push((None, Vec::new()));
+    }
+    let names = sheet_names(&sheets.iter().map(|(project, _)| project.unwrap_or(NO_PROJECT)).collect::<Vec<_>>());
+
+    let mut files = vec![
+        ("[Content_Types].xml".to_string(), content_types(sheets.len()).into_bytes()),
+        ("_rels/.rels".to_string(), PACKAGE_RELS.as_bytes().to_vec()),
+        ("xl/workbook.xml".to_string(), workbook(&names).into_bytes()),
+        ("xl/_rels/workbook.xml.rels".to_string(), workbook_rels(sheets.len()).into_bytes()),
+        ("xl/styles.xml".to_string(), STYLES.as_bytes().to_vec()),
+    ];
+    for (n, (_, todos)) in sheets.iter().enumerate() {
+        files.push((format!("xl/worksheets/sheet{}.xml", n + 1), sheet(todos, config).into_bytes()));
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    write_zip(&mut out, &files)?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(sheets.len())
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+// A todo due at a time of day in a time zone, e.g. 9:00 in Europe/Berlin,
+// rather than on a day in whatever zone the app happens to run in. `at`
+// carries the offset the zone has then, so the moment is known exactly even
+// where the zone's rules are not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deadline {
+    pub at: DateTime<FixedOffset>,
+    pub zone: String,
+}
+
+impl Deadline {
+    // `time` on `day` in `zone`. None for a time the clocks skip there; of
+    // a time they pass twice, the first.
+    #[cfg(feature = "zones")]
+    pub fn pin(day: NaiveDate, time: NaiveTime, zone: &str) -> Option<Deadline> {
+        let tz = find_zone(zone)?;
+        let at = tz.from_local_datetime(&day.and_time(time)).earliest()?;
+        Some(Deadline { at: at.fixed_offset(), zone: tz.name().to_string() })
+    }
+
+    // The same time of day in its zone on another day, e.g. when a
+    // recurring todo moves on.
+    pub fn on(&self, day: NaiveDate) -> Deadline {
+        #[cfg(feature = "zones")]
+        if let Some(moved) = Deadline::pin(day, self.at.time(), &self.zone) {
+            return moved;
+        }
+        // Without the zone's rules, at the offset it had.
+        let at = self.at.offset().from_local_datetime(&day.and_time(self.at.time())).single().unwrap_or(self.at);
+        Deadline { at, zone: self.zone.clone() }
+    }
+
+    pub fn local(&self) -> DateTime<Local> {
+        self.at.with_timezone(&Local)
+    }
+}
+
+// "09:00 Europe/Berlin"
+impl fmt::Display for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.at.format("%H:%M"), self.zone)
+    }
+}
+
+// A zone by its IANA name, "Europe/Berlin", or by the city in it, "Berlin"
+// or "new york", ignoring case.
+#[cfg(feature = "zones")]
+pub fn find_zone(name: &str) -> Option<chrono_tz::Tz> {
+    const REGIONS: [&str; 9] = ["Africa", "America", "Antarctica", "Asia", "Atlantic", "Australia", "Europe", "Indian", "Pacific"];
+    let name = name.trim().replace(' ', "_");
+    let zones = chrono_tz::TZ_VARIANTS;
+    zones.iter().find(|tz| tz.name().eq_ignore_ascii_case(&name)).or_else(|| {
+        // Old names such as "US/Pacific" or "Asia/Calcutta" only if there is
+        // no current one.
+        let city = |tz: &&chrono_tz::Tz| tz.name().rsplit('/').next().is_some_and(|city| city.eq_ignore_ascii_case(&name));
+        let region = |tz: &&chrono_tz::Tz| tz.name().split_once('/').is_some_and(|(region, _)| REGIONS.contains(&region));
+        zones.iter().filter(city).find(region).or_else(|| zones.iter().find(city))
+    }).copied()
+}
+
+// "9am", "9:30pm", "17:00", "noon" or "midnight". A bare hour is not a
+// time, as it may well be a day of the month.
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {},
+    }
+    let (clock, half) = match (input.strip_suffix("am"), input.strip_suffix("pm")) {
+        (Some(clock), _) => (clock.trim_end(), Some(0)),
+        (_, Some(clock)) => (clock.trim_end(), Some(12)),
+        _ => (input.as_str(), None),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if half.is_some() => (clock.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match half {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(half) => hour % 12 + half,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+// "friday 9am Berlin", "2026-10-20 at 17:00 in Europe/London" or "9am New
+// York time": a day as `parse_date` reads it, today if left out, a time
+// and a zone.
+#[cfg(feature = "zones")]
+pub fn parse_deadline(input: &str, today: NaiveDate) -> Option<Deadline> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let at = words.iter().position(|word| parse_time(word).is_some())?;
+    let time = parse_time(words[at])?;
+    let mut day = &words[..at];
+    if day.last().is_some_and(|word| word.eq_ignore_ascii_case("at")) {
+        day = &day[..day.len() - 1];
+    }
+    let day = if day.is_empty() { today } else { crate::dates::parse_date(&day.join(" "), today)? };
+    let mut zone = &words[at + 1..];
+    if zone.first().is_some_and(|word| word.eq_ignore_ascii_case("in")) {
+        zone = &zone[1..];
+    }
+    if zone.last().is_some_and(|word| word.eq_ignore_ascii_case("time")) {
+        zone = &zone[..zone.len() - 1];
+    }
+    if zone.is_empty() {
+        return None;
+    }
+    Deadline::pin(day, time, &zone.join(" "))
+}
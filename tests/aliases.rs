@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+use todo_app::aliases::{expand, split_words, AliasError};
+
+fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(name, line)| (name.to_string(), line.to_string())).collect()
+}
+
+fn args(line: &str) -> Vec<String> {
+    split_words(line).unwrap()
+}
+
+#[test]
+fn splits_like_a_shell() {
+    assert_eq!(args(r#"list --format "{id} {title}" 'a "b"' c\ d ''"#), ["list", "--format", "{id} {title}", r#"a "b""#, "c d", ""]);
+    assert_eq!(split_words("list 'open"), None);
+}
+
+#[test]
+fn appends_the_arguments() {
+    let aliases = aliases(&[("ls", "list --sort due")]);
+    assert_eq!(expand(&aliases, &args("ls --limit 5")).unwrap(), args("list --sort due --limit 5"));
+    assert_eq!(expand(&aliases, &args("-y ls")).unwrap(), args("-y list --sort due"));
+    assert_eq!(expand(&aliases, &args("show 3")).unwrap(), args("show 3"));
+}
+
+#[test]
+fn substitutes_positional_arguments() {
+    let aliases = aliases(&[("note", "done $1 --note"), ("swap", "link $2 $1"), ("all", "search $@ --x"), ("tagged", "list --format '#$1'")]);
+    assert_eq!(expand(&aliases, &args("note 5 fixed it")).unwrap(), args("done 5 --note fixed it"));
+    assert_eq!(expand(&aliases, &args("swap 1 2 3")).unwrap(), args("link 2 1 3"));
+    assert_eq!(expand(&aliases, &args("all a b")).unwrap(), args("search a b --x"));
+    assert_eq!(expand(&aliases, &args("tagged x")).unwrap(), args("list --format #x"));
+    assert_eq!(expand(&aliases, &args("swap 1")), Err(AliasError::MissingArgument { alias: "swap".to_string(), needed: 2 }));
+}
+
+#[test]
+fn expands_aliases_of_aliases_once() {
+    let aliases = aliases(&[("ls", "l --all"), ("l", "list"), ("list", "list --sort due"), ("loop", "loop2"), ("loop2", "loop")]);
+    assert_eq!(expand(&aliases, &args("ls")).unwrap(), args("list --sort due --all"));
+    assert_eq!(expand(&aliases, &args("loop")).unwrap(), args("loop"));
+}
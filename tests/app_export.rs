@@ -0,0 +1,42 @@
+use chrono::NaiveDate;
+use todo_app::import::parse_app_export;
+use todo_app::Priority;
+
+#[test]
+fn reads_microsoft_to_do_lists_of_tasks() {
+    let json = r#"{"value": [{"displayName": "Groceries", "tasks": [
+        {"title": "Buy milk", "status": "notStarted", "importance": "high",
+         "body": {"content": "<p>Two&nbsp;litres</p>", "contentType": "html"},
+         "dueDateTime": {"dateTime": "2026-10-15T00:00:00.0000000", "timeZone": "UTC"}},
+        {"title": "Eggs", "status": "completed", "importance": "normal",
+         "completedDateTime": {"dateTime": "2026-10-10T12:00:00.0000000", "timeZone": "UTC"}}
+    ]}]}"#;
+    let parsed = parse_app_export(json).unwrap();
+    let todos = parsed.todos();
+    assert_eq!(todos.len(), 2);
+    assert_eq!((todos[0].title.as_str(), todos[0].description.as_str()), ("Buy milk", "Two litres"));
+    assert_eq!(todos[0].project.as_deref(), Some("Groceries"));
+    assert_eq!(todos[0].due, NaiveDate::from_ymd_opt(2026, 10, 15));
+    assert_eq!(todos[0].priority, Some(Priority::High));
+    assert!(!todos[0].completed);
+    assert!(todos[1].completed);
+    assert_eq!(todos[1].completed_at.map(|at| at.date_naive()), NaiveDate::from_ymd_opt(2026, 10, 10));
+    assert_eq!(todos[1].priority, None);
+}
+
+#[test]
+fn reads_reminders_csv_with_quoted_fields() {
+    let csv = "Title,Notes,List,Due Date,Priority,Completed,Creation Date,Flagged\n\
+               \"Pay rent\",\"Landlord, \"\"the usual\"\"\",Home,10/31/2026,9,No,2026-09-01,Yes\n\
+               Water plants,,Home,\"Oct 20, 2026 at 9:00 AM\",0,Yes,,\n";
+    let parsed = parse_app_export(csv).unwrap();
+    let todos = parsed.todos();
+    assert_eq!(todos.len(), 2);
+    assert_eq!(todos[0].description, "Landlord, \"the usual\"");
+    assert_eq!(todos[0].due, NaiveDate::from_ymd_opt(2026, 10, 31));
+    assert_eq!(todos[0].priority, Some(Priority::Low));
+    assert_eq!(todos[0].created_at.date_naive(), NaiveDate::from_ymd_opt(2026, 9, 1).unwrap());
+    assert!(todos[0].important && !todos[0].completed);
+    assert_eq!(todos[1].due, NaiveDate::from_ymd_opt(2026, 10, 20));
+    assert!(todos[1].completed);
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use todo_app::TodoList;
+
+fn todo_app(dir: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_todo_app")).args(args).current_dir(dir).status().unwrap();
+    assert!(status.success(), "todo_app {} failed", args.join(" "));
+}
+
+#[test]
+fn zstd_backups_restore_what_was_backed_up() {
+    let root = env::temp_dir().join(format!("todo_app_test_backup_{}", std::process::id()));
+    let (from, to) = (root.join("from"), root.join("to"));
+    fs::create_dir_all(&from).unwrap();
+    fs::create_dir_all(&to).unwrap();
+    let mut todo_list = TodoList::new();
+    todo_list.add_todo("Renew passport".to_string(), "Before March".to_string()).unwrap();
+    todo_list.save_to_file(from.join("todos.json").to_str().unwrap()).unwrap();
+
+    todo_app(&from, &["backup", "create", "todos.tar.zst"]);
+    let archive = from.join("todos.tar.zst");
+    assert_eq!(fs::read(&archive).unwrap()[..4], [0x28, 0xb5, 0x2f, 0xfd]);
+    todo_app(&to, &["backup", "restore", archive.to_str().unwrap()]);
+
+    let restored = TodoList::load_from_file(to.join("todos.json").to_str().unwrap()).unwrap();
+    let todo = &restored.todos()[0];
+    assert_eq!((todo.title.as_str(), todo.description.as_str()), ("Renew passport", "Before March"));
+    fs::remove_dir_all(&root).unwrap();
+}
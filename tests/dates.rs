@@ -0,0 +1,103 @@
+use chrono::{Local, NaiveDate, Weekday};
+use todo_app::dates::{apply_offset, days_before, offset_before, parse_date, postpone_date, DateFormat};
+use todo_app::rrule::Rule;
+use todo_app::workdays::{parse_holidays, Calendar, Holiday};
+
+fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn presets_and_patterns() {
+    let today = day(2026, 10, 14);
+    assert_eq!(DateFormat::parse("iso").unwrap().date(day(2026, 3, 5), today), "2026-03-05");
+    assert_eq!(DateFormat::parse("short").unwrap().date(day(2026, 3, 5), today), "Mar 5");
+    assert_eq!(DateFormat::parse("short").unwrap().date(day(2025, 3, 5), today), "Mar 5 2025");
+    assert_eq!(DateFormat::parse("%d.%m.%Y").unwrap().date(day(2026, 3, 5), today), "05.03.2026");
+    assert_eq!(DateFormat::parse("%Q"), None);
+    assert_eq!(DateFormat::parse("dd/mm"), None);
+}
+
+#[test]
+fn relative_dates() {
+    let today = day(2026, 10, 14);
+    let relative = DateFormat::Relative;
+    assert_eq!(relative.date(today, today), "today");
+    assert_eq!(relative.date(day(2026, 10, 15), today), "tomorrow");
+    assert_eq!(relative.date(day(2026, 10, 13), today), "yesterday");
+    assert_eq!(relative.date(day(2026, 10, 20), today), "in 6 days");
+    assert_eq!(relative.date(day(2026, 9, 30), today), "2 weeks ago");
+    assert_eq!(relative.date(day(2027, 1, 14), today), "in 3 months");
+    assert_eq!(relative.date(day(2023, 10, 14), today), "3 years ago");
+}
+
+#[test]
+fn offsets_past_the_calendar_are_invalid() {
+    let today = day(2026, 10, 14);
+    assert_eq!(apply_offset("+2w", today), Some(day(2026, 10, 28)));
+    assert_eq!(apply_offset("4000000000d", today), None);
+    assert_eq!(apply_offset("4000000000w", today), None);
+    assert_eq!(apply_offset("4000000000m", today), None);
+    assert_eq!(parse_date("4000000000d", today), None);
+    assert_eq!(offset_before("2w", today), Some(day(2026, 9, 30)));
+    assert_eq!(offset_before("4000000000d", today), None);
+    assert_eq!(offset_before("4000000000w", today), None);
+}
+
+#[test]
+fn offsets_with_other_units_are_invalid() {
+    let today = day(2026, 10, 14);
+    assert_eq!(apply_offset("1é", today), None);
+    assert_eq!(offset_before("3é", today), None);
+    assert_eq!(parse_date("+2€", today), None);
+    assert_eq!(apply_offset("é", today), None);
+    let now = Local::now();
+    assert_eq!(days_before(now, 7).map(|cutoff| (now - cutoff).num_days()), Some(7));
+    assert_eq!(days_before(now, 4_000_000_000), None);
+}
+
+#[test]
+fn postponing_counts_from_the_due_date_or_today() {
+    // A Wednesday.
+    let today = day(2026, 10, 14);
+    let calendar = Calendar::new(&[Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri], &[]);
+    assert_eq!(postpone_date("1d", Some(day(2026, 10, 20)), today, &calendar), Some(day(2026, 10, 21)));
+    assert_eq!(postpone_date("1w", Some(day(2026, 10, 1)), today, &calendar), Some(day(2026, 10, 21)));
+    assert_eq!(postpone_date("2d", None, today, &calendar), Some(day(2026, 10, 16)));
+    assert_eq!(postpone_date("next-monday", Some(day(2026, 11, 1)), today, &calendar), Some(day(2026, 10, 19)));
+    assert_eq!(postpone_date("later", None, today, &calendar), None);
+}
+
+#[test]
+fn business_days_skip_weekends_and_holidays() {
+    let workdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    let holidays = parse_holidays("2026-10-19 Fair day\n2026-12-25,Christmas Day\nnot a date\n");
+    assert_eq!(holidays, vec![
+        Holiday { day: day(2026, 10, 19), name: "Fair day".to_string() },
+        Holiday { day: day(2026, 12, 25), name: "Christmas Day".to_string() },
+    ]);
+    let calendar = Calendar::new(&workdays, &holidays);
+    // From a Wednesday, over the weekend and the Monday off.
+    let today = day(2026, 10, 14);
+    assert_eq!(postpone_date("3b", None, today, &calendar), Some(day(2026, 10, 20)));
+    assert_eq!(postpone_date("2 business days", Some(day(2026, 10, 16)), today, &calendar), Some(day(2026, 10, 21)));
+    assert_eq!(postpone_date("1 workday", None, today, &calendar), Some(day(2026, 10, 15)));
+
+    // Every day but the weekend and the holiday; the Saturdays asked for
+    // stay.
+    let daily = Rule::parse("FREQ=DAILY").unwrap();
+    assert_eq!(calendar.move_occurrence(day(2026, 10, 17), &daily), day(2026, 10, 20));
+    let saturdays = Rule::parse("FREQ=WEEKLY;BYDAY=SA").unwrap();
+    assert_eq!(calendar.move_occurrence(day(2026, 10, 17), &saturdays), day(2026, 10, 17));
+}
+
+#[test]
+fn holidays_from_icalendar() {
+    let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20261225\r\nDTEND;VALUE=DATE:20261227\r\nSUMMARY:Christ\r\n mas\\, both days\r\nEND:VEVENT\r\n\
+               BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20260101\r\nRRULE:FREQ=YEARLY\r\nSUMMARY:New Year\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+    let holidays = parse_holidays(ics);
+    assert_eq!(holidays.len(), 13);
+    assert_eq!(holidays[1], Holiday { day: day(2026, 12, 25), name: "Christmas, both days".to_string() });
+    assert_eq!(holidays[2].day, day(2026, 12, 26));
+    assert_eq!(holidays[12].day, day(2036, 1, 1));
+}
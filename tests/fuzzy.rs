@@ -0,0 +1,22 @@
+use todo_app::fuzzy::score;
+
+#[test]
+fn matches_characters_in_order_ignoring_case() {
+    assert!(score("bmk", "Buy milk").is_some());
+    assert!(score("MILK", "Buy milk").is_some());
+    assert_eq!(score("klim", "Buy milk"), None);
+    assert_eq!(score("milks", "Buy milk"), None);
+}
+
+#[test]
+fn prefers_word_starts_and_runs() {
+    let word_start = score("milk", "Buy milk").unwrap();
+    let scattered = score("milk", "summit lake").unwrap();
+    assert!(word_start > scattered);
+    assert!(score("re", "Write report").unwrap() > score("re", "Prepare").unwrap());
+}
+
+#[test]
+fn empty_query_matches_everything() {
+    assert_eq!(score("", "anything"), Some(0));
+}
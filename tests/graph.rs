@@ -0,0 +1,29 @@
+use todo_app::graph::schedule;
+use todo_app::plan;
+use todo_app::TodoList;
+
+#[test]
+fn schedule_runs_independent_work_in_parallel() {
+    let mut todo_list = TodoList::new();
+    let ids: Vec<usize> = ["Launch site", "Write copy", "Design pages", "Buy domain"]
+        .into_iter()
+        .map(|title| todo_list.add_todo(title.to_string(), String::new()).unwrap())
+        .collect();
+    todo_list.set_estimate(ids[0], Some(60));
+    todo_list.set_estimate(ids[1], Some(120));
+    todo_list.set_estimate(ids[2], Some(240));
+    todo_list.set_estimate(ids[3], Some(15));
+    todo_list.add_dependency(ids[0], ids[1]).unwrap();
+    todo_list.add_dependency(ids[0], ids[3]).unwrap();
+    todo_list.add_dependency(ids[1], ids[2]).unwrap();
+
+    let todos: Vec<_> = todo_list.todos().iter().collect();
+    let slots = schedule(&todos, plan::minutes);
+    let slot = |id: usize| *slots.iter().find(|slot| slot.id == id).unwrap();
+    assert_eq!((slot(ids[2]).start, slot(ids[2]).slack), (0, 0));
+    assert_eq!((slot(ids[1]).start, slot(ids[1]).finish, slot(ids[1]).slack), (240, 360, 0));
+    assert_eq!((slot(ids[0]).start, slot(ids[0]).finish, slot(ids[0]).slack), (360, 420, 0));
+    // Buying the domain can wait until the copy is written.
+    assert_eq!((slot(ids[3]).start, slot(ids[3]).slack), (0, 345));
+    assert_eq!(slots.iter().map(|slot| slot.id).collect::<Vec<_>>(), vec![ids[2], ids[3], ids[1], ids[0]]);
+}
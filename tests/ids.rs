@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use todo_app::ids::lacks_ids;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn only_commands_missing_their_ids_need_a_pick() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+    assert!(lacks_ids(&args("done"), today));
+    assert!(lacks_ids(&args("due friday"), today));
+    assert!(!lacks_ids(&args("done 3,4"), today));
+    assert!(!lacks_ids(&args("done @overdue"), today));
+    assert!(!lacks_ids(&args("list"), today));
+
+    // Every export format is a command of its own.
+    for format in ["jsonl", "sqlite FILE", "xlsx report.xlsx"] {
+        assert!(!lacks_ids(&args(&format!("export {}", format)), today), "export {}", format);
+    }
+    assert!(!lacks_ids(&args("export 3 --format markdown"), today));
+    assert!(lacks_ids(&args("export --format markdown"), today));
+    assert!(lacks_ids(&args("export"), today));
+}
@@ -0,0 +1,20 @@
+use todo_app::notify::{payload, Notification, WebhookFormat};
+use todo_app::TodoList;
+
+#[test]
+fn slack_messages_stay_within_slacks_limits() {
+    let mut todo_list = TodoList::new();
+    let ids: Vec<usize> = (0..30).map(|n| todo_list.add_todo(format!("Todo {} {}", n, "é".repeat(180)), String::new()).unwrap()).collect();
+
+    let todos: Vec<_> = todo_list.todos().iter().collect();
+    let due = payload(WebhookFormat::Slack, &Notification::DueSoon(todos));
+    let blocks = due["blocks"].as_array().unwrap();
+    assert!(blocks.len() <= 50);
+    assert_eq!(blocks.iter().filter(|block| block["type"] == "section").count(), 10);
+    assert_eq!(blocks.last().unwrap()["elements"][0]["text"], "…and 20 more");
+
+    let completed = payload(WebhookFormat::Slack, &Notification::Completed(todo_list.get_todo(ids[0]).unwrap()));
+    let header = completed["blocks"][0]["text"]["text"].as_str().unwrap();
+    assert_eq!(header.chars().count(), 150);
+    assert!(header.starts_with("Completed: Todo 0 é") && header.ends_with('…'));
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+use todo_app::paper::{self, Page, PaperSize, Section};
+use todo_app::{Priority, TodoList};
+
+fn day(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn agenda_has_a_box_per_todo_and_wraps_long_titles() {
+    let today = day("2026-10-14");
+    let mut todo_list = TodoList::new();
+    let report = todo_list.add_todo("Send the quarterly report to everyone on the finance team".to_string(), String::new()).unwrap();
+    let invoice = todo_list.add_todo("Pay invoice".to_string(), String::new()).unwrap();
+    let walk = todo_list.add_todo("Walk".to_string(), String::new()).unwrap();
+    todo_list.add_todo("Someday".to_string(), String::new()).unwrap();
+    todo_list.set_due(report, Some(today));
+    todo_list.set_priority(report, Some(Priority::High));
+    todo_list.set_due(invoice, Some(day("2026-10-12")));
+    todo_list.set_habit(walk, true);
+
+    let text = paper::agenda(&todo_list, today).text(40);
+    let expected = "\
+Due today
+  [ ] #1 Send the quarterly report to
+      everyone on the finance team
+      (high)
+
+Overdue
+  [ ] #2 Pay invoice (due 2026-10-12)
+
+Habits
+  [ ] #3 Walk
+";
+    assert!(text.starts_with("Agenda for Wednesday, 14 October 2026\n"));
+    assert!(text.contains(expected), "{}", text);
+    assert!(!text.contains("Someday"));
+}
+
+#[test]
+fn pdf_breaks_long_agendas_into_pages() {
+    let items = (1..=50).map(|n| format!("#{} Todo number {}", n, n)).collect();
+    let page = Page { title: "Agenda".to_string(), subtitle: String::new(), sections: vec![Section { heading: "Due today".to_string(), items }] };
+    let pdf = String::from_utf8(page.pdf(PaperSize::Letter)).unwrap();
+    assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+    assert!(pdf.contains("/Count 2"));
+    assert!(pdf.contains("(Page 2 of 2)"));
+    assert!(pdf.contains("/MediaBox [0 0 612 792]"));
+    // The cross-reference table points at every object.
+    let xref = pdf.find("\nxref\n").unwrap() + 1;
+    let startxref: usize = pdf.split("startxref\n").nth(1).unwrap().lines().next().unwrap().parse().unwrap();
+    assert_eq!(startxref, xref);
+    for (n, line) in pdf[xref..].lines().skip(3).take_while(|line| line.ends_with(" n ")).enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(&format!("{} 0 obj", n + 1)));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::Local;
+use todo_app::priority::inherited_priorities;
+use todo_app::{Priority, TodoList};
+
+#[test]
+fn blockers_take_the_priority_of_what_waits_on_them() {
+    let mut todo_list = TodoList::new();
+    let ids: Vec<usize> = ["Ship release", "Write changelog", "Fix build", "Review PR", "Buy stamps"]
+        .into_iter()
+        .map(|title| todo_list.add_todo(title.to_string(), String::new()).unwrap())
+        .collect();
+    todo_list.set_priority(ids[0], Some(Priority::High));
+    todo_list.set_priority(ids[2], Some(Priority::Low));
+    todo_list.set_priority(ids[3], Some(Priority::High));
+    todo_list.add_dependency(ids[0], ids[1]).unwrap();
+    todo_list.add_dependency(ids[1], ids[2]).unwrap();
+    todo_list.add_dependency(ids[0], ids[3]).unwrap();
+
+    let inherited = inherited_priorities(todo_list.todos(), None, Local::now());
+    assert_eq!(inherited.get(&ids[1]), Some(&(Priority::High, vec![ids[1], ids[0]])));
+    assert_eq!(inherited.get(&ids[2]), Some(&(Priority::High, vec![ids[2], ids[1], ids[0]])));
+    // High already, and waited on by nothing.
+    assert_eq!(inherited.get(&ids[3]), None);
+    assert_eq!(inherited.get(&ids[4]), None);
+
+    todo_list.toggle_with_note(ids[0], None);
+    assert!(inherited_priorities(todo_list.todos(), None, Local::now()).is_empty());
+}
@@ -0,0 +1,82 @@
+use std::env;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use proptest::prelude::*;
+use todo_app::diff::diff;
+use todo_app::storage::{self, MemoryStorage, Storage};
+use todo_app::{Todo, TodoList};
+
+fn json(todo_list: &TodoList) -> serde_json::Value {
+    serde_json::from_str(&todo_list.to_json().unwrap()).unwrap()
+}
+
+// A file name of its own for every case, in the temporary directory.
+fn scratch(extension: &str) -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::SeqCst);
+    env::temp_dir().join(format!("todo_roundtrip_{}_{}.{}", std::process::id(), n, extension)).to_string_lossy().into_owned()
+}
+
+fn remove_scratch(filename: &str) {
+    let _ = fs::remove_file(filename);
+    let _ = fs::remove_dir_all(filename);
+    let _ = fs::remove_file(todo_app::history::history_filename(filename));
+}
+
+proptest! {
+    #[test]
+    fn todo_survives_json(todo in any::<Todo>()) {
+        let text = serde_json::to_string(&todo).unwrap();
+        let back: Todo = serde_json::from_str(&text).unwrap();
+        prop_assert_eq!(serde_json::to_value(&todo).unwrap(), serde_json::to_value(&back).unwrap());
+    }
+
+    #[test]
+    fn list_survives_json(todo_list in any::<TodoList>()) {
+        let back = TodoList::from_json(&todo_list.to_json().unwrap()).unwrap();
+        prop_assert_eq!(json(&todo_list), json(&back));
+        prop_assert!(diff(&todo_list, &back).is_empty());
+    }
+
+    #[test]
+    fn list_survives_memory_storage(todo_list in any::<TodoList>()) {
+        let store = MemoryStorage::new();
+        store.save(&todo_list).unwrap();
+        prop_assert_eq!(json(&todo_list), json(&store.load().unwrap()));
+    }
+
+    #[test]
+    fn list_survives_the_json_file(todo_list in any::<TodoList>()) {
+        let filename = scratch("json");
+        todo_list.save_to_file(&filename).unwrap();
+        let back = TodoList::load_from_file(&filename).unwrap();
+        let mut streamed = Vec::new();
+        TodoList::for_each_in_file(&filename, |todo| {
+            streamed.push(serde_json::to_value(&todo).unwrap());
+            true
+        }).unwrap();
+        let found = todo_list.todos().first().map(|todo| storage::open(&filename).unwrap().get(todo.id).unwrap());
+        remove_scratch(&filename);
+
+        prop_assert_eq!(json(&todo_list), json(&back));
+        let todos: Vec<_> = todo_list.todos().iter().map(|todo| serde_json::to_value(todo).unwrap()).collect();
+        prop_assert_eq!(todos, streamed);
+        if let Some(found) = found {
+            prop_assert_eq!(serde_json::to_value(found).unwrap(), serde_json::to_value(&todo_list.todos()[0]).unwrap());
+        }
+    }
+}
+
+#[cfg(feature = "sled")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn list_survives_sled(todo_list in any::<TodoList>()) {
+        let filename = scratch("sled");
+        todo_list.save_to_file(&filename).unwrap();
+        let back = TodoList::load_from_file(&filename).unwrap();
+        remove_scratch(&filename);
+        prop_assert_eq!(json(&todo_list), json(&back));
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::NaiveDate;
+use todo_app::rrule::{Rule, RuleError};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn first(rule: &str, start: NaiveDate, n: usize) -> Vec<NaiveDate> {
+    Rule::parse(rule).unwrap().occurrences(start).take(n).collect()
+}
+
+#[test]
+fn daily_with_interval() {
+    assert_eq!(
+        first("FREQ=DAILY;INTERVAL=3", date(2026, 2, 26), 3),
+        [date(2026, 2, 26), date(2026, 3, 1), date(2026, 3, 4)]
+    );
+}
+
+#[test]
+fn weekly_on_several_days() {
+    assert_eq!(
+        first("FREQ=WEEKLY;BYDAY=TU,TH", date(2026, 10, 14), 4),
+        [date(2026, 10, 15), date(2026, 10, 20), date(2026, 10, 22), date(2026, 10, 27)]
+    );
+}
+
+#[test]
+fn weekly_defaults_to_the_start_weekday() {
+    assert_eq!(first("FREQ=WEEKLY", date(2026, 10, 14), 2), [date(2026, 10, 14), date(2026, 10, 21)]);
+}
+
+#[test]
+fn every_other_week_depends_on_week_start() {
+    // The example from RFC 5545: with WKST=SU the Sunday belongs to the
+    // following week, with WKST=MO to the one before.
+    let start = date(1997, 8, 5);
+    assert_eq!(
+        first("FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO", start, 10),
+        [date(1997, 8, 5), date(1997, 8, 10), date(1997, 8, 19), date(1997, 8, 24)]
+    );
+    assert_eq!(
+        first("FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU", start, 10),
+        [date(1997, 8, 5), date(1997, 8, 17), date(1997, 8, 19), date(1997, 8, 31)]
+    );
+}
+
+#[test]
+fn second_tuesday_of_the_month() {
+    assert_eq!(
+        first("FREQ=MONTHLY;BYDAY=2TU", date(2026, 10, 14), 3),
+        [date(2026, 11, 10), date(2026, 12, 8), date(2027, 1, 12)]
+    );
+}
+
+#[test]
+fn last_friday_of_the_month() {
+    assert_eq!(
+        first("FREQ=MONTHLY;BYDAY=-1FR", date(2026, 1, 1), 3),
+        [date(2026, 1, 30), date(2026, 2, 27), date(2026, 3, 27)]
+    );
+}
+
+#[test]
+fn last_weekday_of_the_month() {
+    // May 2026 ends on a Sunday and January 2027 on a Sunday too.
+    assert_eq!(
+        first("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", date(2026, 5, 1), 3),
+        [date(2026, 5, 29), date(2026, 6, 30), date(2026, 7, 31)]
+    );
+    assert_eq!(
+        first("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", date(2027, 1, 1), 1),
+        [date(2027, 1, 29)]
+    );
+}
+
+#[test]
+fn last_day_of_the_month() {
+    assert_eq!(
+        first("FREQ=MONTHLY;BYMONTHDAY=-1", date(2028, 1, 15), 3),
+        [date(2028, 1, 31), date(2028, 2, 29), date(2028, 3, 31)]
+    );
+}
+
+#[test]
+fn the_31st_skips_shorter_months() {
+    assert_eq!(
+        first("FREQ=MONTHLY", date(2026, 1, 31), 3),
+        [date(2026, 1, 31), date(2026, 3, 31), date(2026, 5, 31)]
+    );
+}
+
+#[test]
+fn friday_the_13th() {
+    assert_eq!(
+        first("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13", date(2026, 1, 1), 3),
+        [date(2026, 2, 13), date(2026, 3, 13), date(2026, 11, 13)]
+    );
+}
+
+#[test]
+fn thanksgiving() {
+    assert_eq!(
+        first("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH", date(2026, 1, 1), 3),
+        [date(2026, 11, 26), date(2027, 11, 25), date(2028, 11, 23)]
+    );
+}
+
+#[test]
+fn numbered_weekday_within_the_year() {
+    // The 20th Monday of the year, from RFC 5545.
+    assert_eq!(first("FREQ=YEARLY;BYDAY=20MO", date(1997, 1, 1), 3), [date(1997, 5, 19), date(1998, 5, 18), date(1999, 5, 17)]);
+}
+
+#[test]
+fn leap_day_only_in_leap_years() {
+    assert_eq!(
+        first("FREQ=YEARLY", date(2024, 2, 29), 3),
+        [date(2024, 2, 29), date(2028, 2, 29), date(2032, 2, 29)]
+    );
+}
+
+#[test]
+fn last_day_of_the_year() {
+    assert_eq!(first("FREQ=YEARLY;BYYEARDAY=-1", date(2026, 6, 1), 2), [date(2026, 12, 31), date(2027, 12, 31)]);
+}
+
+#[test]
+fn count_includes_only_matching_days() {
+    // The start is a Wednesday and does not fit, so it is not counted.
+    assert_eq!(
+        first("FREQ=WEEKLY;BYDAY=MO;COUNT=2", date(2026, 10, 14), 10),
+        [date(2026, 10, 19), date(2026, 10, 26)]
+    );
+}
+
+#[test]
+fn until_is_inclusive() {
+    assert_eq!(
+        first("FREQ=DAILY;UNTIL=20261016T000000Z", date(2026, 10, 14), 10),
+        [date(2026, 10, 14), date(2026, 10, 15), date(2026, 10, 16)]
+    );
+}
+
+#[test]
+fn impossible_rules_end() {
+    assert_eq!(first("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", date(2026, 1, 1), 1), []);
+}
+
+#[test]
+fn next_after_keeps_the_series() {
+    let rule = Rule::parse("FREQ=WEEKLY;INTERVAL=2").unwrap();
+    assert_eq!(rule.next_after(date(2026, 10, 5), date(2026, 10, 14)), Some(date(2026, 10, 19)));
+    assert_eq!(rule.next_after(date(2026, 10, 5), date(2026, 10, 19)), Some(date(2026, 11, 2)));
+}
+
+#[test]
+fn parsing_is_lenient_about_case_and_prefix() {
+    let rule = Rule::parse("rrule:freq=monthly;byday=+2tu;").unwrap();
+    assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYDAY=2TU");
+}
+
+#[test]
+fn display_round_trips() {
+    let text = "FREQ=YEARLY;INTERVAL=2;UNTIL=20301231;BYMONTH=1,7;BYDAY=-1SU;BYSETPOS=1;WKST=SU";
+    let rule = Rule::parse(text).unwrap();
+    assert_eq!(rule.to_string(), text);
+    assert_eq!(Rule::parse(&rule.to_string()).unwrap(), rule);
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    assert_eq!(Rule::parse("BYDAY=MO"), Err(RuleError::MissingFrequency));
+    assert_eq!(Rule::parse("FREQ=HOURLY"), Err(RuleError::Unsupported("FREQ=HOURLY".to_string())));
+    assert_eq!(Rule::parse("FREQ=DAILY;BYHOUR=9"), Err(RuleError::Unsupported("BYHOUR".to_string())));
+    assert_eq!(Rule::parse("FREQ=DAILY;FOO=1"), Err(RuleError::UnknownPart("FOO".to_string())));
+    assert_eq!(Rule::parse("FREQ=DAILY;COUNT=2;UNTIL=20270101"), Err(RuleError::CountAndUntil));
+    assert!(matches!(Rule::parse("FREQ=MONTHLY;BYDAY=5XX"), Err(RuleError::InvalidValue { .. })));
+    assert!(matches!(Rule::parse("FREQ=MONTHLY;BYMONTHDAY=32"), Err(RuleError::InvalidValue { .. })));
+    assert!(matches!(Rule::parse("FREQ=DAILY;INTERVAL=0"), Err(RuleError::InvalidValue { .. })));
+    assert!(matches!(Rule::parse("FREQ=WEEKLY;BYDAY=2MO"), Err(RuleError::NotWithFrequency(_))));
+    assert!(matches!(Rule::parse("FREQ=MONTHLY;BYYEARDAY=1"), Err(RuleError::NotWithFrequency(_))));
+}
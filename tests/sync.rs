@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+use todo_app::sync::{apply, local_ops, Op};
+use todo_app::{Priority, Todo, TodoList};
+
+// A device as `sync` sees it, talking to a server that is left out: what
+// one device pushes is handed to the others directly.
+struct Device {
+    todo_list: TodoList,
+    synced: Vec<Todo>,
+}
+
+impl Device {
+    fn new() -> Self {
+        Device { todo_list: TodoList::new(), synced: Vec::new() }
+    }
+
+    fn pull(&mut self, ops: &[Op]) {
+        apply(&mut self.todo_list, &mut self.synced, ops.to_vec());
+    }
+
+    fn push(&mut self) -> Vec<Op> {
+        let ops = local_ops(&self.todo_list, &self.synced, &[]);
+        self.synced = self.todo_list.todos().to_vec();
+        ops
+    }
+}
+
+// Two devices holding the same todo, returning its id.
+fn shared_todo(a: &mut Device, b: &mut Device) -> usize {
+    let id = a.todo_list.add_todo("Book flights".to_string(), String::new()).unwrap();
+    let ops = a.push();
+    b.pull(&ops);
+    b.push();
+    id
+}
+
+#[test]
+fn edits_to_different_fields_both_survive() {
+    let (mut a, mut b) = (Device::new(), Device::new());
+    let id = shared_todo(&mut a, &mut b);
+    a.todo_list.set_priority(id, Some(Priority::High));
+    b.todo_list.set_project(id, Some("Travel".to_string()));
+
+    let from_a = a.push();
+    b.pull(&from_a);
+    let from_b = b.push();
+    a.pull(&from_b);
+
+    for device in [&a, &b] {
+        let todo = device.todo_list.get_todo(id).unwrap();
+        assert_eq!(todo.priority, Some(Priority::High));
+        assert_eq!(todo.project.as_deref(), Some("Travel"));
+    }
+}
+
+#[test]
+fn a_delete_wins_over_an_older_edit() {
+    let (mut a, mut b) = (Device::new(), Device::new());
+    let id = shared_todo(&mut a, &mut b);
+    a.todo_list.edit_todo(id, "Book cheaper flights".to_string(), String::new()).unwrap();
+    assert!(b.todo_list.delete_todo(id));
+
+    // The edit reaches the server first; the device that deleted the todo
+    // still does not take it back.
+    let from_a = a.push();
+    b.pull(&from_a);
+    assert!(b.todo_list.get_todo(id).is_none());
+    let from_b = b.push();
+    a.pull(&from_b);
+    assert!(a.todo_list.get_todo(id).is_none());
+    assert!(a.push().is_empty());
+}
+
+#[test]
+fn a_newer_edit_does_not_bring_a_deleted_todo_back() {
+    let (mut a, mut b) = (Device::new(), Device::new());
+    let id = shared_todo(&mut a, &mut b);
+    let before = a.todo_list.to_json().unwrap();
+    assert!(b.todo_list.delete_todo(id));
+    let deleted = b.push();
+
+    // Edited after it was deleted elsewhere, the todo is still deleted once
+    // the delete is pulled.
+    a.todo_list.set_due(id, NaiveDate::from_ymd_opt(2026, 11, 2));
+    a.pull(&deleted);
+    assert!(a.todo_list.get_todo(id).is_none());
+    assert!(a.push().is_empty());
+
+    // Nor does an old copy, put back from a backup of the list and so sent
+    // again: the tombstone keeps it out.
+    a.todo_list = TodoList::from_json(&before).unwrap();
+    a.todo_list.set_priority(id, Some(Priority::Low));
+    let resent = a.push();
+    assert_eq!(resent.len(), 1);
+    b.pull(&resent);
+    assert!(b.todo_list.get_todo(id).is_none());
+}
+
+#[test]
+fn links_to_a_deleted_todo_do_not_come_back() {
+    let (mut a, mut b) = (Device::new(), Device::new());
+    let id = shared_todo(&mut a, &mut b);
+    let other = a.todo_list.add_todo("Pack".to_string(), String::new()).unwrap();
+    a.todo_list.add_dependency(other, id).unwrap();
+    assert!(a.todo_list.relate(other, id));
+    let ops = a.push();
+    b.pull(&ops);
+    b.push();
+
+    // The delete drops the links here; the other device, still holding
+    // them, edits the todo afterwards and syncs first.
+    assert!(a.todo_list.delete_todo(id));
+    b.todo_list.set_priority(other, Some(Priority::High));
+    let from_b = b.push();
+    a.pull(&from_b);
+    let from_a = a.push();
+    b.pull(&from_a);
+
+    for device in [&a, &b] {
+        let todo = device.todo_list.get_todo(other).unwrap();
+        assert!(todo.depends_on.is_empty());
+        assert!(todo.related.is_empty());
+        assert_eq!(todo.priority, Some(Priority::High));
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::NaiveDate;
+use todo_app::import::parse_todo_txt;
+use todo_app::Priority;
+
+#[test]
+fn reads_priorities_dates_projects_and_contexts() {
+    let text = "(A) 2026-10-01 Call the bank +Finances @phone due:2026-10-20\n\nx 2026-10-10 2026-10-02 Buy stamps @errands\n";
+    let parsed = parse_todo_txt(text).unwrap();
+    let todos = parsed.todos();
+    assert_eq!(todos.len(), 2);
+
+    assert_eq!(todos[0].title, "Call the bank");
+    assert_eq!(todos[0].priority, Some(Priority::High));
+    assert_eq!(todos[0].project.as_deref(), Some("Finances"));
+    assert_eq!(todos[0].tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>(), ["@phone"]);
+    assert_eq!(todos[0].due, NaiveDate::from_ymd_opt(2026, 10, 20));
+    assert_eq!(todos[0].created_at.date_naive(), NaiveDate::from_ymd_opt(2026, 10, 1).unwrap());
+    assert!(!todos[0].completed);
+
+    assert_eq!(todos[1].title, "Buy stamps");
+    assert!(todos[1].completed);
+    assert_eq!(todos[1].completed_at.map(|at| at.date_naive()), NaiveDate::from_ymd_opt(2026, 10, 10));
+    assert_eq!(todos[1].created_at.date_naive(), NaiveDate::from_ymd_opt(2026, 10, 2).unwrap());
+}
+
+#[test]
+fn keeps_unknown_words_in_the_title() {
+    let parsed = parse_todo_txt("Plan trip t:2026-11-01 +Travel +Fun rec:1w").unwrap();
+    let todo = &parsed.todos()[0];
+    assert_eq!(todo.title, "Plan trip +Fun rec:1w");
+    assert_eq!(todo.wait_until, NaiveDate::from_ymd_opt(2026, 11, 1));
+    assert_eq!(todo.priority, None);
+}
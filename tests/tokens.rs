@@ -0,0 +1,30 @@
+use std::env;
+use chrono::NaiveDate;
+use todo_app::tokens::{Denied, Scope, Tokens};
+
+fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn tokens_are_checked_for_scope_and_expiry() {
+    let today = day(2026, 10, 14);
+    let missing = env::temp_dir().join("todo_app_test_no_tokens.json");
+    let mut tokens = Tokens::load(missing.to_str().unwrap()).unwrap();
+    // Open to everyone until the first token is created.
+    assert!(tokens.is_open());
+    assert_eq!(tokens.check(None, Scope::Users, today), Ok(None));
+
+    let phone = tokens.create("phone", vec![Scope::Pull, Scope::Push], Some(day(2026, 10, 15))).unwrap();
+    assert_eq!(tokens.create("phone", vec![Scope::Pull], None), None);
+    assert!(!tokens.is_open());
+    assert_eq!(tokens.check(Some(&phone), Scope::Pull, today), Ok(Some("phone")));
+    assert_eq!(tokens.check(Some(&phone), Scope::Register, today), Err(Denied::OutOfScope("phone".to_string(), Scope::Register)));
+    assert_eq!(tokens.check(Some(&phone), Scope::Pull, day(2026, 10, 15)), Err(Denied::Expired("phone".to_string())));
+    assert_eq!(tokens.check(Some("0123456789abcdef"), Scope::Pull, today), Err(Denied::Unknown));
+    assert_eq!(tokens.check(None, Scope::Pull, today), Err(Denied::Missing));
+
+    // With every token revoked nobody gets in.
+    assert!(tokens.revoke("phone"));
+    assert_eq!(tokens.check(Some(&phone), Scope::Pull, today), Err(Denied::Unknown));
+}
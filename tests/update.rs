@@ -0,0 +1,30 @@
+use todo_app::update::{checksum, expected_checksum, github_repository, is_newer};
+
+#[test]
+fn compares_versions_by_number() {
+    assert!(is_newer("0.10.0", "0.9.3"));
+    assert!(is_newer("1.0.0", "0.1.0"));
+    assert!(!is_newer("0.1.0", "0.1.0"));
+    assert!(!is_newer("0.1.0-rc1", "0.1.1"));
+}
+
+#[test]
+fn finds_the_checksum_of_an_asset() {
+    let binary = b"not really a program";
+    let sums = format!(
+        "{}  todo_app-aarch64-macos\n{} *todo_app-x86_64-linux\n",
+        "0".repeat(64),
+        checksum(binary).to_uppercase(),
+    );
+    assert_eq!(expected_checksum(&sums, "todo_app-x86_64-linux"), Some(checksum(binary)));
+    assert_eq!(expected_checksum(&sums, "todo_app-x86_64-windows.exe"), None);
+}
+
+#[test]
+fn reads_the_repository_from_its_url() {
+    assert_eq!(github_repository("https://github.com/sam/todo_app.git").as_deref(), Some("sam/todo_app"));
+    assert_eq!(github_repository("sam/todo_app").as_deref(), Some("sam/todo_app"));
+    assert_eq!(github_repository(""), None);
+    // What `self-update` falls back on.
+    assert_eq!(github_repository(env!("CARGO_PKG_REPOSITORY")).as_deref(), Some("S1933/todo-rust"));
+}
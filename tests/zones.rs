@@ -0,0 +1,36 @@
+use chrono::{NaiveDate, NaiveTime};
+use todo_app::zones::{parse_deadline, parse_time};
+
+fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn times_of_day() {
+    let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+    assert_eq!(parse_time("9am"), time(9, 0));
+    assert_eq!(parse_time("12am"), time(0, 0));
+    assert_eq!(parse_time("9:30PM"), time(21, 30));
+    assert_eq!(parse_time("17:05"), time(17, 5));
+    assert_eq!(parse_time("noon"), time(12, 0));
+    assert_eq!(parse_time("9"), None);
+    assert_eq!(parse_time("13pm"), None);
+    assert_eq!(parse_time("9:5"), None);
+}
+
+#[test]
+fn deadlines_keep_their_time_in_their_zone() {
+    // A Wednesday.
+    let today = day(2026, 10, 14);
+    let deadline = parse_deadline("friday 9am Berlin time", today).unwrap();
+    assert_eq!(deadline.zone, "Europe/Berlin");
+    assert_eq!(deadline.at.to_rfc3339(), "2026-10-16T09:00:00+02:00");
+    assert_eq!(deadline.to_string(), "09:00 Europe/Berlin");
+    // Past the end of summer time, the offset changes and the time does not.
+    assert_eq!(deadline.on(day(2026, 10, 30)).at.to_rfc3339(), "2026-10-30T09:00:00+01:00");
+
+    let deadline = parse_deadline("at 17:00 in new york", today).unwrap();
+    assert_eq!((deadline.zone.as_str(), deadline.at.to_rfc3339()), ("America/New_York", "2026-10-14T17:00:00-04:00".to_string()));
+    assert_eq!(parse_deadline("2026-10-20 9am", today), None);
+    assert_eq!(parse_deadline("2026-10-20 9am Atlantis", today), None);
+}